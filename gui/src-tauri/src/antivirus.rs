@@ -0,0 +1,425 @@
+//! ClamAV scanning of files arriving on servers that have it turned on. The ingest
+//! watcher hands each new file here before running its rules; a positive is moved
+//! out of the share into the data directory's quarantine and raised as a
+//! `virus-found` event, and the rules never see it. Files breaking a server's
+//! upload policy can be sent to the same quarantine.
+
+use super::{
+    events, get_data_dir, host_path_of, load_app_settings, load_credentials, new_command,
+    read_json_file, record_audit, update_app_settings, update_credentials, update_json_file,
+    write_audit_entry, AuditEntry, CommandResult, ServerInfo, QUARANTINE_DIR,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use ts_rs::TS;
+
+/// How long clamd may take over one file
+const CLAMD_TIMEOUT: Duration = Duration::from_secs(60);
+/// Bytes per INSTREAM chunk; clamd's StreamMaxLength still caps the whole file
+const CLAMD_CHUNK: usize = 64 * 1024;
+/// Host paths of restored files, let through once when the watcher sees them arrive
+const RESTORED_FILE: &str = "restored.json";
+
+/// Where files are sent to be scanned
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum ScanEngine {
+    /// A running clamd: "host:port" for TCP, or the path of its Unix socket
+    Clamd { address: String },
+    /// A throwaway container of a ClamAV image (e.g. "clamav/clamav:stable") per
+    /// file. Needs no setup but is slow, as every run loads the signatures anew.
+    Container { image: String },
+}
+
+/// Emitted as `virus-found` when a scan flags an arrived file
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct VirusFound {
+    pub server: String,
+    /// Where the file arrived, inside the container
+    pub path: String,
+    pub signature: String,
+    /// Id of the quarantined copy; `None` if moving it failed and it is still shared
+    pub quarantine_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A file held in quarantine, described by the JSON kept next to it
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct QuarantinedFile {
+    pub id: String,
+    pub server: String,
+    pub path: String,
+    /// Where it was on the host, and goes back to on restore
+    pub host_path: String,
+    /// The virus signature, or the upload policy rule it broke
+    pub reason: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    pub quarantined_at: String,
+}
+
+fn quarantine_dir() -> PathBuf {
+    get_data_dir().join(QUARANTINE_DIR)
+}
+
+/// Send the file to clamd with INSTREAM and read back its verdict
+fn clamd_instream(mut stream: impl Read + Write, file: &mut fs::File) -> Result<String, String> {
+    stream
+        .write_all(b"zINSTREAM\0")
+        .map_err(|e| e.to_string())?;
+    let mut chunk = vec![0u8; CLAMD_CHUNK];
+    loop {
+        let read = file.read(&mut chunk).map_err(|e| e.to_string())?;
+        stream
+            .write_all(&(read as u32).to_be_bytes())
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        stream
+            .write_all(&chunk[..read])
+            .map_err(|e| e.to_string())?;
+    }
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(|e| e.to_string())?;
+    Ok(reply.trim_end_matches(['\0', '\n']).to_string())
+}
+
+/// Connect to clamd over its Unix socket (a path) or TCP (host:port)
+fn clamd_reply(address: &str, file: &mut fs::File) -> Result<String, String> {
+    #[cfg(unix)]
+    if address.starts_with('/') {
+        let stream = std::os::unix::net::UnixStream::connect(address).map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(CLAMD_TIMEOUT)).ok();
+        stream.set_write_timeout(Some(CLAMD_TIMEOUT)).ok();
+        return clamd_instream(stream, file);
+    }
+    let stream = std::net::TcpStream::connect(address).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(CLAMD_TIMEOUT)).ok();
+    stream.set_write_timeout(Some(CLAMD_TIMEOUT)).ok();
+    clamd_instream(stream, file)
+}
+
+fn scan_with_clamd(address: &str, path: &Path) -> Result<Option<String>, String> {
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let reply = clamd_reply(address, &mut file)?;
+    // "stream: OK", "stream: Eicar-Signature FOUND" or "... ERROR"
+    let verdict = reply.strip_prefix("stream: ").unwrap_or(&reply);
+    if verdict == "OK" {
+        Ok(None)
+    } else if let Some(signature) = verdict.strip_suffix(" FOUND") {
+        Ok(Some(signature.to_string()))
+    } else {
+        Err(format!("clamd: {}", verdict))
+    }
+}
+
+/// Pipe the file into `clamscan -` in a fresh container without network access
+fn scan_with_container(image: &str, path: &Path) -> Result<Option<String>, String> {
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut child = new_command("docker")
+        .args([
+            "run",
+            "--rm",
+            "-i",
+            "--network",
+            "none",
+            image,
+            "clamscan",
+            "--no-summary",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    // Fed from its own thread so a chatty clamscan can't fill its pipes and stall
+    let mut stdin = child.stdin.take().ok_or("No scanner input")?;
+    let feeder = std::thread::spawn(move || std::io::copy(&mut file, &mut stdin));
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    feeder.join().ok();
+    // clamscan exits 1 when it found something and 2 on errors
+    match output.status.code() {
+        Some(0) => Ok(None),
+        Some(1) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let signature = stdout
+                .lines()
+                .find_map(|line| line.strip_prefix("stdin: ")?.strip_suffix(" FOUND"))
+                .unwrap_or("unknown");
+            Ok(Some(signature.to_string()))
+        }
+        _ => Err(format!(
+            "clamscan: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+fn scan_file(engine: &ScanEngine, path: &Path) -> Result<Option<String>, String> {
+    match engine {
+        ScanEngine::Clamd { address } => scan_with_clamd(address, path),
+        ScanEngine::Container { image } => scan_with_container(image, path),
+    }
+}
+
+/// Move a file out of the share, readable by its owner only
+pub(crate) fn quarantine(
+    server: &ServerInfo,
+    path: &str,
+    host_path: &Path,
+    reason: &str,
+) -> Result<String, String> {
+    let dir = quarantine_dir();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let now = chrono::Local::now();
+    let id = format!("{}-{}", server.name, now.format("%Y%m%d-%H%M%S%.6f"));
+    let size = fs::metadata(host_path).map(|m| m.len()).unwrap_or(0);
+    let target = dir.join(&id);
+    // The share may be on another filesystem than the data directory
+    if fs::rename(host_path, &target).is_err() {
+        fs::copy(host_path, &target).map_err(|e| e.to_string())?;
+        fs::remove_file(host_path).map_err(|e| e.to_string())?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o400)).ok();
+    }
+    let record = QuarantinedFile {
+        id: id.clone(),
+        server: server.name.clone(),
+        path: path.to_string(),
+        host_path: host_path.to_string_lossy().to_string(),
+        reason: reason.to_string(),
+        size,
+        quarantined_at: now.to_rfc3339(),
+    };
+    let json = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    fs::write(dir.join(format!("{}.json", id)), json).map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Servers whose arrivals get scanned, with the engine to use; empty while no
+/// engine is configured
+pub(crate) fn scanned_servers() -> Option<(ScanEngine, Vec<String>)> {
+    let engine = load_app_settings().virus_scan?;
+    let servers = load_credentials()
+        .into_iter()
+        .filter(|(_, creds)| creds.virus_scan)
+        .map(|(name, _)| name)
+        .collect();
+    Some((engine, servers))
+}
+
+/// Scan a file that arrived on `server`, quarantining it if infected. True when it
+/// is clean and the ingest rules may go ahead; a file that couldn't be scanned is
+/// left where it is but not handed on either.
+pub(crate) fn admit_arrival(server: &ServerInfo, path: &str, engine: &ScanEngine) -> bool {
+    let Some(host_path) = host_path_of(server, path) else {
+        record_audit(
+            &server.name,
+            "virus-scan",
+            Some(&format!("{}: not in the share, so not scanned", path)),
+        );
+        return false;
+    };
+    let restored_path = quarantine_dir().join(RESTORED_FILE);
+    let key = host_path.to_string_lossy().to_string();
+    if read_json_file::<HashSet<String>>(&restored_path).contains(&key) {
+        update_json_file(&restored_path, |restored: &mut HashSet<String>| {
+            restored.remove(&key)
+        });
+        return true;
+    }
+    let signature = match scan_file(engine, &host_path) {
+        Ok(None) => return true,
+        Ok(Some(signature)) => signature,
+        Err(e) => {
+            record_audit(
+                &server.name,
+                "virus-scan",
+                Some(&format!("{}: {}", path, e)),
+            );
+            return false;
+        }
+    };
+
+    let result = quarantine(server, path, &host_path, &signature);
+    write_audit_entry(AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        server: server.name.clone(),
+        server_id: None,
+        action: "quarantine".to_string(),
+        success: result.is_ok(),
+        detail: Some(match &result {
+            Ok(_) => format!("{}: {}", path, signature),
+            Err(e) => format!("{}: {}, not moved: {}", path, signature, e),
+        }),
+    });
+    events::publish(events::Event::VirusFound(VirusFound {
+        server: server.name.clone(),
+        path: path.to_string(),
+        signature,
+        quarantine_id: result.as_ref().ok().cloned(),
+        error: result.err(),
+    }));
+    false
+}
+
+#[tauri::command]
+pub fn get_scan_engine() -> Option<ScanEngine> {
+    load_app_settings().virus_scan
+}
+
+/// Set the engine every scanning server uses; `None` stops all scanning
+#[tauri::command]
+pub fn set_scan_engine(engine: Option<ScanEngine>) -> CommandResult {
+    let invalid = match &engine {
+        Some(ScanEngine::Clamd { address }) => address.trim().is_empty(),
+        Some(ScanEngine::Container { image }) => image.trim().is_empty(),
+        None => false,
+    };
+    if invalid {
+        return CommandResult {
+            success: false,
+            error: Some("The scan engine needs an address or image".to_string()),
+        };
+    }
+    update_app_settings(|settings| settings.virus_scan = engine);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// Scan files arriving on the server from now on. They are picked up from its
+/// share root and the directories its ingest rules watch.
+#[tauri::command]
+pub fn set_virus_scan(name: String, enabled: bool) -> CommandResult {
+    let result = update_credentials(|all| all.get_mut(&name).map(|c| c.virus_scan = enabled))
+        .ok_or_else(|| format!("No stored configuration for {}", name));
+    record_audit(
+        &name,
+        if enabled {
+            "enable-virus-scan"
+        } else {
+            "disable-virus-scan"
+        },
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Quarantined files, newest first, optionally of one server only
+#[tauri::command]
+pub fn list_quarantine(server: Option<String>) -> Vec<QuarantinedFile> {
+    let mut files: Vec<QuarantinedFile> = fs::read_dir(quarantine_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+                .filter(|e| e.file_name() != RESTORED_FILE)
+                .filter_map(|e| serde_json::from_str(&fs::read_to_string(e.path()).ok()?).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.retain(|f: &QuarantinedFile| server.as_ref().is_none_or(|s| &f.server == s));
+    files.sort_by(|a, b| b.quarantined_at.cmp(&a.quarantined_at));
+    files
+}
+
+fn find_quarantined(id: &str) -> Result<QuarantinedFile, String> {
+    list_quarantine(None)
+        .into_iter()
+        .find(|f| f.id == id)
+        .ok_or_else(|| format!("No quarantined file {}", id))
+}
+
+#[tauri::command]
+pub fn delete_quarantined(id: String) -> CommandResult {
+    let result = find_quarantined(&id).and_then(|file| {
+        let dir = quarantine_dir();
+        fs::remove_file(dir.join(&file.id)).map_err(|e| e.to_string())?;
+        fs::remove_file(dir.join(format!("{}.json", file.id))).ok();
+        Ok(file)
+    });
+    match result {
+        Ok(file) => {
+            record_audit(&file.server, "delete-quarantined", None);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Put a false positive back where it arrived. The watcher lets it through without
+/// scanning it again.
+#[tauri::command]
+pub fn restore_quarantined(id: String) -> CommandResult {
+    let result = find_quarantined(&id).and_then(|file| {
+        let dir = quarantine_dir();
+        let target = Path::new(&file.host_path);
+        if target.exists() {
+            return Err(format!("{} exists again; move it aside first", file.path));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let source = dir.join(&file.id);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&source, fs::Permissions::from_mode(0o644)).ok();
+        }
+        if fs::rename(&source, target).is_err() {
+            fs::copy(&source, target).map_err(|e| e.to_string())?;
+            fs::remove_file(&source).ok();
+        }
+        fs::remove_file(dir.join(format!("{}.json", file.id))).ok();
+        update_json_file(
+            &dir.join(RESTORED_FILE),
+            |restored: &mut HashSet<String>| restored.insert(file.host_path.clone()),
+        );
+        Ok(file)
+    });
+    match result {
+        Ok(file) => {
+            record_audit(&file.server, "restore-quarantined", None);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
@@ -0,0 +1,157 @@
+//! Extra `docker run` arguments a server can carry. They come from the config form
+//! but also from paired peers and the team sync repo, so only flags whose effect is
+//! understood get through: environment, labels, ulimits, memory/CPU limits, DNS and
+//! extra hosts. Anything else, including positional arguments, is refused.
+
+/// Allowed `docker run` flags with their short form; every one of them takes a value
+const ALLOWED_DOCKER_ARGS: &[(&str, Option<char>)] = &[
+    ("--env", Some('e')),
+    ("--label", Some('l')),
+    ("--ulimit", None),
+    ("--memory", Some('m')),
+    ("--memory-reservation", None),
+    ("--memory-swap", None),
+    ("--cpus", None),
+    ("--cpu-shares", Some('c')),
+    ("--cpuset-cpus", None),
+    ("--pids-limit", None),
+    ("--dns", None),
+    ("--dns-search", None),
+    ("--dns-option", None),
+    ("--add-host", None),
+];
+
+/// Refuse extra `docker run` arguments that aren't on the allowlist or that would
+/// override what dsftp sets itself
+pub(crate) fn check_extra_docker_args(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, value) = split_flag(arg)?;
+        let value = match value {
+            Some(value) => value,
+            None => args
+                .next()
+                .ok_or_else(|| format!("docker run argument {} needs a value", flag))?,
+        };
+        check_value(flag, value)?;
+    }
+    Ok(())
+}
+
+/// The long name of the flag `arg` sets and its value, if given in the same argument
+/// ("--env=A=1", "-eA=1", "-e=A=1")
+fn split_flag(arg: &str) -> Result<(&'static str, Option<&str>), String> {
+    let not_allowed = || format!("docker run argument not allowed: {}", arg);
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+        return ALLOWED_DOCKER_ARGS
+            .iter()
+            .find(|(flag, _)| flag[2..] == *name)
+            .map(|(flag, _)| (*flag, value))
+            .ok_or_else(not_allowed);
+    }
+    // None of the allowed short flags is boolean, so whatever follows the letter is
+    // its value; a cluster like "-itv" is refused by its first letter
+    let mut chars = arg.strip_prefix('-').ok_or_else(not_allowed)?.chars();
+    let letter = chars.next().ok_or_else(not_allowed)?;
+    let (flag, _) = ALLOWED_DOCKER_ARGS
+        .iter()
+        .find(|(_, short)| *short == Some(letter))
+        .ok_or_else(not_allowed)?;
+    let rest = chars.as_str();
+    let value = rest.strip_prefix('=').unwrap_or(rest);
+    Ok((flag, (!rest.is_empty()).then_some(value)))
+}
+
+/// dsftp's own environment and labels identify the server; they can't be overridden
+fn check_value(flag: &str, value: &str) -> Result<(), String> {
+    match flag {
+        "--env" if value.starts_with("DSFTP_") => {
+            Err("DSFTP_ environment variables are set by dsftp".to_string())
+        }
+        "--label" if value.starts_with("dsftp.") => {
+            Err("dsftp.* labels are set by dsftp".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_extra_docker_args;
+
+    fn check(args: &[&str]) -> Result<(), String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        check_extra_docker_args(&args)
+    }
+
+    #[test]
+    fn allows_modelled_flags_in_every_form() {
+        assert!(check(&[
+            "-e",
+            "TZ=UTC",
+            "--env",
+            "A=1",
+            "--env=B=2",
+            "-eC=3",
+            "-e=D=4"
+        ])
+        .is_ok());
+        assert!(check(&["--memory=512m", "-m", "1g", "--cpus", "1.5", "-c512"]).is_ok());
+        assert!(check(&["--ulimit", "nofile=1024:2048", "--dns=1.1.1.1"]).is_ok());
+        assert!(check(&["--add-host", "db:10.0.0.2", "-l", "team=ops"]).is_ok());
+        assert!(check(&[]).is_ok());
+    }
+
+    #[test]
+    fn refuses_flags_off_the_allowlist() {
+        for args in [
+            &["--privileged"][..],
+            &["--device-cgroup-rule=c 1:3 rwm"],
+            &["--network=container:other"],
+            &["--network", "host"],
+            &["--runtime", "runc"],
+            &["--tmpfs", "/run"],
+            &["--volume-driver=local"],
+            &["--sysctl", "net.ipv4.ip_forward=1"],
+            &["-v", "/:/host"],
+            &["--env-file", "/etc/shadow"],
+        ] {
+            assert!(check(args).is_err(), "{:?} was allowed", args);
+        }
+    }
+
+    #[test]
+    fn refuses_short_flag_clusters() {
+        assert!(check(&["-itv", "/:/host"]).is_err());
+        assert!(check(&["-dp2222:22"]).is_err());
+        assert!(check(&["-v/:/host"]).is_err());
+        assert!(check(&["-"]).is_err());
+    }
+
+    #[test]
+    fn refuses_equals_forms_of_denied_flags() {
+        assert!(check(&["--volume=/:/host"]).is_err());
+        assert!(check(&["--mount=type=bind,src=/,dst=/host"]).is_err());
+        assert!(check(&["--cap-add=SYS_ADMIN"]).is_err());
+        assert!(check(&["--envx=A=1"]).is_err());
+    }
+
+    #[test]
+    fn refuses_positionals_and_missing_values() {
+        assert!(check(&["alpine"]).is_err());
+        assert!(check(&["-e", "A=1", "sh"]).is_err());
+        assert!(check(&["--", "sh"]).is_err());
+        assert!(check(&["--memory"]).is_err());
+    }
+
+    #[test]
+    fn refuses_overriding_dsftp_identity() {
+        assert!(check(&["-e", "DSFTP_SERVER_ID=other"]).is_err());
+        assert!(check(&["--env=DSFTP_SERVER_ID=other"]).is_err());
+        assert!(check(&["--label", "dsftp.managed=true"]).is_err());
+    }
+}
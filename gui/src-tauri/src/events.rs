@@ -0,0 +1,179 @@
+//! Typed events the watchers and alerting publish, fanned out over a broadcast
+//! channel to whoever subscribes: the bridge to the frontend, the history store and
+//! the daemon's relay to connected GUIs. A new integration is one more subscriber
+//! rather than another call at every place an event happens.
+//!
+//! Progress of a single command (pulls, creates, transfers, listings) is not routed
+//! here; it goes straight to the window that asked for it.
+
+use super::antivirus::VirusFound;
+use super::pending::ReplayedOperation;
+use super::{
+    record_history, server_id, AutoHealEvent, BindingIssue, ConfigChangeEvent,
+    CredentialsChangeEvent, IdleStop, IngestEvent, LowDiskSpace, NetworkChangeEvent,
+    PasswordRotation, PolicyViolation, ResourceAlert,
+};
+use serde::Serialize;
+use std::sync::LazyLock;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+/// Events a slow subscriber may fall behind by before it starts missing some
+const BUS_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    ServerDown(AutoHealEvent),
+    AutoHeal(AutoHealEvent),
+    NetworkChanged(NetworkChangeEvent),
+    FileIngested(IngestEvent),
+    PasswordRotated(PasswordRotation),
+    LowDiskSpace(LowDiskSpace),
+    AlertRaised(ResourceAlert),
+    AlertCleared(ResourceAlert),
+    CredentialsChanged(CredentialsChangeEvent),
+    ConfigExternallyChanged(ConfigChangeEvent),
+    /// Found by the startup check of bind addresses
+    BindingIssue(BindingIssue),
+    IdleStopped(IdleStop),
+    VirusFound(VirusFound),
+    PolicyViolation(PolicyViolation),
+    /// A change queued while Docker was unreachable was replayed or held back
+    OperationReplayed(ReplayedOperation),
+}
+
+impl Event {
+    /// Name of the Tauri event the frontend listens for
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::ServerDown(_) => "server-down",
+            Event::AutoHeal(_) => "auto-heal",
+            Event::NetworkChanged(_) => "network-changed",
+            Event::FileIngested(_) => "file-ingested",
+            Event::PasswordRotated(_) => "password-rotated",
+            Event::LowDiskSpace(_) => "low-disk-space",
+            Event::AlertRaised(_) => "alert-raised",
+            Event::AlertCleared(_) => "alert-cleared",
+            Event::CredentialsChanged(_) => "credentials-changed",
+            Event::ConfigExternallyChanged(_) => "config-externally-changed",
+            Event::BindingIssue(_) => "binding-issue",
+            Event::IdleStopped(_) => "idle-stopped",
+            Event::VirusFound(_) => "virus-found",
+            Event::PolicyViolation(_) => "policy-violation",
+            Event::OperationReplayed(_) => "operation-replayed",
+        }
+    }
+
+    pub fn server(&self) -> Option<&str> {
+        match self {
+            Event::ServerDown(e) | Event::AutoHeal(e) => Some(&e.server),
+            Event::FileIngested(e) => Some(&e.server),
+            Event::PasswordRotated(e) => Some(&e.server),
+            Event::LowDiskSpace(e) => Some(&e.server),
+            Event::AlertRaised(e) | Event::AlertCleared(e) => Some(&e.server),
+            Event::BindingIssue(e) => Some(&e.server),
+            Event::IdleStopped(e) => Some(&e.server),
+            Event::VirusFound(e) => Some(&e.server),
+            Event::PolicyViolation(e) => Some(&e.server),
+            Event::OperationReplayed(e) => Some(&e.server),
+            Event::NetworkChanged(_)
+            | Event::CredentialsChanged(_)
+            | Event::ConfigExternallyChanged(_) => None,
+        }
+    }
+
+    pub fn payload(&self) -> serde_json::Value {
+        fn json(value: &impl Serialize) -> serde_json::Value {
+            serde_json::to_value(value).unwrap_or_default()
+        }
+        let mut payload = match self {
+            Event::ServerDown(e) | Event::AutoHeal(e) => json(e),
+            Event::NetworkChanged(e) => json(e),
+            Event::FileIngested(e) => json(e),
+            Event::PasswordRotated(e) => json(e),
+            Event::LowDiskSpace(e) => json(e),
+            Event::AlertRaised(e) | Event::AlertCleared(e) => json(e),
+            Event::CredentialsChanged(e) => json(e),
+            Event::ConfigExternallyChanged(e) => json(e),
+            Event::BindingIssue(e) => json(e),
+            Event::IdleStopped(e) => json(e),
+            Event::VirusFound(e) => json(e),
+            Event::PolicyViolation(e) => json(e),
+            Event::OperationReplayed(e) => json(e),
+        };
+        // Lets subscribers tell a renamed server's events apart from a new one's
+        if let (Some(id), Some(fields)) =
+            (self.server().and_then(server_id), payload.as_object_mut())
+        {
+            fields.insert("server_id".to_string(), id.into());
+        }
+        payload
+    }
+
+    /// Kind the history store keeps this event under, if it keeps it
+    fn history_kind(&self) -> Option<&'static str> {
+        match self {
+            Event::NetworkChanged(_) => Some("network"),
+            Event::AlertRaised(_) => Some("alert-raised"),
+            Event::AlertCleared(_) => Some("alert-cleared"),
+            Event::VirusFound(_) => Some("virus-found"),
+            _ => None,
+        }
+    }
+
+    /// Events of the server watcher, which a daemon passes on to connected GUIs.
+    /// Alerts aren't among them: a relaying GUI evaluates its own.
+    pub fn from_watcher(&self) -> bool {
+        matches!(
+            self,
+            Event::ServerDown(_)
+                | Event::AutoHeal(_)
+                | Event::NetworkChanged(_)
+                | Event::FileIngested(_)
+                | Event::PasswordRotated(_)
+                | Event::LowDiskSpace(_)
+                | Event::IdleStopped(_)
+                | Event::VirusFound(_)
+                | Event::PolicyViolation(_)
+                | Event::OperationReplayed(_)
+        )
+    }
+}
+
+static BUS: LazyLock<broadcast::Sender<Event>> =
+    LazyLock::new(|| broadcast::channel(BUS_CAPACITY).0);
+
+/// Hand an event to every subscriber; dropped if there are none
+pub fn publish(event: Event) {
+    let _ = BUS.send(event);
+}
+
+/// Call `handle` with every event published from now on, on a thread of its own
+pub fn subscribe(name: &'static str, mut handle: impl FnMut(&Event) + Send + 'static) {
+    let mut events = BUS.subscribe();
+    std::thread::spawn(move || loop {
+        match events.blocking_recv() {
+            Ok(event) => handle(&event),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                log::warn!("{} fell behind and missed {} events", name, missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    });
+}
+
+/// Forward every event to the frontend under its Tauri event name
+pub fn bridge_to_frontend(app: AppHandle) {
+    subscribe("frontend bridge", move |event| {
+        let _ = app.emit(event.name(), event.payload());
+    });
+}
+
+/// Keep the events the history store tracks
+pub fn record_to_history() {
+    subscribe("history", |event| {
+        if let Some(kind) = event.history_kind() {
+            record_history(event.server(), kind, event.payload());
+        }
+    });
+}
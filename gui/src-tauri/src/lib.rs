@@ -1,455 +1,16877 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use subtle::ConstantTimeEq;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use ts_rs::TS;
+
+mod antivirus;
+mod docker_args;
+mod events;
+mod notify;
+mod pending;
+mod schedule;
+mod tiering;
+mod tunnels;
 
 const SFTP_IMAGE: &str = "atmoz/sftp";
 const CONFIG_FILE: &str = "sftp-servers.json";
+/// Replaces the per-user config directory, e.g. to keep test runs off real servers
+const CONFIG_DIR_ENV: &str = "DSFTP_CONFIG_DIR";
+/// Replaces the data directory; when unset but `DSFTP_CONFIG_DIR` is, data stays
+/// next to the config
+const DATA_DIR_ENV: &str = "DSFTP_DATA_DIR";
 const NETWORK_CONFIG_FILE: &str = "network-config.json";
+const APP_SETTINGS_FILE: &str = "app-settings.json";
+const CONFIG_LOCK_FILE: &str = ".config.lock";
+const AUDIT_LOG_FILE: &str = "audit.log";
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+/// Frontend state kept across restarts (layouts, last paths, filters), by scope
+const UI_STATE_FILE: &str = "ui-state.json";
+/// Largest value a single scope may hold, as JSON
+const MAX_UI_STATE_BYTES: usize = 64 * 1024;
+const MAX_UI_STATE_TOTAL_BYTES: usize = 1024 * 1024;
+const MAX_UI_SCOPE_LEN: usize = 128;
+const CLEANUP_RULES_FILE: &str = "cleanup-rules.json";
+const INGEST_RULES_FILE: &str = "ingest-rules.json";
+/// Which files of each server move to a backup target once they go untouched
+const TIERING_RULES_FILE: &str = "tiering-rules.json";
+const HISTORY_FILE: &str = "history.jsonl";
+const API_TOKENS_FILE: &str = "api-tokens.json";
+/// Role of each API token, by token id
+const API_POLICY_FILE: &str = "api-policy.json";
+const SHARE_LINKS_FILE: &str = "share-links.json";
+const SNAPSHOT_SHARES_FILE: &str = "snapshot-shares.json";
+/// Frozen copies served by snapshot shares, one subdirectory per server
+const SNAPSHOTS_DIR: &str = "snapshots";
+/// Infected arrivals moved out of their share, with a JSON description of each
+const QUARANTINE_DIR: &str = "quarantine";
+/// Changes made while Docker was unreachable, waiting to be replayed
+const PENDING_OPERATIONS_FILE: &str = "pending-operations.json";
+/// Files tiering moved off each server's share, one JSON file per server
+const TIERED_DIR: &str = "tiered";
+/// Host-folder file lists `diff_volume` compares against, one subdirectory per server
+const VOLUME_INDEX_DIR: &str = "volume-index";
+/// How often the watcher records each server's file list
+const VOLUME_INDEX_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// The watcher's file lists kept per server, a week's worth; named ones stay
+const MAX_AUTO_VOLUME_INDEXES: usize = 28;
+const AUTO_INDEX_PREFIX: &str = "auto-";
+const NAMED_INDEX_PREFIX: &str = "named-";
+const INDEX_STAMP_FORMAT: &str = "%Y%m%dT%H%M%S%z";
+/// Entries each of `diff_volume`'s lists is cut to
+const MAX_DIFF_ENTRIES: usize = 10_000;
+/// Container logs copied out of Docker, one JSON-lines file per server, kept after
+/// the container is gone
+const CONTAINER_LOGS_DIR: &str = "container-logs";
+/// How often the watcher copies new log lines
+const LOG_PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+/// Lines `get_persisted_logs` returns when not told how many
+const DEFAULT_PERSISTED_LOG_LINES: usize = 1000;
+const MAX_LOG_RETENTION_DAYS: u32 = 3650;
+/// Digests and scan results of pulled images
+const IMAGE_DIGESTS_FILE: &str = "image-digests.json";
+/// Where a running daemon publishes its IPC port and token
+const DAEMON_FILE: &str = "daemon.json";
+/// Host directory of startup scripts mounted into atmoz's /etc/sftp.d
+const SFTP_HOOKS_DIR: &str = "sftp.d";
+/// Host directory of user init scripts, one subdirectory per server
+const INIT_SCRIPTS_DIR: &str = "init.d";
+/// Public keys of imported users, one subdirectory per server
+const USER_KEYS_DIR: &str = "user-keys";
+/// Empty directory and file mounted over paths a server's exclude patterns hide
+const EXCLUDE_MASKS_DIR: &str = "exclude-masks";
+/// Generated Dockerfiles of servers with an image build, one subdirectory per server
+const IMAGE_BUILDS_DIR: &str = "image-builds";
+/// Repository of the images built for them, tagged per server
+const CUSTOM_IMAGE_REPO: &str = "dsftp-custom";
+/// Label holding the build spec an image was made from, compared to tell whether
+/// it needs rebuilding
+const IMAGE_BUILD_LABEL: &str = "dsftp.image-build";
+/// Container label holding the server's id, which survives renames and recreation
+const SERVER_ID_LABEL: &str = "dsftp.server-id";
+const EXCLUDE_MASK_EMPTY_DIR: &str = "empty";
+const EXCLUDE_MASK_EMPTY_FILE: &str = "empty-file";
+/// Mounts are fixed per container, so a pattern matching a huge tree is capped
+const MAX_EXCLUDE_MASKS: usize = 256;
+/// Largest file `read_file_text` opens for editing
+const MAX_TEXT_EDIT_BYTES: u64 = 1024 * 1024;
+const TEAM_CONFIG_DIR: &str = "team-config";
+const TEAM_CONFIG_FILE: &str = "servers.json";
+const BACKUP_DIR: &str = "backups";
+/// Servers removed with `archive`, one subdirectory each
+const REMOVED_DIR: &str = "removed";
+const REMOVED_SERVER_FILE: &str = "server.json";
+const REMOVED_VOLUME_FILE: &str = "volume.tar.gz";
+const PENDING_BACKUPS_FILE: &str = "pending.json";
+const SECRETS_FILE: &str = "secrets.enc.json";
+const SECRETS_KEY_FILE: &str = "secrets.key";
+const KEYCHAIN_SERVICE: &str = "dsftp";
+/// Secret store entry of the SMTP password; no container name starts with '@'
+const SMTP_SECRET: &str = "@smtp";
+/// Set (to anything but "0") to answer `docker` commands from the in-memory mock runtime
+const MOCK_RUNTIME_ENV: &str = "DSFTP_MOCK";
+/// Host directory holding the built-in SSH certificate authority
+const SSH_CA_DIR: &str = "ssh-ca";
+/// Makes sshd re-read its config; atmoz runs sshd as PID 1 when the pid file is
+/// missing
+const SSHD_RELOAD_SCRIPT: &str = r#"kill -HUP "$(cat /var/run/sshd.pid 2>/dev/null || echo 1)""#;
+/// Run by atmoz before sshd starts on servers created while the CA exists
+const SSH_CA_TRUST_SCRIPT: &str = r#"#!/bin/sh
+# Written by dsftp
+grep -q '^TrustedUserCAKeys' /etc/ssh/sshd_config ||
+    echo 'TrustedUserCAKeys /etc/ssh/dsftp_ca.pub' >> /etc/ssh/sshd_config
+# A certificate only opens the account it names on the server it was issued for
+grep -q '^AuthorizedPrincipalsFile' /etc/ssh/sshd_config ||
+    echo 'AuthorizedPrincipalsFile /etc/ssh/dsftp_principals/%u' >> /etc/ssh/sshd_config
+mkdir -p /etc/ssh/dsftp_principals
+rm -f /etc/ssh/dsftp_principals/*
+[ -n "$DSFTP_SERVER_ID" ] || exit 0
+# Only the SFTP accounts: the ones atmoz created from users.conf, its arguments and
+# SFTP_USERS, plus those dsftp added to the running container
+cat /var/run/sftp/users.conf /etc/sftp/users.conf /etc/ssh/dsftp_users 2>/dev/null |
+grep -v '^[[:space:]]*#' | cut -d: -f1 | sort -u |
+while read -r user; do
+    case "$user" in ""|*/*|.*) continue ;; esac
+    id "$user" >/dev/null 2>&1 || continue
+    echo "$user@$DSFTP_SERVER_ID" > "/etc/ssh/dsftp_principals/$user"
+done
+"#;
+/// Container environment variable holding the server id, for the trust hook
+const SERVER_ID_ENV: &str = "DSFTP_SERVER_ID";
+/// Host directory of SSH host keys and users.conf captured from containers, one
+/// subdirectory per server
+const HOST_IDENTITY_DIR: &str = "host-keys";
+/// Host keys atmoz generates on first start
+const SSH_HOST_KEY_FILES: &[&str] = &[
+    "ssh_host_ed25519_key",
+    "ssh_host_ed25519_key.pub",
+    "ssh_host_rsa_key",
+    "ssh_host_rsa_key.pub",
+];
+/// What dsftp accumulates rather than what the user configures. These live in the
+/// data directory and move with it; older versions kept them in the config directory.
+const DATA_ENTRIES: &[&str] = &[
+    AUDIT_LOG_FILE,
+    HISTORY_FILE,
+    IMAGE_DIGESTS_FILE,
+    BACKUP_DIR,
+    REMOVED_DIR,
+    HOST_IDENTITY_DIR,
+    SNAPSHOTS_DIR,
+    VOLUME_INDEX_DIR,
+    CONTAINER_LOGS_DIR,
+    QUARANTINE_DIR,
+    TIERED_DIR,
+    PENDING_OPERATIONS_FILE,
+];
+/// Data entries bind-mounted into containers, which get a link at their old place
+/// when moved so existing servers still find them
+const MOUNTED_DATA_ENTRIES: &[&str] = &[HOST_IDENTITY_DIR, SNAPSHOTS_DIR];
+/// Run by atmoz before sshd starts: put back the host keys a server had before it was
+/// recreated. Copied rather than mounted in place, as sshd rejects keys whose mode a
+/// bind mount may have widened.
+const HOST_KEYS_RESTORE_SCRIPT: &str = r#"#!/bin/sh
+# Written by dsftp
+cp /etc/dsftp/host-keys/ssh_host_* /etc/ssh/ 2>/dev/null || exit 0
+chmod 600 /etc/ssh/ssh_host_*_key
+"#;
+const ANONYMOUS_USER: &str = "anonymous";
+/// Shared password for anonymous servers; atmoz locks accounts with an empty one
+const ANONYMOUS_PASSWORD: &str = "anonymous";
+/// Stands in for a password in errors, logs and audit entries
+const REDACTED: &str = "********";
+/// Shorter passwords match ordinary text too easily to be scrubbed from it
+const MIN_REDACTED_LEN: usize = 4;
+/// Run by atmoz before sshd starts; `{flags}` becomes the internal-sftp options
+const SFTP_HOOK_SCRIPT: &str = r#"#!/bin/sh
+# Written by dsftp
+sed -i -E 's/^(Subsystem sftp|ForceCommand) internal-sftp.*$/\1 internal-sftp{flags}/' \
+    /etc/ssh/sshd_config
+"#;
+/// Appended to the hook for anonymous servers: close the forwarding features the
+/// stock atmoz config leaves on
+const ANONYMOUS_HARDENING_SCRIPT: &str = r#"grep -q '^# dsftp anonymous$' /etc/ssh/sshd_config || cat >> /etc/ssh/sshd_config <<'EOF'
+# dsftp anonymous
+AllowAgentForwarding no
+PermitTunnel no
+PermitUserRC no
+EOF
+"#;
+/// Compact the history file once it grows past this size
+const HISTORY_MAX_BYTES: u64 = 4 * 1024 * 1024;
+const HISTORY_KEEP_RECORDS: usize = 10_000;
+const MAX_RECENT_PATHS: usize = 20;
+const SEARCH_RESULT_LIMIT: usize = 20;
+/// Stopped this long, a server is offered for archiving
+const SUGGEST_ARCHIVE_AFTER_DAYS: u64 = 60;
+/// Manual starts within `SUGGEST_WINDOW_DAYS` that suggest a restart policy
+const SUGGEST_FREQUENT_STARTS: usize = 5;
+const SUGGEST_WINDOW_DAYS: u64 = 14;
+const MAX_LIST_DEPTH: u32 = 32;
+/// Paths hashed per `docker exec`, well under the container's argument limit
+const HASH_BATCH_SIZE: usize = 200;
+const DEFAULT_LARGEST_FILES: usize = 50;
+const LISTING_CHUNK_SIZE: usize = 1000;
+const LISTING_CHUNK_INTERVAL: Duration = Duration::from_millis(200);
+const TRANSFER_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Weight of the newest sample in a transfer's moving average of throughput
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+const DASHBOARD_RECENT_ERRORS: usize = 10;
+const DETAIL_RECENT_EVENTS: usize = 20;
+/// S3 needs parts of at least 5 MiB (except the last)
+const BACKUP_PART_SIZE: usize = 16 * 1024 * 1024;
+const BACKUP_PART_RETRIES: u32 = 4;
+/// Most commands finish in well under a second; this only catches a hung daemon
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+/// For `docker exec` scripts that walk whole trees, git talking to remotes and trivy
+/// fetching its vulnerability database. Commands that copy or archive a whole volume,
+/// or pull an image, have no time limit; they take as long as the data does.
+const LONG_COMMAND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const DAEMON_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+const SERVER_WATCH_INTERVAL: Duration = Duration::from_secs(10);
+/// Calls per second any one command may make; the frontend polls some commands,
+/// but never this fast
+const DEFAULT_COMMAND_RATE_LIMIT: u32 = 50;
+/// Tighter limits for commands that start heavy work
+const COMMAND_RATE_LIMITS: &[(&str, u32)] = &[
+    ("create_server", 2),
+    ("pull_image", 2),
+    ("backup_server", 2),
+    ("verify_image", 2),
+    ("cleanup_docker_artifacts", 2),
+];
+const MAX_COMMAND_ARGS_BYTES: usize = 16 * 1024 * 1024;
+/// How long a directory listing is reused; changes made through dsftp and files
+/// the ingest watcher sees arrive drop it sooner
+const LISTING_CACHE_TTL: Duration = Duration::from_secs(15);
+const LISTING_CACHE_ENTRIES: usize = 256;
+/// Handlers blocking the IPC thread this long are logged
+const SLOW_COMMAND_THRESHOLD: Duration = Duration::from_millis(500);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Paths removed per `docker exec rm`
+const CLEANUP_BATCH_SIZE: usize = 200;
+/// Landing pages listen this many ports above the server they describe
+const LANDING_PAGE_PORT_OFFSET: u16 = 1;
+/// Container paths atmoz/sftp rewrites on every start, left out of diffs
+const EXPECTED_CONTAINER_CHANGES: &[&str] = &[
+    "/etc/passwd",
+    "/etc/passwd-",
+    "/etc/group",
+    "/etc/group-",
+    "/etc/shadow",
+    "/etc/shadow-",
+    "/etc/gshadow",
+    "/etc/gshadow-",
+    "/etc/ssh",
+    "/etc/sftp",
+    "/run",
+    "/var/run",
+    "/tmp",
+    "/var/log",
+];
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// mDNS service type other dsftp instances browse for
+const PAIRING_SERVICE: &str = "_dsftp._tcp.local.";
+/// How long discovery listens for advertisements
+const PAIRING_BROWSE_TIME: Duration = Duration::from_secs(3);
+/// Wrong codes tolerated before the pairing offer is withdrawn
+const PAIRING_MAX_ATTEMPTS: u32 = 5;
+const PAIRING_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// A relay's ssh counts as connected once it has stayed up this long, since
+/// ExitOnForwardFailure makes it quit early when the forward is refused
+const RELAY_SETTLE_TIME: Duration = Duration::from_secs(5);
+/// Pause before a dropped relay is reconnected
+const RELAY_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+const GENERATED_PASSWORD_LEN: usize = 20;
+const MIN_SELF_SERVICE_PASSWORD_LEN: usize = 8;
+/// Wrong current passwords the self-service form takes for a server before it
+/// locks for `PASSWORD_CHANGE_LOCKOUT`
+const MAX_PASSWORD_CHANGE_FAILURES: u32 = 5;
+const PASSWORD_CHANGE_LOCKOUT: Duration = Duration::from_secs(15 * 60);
+/// Largest form body the self-service page reads
+const MAX_FORM_BYTES: u64 = 4096;
+/// A year; longer maintenance is better done by stopping the server
+const MAX_MAINTENANCE_MINUTES: u64 = 366 * 24 * 60;
+/// USER_HZ, which Linux fixes at 100 on every architecture Docker runs on
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+const DEFAULT_CERTIFICATE_TTL_MINUTES: u32 = 60;
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often an on-demand listener checks whether it was released
+const ON_DEMAND_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Memory a new server is assumed to need: sshd and a few sessions
+const SERVER_MEMORY_ESTIMATE: u64 = 64 * 1024 * 1024;
+/// Share of Docker's memory in use past which the host counts as overcommitted
+const MEMORY_COMMIT_LIMIT: f64 = 0.9;
+/// Running containers per CPU past which a new server is likely to be starved
+const CONTAINERS_PER_CPU: usize = 10;
+const CAPACITY_CACHE_TTL: Duration = Duration::from_secs(30);
+/// A running server not answering SSH yet counts as starting for this long
+const STARTING_GRACE: Duration = Duration::from_secs(30);
+/// How long a batch start waits for each container to come up before giving up
+const DEPENDENCY_READY_TIMEOUT: Duration = Duration::from_secs(60);
+const DEPENDENCY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Servers stopped on purpose through dsftp; auto-heal leaves them alone
+static MANUAL_STOPS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Streaming listings the frontend asked to stop
+static CANCELLED_LISTINGS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Containers of the simulated runtime used when DSFTP_MOCK is set
+static MOCK_CONTAINERS: LazyLock<Mutex<Vec<MockContainer>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// Operations the frontend asked to stop
+static CANCELLED_OPERATIONS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+thread_local! {
+    /// Operation the current thread is working for; see `run_operation`
+    static CURRENT_OPERATION: RefCell<Option<OperationState>> = const { RefCell::new(None) };
+}
+
+/// Modification times of config files written by this process
+static SELF_WRITES: LazyLock<Mutex<HashMap<PathBuf, SystemTime>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Shared by every thread, including the daemon's and the HTTP API's, none of
+/// which have an `AppHandle` to reach managed state through
+static COMMAND_METRICS: LazyLock<Mutex<HashMap<String, CommandMetrics>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static COMMAND_WINDOWS: LazyLock<Mutex<HashMap<String, CommandWindow>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Latest progress of each running transfer, by transfer id
+static ACTIVE_TRANSFERS: LazyLock<Mutex<HashMap<String, TransferProgress>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static LISTING_CACHE: LazyLock<Mutex<HashMap<ListingKey, CachedListing>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static CREDENTIALS: LazyLock<CredentialStore> = LazyLock::new(|| CredentialStore {
+    cache: RwLock::new(None),
+    dirty: Mutex::new(BTreeSet::new()),
+    generation: AtomicU64::new(0),
+});
+
+/// Built from the stored passwords, for the `CredentialStore::generation` it was
+/// built at
+static REDACTOR: LazyLock<Mutex<Option<(u64, Arc<Redactor>)>>> = LazyLock::new(|| Mutex::new(None));
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StoredCredentials {
+    /// Stable identity of the server, also on its container as `SERVER_ID_LABEL`
+    #[serde(default)]
+    pub id: Option<String>,
     pub username: String,
     pub password: String,
     pub host_path: String,
     pub container_path: String,
     #[serde(default)]
     pub bind_ip: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Containers (managed or not) that must be up before this server starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub extra_docker_args: Vec<String>,
+    #[serde(default)]
+    pub anonymous_read_only: bool,
+    #[serde(default)]
+    pub umask: Option<String>,
+    #[serde(default)]
+    pub file_mode: Option<String>,
+    #[serde(default)]
+    pub dir_mode: Option<String>,
+    /// Paths hidden from the share, written like .gitignore entries
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Rotate the password automatically this often
+    #[serde(default)]
+    pub rotate_password_days: Option<u32>,
+    #[serde(default)]
+    pub password_rotated_at: Option<String>,
+    /// Users may change their own password at `/password/<server>` on the HTTP API
+    #[serde(default)]
+    pub password_self_service: bool,
+    /// Closed to new logins by `enter_maintenance`
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceWindow>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub banner: Option<String>,
+    #[serde(default)]
+    pub image_build: Option<ImageBuild>,
+    /// Scan arriving files with the app's scan engine
+    #[serde(default)]
+    pub virus_scan: bool,
+    #[serde(default)]
+    pub file_policy: Option<FilePolicy>,
+    /// Tunnel through an SSH jump host for clients that can't reach this machine
+    #[serde(default)]
+    pub relay: Option<RelayConfig>,
+    #[serde(default)]
+    pub tunnel: Option<tunnels::TunnelConfig>,
+    /// Started by the first connection while stopped, see `set_on_demand`
+    #[serde(default)]
+    pub on_demand: Option<OnDemandConfig>,
+    /// Stopped after this many minutes without sessions or transfers
+    #[serde(default)]
+    pub idle_stop_minutes: Option<u32>,
+    /// SHA-256 of the bearer token for `PUT /ingest/<server>/...` on the HTTP API
+    #[serde(default)]
+    pub ingest_token_sha256: Option<String>,
+    /// The token itself, as stored before only its digest was kept; replaced by
+    /// `migrate_token_digests`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingest_token: Option<String>,
+    /// Where this server's notifications go; the desktop when unset
+    #[serde(default)]
+    pub notification_channels: Option<Vec<notify::NotificationChannel>>,
+    /// Accounts added with `import_users`; their passwords stay in this file
+    /// whatever the secret store
+    #[serde(default)]
+    pub users: Vec<SftpUser>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct NetworkConfig {
     pub preferred_interface: Option<String>,
     pub preferred_ip: Option<String>,
+    /// Name substrings (case-insensitive) that mark an interface as VPN, on top of
+    /// the built-in list
+    #[serde(default)]
+    pub vpn_patterns: Vec<String>,
+    /// Manual VPN classification per interface name; wins over every pattern
+    #[serde(default)]
+    pub vpn_overrides: HashMap<String, bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NetworkInterface {
-    pub name: String,
-    pub address: String,
-    pub is_vpn: bool,
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, TS)]
+#[ts(export)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NetworkInfo {
-    pub current_ip: String,
-    pub current_interface: Option<String>,
-    pub is_vpn: bool,
-    pub preferred_ip: Option<String>,
-    pub preferred_interface: Option<String>,
-    pub interfaces: Vec<NetworkInterface>,
+impl PortRange {
+    fn contains(&self, port: u16) -> bool {
+        port >= self.start && port <= self.end
+    }
 }
 
-fn get_config_path() -> PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("sftp-manager");
-    fs::create_dir_all(&config_dir).ok();
-    config_dir.join(CONFIG_FILE)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AppSettings {
+    /// Ports servers may be published on; `None` allows any port
+    #[serde(default)]
+    pub port_range: Option<PortRange>,
+    #[serde(default)]
+    pub secret_store: SecretStoreKind,
+    /// Team config repository last synced with `sync_config_repo`
+    #[serde(default)]
+    pub sync_repo: Option<String>,
+    /// Named destinations for `backup_server`
+    #[serde(default)]
+    pub backup_targets: HashMap<String, BackupTarget>,
+    /// BCP 47 tag (e.g. "ko-KR") used for the `*_display` strings; English when unset
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub auto_heal: AutoHealPolicy,
+    #[serde(default)]
+    pub alerts: AlertThresholds,
+    /// Embedded HTTP API for CI uploads and share links; off when unset
+    #[serde(default)]
+    pub api: Option<ApiSettings>,
+    #[serde(default)]
+    pub disk_guard: DiskGuard,
+    /// Ids of suggestions the user doesn't want to see again
+    #[serde(default)]
+    pub dismissed_suggestions: Vec<String>,
+    /// Data directory moved off the platform default with `set_data_dir`
+    #[serde(default)]
+    pub data_dir: Option<String>,
+    /// Outgoing mail server for email notifications
+    #[serde(default)]
+    pub smtp: Option<notify::SmtpSettings>,
+    /// Keep managed servers' container logs in the data directory; off when unset
+    #[serde(default)]
+    pub log_retention: Option<LogRetention>,
+    /// ClamAV used for servers with `virus_scan` on; nothing is scanned when unset
+    #[serde(default)]
+    pub virus_scan: Option<antivirus::ScanEngine>,
 }
 
-fn load_credentials() -> HashMap<String, StoredCredentials> {
-    let path = get_config_path();
-    if let Ok(content) = fs::read_to_string(&path) {
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        HashMap::new()
-    }
+/// How long persisted container logs are kept
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct LogRetention {
+    pub days: u32,
+    /// Per server; the oldest lines go first past it
+    #[ts(type = "number")]
+    pub max_mb: u64,
 }
 
-fn save_credentials(creds: &HashMap<String, StoredCredentials>) {
-    let path = get_config_path();
-    if let Ok(content) = serde_json::to_string_pretty(creds) {
-        fs::write(path, content).ok();
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            max_mb: 50,
+        }
     }
 }
 
-fn get_network_config_path() -> PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("sftp-manager");
-    fs::create_dir_all(&config_dir).ok();
-    config_dir.join(NETWORK_CONFIG_FILE)
+/// Free space kept on every filesystem dsftp writes to
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct DiskGuard {
+    /// Uploads and backups that would leave less than this free are refused, and
+    /// shares below it raise a `low-disk-space` event
+    #[ts(type = "number")]
+    pub min_free_mb: u64,
 }
 
-fn load_network_config() -> NetworkConfig {
-    let path = get_network_config_path();
-    if let Ok(content) = fs::read_to_string(&path) {
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        NetworkConfig::default()
+impl Default for DiskGuard {
+    fn default() -> Self {
+        Self { min_free_mb: 1024 }
     }
 }
 
-fn save_network_config(config: &NetworkConfig) {
-    let path = get_network_config_path();
-    if let Ok(content) = serde_json::to_string_pretty(config) {
-        fs::write(path, content).ok();
-    }
-}
-
-fn is_vpn_interface(name: &str) -> bool {
-    let vpn_patterns = [
-        "zerotier",
-        "tailscale",
-        "wireguard",
-        "wg0",
-        "wg1",
-        "tun",
-        "tap",
-        "vpn",
-        "hamachi",
-        "radmin",
-    ];
-    let name_lower = name.to_lowercase();
-    vpn_patterns.iter().any(|p| name_lower.contains(p))
+/// Why a write was refused by the disk guard
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct InsufficientSpace {
+    pub path: String,
+    /// Bytes the write needs, the guard's headroom included
+    #[ts(type = "number")]
+    pub required: u64,
+    #[ts(type = "number")]
+    pub available: u64,
 }
 
-fn store_server_credentials(name: &str, creds: StoredCredentials) {
-    let mut all_creds = load_credentials();
-    all_creds.insert(name.to_string(), creds);
-    save_credentials(&all_creds);
+impl std::fmt::Display for InsufficientSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let locale = current_locale();
+        write!(
+            f,
+            "Not enough space on {}: {} needed, {} free",
+            self.path,
+            format_size(self.required, &locale),
+            format_size(self.available, &locale)
+        )
+    }
 }
 
-fn remove_server_credentials(name: &str) {
-    let mut all_creds = load_credentials();
-    all_creds.remove(name);
-    save_credentials(&all_creds);
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct LowDiskSpace {
+    pub server: String,
+    pub path: String,
+    #[ts(type = "number")]
+    pub available: u64,
+    #[ts(type = "number")]
+    pub threshold: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ServerConfig {
-    pub name: String,
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct ApiSettings {
+    /// Address to listen on; every interface when unset
+    #[serde(default)]
+    pub bind_ip: Option<String>,
     pub port: u16,
-    pub host_path: String,
-    pub container_path: String,
-    pub username: String,
-    pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ServerInfo {
-    pub name: String,
-    pub port: u16,
-    pub host_path: String,
-    pub container_path: String,
-    pub username: String,
-    pub password: String,
-    pub status: String,
-    pub created_at: Option<String>,
-    pub bind_ip: Option<String>,
+/// Resource limits checked by the stats sampler; `None` disables a check
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct AlertThresholds {
+    #[ts(type = "number | null")]
+    pub memory_mb: Option<u64>,
+    pub cpu_percent: Option<f64>,
+    /// How long CPU must stay above `cpu_percent` before alerting
+    #[ts(type = "number")]
+    pub cpu_minutes: u64,
+    /// Fill level of the filesystem holding the shared volume
+    pub volume_percent: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CommandResult {
-    pub success: bool,
-    pub error: Option<String>,
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            memory_mb: None,
+            cpu_percent: None,
+            cpu_minutes: 5,
+            volume_percent: Some(90.0),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateResult {
-    pub success: bool,
-    pub server: Option<ServerInfo>,
-    pub error: Option<String>,
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ContainerStats {
+    pub cpu_percent: f64,
+    #[ts(type = "number")]
+    pub memory_bytes: u64,
+    #[ts(type = "number")]
+    pub memory_limit_bytes: u64,
+    pub volume_used_percent: Option<f64>,
+    pub sampled_at: String,
+    /// `memory_bytes` formatted for the configured locale
+    #[serde(default)]
+    pub memory_display: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct FileEntry {
-    pub name: String,
-    pub path: String,
-    pub is_dir: bool,
-    pub size: u64,
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ResourceAlert {
+    pub server: String,
+    /// "memory", "cpu" or "volume"
+    pub kind: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+    pub raised_at: String,
 }
 
-// Docker helper functions
-fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
-    // Set PATH explicitly for macOS to find docker
-    #[cfg(target_os = "macos")]
-    let mut command = Command::new(cmd);
-    #[cfg(target_os = "macos")]
-    {
-        command.env("PATH", "/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin:/sbin:/usr/sbin");
-    }
-    #[cfg(not(target_os = "macos"))]
-    let mut command = Command::new(cmd);
+/// Latest stats sample per running server
+#[derive(Default)]
+pub struct StatsCache(Mutex<HashMap<String, ContainerStats>>);
 
-    command
-        .args(args)
-        .output()
-        .map_err(|e| e.to_string())
-        .and_then(|output| {
-            if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
-            } else {
-                Err(String::from_utf8_lossy(&output.stderr).to_string())
-            }
-        })
+/// Running landing pages, keyed by server name
+#[derive(Default)]
+pub struct LandingPages(Mutex<HashMap<String, LandingPage>>);
+
+pub struct LandingPage {
+    port: u16,
+    http: Arc<tiny_http::Server>,
 }
 
-/// Check if a container is using atmoz/sftp image
-fn is_sftp_container(name: &str) -> bool {
-    if let Ok(output) = run_command(
-        "docker",
-        &["inspect", "--format", "{{.Config.Image}}", name],
-    ) {
-        let image = output.trim();
-        return image == SFTP_IMAGE || image.starts_with(&format!("{}:", SFTP_IMAGE));
-    }
-    false
+/// This instance's pairing offer while it's advertised on the LAN
+#[derive(Default)]
+pub struct PairingHost(Mutex<Option<PairingOffer>>);
+
+pub struct PairingOffer {
+    port: u16,
+    code: String,
+    instance: String,
+    http: Arc<tiny_http::Server>,
+    mdns: mdns_sd::ServiceDaemon,
 }
 
-#[tauri::command]
-fn check_docker() -> bool {
-    run_command("docker", &["--version"]).is_ok()
+/// Peers this instance paired with, keyed by instance name; forgotten on exit
+#[derive(Default)]
+pub struct PairedPeers(Mutex<HashMap<String, PairedPeer>>);
+
+/// Alerts currently raised, keyed by (server, kind)
+#[derive(Default)]
+pub struct ActiveAlerts(Mutex<HashMap<(String, String), ResourceAlert>>);
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct HistoryRecord {
+    pub timestamp: String,
+    pub server: Option<String>,
+    #[serde(default)]
+    pub server_id: Option<String>,
+    pub kind: String,
+    pub data: serde_json::Value,
 }
 
-#[tauri::command]
-fn get_local_ip() -> String {
-    // Cross-platform: Try different methods to get local IP
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct AutoHealPolicy {
+    pub enabled: bool,
+    /// Restart attempts before giving up and notifying the user
+    pub max_retries: u32,
+    #[ts(type = "number")]
+    pub initial_backoff_secs: u64,
+    #[ts(type = "number")]
+    pub max_backoff_secs: u64,
+    /// Consecutive failed health probes before a running server counts as down
+    pub unhealthy_threshold: u32,
+}
 
-    // Method 1: Linux - use hostname -I (GNU extension, not available on macOS)
-    #[cfg(target_os = "linux")]
-    {
-        if let Ok(output) = run_command("hostname", &["-I"]) {
-            if let Some(ip) = output.trim().split_whitespace().next() {
-                if !ip.is_empty() && ip != "127.0.0.1" {
-                    return ip.to_string();
-                }
-            }
+impl Default for AutoHealPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: 5,
+            initial_backoff_secs: 5,
+            max_backoff_secs: 300,
+            unhealthy_threshold: 3,
         }
     }
+}
 
-    // Method 2: macOS - use ipconfig getifaddr with dynamic interface discovery
-    #[cfg(target_os = "macos")]
-    {
-        // Get list of network services dynamically
-        if let Ok(output) = run_command("sh", &["-c", "ifconfig -l"]) {
-            for iface in output.trim().split_whitespace() {
-                // Skip loopback and other non-ethernet interfaces
-                if iface.starts_with("lo") || iface.starts_with("gif") || iface.starts_with("stf") {
-                    continue;
-                }
-                if let Ok(ip_output) = run_command("ipconfig", &["getifaddr", iface]) {
-                    let ip = ip_output.trim();
-                    if !ip.is_empty() && !ip.starts_with("127.") {
-                        return ip.to_string();
-                    }
-                }
-            }
-        }
-        // Fallback to common interface names
-        for iface in &["en0", "en1", "en2", "en3", "en4", "en5", "en10", "en11"] {
-            if let Ok(output) = run_command("ipconfig", &["getifaddr", iface]) {
-                let ip = output.trim();
-                if !ip.is_empty() {
-                    return ip.to_string();
-                }
-            }
-        }
-    }
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct AutoHealEvent {
+    pub server: String,
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub reason: String,
+    /// "restarting", "recovered" or "gave_up"
+    pub outcome: String,
+    pub error: Option<String>,
+}
 
-    // Method 3: Windows - use PowerShell (includes both DHCP and static IPs)
-    #[cfg(target_os = "windows")]
-    {
-        if let Ok(output) = run_command("powershell", &[
-            "-Command",
-            "(Get-NetIPAddress -AddressFamily IPv4 | Where-Object {$_.InterfaceAlias -notlike '*Loopback*' -and $_.IPAddress -notlike '127.*' -and $_.IPAddress -notlike '169.254.*'}).IPAddress | Select-Object -First 1"
-        ]) {
-            let ip = output.trim().to_string();
-            if !ip.is_empty() {
-                return ip;
-            }
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PortAllocation {
+    pub port: u16,
+    pub ip: String,
+    pub server: String,
+    /// "bound" for published container ports, "reserved" for pending creates
+    pub state: String,
+}
+
+/// Ports claimed by in-flight `create_server` calls, keyed by port
+#[derive(Default)]
+pub struct PortReservations(Mutex<HashMap<u16, String>>);
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub server: String,
+    /// Identifies the server across renames; missing from entries of unmanaged
+    /// containers
+    #[serde(default)]
+    pub server_id: Option<String>,
+    pub action: String,
+    pub success: bool,
+    pub detail: Option<String>,
+}
+
+/// Retention rule for servers used as drop-boxes
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum CleanupRule {
+    /// Delete files under `path` last modified more than `days` ago
+    MaxAge { path: String, days: u32 },
+    /// Keep the files under `path` below `max_bytes`, oldest deleted first
+    MaxSize {
+        path: String,
+        #[ts(type = "number")]
+        max_bytes: u64,
+    },
+}
+
+impl CleanupRule {
+    fn path(&self) -> &str {
+        match self {
+            CleanupRule::MaxAge { path, .. } | CleanupRule::MaxSize { path, .. } => path,
         }
     }
+}
 
-    // Fallback
-    "127.0.0.1".to_string()
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CleanupCandidate {
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    #[ts(type = "number")]
+    pub modified: i64,
+    /// Which rule selected the file, e.g. "older than 30 days in /home/user/in"
+    pub reason: String,
 }
 
-#[tauri::command]
-fn list_servers() -> Vec<ServerInfo> {
-    // Load stored credentials
-    let stored_creds = load_credentials();
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CleanupReport {
+    pub server: String,
+    pub dry_run: bool,
+    /// Files selected for deletion, or deleted when not a dry run
+    pub files: Vec<CleanupCandidate>,
+    #[ts(type = "number")]
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
 
-    // List only atmoz/sftp containers
-    let result = run_command(
-        "docker",
-        &[
-            "ps",
-            "-a",
-            "--filter",
-            &format!("ancestor={}", SFTP_IMAGE),
-            "--format",
-            "{{.Names}}|{{.Status}}|{{.Ports}}",
-        ],
-    );
+/// What to do with a file that arrived in a watched directory
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum IngestAction {
+    /// Move the file to another directory inside the container
+    Move { to: String },
+    /// Run a program on the host. `{path}` in the arguments becomes the file's host
+    /// path, `{name}` its file name and `{server}` the server name. No shell is involved.
+    Command { program: String, args: Vec<String> },
+    /// POST the arrival as JSON to `url`
+    Webhook { url: String },
+}
 
-    match result {
-        Ok(output) => {
-            if output.trim().is_empty() {
-                return vec![];
-            }
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IngestRule {
+    /// Glob matched against the file name
+    pub pattern: String,
+    /// Container directory to watch (not recursive); the server's container path when unset
+    #[serde(default)]
+    pub path: Option<String>,
+    pub action: IngestAction,
+}
 
-            output
-                .trim()
+/// Emitted as `file-ingested` and audit-logged for every action run
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IngestEvent {
+    pub server: String,
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    pub action: IngestAction,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// What happens to an upload that breaks its server's file policy
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum PolicyAction {
+    /// Moved into the data directory's quarantine, from where it can be restored
+    #[default]
+    Quarantine,
+    Delete,
+}
+
+/// What a share accepts. The ingest watcher checks files as they arrive in the
+/// share root and the directories ingest rules watch; files already there stay.
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct FilePolicy {
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub max_file_bytes: Option<u64>,
+    /// Extensions without the dot ("zip", "tar.gz"); any are allowed when empty
+    #[serde(default)]
+    pub allowed_extensions: Vec<String>,
+    #[serde(default)]
+    pub blocked_extensions: Vec<String>,
+    /// Arrivals that would take a directory past this many files are refused
+    #[serde(default)]
+    pub max_files_per_dir: Option<u32>,
+    #[serde(default)]
+    pub action: PolicyAction,
+}
+
+/// Emitted as `policy-violation` for every upload a file policy refused
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PolicyViolation {
+    pub server: String,
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    /// The rule it broke, in words
+    pub rule: String,
+    pub action: PolicyAction,
+    pub quarantine_id: Option<String>,
+    /// Set when the action failed, leaving the file where it arrived
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Bookmark {
+    pub label: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ServerBookmarks {
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// Most recently browsed first
+    #[serde(default)]
+    pub recent_paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DashboardSummary {
+    #[ts(type = "number")]
+    pub total_servers: usize,
+    #[ts(type = "number")]
+    pub running: usize,
+    #[ts(type = "number")]
+    pub stopped: usize,
+    #[ts(type = "number")]
+    pub total_exported_bytes: u64,
+    pub total_exported_display: String,
+    #[ts(type = "number")]
+    pub active_sessions: usize,
+    pub docker_available: bool,
+    pub bind_ip: String,
+    pub bind_interface: Option<String>,
+    pub is_vpn: bool,
+    pub recent_errors: Vec<AuditEntry>,
+}
+
+/// One finished run of a server, kept in the history store as kind "uptime"
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct UptimeSession {
+    pub started_at: String,
+    pub ended_at: String,
+    #[ts(type = "number")]
+    pub seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct UptimeSummary {
+    /// Recorded sessions plus the current run
+    #[ts(type = "number")]
+    pub total_uptime_secs: u64,
+    pub sessions: usize,
+    #[ts(type = "number | null")]
+    pub current_uptime_secs: Option<u64>,
+    /// `total_uptime_secs` formatted for the configured locale
+    pub total_uptime_display: String,
+    /// Start of the oldest recorded session
+    pub tracked_since: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct MountInfo {
+    pub source: String,
+    pub destination: String,
+    pub read_only: bool,
+}
+
+/// The parts of `docker inspect` the GUI cares about
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ContainerInspect {
+    pub id: String,
+    pub image: String,
+    pub status: String,
+    pub health: Option<String>,
+    pub created_at: Option<String>,
+    pub started_at: Option<String>,
+    pub restart_policy: Option<RestartPolicy>,
+    pub mounts: Vec<MountInfo>,
+    pub env: Vec<String>,
+    /// The `SERVER_ID_LABEL` dsftp put on the container
+    pub server_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ConnectionInfo {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// e.g. "sftp://user@192.168.1.10:2222"
+    pub url: String,
+    /// e.g. "sftp -P 2222 user@192.168.1.10"
+    pub command: String,
+    /// Internet-facing address of the server's cloudflared or ngrok tunnel
+    pub public_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ServerDetails {
+    /// Stored config and current status
+    pub server: ServerInfo,
+    pub container: ContainerInspect,
+    pub stats: Option<ContainerStats>,
+    pub alerts: Vec<ResourceAlert>,
+    /// Audit entries for this server, newest first
+    pub recent_events: Vec<AuditEntry>,
+    pub connection: ConnectionInfo,
+    pub uptime: UptimeSummary,
+    /// Container paths currently hidden by the exclude patterns
+    pub excluded_paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub address: String,
+    pub is_vpn: bool,
+    /// Subnet prefix length, when the platform reports it
+    #[serde(default)]
+    pub prefix_len: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ReachabilityReport {
+    pub client_ip: String,
+    pub bind_ip: String,
+    pub bind_interface: Option<String>,
+    /// The client sits on a subnet the server is listening on
+    pub same_network: bool,
+    /// Local interface whose subnet contains the client, if any
+    pub client_interface: Option<NetworkInterface>,
+    pub warning: Option<String>,
+    /// Address to bind to instead so the client can connect
+    pub suggested_ip: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct NetworkChangeEvent {
+    pub timestamp: String,
+    /// "appeared" or "disappeared"
+    pub change: String,
+    pub interface: NetworkInterface,
+    /// Servers bound to the address that went away
+    pub affected_servers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct NetworkInfo {
+    pub current_ip: String,
+    pub current_interface: Option<String>,
+    pub is_vpn: bool,
+    pub preferred_ip: Option<String>,
+    pub preferred_interface: Option<String>,
+    pub interfaces: Vec<NetworkInterface>,
+    pub vpn_patterns: Vec<String>,
+    pub vpn_overrides: HashMap<String, bool>,
+}
+
+fn get_config_dir() -> PathBuf {
+    let config_dir = std::env::var_os(CONFIG_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::config_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("sftp-manager")
+        });
+    fs::create_dir_all(&config_dir).ok();
+    config_dir
+}
+
+/// Data directory used when `custom` (the `data_dir` setting) is the configured one
+fn resolve_data_dir(custom: Option<&str>) -> PathBuf {
+    std::env::var_os(DATA_DIR_ENV)
+        .map(PathBuf::from)
+        .or_else(|| custom.map(PathBuf::from))
+        .or_else(|| std::env::var_os(CONFIG_DIR_ENV).map(PathBuf::from))
+        .unwrap_or_else(|| {
+            dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("sftp-manager")
+        })
+}
+
+/// Where backups, history, logs and captured host keys live, apart from the config
+fn get_data_dir() -> PathBuf {
+    // Not through load_app_settings: callers may hold the config lock already
+    let settings: AppSettings = read_json_file(&get_app_settings_path());
+    let data_dir = resolve_data_dir(settings.data_dir.as_deref());
+    fs::create_dir_all(&data_dir).ok();
+    data_dir
+}
+
+fn get_config_path() -> PathBuf {
+    get_config_dir().join(CONFIG_FILE)
+}
+
+/// Take the cross-process config lock, shared by every dsftp GUI and daemon process;
+/// it is released when the returned handle is dropped. The TypeScript CLI
+/// (`core/config.ts`) doesn't take it, so its writes can still race these.
+fn lock_config(exclusive: bool) -> Option<fs::File> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(get_config_dir().join(CONFIG_LOCK_FILE))
+        .ok()?;
+    let locked = if exclusive {
+        file.lock_exclusive()
+    } else {
+        file.lock_shared()
+    };
+    locked.ok().map(|_| file)
+}
+
+fn read_json_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+    if let Ok(content) = fs::read_to_string(path) {
+        serde_json::from_str(&content).unwrap_or_default()
+    } else {
+        T::default()
+    }
+}
+
+/// Write via a temp file + rename so readers never observe a half-written file
+fn write_json_file<T: Serialize>(path: &Path, value: &T) {
+    write_json(path, value, false)
+}
+
+/// `write_json_file` for files holding secrets, readable only by the user
+fn write_private_json_file<T: Serialize>(path: &Path, value: &T) {
+    write_json(path, value, true)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T, private: bool) {
+    let Ok(content) = serde_json::to_string_pretty(value) else {
+        return;
+    };
+    let tmp_path = path.with_extension("json.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+        // The mode only applies to a new file, so don't reuse a leftover one
+        fs::remove_file(&tmp_path).ok();
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let written = options
+        .open(&tmp_path)
+        .and_then(|mut file| file.write_all(content.as_bytes()));
+    if written.is_err() || fs::rename(&tmp_path, path).is_err() {
+        fs::remove_file(&tmp_path).ok();
+        return;
+    }
+    // Remember our own write so the watcher doesn't report it as external
+    if let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) {
+        if let Ok(mut writes) = SELF_WRITES.lock() {
+            writes.insert(path.to_path_buf(), modified);
+        }
+    }
+}
+
+/// In-memory copy of sftp-servers.json. Reads are served from memory until the
+/// file's mtime or size shows another process wrote it; updates write through
+/// under the config lock. Servers whose entry changed are kept for the config
+/// watcher, which reports them as `credentials-changed`.
+struct CredentialStore {
+    cache: RwLock<Option<CachedCredentials>>,
+    dirty: Mutex<BTreeSet<String>>,
+    /// Bumped whenever the cached copy is replaced, so what's derived from it
+    /// (the `Redactor`) knows to rebuild
+    generation: AtomicU64,
+}
+
+struct CachedCredentials {
+    servers: HashMap<String, StoredCredentials>,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+impl CredentialStore {
+    fn cached(
+        &self,
+        stamp: Option<(SystemTime, u64)>,
+    ) -> Option<HashMap<String, StoredCredentials>> {
+        let cache = self.cache.read().ok()?;
+        cache
+            .as_ref()
+            .filter(|c| c.stamp == stamp)
+            .map(|c| c.servers.clone())
+    }
+
+    fn load(&self) -> HashMap<String, StoredCredentials> {
+        let path = get_config_path();
+        if let Some(servers) = self.cached(file_stamp(&path)) {
+            return servers;
+        }
+        let _lock = lock_config(false);
+        self.reload(&path)
+    }
+
+    /// Re-read the file; the caller holds the config lock
+    fn reload(&self, path: &Path) -> HashMap<String, StoredCredentials> {
+        let stamp = file_stamp(path);
+        let servers: HashMap<String, StoredCredentials> = read_json_file(path);
+        if let Ok(mut cache) = self.cache.write() {
+            if let Some(previous) = cache.as_ref() {
+                self.mark_changed(&previous.servers, &servers);
+            }
+            *cache = Some(CachedCredentials {
+                servers: servers.clone(),
+                stamp,
+            });
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        servers
+    }
+
+    fn update<R>(&self, f: impl FnOnce(&mut HashMap<String, StoredCredentials>) -> R) -> R {
+        let path = get_config_path();
+        let _lock = lock_config(true);
+        let stamp = file_stamp(&path);
+        let mut servers = self.cached(stamp).unwrap_or_else(|| self.reload(&path));
+        let before = servers.clone();
+        let result = f(&mut servers);
+        self.mark_changed(&before, &servers);
+        // Inline passwords and other secrets live here
+        write_private_json_file(&path, &servers);
+
+        let written = file_stamp(&path);
+        if let Ok(mut cache) = self.cache.write() {
+            // An unchanged stamp means the write failed; don't serve what isn't on disk
+            *cache = (written != stamp).then_some(CachedCredentials {
+                servers,
+                stamp: written,
+            });
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        result
+    }
+
+    /// The current generation, after picking up a write by another process
+    fn generation(&self) -> u64 {
+        let stamp = file_stamp(&get_config_path());
+        let fresh = self
+            .cache
+            .read()
+            .is_ok_and(|cache| cache.as_ref().is_some_and(|c| c.stamp == stamp));
+        if !fresh {
+            self.load();
+        }
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    fn mark_changed(
+        &self,
+        before: &HashMap<String, StoredCredentials>,
+        after: &HashMap<String, StoredCredentials>,
+    ) {
+        let changed = before.keys().chain(after.keys()).filter(|name| {
+            serde_json::to_value(before.get(*name)).ok()
+                != serde_json::to_value(after.get(*name)).ok()
+        });
+        if let Ok(mut dirty) = self.dirty.lock() {
+            dirty.extend(changed.cloned());
+        }
+    }
+
+    /// Servers changed since the last call
+    fn take_changed(&self) -> Vec<String> {
+        self.dirty
+            .lock()
+            .map(|mut dirty| std::mem::take(&mut *dirty).into_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn load_credentials() -> HashMap<String, StoredCredentials> {
+    let mut creds = CREDENTIALS.load();
+    let store = secret_store(load_app_settings().secret_store);
+    if !store.inline() {
+        for (name, entry) in creds.iter_mut() {
+            if let Ok(Some(password)) = store.get(name) {
+                entry.password = password;
+            }
+        }
+    }
+    creds
+}
+
+/// Stored passwords to scrub from text that may be shown, logged or reported
+struct Redactor(Vec<String>);
+
+impl Redactor {
+    fn load() -> Self {
+        let mut secrets: Vec<String> = load_credentials()
+            .into_values()
+            .map(|c| c.password)
+            .filter(|p| p.len() >= MIN_REDACTED_LEN)
+            .collect();
+        // Longest first, so one password containing another is masked whole
+        secrets.sort_by_key(|p| Reverse(p.len()));
+        Redactor(secrets)
+    }
+
+    /// Shared until the stored credentials change. Built outside the lock: reading
+    /// the secret store can be slow.
+    fn current() -> Arc<Self> {
+        let generation = CREDENTIALS.generation();
+        if let Some((built_at, redactor)) = REDACTOR.lock().ok().and_then(|r| r.clone()) {
+            if built_at == generation {
+                return redactor;
+            }
+        }
+        let redactor = Arc::new(Redactor::load());
+        if let Ok(mut cached) = REDACTOR.lock() {
+            *cached = Some((generation, redactor.clone()));
+        }
+        redactor
+    }
+
+    fn apply(&self, text: &str) -> String {
+        self.0.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+    }
+}
+
+fn redact_secrets(text: &str) -> String {
+    Redactor::current().apply(text)
+}
+
+/// Where server passwords are kept
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum SecretStoreKind {
+    /// Inline in sftp-servers.json, readable by anything that can read the file
+    #[default]
+    Json,
+    /// macOS Keychain, Windows Credential Manager or the Secret Service on Linux
+    Keychain,
+    /// secrets.enc.json, sealed with a key kept in a separate owner-only file
+    EncryptedFile,
+}
+
+trait SecretStore {
+    fn get(&self, server: &str) -> Result<Option<String>, String>;
+    fn set(&self, server: &str, secret: &str) -> Result<(), String>;
+    fn remove(&self, server: &str) -> Result<(), String>;
+    /// Passwords live in sftp-servers.json itself rather than beside it
+    fn inline(&self) -> bool {
+        false
+    }
+}
+
+fn secret_store(kind: SecretStoreKind) -> Box<dyn SecretStore> {
+    match kind {
+        SecretStoreKind::Json => Box::new(JsonSecretStore),
+        SecretStoreKind::Keychain => Box::new(KeychainSecretStore),
+        SecretStoreKind::EncryptedFile => Box::new(EncryptedFileSecretStore),
+    }
+}
+
+struct JsonSecretStore;
+
+impl SecretStore for JsonSecretStore {
+    fn get(&self, server: &str) -> Result<Option<String>, String> {
+        let creds = CREDENTIALS.load();
+        Ok(creds.get(server).map(|c| c.password.clone()))
+    }
+
+    fn set(&self, server: &str, secret: &str) -> Result<(), String> {
+        update_credentials(|all| {
+            if let Some(creds) = all.get_mut(server) {
+                creds.password = secret.to_string();
+            }
+        });
+        Ok(())
+    }
+
+    fn remove(&self, server: &str) -> Result<(), String> {
+        self.set(server, "")
+    }
+
+    fn inline(&self) -> bool {
+        true
+    }
+}
+
+struct KeychainSecretStore;
+
+impl KeychainSecretStore {
+    fn entry(server: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, server).map_err(|e| e.to_string())
+    }
+}
+
+impl SecretStore for KeychainSecretStore {
+    fn get(&self, server: &str) -> Result<Option<String>, String> {
+        match Self::entry(server)?.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn set(&self, server: &str, secret: &str) -> Result<(), String> {
+        Self::entry(server)?
+            .set_password(secret)
+            .map_err(|e| e.to_string())
+    }
+
+    fn remove(&self, server: &str) -> Result<(), String> {
+        match Self::entry(server)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// ChaCha20-Poly1305 sealed secrets. The key sits next to the config, so this guards
+/// against the secrets file being copied or synced on its own, not against a local
+/// attacker who can read the whole config directory.
+struct EncryptedFileSecretStore;
+
+impl EncryptedFileSecretStore {
+    fn cipher() -> Result<ChaCha20Poly1305, String> {
+        let path = get_config_dir().join(SECRETS_KEY_FILE);
+        let key = match fs::read(&path) {
+            Ok(bytes) if bytes.len() == 32 => Key::clone_from_slice(&bytes),
+            // Replacing a damaged key would make every sealed secret unreadable
+            Ok(bytes) => {
+                return Err(format!(
+                    "{} is not a valid key ({} bytes); restore it from a backup",
+                    path.display(),
+                    bytes.len()
+                ))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                let mut options = fs::OpenOptions::new();
+                options.write(true).create_new(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::OpenOptionsExt;
+                    options.mode(0o600);
+                }
+                match options.open(&path) {
+                    Ok(mut file) => file.write_all(key.as_slice()).map_err(|e| e.to_string())?,
+                    // Another process created it first; use theirs
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        return Self::cipher()
+                    }
+                    Err(e) => return Err(e.to_string()),
+                }
+                key
+            }
+            Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+        };
+        Ok(ChaCha20Poly1305::new(&key))
+    }
+
+    fn path() -> PathBuf {
+        get_config_dir().join(SECRETS_FILE)
+    }
+}
+
+impl SecretStore for EncryptedFileSecretStore {
+    fn get(&self, server: &str) -> Result<Option<String>, String> {
+        let sealed: HashMap<String, String> = {
+            let _lock = lock_config(false);
+            read_json_file(&Self::path())
+        };
+        let Some(hex) = sealed.get(server) else {
+            return Ok(None);
+        };
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2).unwrap_or(""), 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| e.to_string())?;
+        if bytes.len() < 12 {
+            return Err(format!("Corrupt secret for {}", server));
+        }
+        let (nonce, ciphertext) = bytes.split_at(12);
+        let plain = Self::cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| format!("Cannot decrypt the secret for {}", server))?;
+        String::from_utf8(plain)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    fn set(&self, server: &str, secret: &str) -> Result<(), String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = Self::cipher()?
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|e| e.to_string())?;
+        let hex: String = nonce
+            .iter()
+            .chain(&ciphertext)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        update_json_file(&Self::path(), |sealed: &mut HashMap<String, String>| {
+            sealed.insert(server.to_string(), hex);
+        });
+        Ok(())
+    }
+
+    fn remove(&self, server: &str) -> Result<(), String> {
+        update_json_file(&Self::path(), |sealed: &mut HashMap<String, String>| {
+            sealed.remove(server);
+        });
+        Ok(())
+    }
+}
+
+/// Read-modify-write a JSON config file while holding the exclusive lock
+fn update_json_file<T, R>(path: &Path, f: impl FnOnce(&mut T) -> R) -> R
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    update_json(path, f, false)
+}
+
+/// `update_json_file` for files holding secrets, readable only by the user
+fn update_private_json_file<T, R>(path: &Path, f: impl FnOnce(&mut T) -> R) -> R
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    update_json(path, f, true)
+}
+
+fn update_json<T, R>(path: &Path, f: impl FnOnce(&mut T) -> R, private: bool) -> R
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let _lock = lock_config(true);
+    let mut value: T = read_json_file(path);
+    let result = f(&mut value);
+    write_json(path, &value, private);
+    result
+}
+
+fn update_credentials<R>(f: impl FnOnce(&mut HashMap<String, StoredCredentials>) -> R) -> R {
+    CREDENTIALS.update(f)
+}
+
+fn get_bookmarks_path() -> PathBuf {
+    get_config_dir().join(BOOKMARKS_FILE)
+}
+
+fn load_bookmarks() -> HashMap<String, ServerBookmarks> {
+    let _lock = lock_config(false);
+    read_json_file(&get_bookmarks_path())
+}
+
+fn update_bookmarks<R>(f: impl FnOnce(&mut HashMap<String, ServerBookmarks>) -> R) -> R {
+    update_json_file(&get_bookmarks_path(), f)
+}
+
+fn get_ui_state_path() -> PathBuf {
+    get_config_dir().join(UI_STATE_FILE)
+}
+
+fn load_ui_state() -> HashMap<String, serde_json::Value> {
+    let _lock = lock_config(false);
+    read_json_file(&get_ui_state_path())
+}
+
+fn get_cleanup_rules_path() -> PathBuf {
+    get_config_dir().join(CLEANUP_RULES_FILE)
+}
+
+fn load_cleanup_rules() -> HashMap<String, Vec<CleanupRule>> {
+    let _lock = lock_config(false);
+    read_json_file(&get_cleanup_rules_path())
+}
+
+fn get_ingest_rules_path() -> PathBuf {
+    get_config_dir().join(INGEST_RULES_FILE)
+}
+
+fn load_ingest_rules() -> HashMap<String, Vec<IngestRule>> {
+    let _lock = lock_config(false);
+    read_json_file(&get_ingest_rules_path())
+}
+
+/// Move `path` to the front of the server's recent list
+fn record_recent_path(name: &str, path: &str) {
+    update_bookmarks(|all| {
+        let recent = &mut all.entry(name.to_string()).or_default().recent_paths;
+        recent.retain(|p| p != path);
+        recent.insert(0, path.to_string());
+        recent.truncate(MAX_RECENT_PATHS);
+    });
+}
+
+fn get_network_config_path() -> PathBuf {
+    get_config_dir().join(NETWORK_CONFIG_FILE)
+}
+
+fn load_network_config() -> NetworkConfig {
+    let _lock = lock_config(false);
+    read_json_file(&get_network_config_path())
+}
+
+fn save_network_config(config: &NetworkConfig) {
+    let _lock = lock_config(true);
+    write_json_file(&get_network_config_path(), config);
+}
+
+fn get_app_settings_path() -> PathBuf {
+    get_config_dir().join(APP_SETTINGS_FILE)
+}
+
+fn load_app_settings() -> AppSettings {
+    let _lock = lock_config(false);
+    read_json_file(&get_app_settings_path())
+}
+
+/// Change the app settings under the exclusive config lock, so a change made
+/// meanwhile by another window or dsftp process isn't overwritten
+fn update_app_settings<R>(f: impl FnOnce(&mut AppSettings) -> R) -> R {
+    update_json_file(&get_app_settings_path(), f)
+}
+
+fn get_audit_log_path() -> PathBuf {
+    get_data_dir().join(AUDIT_LOG_FILE)
+}
+
+/// Append one entry to the audit log (JSON lines); `error` marks the action as failed
+fn record_audit(server: &str, action: &str, error: Option<&str>) {
+    write_audit_entry(AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        server: server.to_string(),
+        server_id: None,
+        action: action.to_string(),
+        success: error.is_none(),
+        detail: error.map(|e| e.trim().to_string()),
+    });
+}
+
+fn write_audit_entry(mut entry: AuditEntry) {
+    // Whatever dsftp audits may have changed the server's files
+    invalidate_listings(&entry.server);
+    if entry.server_id.is_none() {
+        entry.server_id = server_id(&entry.server);
+    }
+    entry.detail = entry.detail.map(|d| redact_secrets(&d));
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    if let Ok(mut file) = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_audit_log_path())
+    {
+        writeln!(file, "{}", line).ok();
+    }
+}
+
+fn load_audit_log() -> Vec<AuditEntry> {
+    fs::read_to_string(get_audit_log_path())
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Stable id of the server now called `name`, if dsftp manages it
+fn server_id(name: &str) -> Option<String> {
+    CREDENTIALS.load().remove(name)?.id
+}
+
+/// Whether a record made under `record_server` belongs to the server now called
+/// `name`. Records and servers that both have an id match on it, so records from
+/// before a rename still count and a reused name doesn't inherit old ones.
+fn is_same_server(
+    record_server: &str,
+    record_id: Option<&str>,
+    name: &str,
+    id: Option<&str>,
+) -> bool {
+    match (record_id, id) {
+        (Some(record_id), Some(id)) => record_id == id,
+        _ => record_server == name,
+    }
+}
+
+/// Give every stored server an id. Records already in the audit log and history
+/// are attributed to the server that has their name now, which is right unless a
+/// name was used by an earlier, removed server.
+fn migrate_server_ids() {
+    let assigned: HashMap<String, String> = update_credentials(|all| {
+        all.iter_mut()
+            .filter(|(_, creds)| creds.id.is_none())
+            .map(|(name, creds)| {
+                let id = new_server_id();
+                creds.id = Some(id.clone());
+                (name.clone(), id)
+            })
+            .collect()
+    });
+    if assigned.is_empty() {
+        return;
+    }
+
+    fn backfill<T: Serialize + DeserializeOwned>(
+        path: &Path,
+        assign: impl Fn(&mut T) -> bool,
+    ) -> Result<(), String> {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Ok(());
+        };
+        let mut changed = false;
+        let lines: Vec<String> = content
+            .lines()
+            .map(|line| {
+                let Ok(mut record) = serde_json::from_str::<T>(line) else {
+                    return line.to_string();
+                };
+                if !assign(&mut record) {
+                    return line.to_string();
+                }
+                changed = true;
+                serde_json::to_string(&record).unwrap_or_else(|_| line.to_string())
+            })
+            .collect();
+        if !changed {
+            return Ok(());
+        }
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, lines.join("\n") + "\n").map_err(|e| e.to_string())?;
+        fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+    }
+
+    let _lock = lock_config(true);
+    let audit = backfill(
+        &get_audit_log_path(),
+        |entry: &mut AuditEntry| match assigned.get(&entry.server) {
+            Some(id) if entry.server_id.is_none() => {
+                entry.server_id = Some(id.clone());
+                true
+            }
+            _ => false,
+        },
+    );
+    let history = backfill(
+        &get_history_path(),
+        |record: &mut HistoryRecord| match record.server.as_ref().and_then(|s| assigned.get(s)) {
+            Some(id) if record.server_id.is_none() => {
+                record.server_id = Some(id.clone());
+                true
+            }
+            _ => false,
+        },
+    );
+    if let Err(e) = audit.and(history) {
+        log::error!("Failed to add server ids to past records: {}", e);
+    }
+}
+
+/// Follow servers renamed with `docker rename`: the container keeps its id label,
+/// so its stored settings and password move to the new name
+fn follow_renames() {
+    let format = format!("{{{{.Names}}}}|{{{{.Label \"{}\"}}}}", SERVER_ID_LABEL);
+    let Ok(output) = run_command(
+        "docker",
+        &[
+            "ps",
+            "-a",
+            "--filter",
+            &format!("label={}", SERVER_ID_LABEL),
+            "--format",
+            &format,
+        ],
+    ) else {
+        return;
+    };
+    let containers: HashMap<&str, &str> = output
+        .lines()
+        .filter_map(|line| line.split_once('|'))
+        .collect();
+    let creds = CREDENTIALS.load();
+    for (&name, &id) in &containers {
+        if creds.contains_key(name) {
+            continue;
+        }
+        let Some(old) = creds
+            .iter()
+            .find(|(old, c)| c.id.as_deref() == Some(id) && !containers.contains_key(old.as_str()))
+            .map(|(old, _)| old.clone())
+        else {
+            continue;
+        };
+        let result = rename_stored_server(&old, name);
+        write_audit_entry(AuditEntry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            server: name.to_string(),
+            server_id: Some(id.to_string()),
+            action: "rename".to_string(),
+            success: result.is_ok(),
+            detail: Some(match &result {
+                Ok(()) => format!("{} -> {}", old, name),
+                Err(e) => format!("{} -> {}: {}", old, name, e),
+            }),
+        });
+    }
+}
+
+/// Move what dsftp keeps under a server's name to `new`: credentials, the password
+/// in the secret store and the server's rules
+fn rename_stored_server(old: &str, new: &str) -> Result<(), String> {
+    let store = secret_store(load_app_settings().secret_store);
+    if !store.inline() {
+        if let Some(password) = store.get(old)? {
+            store.set(new, &password)?;
+            store.remove(old).ok();
+        }
+    }
+    update_credentials(|all| {
+        if let Some(creds) = all.remove(old) {
+            all.insert(new.to_string(), creds);
+        }
+    });
+    for file in [CLEANUP_RULES_FILE, INGEST_RULES_FILE, TIERING_RULES_FILE] {
+        let path = get_config_dir().join(file);
+        let rules: HashMap<String, serde_json::Value> = read_json_file(&path);
+        if rules.contains_key(old) {
+            update_json_file(&path, |all: &mut HashMap<String, serde_json::Value>| {
+                if let Some(rules) = all.remove(old) {
+                    all.insert(new.to_string(), rules);
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+fn get_history_path() -> PathBuf {
+    get_data_dir().join(HISTORY_FILE)
+}
+
+/// Append an event to the history store (JSON lines), compacting it when it gets large
+fn record_history(server: Option<&str>, kind: &str, data: serde_json::Value) {
+    let record = HistoryRecord {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        server: server.map(|s| s.to_string()),
+        server_id: server.and_then(server_id),
+        kind: kind.to_string(),
+        data,
+    };
+    let Ok(line) = serde_json::to_string(&record) else {
+        return;
+    };
+
+    let _lock = lock_config(true);
+    let path = get_history_path();
+    if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(&path) {
+        writeln!(file, "{}", line).ok();
+    }
+
+    if fs::metadata(&path).map(|m| m.len()).unwrap_or(0) > HISTORY_MAX_BYTES {
+        if let Ok(content) = fs::read_to_string(&path) {
+            let lines: Vec<&str> = content.lines().collect();
+            let keep = &lines[lines.len().saturating_sub(HISTORY_KEEP_RECORDS)..];
+            let tmp_path = path.with_extension("jsonl.tmp");
+            if fs::write(&tmp_path, keep.join("\n") + "\n").is_ok() {
+                fs::rename(&tmp_path, &path).ok();
+            }
+        }
+    }
+}
+
+fn load_history() -> Vec<HistoryRecord> {
+    let _lock = lock_config(false);
+    fs::read_to_string(get_history_path())
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_history(
+    server: Option<String>,
+    kind: Option<String>,
+    limit: Option<usize>,
+) -> Vec<HistoryRecord> {
+    let id = server.as_deref().and_then(server_id);
+    // Newest first
+    load_history()
+        .into_iter()
+        .rev()
+        .filter(|r| {
+            server.as_deref().is_none_or(|name| {
+                is_same_server(
+                    r.server.as_deref().unwrap_or_default(),
+                    r.server_id.as_deref(),
+                    name,
+                    id.as_deref(),
+                )
+            })
+        })
+        .filter(|r| kind.as_ref().is_none_or(|k| &r.kind == k))
+        .take(limit.unwrap_or(200))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum TransferDirection {
+    /// From this machine into a server
+    Upload,
+    ServerToServer,
+    /// From this machine to a paired peer's server
+    PeerPush,
+    /// From a paired peer's server to this machine
+    PeerPull,
+    /// Received through `PUT /ingest/...` on the HTTP API
+    Ingest,
+}
+
+/// One file of a transfer, kept in the history store as kind "transfer"
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TransferRecord {
+    pub direction: TransferDirection,
+    pub source: String,
+    pub destination: String,
+    /// The other end: source server, peer instance or API client address
+    pub peer: Option<String>,
+    #[ts(type = "number")]
+    pub bytes: u64,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+fn record_transfer(server: Option<&str>, record: TransferRecord) {
+    if let Ok(data) = serde_json::to_value(record) {
+        record_history(server, "transfer", data);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct TransferHistoryFilter {
+    pub server: Option<String>,
+    /// Case-insensitive text the source or destination path contains
+    pub file: Option<String>,
+    pub direction: Option<TransferDirection>,
+    /// Only completed (true) or failed (false) transfers
+    pub success: Option<bool>,
+    /// RFC3339 bounds on when the transfer finished
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub offset: usize,
+    /// Page size; 100 when unset
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TransferHistoryEntry {
+    pub timestamp: String,
+    pub server: Option<String>,
+    pub transfer: TransferRecord,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TransferHistoryPage {
+    /// Newest first
+    pub entries: Vec<TransferHistoryEntry>,
+    /// Matches across all pages
+    pub total: usize,
+}
+
+/// Past transfers, one entry per file, newest first
+#[tauri::command]
+fn get_transfer_history(
+    filter: Option<TransferHistoryFilter>,
+) -> Result<TransferHistoryPage, String> {
+    let filter = filter.unwrap_or_default();
+    let bound = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|v| {
+                chrono::DateTime::parse_from_rfc3339(v)
+                    .map_err(|e| format!("Invalid date '{}': {}", v, e))
+            })
+            .transpose()
+    };
+    let (since, until) = (bound(&filter.since)?, bound(&filter.until)?);
+    let file = filter.file.as_deref().map(str::to_lowercase);
+
+    let id = filter.server.as_deref().and_then(server_id);
+    let matches: Vec<TransferHistoryEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|r| r.kind == "transfer")
+        .filter(|r| {
+            filter.server.as_deref().is_none_or(|name| {
+                is_same_server(
+                    r.server.as_deref().unwrap_or_default(),
+                    r.server_id.as_deref(),
+                    name,
+                    id.as_deref(),
+                )
+            })
+        })
+        .filter(|r| {
+            let time = chrono::DateTime::parse_from_rfc3339(&r.timestamp).ok();
+            since.is_none_or(|s| time.is_some_and(|t| t >= s))
+                && until.is_none_or(|u| time.is_some_and(|t| t <= u))
+        })
+        .filter_map(|r| {
+            Some(TransferHistoryEntry {
+                transfer: serde_json::from_value(r.data).ok()?,
+                timestamp: r.timestamp,
+                server: r.server,
+            })
+        })
+        .filter(|e| filter.direction.is_none_or(|d| e.transfer.direction == d))
+        .filter(|e| filter.success.is_none_or(|s| e.transfer.success == s))
+        .filter(|e| {
+            file.as_ref().is_none_or(|f| {
+                e.transfer.source.to_lowercase().contains(f)
+                    || e.transfer.destination.to_lowercase().contains(f)
+            })
+        })
+        .collect();
+    let total = matches.len();
+    Ok(TransferHistoryPage {
+        entries: matches
+            .into_iter()
+            .skip(filter.offset)
+            .take(filter.limit.unwrap_or(100))
+            .collect(),
+        total,
+    })
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ConfigChangeEvent {
+    pub file: String,
+}
+
+/// Servers whose stored settings changed, from this process or another
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct CredentialsChangeEvent {
+    pub servers: Vec<String>,
+}
+
+/// Poll the config files and emit `config-externally-changed` when another process
+/// edits them, and `credentials-changed` for every server whose entry changed
+fn spawn_config_watcher() {
+    std::thread::spawn(move || {
+        let files = [
+            CONFIG_FILE,
+            NETWORK_CONFIG_FILE,
+            APP_SETTINGS_FILE,
+            BOOKMARKS_FILE,
+            CLEANUP_RULES_FILE,
+            INGEST_RULES_FILE,
+            TIERING_RULES_FILE,
+        ];
+        let modified_at = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        let mut seen: HashMap<PathBuf, Option<SystemTime>> = files
+            .iter()
+            .map(|f| {
+                let path = get_config_dir().join(f);
+                let modified = modified_at(&path);
+                (path, modified)
+            })
+            .collect();
+
+        loop {
+            std::thread::sleep(CONFIG_WATCH_INTERVAL);
+
+            for file in files {
+                let path = get_config_dir().join(file);
+                let current = modified_at(&path);
+                let previous = seen.insert(path.clone(), current).flatten();
+                if current == previous {
+                    continue;
+                }
+
+                let ours = match (current, SELF_WRITES.lock()) {
+                    (Some(current), Ok(writes)) => writes.get(&path) == Some(&current),
+                    _ => false,
+                };
+                if !ours && file == CONFIG_FILE {
+                    // Picks up the other process's edits for `credentials-changed`
+                    CREDENTIALS.load();
+                }
+                if !ours {
+                    events::publish(events::Event::ConfigExternallyChanged(ConfigChangeEvent {
+                        file: file.to_string(),
+                    }));
+                }
+            }
+
+            let servers = CREDENTIALS.take_changed();
+            if !servers.is_empty() {
+                events::publish(events::Event::CredentialsChanged(CredentialsChangeEvent {
+                    servers,
+                }));
+            }
+        }
+    });
+}
+
+const DEFAULT_VPN_PATTERNS: &[&str] = &[
+    "zerotier",
+    "tailscale",
+    "wireguard",
+    "wg0",
+    "wg1",
+    "tun",
+    "tap",
+    "vpn",
+    "hamachi",
+    "radmin",
+];
+
+fn is_vpn_interface(name: &str, config: &NetworkConfig) -> bool {
+    if let Some(&is_vpn) = config.vpn_overrides.get(name) {
+        return is_vpn;
+    }
+    let name_lower = name.to_lowercase();
+    DEFAULT_VPN_PATTERNS
+        .iter()
+        .copied()
+        .chain(config.vpn_patterns.iter().map(|p| p.as_str()))
+        .any(|p| !p.is_empty() && name_lower.contains(&p.to_lowercase()))
+}
+
+fn store_server_credentials(name: &str, mut creds: StoredCredentials) -> Result<(), String> {
+    let store = secret_store(load_app_settings().secret_store);
+    if !store.inline() {
+        store.set(name, &creds.password)?;
+        creds.password.clear();
+    }
+    update_credentials(|all_creds| {
+        all_creds.insert(name.to_string(), creds);
+    });
+    Ok(())
+}
+
+fn remove_server_credentials(name: &str) {
+    secret_store(load_app_settings().secret_store)
+        .remove(name)
+        .ok();
+    update_credentials(|all_creds| {
+        all_creds.remove(name);
+    });
+}
+
+#[tauri::command]
+fn get_secret_store() -> SecretStoreKind {
+    load_app_settings().secret_store
+}
+
+/// Switch password storage, moving every stored password to the new backend
+#[tauri::command]
+fn set_secret_store(kind: SecretStoreKind) -> CommandResult {
+    let result = move_secrets(kind);
+    CommandResult {
+        success: result.is_ok(),
+        error: result.err(),
+    }
+}
+
+/// Copy every password to the `kind` backend, then switch to it. Any read or write
+/// that fails aborts the switch with the old backend still holding everything.
+fn move_secrets(kind: SecretStoreKind) -> Result<(), String> {
+    let current = load_app_settings().secret_store;
+    if current == kind {
+        return Ok(());
+    }
+    let (old, new) = (secret_store(current), secret_store(kind));
+
+    // Read straight from the old backend; load_credentials() would hide a failure
+    let mut moved = Vec::new();
+    for name in CREDENTIALS.load().keys() {
+        let password = old
+            .get(name)
+            .map_err(|e| format!("Failed to read the password of {}: {}", name, e))?;
+        if let Some(password) = password.filter(|p| !p.is_empty()) {
+            new.set(name, &password)
+                .map_err(|e| format!("Failed to move the password of {}: {}", name, e))?;
+            moved.push(name.clone());
+        }
+    }
+
+    // The SMTP password moves along, between the settings file and the store. The
+    // stores take the config lock themselves, so they're written before the settings.
+    let settings = load_app_settings();
+    let smtp_password = match &settings.smtp {
+        Some(smtp) if old.inline() => Some(smtp.password.clone()),
+        Some(_) => old
+            .get(SMTP_SECRET)
+            .map_err(|e| format!("Failed to read the SMTP password: {}", e))?,
+        None => None,
+    };
+    if let Some(password) = smtp_password.as_ref().filter(|_| !new.inline()) {
+        new.set(SMTP_SECRET, password)
+            .map_err(|e| format!("Failed to move the SMTP password: {}", e))?;
+    }
+    // So do the secret keys of S3 backup targets
+    let mut s3_keys = HashMap::new();
+    for (target, backup) in &settings.backup_targets {
+        let BackupTarget::S3(s3) = backup else {
+            continue;
+        };
+        // Keys saved before they went to the store are still in the settings
+        let key = if old.inline() || !s3.secret_key.is_empty() {
+            Some(s3.secret_key.clone())
+        } else {
+            old.get(&s3_secret(target))
+                .map_err(|e| format!("Failed to read the secret key of {}: {}", target, e))?
+        };
+        let Some(key) = key.filter(|k| !k.is_empty()) else {
+            continue;
+        };
+        if !new.inline() {
+            new.set(&s3_secret(target), &key)
+                .map_err(|e| format!("Failed to move the secret key of {}: {}", target, e))?;
+        }
+        s3_keys.insert(target.clone(), key);
+    }
+    update_app_settings(|settings| {
+        if let (Some(smtp), Some(password)) = (settings.smtp.as_mut(), smtp_password.clone()) {
+            smtp.password = if new.inline() {
+                password
+            } else {
+                String::new()
+            };
+        }
+        for (target, key) in &s3_keys {
+            if let Some(BackupTarget::S3(s3)) = settings.backup_targets.get_mut(target) {
+                s3.secret_key = if new.inline() {
+                    key.clone()
+                } else {
+                    String::new()
+                };
+            }
+        }
+        settings.secret_store = kind;
+    });
+
+    // Only drop the old copies that were read, now that the new backend holds them
+    for name in &moved {
+        old.remove(name).ok();
+    }
+    if !old.inline() {
+        if smtp_password.is_some() {
+            old.remove(SMTP_SECRET).ok();
+        }
+        for target in s3_keys.keys() {
+            old.remove(&s3_secret(target)).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Docker restart policy for a server container
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, TS)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+#[ts(export)]
+pub enum RestartPolicy {
+    No,
+    /// Restart after a non-zero exit; `max_retries` of `None` retries forever
+    OnFailure {
+        max_retries: Option<u32>,
+    },
+    Always,
+    #[default]
+    UnlessStopped,
+}
+
+impl RestartPolicy {
+    /// Value for `docker run/update --restart`
+    fn docker_arg(&self) -> String {
+        match self {
+            RestartPolicy::No => "no".to_string(),
+            RestartPolicy::OnFailure {
+                max_retries: Some(n),
+            } => format!("on-failure:{}", n),
+            RestartPolicy::OnFailure { max_retries: None } => "on-failure".to_string(),
+            RestartPolicy::Always => "always".to_string(),
+            RestartPolicy::UnlessStopped => "unless-stopped".to_string(),
+        }
+    }
+
+    /// From `docker inspect`'s `.HostConfig.RestartPolicy` name and retry count
+    fn from_inspect(name: &str, max_retries: u32) -> Option<Self> {
+        match name {
+            "" | "no" => Some(RestartPolicy::No),
+            "on-failure" => Some(RestartPolicy::OnFailure {
+                max_retries: Some(max_retries).filter(|n| *n > 0),
+            }),
+            "always" => Some(RestartPolicy::Always),
+            "unless-stopped" => Some(RestartPolicy::UnlessStopped),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ServerConfig {
+    pub name: String,
+    pub port: u16,
+    pub host_path: String,
+    pub container_path: String,
+    pub username: String,
+    pub password: String,
+    /// IANA zone name (e.g. "Asia/Seoul"), or "local" to follow the host; UTC when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Appended to the generated `docker run` for options dsftp doesn't model
+    #[serde(default)]
+    pub extra_docker_args: Vec<String>,
+    /// Serve the share read-only to a well-known `anonymous` user; `username` is
+    /// ignored and an empty `password` falls back to "anonymous"
+    #[serde(default)]
+    pub anonymous_read_only: bool,
+    /// Octal umask for internal-sftp (e.g. "0022")
+    #[serde(default)]
+    pub umask: Option<String>,
+    /// Octal mode forced on every file created over SFTP, overriding the client
+    #[serde(default)]
+    pub file_mode: Option<String>,
+    /// Octal mode applied to directories dsftp creates; SFTP clients' own mkdirs
+    /// only honour `umask`, as internal-sftp has no forced directory mode
+    #[serde(default)]
+    pub dir_mode: Option<String>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    /// Shown to clients before they log in: usage policy, who to contact
+    #[serde(default)]
+    pub banner: Option<String>,
+    /// Run on an image derived from atmoz/sftp with extra packages; stock when unset
+    #[serde(default)]
+    pub image_build: Option<ImageBuild>,
+    /// Paths hidden from the share, written like .gitignore entries: "node_modules/",
+    /// "*.tmp", "/build/" (at the share root only)
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Kept by recreates; dsftp assigns it, so it is never read from callers or
+    /// written into shared configs
+    #[serde(skip)]
+    pub id: Option<String>,
+}
+
+impl ServerConfig {
+    /// The config a managed server was created from, for recreating it or
+    /// rewriting its startup hook
+    fn from_info(server: &ServerInfo, creds: &StoredCredentials) -> Self {
+        ServerConfig {
+            name: server.name.clone(),
+            port: server.port,
+            host_path: creds.host_path.clone(),
+            container_path: creds.container_path.clone(),
+            username: creds.username.clone(),
+            password: creds.password.clone(),
+            timezone: creds.timezone.clone(),
+            restart_policy: server.restart_policy.unwrap_or_default(),
+            depends_on: creds.depends_on.clone(),
+            extra_docker_args: creds.extra_docker_args.clone(),
+            anonymous_read_only: creds.anonymous_read_only,
+            umask: creds.umask.clone(),
+            file_mode: creds.file_mode.clone(),
+            dir_mode: creds.dir_mode.clone(),
+            rate_limit: creds.rate_limit.clone(),
+            banner: creds.banner.clone(),
+            image_build: creds.image_build.clone(),
+            exclude_patterns: creds.exclude_patterns.clone(),
+            id: creds.id.clone(),
+        }
+    }
+
+    /// A server from the team config repo or a peer; machine-local settings are
+    /// left unset
+    fn from_shared(shared: SharedServer, password: String) -> Self {
+        ServerConfig {
+            name: shared.name,
+            port: shared.port,
+            host_path: shared.host_path,
+            container_path: shared.container_path,
+            username: shared.username,
+            password,
+            timezone: shared.timezone,
+            restart_policy: shared.restart_policy.unwrap_or_default(),
+            depends_on: shared.depends_on,
+            extra_docker_args: shared.extra_docker_args,
+            anonymous_read_only: shared.anonymous_read_only,
+            umask: shared.umask,
+            file_mode: shared.file_mode,
+            dir_mode: shared.dir_mode,
+            exclude_patterns: shared.exclude_patterns,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ServerInfo {
+    /// Stable identity, unlike the container name; `None` until dsftp manages the
+    /// container
+    pub id: Option<String>,
+    pub name: String,
+    pub port: u16,
+    pub host_path: String,
+    pub container_path: String,
+    pub username: String,
+    /// For dsftp's own use only and never serialized; the frontend asks
+    /// `reveal_credentials` when the user wants to see it
+    #[serde(skip)]
+    pub password: String,
+    /// "running" or "stopped"; `state` says why
+    pub status: String,
+    pub state: ServerState,
+    pub created_at: Option<String>,
+    /// When the current run began; `None` while stopped
+    pub started_at: Option<String>,
+    /// Seconds since `started_at`
+    #[ts(type = "number | null")]
+    pub uptime_secs: Option<u64>,
+    pub bind_ip: Option<String>,
+    pub timezone: Option<String>,
+    /// Current policy of the container, as reported by Docker
+    pub restart_policy: Option<RestartPolicy>,
+    pub depends_on: Vec<String>,
+    pub anonymous_read_only: bool,
+    pub umask: Option<String>,
+    pub file_mode: Option<String>,
+    pub dir_mode: Option<String>,
+    pub exclude_patterns: Vec<String>,
+    /// Set when the server is also published through an SSH jump host
+    pub relay: Option<RelayStatus>,
+    pub maintenance: Option<MaintenanceWindow>,
+    pub on_demand: Option<OnDemandConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CommandResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Error of the long-running commands that can be cancelled, telling a hung or
+/// cancelled external command apart from one that failed
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum CommandError {
+    Failed {
+        message: String,
+    },
+    /// Killed after running past its timeout, e.g. because the Docker daemon hung
+    TimedOut {
+        command: String,
+        #[ts(type = "number")]
+        after_secs: u64,
+    },
+    /// Killed because its operation was cancelled with `cancel_operation`
+    Cancelled {
+        command: String,
+    },
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Failed { message } => f.write_str(message),
+            CommandError::TimedOut {
+                command,
+                after_secs,
+            } => write!(f, "{} timed out after {}s", command, after_secs),
+            CommandError::Cancelled { command } => write!(f, "{} was cancelled", command),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed { message }
+    }
+}
+
+struct OperationState {
+    /// Set when the frontend passed an id, which makes the operation cancellable
+    id: Option<String>,
+    /// The timeout or cancellation that killed one of its commands
+    interrupted: Option<CommandError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct BatchStartResult {
+    pub server: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MountResult {
+    pub success: bool,
+    /// Drive letter on Windows, mount directory on macOS
+    pub mount_point: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, TS)]
+#[ts(export)]
+pub struct CreateResult {
+    pub success: bool,
+    pub server: Option<ServerInfo>,
+    pub error: Option<String>,
+    /// Full `docker run` command line, for dry runs
+    #[serde(default)]
+    pub docker_command: Option<Vec<String>>,
+    /// Step that failed, when creation did
+    #[serde(default)]
+    pub failed_step: Option<CreateStep>,
+}
+
+/// Stages of `create_server`, in the order they run
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum CreateStep {
+    Validate,
+    PullImage,
+    /// Hook scripts and the port reservation
+    Prepare,
+    RunContainer,
+    /// Until sshd answers on the published port
+    WaitReady,
+    StoreCredentials,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum CreateStepState {
+    Started,
+    Completed,
+    Failed,
+}
+
+/// Payload of the `create-progress` event
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct CreateProgress {
+    pub server: String,
+    pub step: CreateStep,
+    pub state: CreateStepState,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ValidationIssue {
+    /// `ServerConfig` field the issue is about, or "image"
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, TS)]
+#[ts(export)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(ValidationIssue {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn warn(&mut self, field: &str, message: impl Into<String>) {
+        self.warnings.push(ValidationIssue {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum FileSortKey {
+    #[default]
+    Name,
+    Size,
+    Mtime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct ListFilesOptions {
+    pub include_hidden: bool,
+    /// Glob patterns matched against file names; empty means everything
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub sort_by: FileSortKey,
+    pub descending: bool,
+    #[ts(type = "number")]
+    pub offset: usize,
+    #[ts(type = "number | null")]
+    pub limit: Option<usize>,
+    /// 1 lists only direct children
+    pub depth: u32,
+    /// Skip the listing cache, e.g. for a manual reload
+    pub refresh: bool,
+}
+
+impl Default for ListFilesOptions {
+    fn default() -> Self {
+        Self {
+            include_hidden: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            sort_by: FileSortKey::Name,
+            descending: false,
+            offset: 0,
+            limit: None,
+            depth: 1,
+            refresh: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct FileEntry {
+    /// Relative to the listed directory (just the file name at depth 1)
+    pub name: String,
+    pub path: String,
+    /// True for directories and for symlinks that point at one
+    pub is_dir: bool,
+    #[ts(type = "number")]
+    pub size: u64,
+    /// `size` formatted for the configured locale
+    #[serde(default)]
+    pub size_display: String,
+    #[serde(default)]
+    pub is_symlink: bool,
+    #[serde(default)]
+    pub link_target: Option<String>,
+    /// Symlink whose target doesn't exist
+    #[serde(default)]
+    pub is_broken_link: bool,
+    /// Last modification, seconds since the Unix epoch
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub modified: Option<i64>,
+    /// Symbolic mode as shown by `ls -l`, e.g. "drwxr-xr-x"
+    #[serde(default)]
+    pub permissions: String,
+    #[serde(default)]
+    pub uid: u32,
+    #[serde(default)]
+    pub gid: u32,
+    /// Names from the container's passwd/group, when the ids map to one
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Number of direct children, for directories
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub entry_count: Option<u64>,
+    /// The on-disk name isn't valid UTF-8; `name`/`path` are a lossy rendering and
+    /// can't be used for further operations
+    #[serde(default)]
+    pub lossy_name: bool,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ImagePullProgress {
+    pub image: String,
+    pub layer: Option<String>,
+    pub status: String,
+    #[ts(type = "number")]
+    pub layers_total: usize,
+    #[ts(type = "number")]
+    pub layers_done: usize,
+    pub done: bool,
+}
+
+/// What to do when a transfer's destination already exists
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ConflictPolicy {
+    #[default]
+    Fail,
+    Overwrite,
+    Skip,
+    /// Keep both by appending " (1)", " (2)", ... to the new name
+    Rename,
+    /// Overwrite only when the incoming file is newer than the existing one
+    NewerWins,
+}
+
+/// Outcome of applying a `ConflictPolicy` to one destination path
+enum ConflictAction {
+    Write { path: String, overwrite: bool },
+    Skip,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct UploadConflict {
+    pub local_path: String,
+    pub remote_path: String,
+    #[ts(type = "number")]
+    pub local_size: u64,
+    #[ts(type = "number")]
+    pub remote_size: u64,
+    #[ts(type = "number | null")]
+    pub local_modified: Option<i64>,
+    #[ts(type = "number | null")]
+    pub remote_modified: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, TS)]
+#[ts(export)]
+pub struct UploadResult {
+    pub success: bool,
+    pub transfer_id: String,
+    pub uploaded: Vec<String>,
+    pub skipped: Vec<String>,
+    #[ts(type = "number")]
+    pub bytes_transferred: u64,
+    pub errors: Vec<String>,
+    /// Set when nothing was uploaded because the share is short on space
+    pub insufficient_space: Option<InsufficientSpace>,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct TransferProgress {
+    pub transfer_id: String,
+    pub source: String,
+    pub destination: String,
+    #[ts(type = "number")]
+    pub bytes_transferred: u64,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+    /// Files finished and queued in this transfer; 1 and 1 for a single tree
+    pub files_done: usize,
+    pub files_total: usize,
+    /// Exponentially weighted moving average
+    pub bytes_per_sec: f64,
+    /// Seconds left at the current rate, once there is one
+    #[ts(type = "number | null")]
+    pub eta_secs: Option<u64>,
+    pub started_at: String,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Every running transfer together, so the GUI can rebuild its view after a reload
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ActiveTransfers {
+    pub transfers: Vec<TransferProgress>,
+    #[ts(type = "number")]
+    pub bytes_transferred: u64,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+    pub bytes_per_sec: f64,
+    #[ts(type = "number | null")]
+    pub eta_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TransferResult {
+    pub success: bool,
+    pub transfer_id: String,
+    pub destination_path: Option<String>,
+    #[ts(type = "number")]
+    pub bytes_transferred: u64,
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+// Docker helper functions
+fn new_command(cmd: &str) -> Command {
+    // Set PATH explicitly for macOS to find docker
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new(cmd);
+    #[cfg(target_os = "macos")]
+    {
+        command.env("PATH", "/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin:/sbin:/usr/sbin");
+    }
+    #[cfg(not(target_os = "macos"))]
+    let command = Command::new(cmd);
+
+    command
+}
+
+fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
+    run_command_bytes(cmd, args).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like `run_command`, but keeps stdout as raw bytes (file names need not be UTF-8)
+fn run_command_bytes(cmd: &str, args: &[&str]) -> Result<Vec<u8>, String> {
+    if cmd == "docker" && mock_runtime() {
+        return mock_docker(args);
+    }
+    let output = run_process(cmd, args).map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        // Errors end up in dialogs and the audit log; the arguments may have carried a
+        // password the tool echoed back
+        Err(redact_secrets(&String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+/// How long a command may run before it is killed; `None` lets it run until it
+/// finishes or its operation is cancelled
+fn command_timeout(cmd: &str, args: &[&str]) -> Option<Duration> {
+    match (cmd, args.first().copied()) {
+        ("tar" | "cp", _) | ("docker", Some("pull" | "cp")) => None,
+        ("docker", Some("exec" | "build")) | ("git", _) | ("trivy", _) => {
+            Some(LONG_COMMAND_TIMEOUT)
+        }
+        _ => Some(COMMAND_TIMEOUT),
+    }
+}
+
+/// Run a command to completion, killing it once it outlives `command_timeout` or the
+/// operation it runs for is cancelled. Killing `docker exec` stops the CLI only; the
+/// process inside the container may linger until it finishes.
+fn run_process(cmd: &str, args: &[&str]) -> Result<std::process::Output, CommandError> {
+    run_process_with_input(cmd, args, None)
+}
+
+/// `run_process`, feeding `input` to the command's stdin
+fn run_process_with_input(
+    cmd: &str,
+    args: &[&str],
+    input: Option<Vec<u8>>,
+) -> Result<std::process::Output, CommandError> {
+    let mut child = new_command(cmd)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CommandError::from(e.to_string()))?;
+
+    // Written on its own thread as well, so a child that never reads can't hang us
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        std::thread::spawn(move || stdin.write_all(&input).ok());
+    }
+    // Drain both pipes on their own threads so a chatty child never blocks on a full one
+    let stdout = child.stdout.take().map(read_to_end_in_background);
+    let stderr = child.stderr.take().map(read_to_end_in_background);
+
+    let timeout = command_timeout(cmd, args);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let command = format!("{} {}", cmd, args.first().unwrap_or(&""))
+        .trim()
+        .to_string();
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| CommandError::from(e.to_string()))?
+        {
+            break status;
+        }
+        let interrupted = if operation_cancelled() {
+            Some(CommandError::Cancelled {
+                command: command.clone(),
+            })
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(CommandError::TimedOut {
+                command: command.clone(),
+                after_secs: timeout.unwrap_or_default().as_secs(),
+            })
+        } else {
+            None
+        };
+        if let Some(error) = interrupted {
+            child.kill().ok();
+            child.wait().ok();
+            mark_interrupted(&error);
+            return Err(error);
+        }
+        std::thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+
+    let join = |handle: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        handle.and_then(|h| h.join().ok()).unwrap_or_default()
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: join(stdout),
+        stderr: join(stderr),
+    })
+}
+
+fn read_to_end_in_background<R: Read + Send + 'static>(
+    mut pipe: R,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        pipe.read_to_end(&mut buf).ok();
+        buf
+    })
+}
+
+/// Remember why the current operation's command was killed, so `run_operation`
+/// reports that rather than the command's own error
+fn mark_interrupted(error: &CommandError) {
+    CURRENT_OPERATION.with(|op| {
+        if let Some(op) = op.borrow_mut().as_mut() {
+            op.interrupted = Some(error.clone());
+        }
+    });
+}
+
+fn operation_cancelled() -> bool {
+    CURRENT_OPERATION.with(|op| {
+        op.borrow()
+            .as_ref()
+            .and_then(|op| op.id.as_ref())
+            .is_some_and(|id| {
+                CANCELLED_OPERATIONS
+                    .lock()
+                    .is_ok_and(|cancelled| cancelled.contains(id))
+            })
+    })
+}
+
+/// Run `f` as an operation. With an id, `cancel_operation(id)` kills the command it
+/// is waiting on. A failure caused by a timeout or cancellation comes back as that
+/// variant rather than as the message it bubbled up with.
+fn run_operation<T>(
+    id: Option<String>,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, CommandError> {
+    let previous = CURRENT_OPERATION.with(|op| {
+        op.replace(Some(OperationState {
+            id: id.clone(),
+            interrupted: None,
+        }))
+    });
+    let result = f();
+    let state = CURRENT_OPERATION.with(|op| op.replace(previous));
+    if let Some(id) = &id {
+        if let Ok(mut cancelled) = CANCELLED_OPERATIONS.lock() {
+            cancelled.remove(id);
+        }
+    }
+    result.map_err(|message| {
+        state
+            .and_then(|state| state.interrupted)
+            .unwrap_or(CommandError::Failed { message })
+    })
+}
+
+/// Paths handed to `docker exec` must name exactly one file. Names that came back
+/// lossily decoded (invalid UTF-8 replaced by U+FFFD) can't be addressed again.
+fn check_exec_path(path: &str) -> Result<(), String> {
+    if path.contains('\u{FFFD}') {
+        Err(format!(
+            "'{}' contains bytes that are not valid UTF-8 and can't be addressed",
+            path
+        ))
+    } else if path.contains('\0') {
+        Err("Path contains a NUL byte".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Keep a relative path starting with '-' from being parsed as an option by tools
+/// that don't accept `--` (find, test)
+fn exec_path_arg(path: &str) -> String {
+    if path.starts_with('-') {
+        format!("./{}", path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// Check if a container is using atmoz/sftp image
+fn is_sftp_container(name: &str) -> bool {
+    if let Ok(output) = run_command(
+        "docker",
+        &["inspect", "--format", "{{.Config.Image}}", name],
+    ) {
+        let image = output.trim();
+        return image == SFTP_IMAGE
+            || image.starts_with(&format!("{}:", SFTP_IMAGE))
+            || image.starts_with(&format!("{}/", CUSTOM_IMAGE_REPO));
+    }
+    false
+}
+
+fn image_ref(tag: Option<&str>) -> String {
+    match tag {
+        Some(tag) if !tag.trim().is_empty() => format!("{}:{}", SFTP_IMAGE, tag.trim()),
+        _ => format!("{}:latest", SFTP_IMAGE),
+    }
+}
+
+fn image_exists(image: &str) -> bool {
+    run_command("docker", &["image", "inspect", "--format", "{{.Id}}", image]).is_ok()
+}
+
+/// Split a `docker pull` output line into (layer id, status).
+/// Layer lines look like "a1b2c3d4e5f6: Pull complete"; everything else is global.
+fn parse_pull_line(line: &str) -> (Option<String>, String) {
+    if let Some((id, status)) = line.split_once(": ") {
+        if id.len() == 12 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return (Some(id.to_string()), status.trim().to_string());
+        }
+    }
+    (None, line.trim().to_string())
+}
+
+/// Run `docker pull` and forward each layer update as an `image-pull-progress` event
+fn pull_image_internal<R: Runtime>(app: &AppHandle<R>, image: &str) -> Result<(), String> {
+    let mut child = new_command("docker")
+        .args(["pull", image])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // layer id -> finished
+    let mut layers: HashMap<String, bool> = HashMap::new();
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            // Pulls have no time limit, but the operation running them can be cancelled
+            if operation_cancelled() {
+                child.kill().ok();
+                child.wait().ok();
+                let error = CommandError::Cancelled {
+                    command: "docker pull".to_string(),
+                };
+                mark_interrupted(&error);
+                return Err(error.to_string());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (layer, status) = parse_pull_line(&line);
+            if let Some(ref id) = layer {
+                let finished = status.starts_with("Pull complete")
+                    || status.starts_with("Already exists");
+                let entry = layers.entry(id.clone()).or_insert(false);
+                *entry = *entry || finished;
+            }
+
+            let _ = app.emit(
+                "image-pull-progress",
+                ImagePullProgress {
+                    image: image.to_string(),
+                    layer,
+                    status,
+                    layers_total: layers.len(),
+                    layers_done: layers.values().filter(|done| **done).count(),
+                    done: false,
+                },
+            );
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let success = output.status.success();
+
+    let _ = app.emit(
+        "image-pull-progress",
+        ImagePullProgress {
+            image: image.to_string(),
+            layer: None,
+            status: if success {
+                "Pull complete".to_string()
+            } else {
+                "Pull failed".to_string()
+            },
+            layers_total: layers.len(),
+            layers_done: layers.values().filter(|done| **done).count(),
+            done: true,
+        },
+    );
+
+    if success {
+        record_image_pull(image);
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+/// Make sure the image is available locally, pulling it (with progress events) if not
+fn ensure_image<R: Runtime>(app: &AppHandle<R>, image: &str) -> Result<(), String> {
+    if image_exists(image) {
+        return Ok(());
+    }
+    pull_image_internal(app, image)
+}
+
+// Simulated Docker for machines without it. With DSFTP_MOCK set, every `docker`
+// invocation made through `run_command` is answered from memory: enough of the CLI
+// (run, ps, inspect, start/stop, logs, stats and the exec scripts behind file browsing)
+// for frontend work and integration tests to drive each command. Bind mounts map onto
+// the real host directories, so browsing shows actual files. Commands that stream from
+// a spawned `docker` process (pulls, transfers, streaming listings) are not simulated.
+
+type MockTime = chrono::DateTime<chrono::Utc>;
+
+struct MockContainer {
+    id: String,
+    name: String,
+    image: String,
+    /// Arguments after the image: atmoz's `user:pass:uid` specs
+    args: Vec<String>,
+    env: Vec<String>,
+    /// (host ip, host port, container port)
+    ports: Vec<(String, String, String)>,
+    /// (host source, container destination, read-only)
+    mounts: Vec<(String, String, bool)>,
+    restart_policy: String,
+    created: MockTime,
+    started: Option<MockTime>,
+    finished: Option<MockTime>,
+    running: bool,
+    logs: Vec<(MockTime, String)>,
+}
+
+/// `docker run` options that take a separate value, so it isn't mistaken for the image
+const MOCK_VALUED_FLAGS: &[&str] = &[
+    "--filter",
+    "--format",
+    "--type",
+    "--tail",
+    "--name",
+    "-p",
+    "--publish",
+    "-v",
+    "--volume",
+    "-e",
+    "--env",
+    "--env-file",
+    "--restart",
+    "-m",
+    "--memory",
+    "--memory-swap",
+    "--cpus",
+    "--pids-limit",
+    "--network",
+    "-h",
+    "--hostname",
+    "-l",
+    "--label",
+    "-u",
+    "--user",
+    "-w",
+    "--workdir",
+    "--ulimit",
+    "--dns",
+    "--add-host",
+    "--tmpfs",
+    "--mount",
+    "--cap-drop",
+    "--log-driver",
+    "--log-opt",
+    "--entrypoint",
+    "--platform",
+    "--shm-size",
+    "--stop-signal",
+    "--stop-timeout",
+];
+
+fn mock_runtime() -> bool {
+    std::env::var(MOCK_RUNTIME_ENV).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+fn mock_timestamp(time: Option<MockTime>) -> String {
+    time.map_or_else(
+        || "0001-01-01T00:00:00Z".to_string(),
+        |t| t.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+    )
+}
+
+/// Age in the words `docker ps` uses for its status column
+fn mock_age(since: MockTime) -> String {
+    let secs = (chrono::Utc::now() - since).num_seconds().max(0);
+    match secs {
+        0 => "Less than a second".to_string(),
+        1..=59 => format!("{} seconds", secs),
+        60..=119 => "About a minute".to_string(),
+        120..=3599 => format!("{} minutes", secs / 60),
+        3600..=7199 => "About an hour".to_string(),
+        7200..=172_799 => format!("{} hours", secs / 3600),
+        _ => format!("{} days", secs / 86_400),
+    }
+}
+
+impl MockContainer {
+    fn matches(&self, id_or_name: &str) -> bool {
+        self.name == id_or_name.trim_start_matches('/')
+            || (id_or_name.len() >= 4 && self.id.starts_with(id_or_name))
+    }
+
+    fn status(&self) -> &'static str {
+        match (self.running, self.started) {
+            (true, _) => "running",
+            (false, None) => "created",
+            (false, Some(_)) => "exited",
+        }
+    }
+
+    /// First user of the atmoz spec, who owns the uploaded files
+    fn owner(&self) -> &str {
+        self.args
+            .first()
+            .and_then(|spec| spec.split(':').next())
+            .unwrap_or("root")
+    }
+
+    fn log(&mut self, line: &str) {
+        self.logs.push((chrono::Utc::now(), line.to_string()));
+    }
+
+    fn start(&mut self) {
+        if self.running {
+            return;
+        }
+        self.running = true;
+        self.started = Some(chrono::Utc::now());
+        self.log("[/entrypoint] Executing sshd");
+        self.log("Server listening on 0.0.0.0 port 22.");
+        self.log("Server listening on :: port 22.");
+    }
+
+    fn stop(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.running = false;
+        self.finished = Some(chrono::Utc::now());
+        self.log("Received signal 15; terminating.");
+    }
+
+    /// Host path behind a container path through the longest matching bind mount,
+    /// and whether that mount is read-only
+    fn host_path(&self, path: &str) -> Option<(PathBuf, bool)> {
+        self.mounts
+            .iter()
+            .filter_map(|(source, destination, read_only)| {
+                let rest = path.strip_prefix(destination.trim_end_matches('/'))?;
+                (rest.is_empty() || rest.starts_with('/')).then(|| {
+                    (
+                        destination.len(),
+                        Path::new(source).join(rest.trim_start_matches('/')),
+                        *read_only,
+                    )
+                })
+            })
+            .max_by_key(|(len, _, _)| *len)
+            .map(|(_, host, read_only)| (host, read_only))
+    }
+
+    fn existing_host_path(&self, path: &str) -> Result<PathBuf, String> {
+        self.host_path(path)
+            .map(|(host, _)| host)
+            .filter(|host| host.symlink_metadata().is_ok())
+            .ok_or_else(|| format!("{}: No such file or directory", path))
+    }
+
+    fn writable_host_path(&self, path: &str) -> Result<PathBuf, String> {
+        match self.host_path(path) {
+            Some((_, true)) => Err(format!("{}: Read-only file system", path)),
+            Some((host, false)) => Ok(host),
+            None => Err(format!("{}: No such file or directory", path)),
+        }
+    }
+
+    /// What `docker inspect` prints for this container
+    fn inspect(&self) -> serde_json::Value {
+        let (policy, retries) = self
+            .restart_policy
+            .split_once(':')
+            .unwrap_or((self.restart_policy.as_str(), "0"));
+        let mut bindings: HashMap<String, Vec<serde_json::Value>> = HashMap::new();
+        for (ip, host_port, port) in &self.ports {
+            bindings
+                .entry(format!("{}/tcp", port))
+                .or_default()
+                .push(serde_json::json!({ "HostIp": ip, "HostPort": host_port }));
+        }
+        let mounts: Vec<serde_json::Value> = self
+            .mounts
+            .iter()
+            .map(|(source, destination, read_only)| {
+                serde_json::json!({
+                    "Type": "bind",
+                    "Source": source,
+                    "Destination": destination,
+                    "RW": !read_only,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "Id": self.id,
+            "Name": format!("/{}", self.name),
+            "Created": mock_timestamp(Some(self.created)),
+            "Config": { "Image": self.image, "Env": self.env, "Cmd": self.args },
+            "State": {
+                "Status": self.status(),
+                "Running": self.running,
+                "ExitCode": 0,
+                "OOMKilled": false,
+                "Error": "",
+                "StartedAt": mock_timestamp(self.started),
+                "FinishedAt": mock_timestamp(self.finished),
+            },
+            "HostConfig": {
+                "PortBindings": bindings,
+                "RestartPolicy": {
+                    "Name": policy,
+                    "MaximumRetryCount": retries.parse::<u64>().unwrap_or(0),
+                },
+            },
+            "Mounts": mounts,
+        })
+    }
+
+    /// The fields `docker ps --format` can name
+    fn ps_fields(&self) -> serde_json::Value {
+        let status = match (self.running, self.started, self.finished) {
+            (true, Some(started), _) => format!("Up {}", mock_age(started)),
+            (false, _, Some(finished)) => format!("Exited (0) {} ago", mock_age(finished)),
+            _ => "Created".to_string(),
+        };
+        let ports = if self.running {
+            self.ports
+                .iter()
+                .map(|(ip, host_port, port)| format!("{}:{}->{}/tcp", ip, host_port, port))
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            String::new()
+        };
+        serde_json::json!({
+            "ID": &self.id[..12],
+            "Names": self.name,
+            "Image": self.image,
+            "Status": status,
+            "Ports": ports,
+        })
+    }
+}
+
+/// Expand the subset of Go templates dsftp passes to `--format`: fields, `json` and
+/// `if`/`end` on a field
+fn mock_render(template: &str, data: &serde_json::Value) -> String {
+    let field = |path: &str| {
+        path.trim()
+            .trim_start_matches('.')
+            .split('.')
+            .fold(data, |value, key| &value[key])
+    };
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let action = rest[start + 2..start + len].trim();
+        rest = &rest[start + len + 2..];
+
+        if let Some(condition) = action.strip_prefix("if ") {
+            if field(condition).is_null() {
+                let end = rest
+                    .find("{{end}}")
+                    .map_or(rest.len(), |i| i + "{{end}}".len());
+                rest = &rest[end..];
+            }
+        } else if let Some(path) = action.strip_prefix("json ") {
+            out.push_str(&field(path).to_string());
+        } else if action != "end" {
+            match field(action) {
+                serde_json::Value::String(text) => out.push_str(text),
+                value => out.push_str(&value.to_string()),
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split arguments into flags (with their values) and operands; everything from the
+/// first operand on is an operand, like the image and command of `docker run`
+fn mock_parse_args<'a>(args: &[&'a str]) -> (Vec<(&'a str, &'a str)>, Vec<&'a str>) {
+    let mut flags = Vec::new();
+    let mut operands = Vec::new();
+    let mut iter = args.iter().copied();
+    while let Some(arg) = iter.next() {
+        if !operands.is_empty() || !arg.starts_with('-') {
+            operands.push(arg);
+        } else if let Some((flag, value)) = arg.split_once('=') {
+            flags.push((flag, value));
+        } else if MOCK_VALUED_FLAGS.contains(&arg) {
+            flags.push((arg, iter.next().unwrap_or_default()));
+        } else {
+            flags.push((arg, ""));
+        }
+    }
+    (flags, operands)
+}
+
+fn mock_flag_values<'a>(flags: &[(&str, &'a str)], names: &[&str]) -> Vec<&'a str> {
+    flags
+        .iter()
+        .filter(|(flag, _)| names.contains(flag))
+        .map(|(_, value)| *value)
+        .collect()
+}
+
+/// Last value given for a flag, which is the one docker uses
+fn mock_flag<'a>(flags: &[(&str, &'a str)], names: &[&str]) -> Option<&'a str> {
+    mock_flag_values(flags, names).pop()
+}
+
+fn mock_lines(lines: Vec<String>) -> Vec<u8> {
+    lines
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn mock_find<'c>(
+    containers: &'c mut [MockContainer],
+    id_or_name: &str,
+) -> Result<&'c mut MockContainer, String> {
+    containers
+        .iter_mut()
+        .find(|c| c.matches(id_or_name))
+        .ok_or_else(|| {
+            format!(
+                "Error response from daemon: No such container: {}",
+                id_or_name
+            )
+        })
+}
+
+/// Answer a `docker` command line from the in-memory runtime
+fn mock_docker(args: &[&str]) -> Result<Vec<u8>, String> {
+    let mut containers = MOCK_CONTAINERS
+        .lock()
+        .map_err(|_| "mock runtime state is poisoned".to_string())?;
+    let (command, rest) = args.split_first().ok_or("docker: no command given")?;
+    let (flags, operands) = mock_parse_args(rest);
+    let has = |names: &[&str]| flags.iter().any(|(flag, _)| names.contains(flag));
+
+    match *command {
+        "--version" | "version" => Ok(b"Docker version 27.0.0, build dsftp-mock\n".to_vec()),
+        "image" if operands.first() == Some(&"inspect") => {
+            // Every image is "pulled" already
+            Ok(
+                b"sha256:0000000000000000000000000000000000000000000000000000000000000000\n"
+                    .to_vec(),
+            )
+        }
+        "ps" => {
+            let ancestors: Vec<&str> = flags
+                .iter()
+                .filter(|(flag, _)| *flag == "--filter")
+                .filter_map(|(_, value)| value.strip_prefix("ancestor="))
+                .collect();
+            let format = mock_flag(&flags, &["--format"]).unwrap_or("{{.ID}}\t{{.Names}}");
+            let lines: Vec<String> = containers
+                .iter()
+                .filter(|c| c.running || has(&["-a", "--all", "-aq"]))
+                .filter(|c| {
+                    ancestors.iter().all(|image| {
+                        c.image == *image || c.image.starts_with(&format!("{}:", image))
+                    })
+                })
+                .map(|c| {
+                    if has(&["-q", "--quiet", "-aq"]) {
+                        c.id[..12].to_string()
+                    } else {
+                        mock_render(format, &c.ps_fields())
+                    }
+                })
+                .collect();
+            Ok(mock_lines(lines))
+        }
+        "inspect" => {
+            // Unlike docker, only fails when none of the names exist; missing ones are
+            // skipped, which is all `inspect_container_states` relies on
+            let found: Vec<&MockContainer> = operands
+                .iter()
+                .filter_map(|name| containers.iter().find(|c| c.matches(name)))
+                .collect();
+            if found.is_empty() {
+                return Err(format!("Error: No such object: {}", operands.join(" ")));
+            }
+            match mock_flag(&flags, &["--format", "-f"]) {
+                Some(format) => Ok(mock_lines(
+                    found
+                        .iter()
+                        .map(|c| mock_render(format, &c.inspect()))
+                        .collect(),
+                )),
+                None => {
+                    let all: Vec<serde_json::Value> = found.iter().map(|c| c.inspect()).collect();
+                    serde_json::to_vec_pretty(&all).map_err(|e| e.to_string())
+                }
+            }
+        }
+        "run" => {
+            let Some((image, args)) = operands.split_first() else {
+                return Err("docker: 'docker run' requires at least 1 argument".to_string());
+            };
+            let id = format!("{}{}", random_token(), random_token());
+            let name = mock_flag(&flags, &["--name"])
+                .map_or_else(|| format!("mock_{}", &id[..8]), |name| name.to_string());
+            if containers.iter().any(|c| c.name == name) {
+                return Err(format!(
+                    "docker: Error response from daemon: Conflict. The container name \"/{}\" is already in use.",
+                    name
+                ));
+            }
+
+            let mut ports = Vec::new();
+            for spec in mock_flag_values(&flags, &["-p", "--publish"]) {
+                let parts: Vec<&str> = spec.split(':').collect();
+                let (ip, host_port, port) = match parts.as_slice() {
+                    [ip, host_port, port] => (*ip, *host_port, *port),
+                    [host_port, port] => ("0.0.0.0", *host_port, *port),
+                    _ => return Err(format!("docker: Invalid port spec: {}", spec)),
+                };
+                let taken = containers.iter().filter(|c| c.running).any(|c| {
+                    c.ports.iter().any(|(other_ip, other_port, _)| {
+                        other_port == host_port && bindings_conflict(ip, other_ip)
+                    })
+                });
+                if taken {
+                    return Err(format!(
+                        "docker: Error response from daemon: driver failed programming external connectivity on endpoint {}: Bind for {}:{} failed: port is already allocated.",
+                        name, ip, host_port
+                    ));
+                }
+                ports.push((
+                    ip.to_string(),
+                    host_port.to_string(),
+                    port.trim_end_matches("/tcp").to_string(),
+                ));
+            }
+
+            let mut mounts = Vec::new();
+            for spec in mock_flag_values(&flags, &["-v", "--volume"]) {
+                let (spec, read_only) = match spec.strip_suffix(":ro") {
+                    Some(spec) => (spec, true),
+                    None => (spec.strip_suffix(":rw").unwrap_or(spec), false),
+                };
+                // The destination is absolute; the source may carry a drive letter
+                let Some(split) = spec.rfind(":/") else {
+                    return Err(format!("docker: Invalid volume spec: {}", spec));
+                };
+                mounts.push((
+                    spec[..split].to_string(),
+                    spec[split + 1..].to_string(),
+                    read_only,
+                ));
+            }
+
+            let now = chrono::Utc::now();
+            let mut container = MockContainer {
+                id: id.clone(),
+                name,
+                image: image.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+                env: mock_flag_values(&flags, &["-e", "--env"])
+                    .into_iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+                ports,
+                mounts,
+                restart_policy: mock_flag(&flags, &["--restart"])
+                    .unwrap_or("no")
+                    .to_string(),
+                created: now,
+                started: None,
+                finished: None,
+                running: false,
+                logs: Vec::new(),
+            };
+            container.start();
+            containers.push(container);
+            Ok(format!("{}\n", id).into_bytes())
+        }
+        "start" | "stop" | "restart" => {
+            for name in &operands {
+                let container = mock_find(&mut containers, name)?;
+                if *command != "start" {
+                    container.stop();
+                }
+                if *command != "stop" {
+                    container.start();
+                }
+            }
+            Ok(mock_lines(operands.iter().map(|n| n.to_string()).collect()))
+        }
+        "rm" => {
+            for name in &operands {
+                let container = mock_find(&mut containers, name)?;
+                if container.running && !has(&["-f", "--force"]) {
+                    return Err(format!(
+                        "Error response from daemon: cannot remove container \"/{}\": container is running: stop the container before removing or force remove",
+                        container.name
+                    ));
+                }
+                containers.retain(|c| !c.matches(name));
+            }
+            Ok(mock_lines(operands.iter().map(|n| n.to_string()).collect()))
+        }
+        "update" => {
+            let policy = mock_flag(&flags, &["--restart"]);
+            for name in &operands {
+                let container = mock_find(&mut containers, name)?;
+                if let Some(policy) = policy {
+                    container.restart_policy = policy.to_string();
+                }
+            }
+            Ok(mock_lines(operands.iter().map(|n| n.to_string()).collect()))
+        }
+        "logs" => {
+            let name = operands
+                .first()
+                .ok_or("docker: 'docker logs' requires 1 argument")?;
+            let container = mock_find(&mut containers, name)?;
+            let tail = mock_flag(&flags, &["--tail", "-n"])
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(usize::MAX);
+            let timestamps = has(&["--timestamps", "-t"]);
+            let skip = container.logs.len().saturating_sub(tail);
+            Ok(mock_lines(
+                container.logs[skip..]
+                    .iter()
+                    .map(|(time, line)| {
+                        if timestamps {
+                            format!("{} {}", mock_timestamp(Some(*time)), line)
+                        } else {
+                            line.clone()
+                        }
+                    })
+                    .collect(),
+            ))
+        }
+        "stats" => {
+            let format = mock_flag(&flags, &["--format"])
+                .unwrap_or("{{.Name}}\t{{.CPUPerc}}\t{{.MemUsage}}");
+            let mut lines = Vec::new();
+            for name in &operands {
+                let container = mock_find(&mut containers, name)?;
+                if container.running {
+                    let fields = serde_json::json!({
+                        "Name": container.name,
+                        "CPUPerc": "0.02%",
+                        "MemUsage": "3.4MiB / 1.944GiB",
+                    });
+                    lines.push(mock_render(format, &fields));
+                }
+            }
+            Ok(mock_lines(lines))
+        }
+        "top" => {
+            let name = operands
+                .first()
+                .ok_or("docker: 'docker top' requires 1 argument")?;
+            let container = mock_find(&mut containers, name)?;
+            if !container.running {
+                return Err(format!(
+                    "Error response from daemon: container {} is not running",
+                    container.id
+                ));
+            }
+            Ok(b"UID PID PPID C STIME TTY TIME CMD\nroot 1 0 0 00:00 ? 00:00:00 sshd: /usr/sbin/sshd -D -e [listener] 0 of 10-100 startups\n".to_vec())
+        }
+        "exec" => {
+            let Some((name, command)) = operands.split_first() else {
+                return Err("docker: 'docker exec' requires at least 2 arguments".to_string());
+            };
+            let container = mock_find(&mut containers, name)?;
+            if !container.running {
+                return Err(format!(
+                    "Error response from daemon: container {} is not running",
+                    container.id
+                ));
+            }
+            mock_exec(container, command)
+        }
+        other => Err(format!(
+            "docker {}: not simulated by the mock runtime",
+            other
+        )),
+    }
+}
+
+/// The commands dsftp runs inside containers, answered against the bind-mounted host
+/// directories
+fn mock_exec(container: &MockContainer, command: &[&str]) -> Result<Vec<u8>, String> {
+    match command {
+        ["sh", "-c", script, _, root, depth, ..] if *script == LIST_FILES_SCRIPT => {
+            let host_root = container
+                .host_path(root)
+                .map(|(host, _)| host)
+                .filter(|host| host.is_dir())
+                .ok_or_else(|| format!("No such directory: {}\n", root))?;
+            let mut out = Vec::new();
+            mock_list_dir(
+                container.owner(),
+                &host_root,
+                root.trim_end_matches('/'),
+                "",
+                depth.parse().unwrap_or(1),
+                &mut out,
+            );
+            Ok(out)
+        }
+        ["sh", "-c", script, _, dirs @ ..] if *script == COUNT_CHILDREN_SCRIPT => Ok(mock_lines(
+            dirs.iter()
+                .map(|dir| {
+                    container
+                        .host_path(dir)
+                        .and_then(|(host, _)| fs::read_dir(host).ok())
+                        .map_or(0, |entries| entries.count())
+                        .to_string()
+                })
+                .collect(),
+        )),
+        ["sh", "-c", script] if *script == LIST_SESSIONS_SCRIPT => {
+            // /proc/uptime of the container, and no clients connected
+            let uptime = container
+                .started
+                .map_or(0, |started| (chrono::Utc::now() - started).num_seconds());
+            Ok(format!("{}.00 0.00\n", uptime).into_bytes())
+        }
+        ["test", "-e", path] => container
+            .existing_host_path(path)
+            .map(|_| Vec::new())
+            .map_err(|_| String::new()),
+        ["stat", "-c", "%Y", "--", path] => {
+            let host = container.existing_host_path(path)?;
+            let mtime = fs::symlink_metadata(host)
+                .ok()
+                .and_then(|meta| local_mtime(&meta))
+                .unwrap_or(0);
+            Ok(format!("{}\n", mtime).into_bytes())
+        }
+        ["du", "-sb", "--", path] => {
+            let host = container.existing_host_path(path)?;
+            Ok(format!("{}\t{}\n", mock_tree_size(&host), path).into_bytes())
+        }
+        ["df", "-P", "--", path] => {
+            let host = container.existing_host_path(path)?;
+            let total = fs2::total_space(&host).map_err(|e| e.to_string())?;
+            let available = fs2::available_space(&host).map_err(|e| e.to_string())?;
+            let used = total.saturating_sub(available);
+            let capacity = (used * 100).div_ceil(total.max(1));
+            Ok(format!(
+                "Filesystem 1024-blocks Used Available Capacity Mounted on\nmock {} {} {} {}% {}\n",
+                total / 1024,
+                used / 1024,
+                available / 1024,
+                capacity,
+                path
+            )
+            .into_bytes())
+        }
+        ["mkdir", "-p", "--", paths @ ..] => {
+            for path in paths {
+                let host = container.writable_host_path(path)?;
+                fs::create_dir_all(host).map_err(|e| format!("{}: {}", path, e))?;
+            }
+            Ok(Vec::new())
+        }
+        ["rm", "-f", "--", paths @ ..] => {
+            for path in paths {
+                let host = container.writable_host_path(path)?;
+                fs::remove_file(host).ok();
+            }
+            Ok(Vec::new())
+        }
+        // Signals and account changes have nothing to act on
+        ["kill", ..] | ["usermod", ..] => Ok(Vec::new()),
+        _ => Err(format!(
+            "{}: not simulated by the mock runtime",
+            command.first().unwrap_or(&"exec")
+        )),
+    }
+}
+
+fn mock_file_type(meta: &fs::Metadata) -> &'static str {
+    if meta.file_type().is_symlink() {
+        "l"
+    } else if meta.is_dir() {
+        "d"
+    } else {
+        "f"
+    }
+}
+
+/// `ls -l` style mode string, like find's %M
+fn mock_mode(meta: &fs::Metadata) -> String {
+    #[cfg(unix)]
+    let bits = std::os::unix::fs::PermissionsExt::mode(&meta.permissions());
+    #[cfg(not(unix))]
+    let bits = match (meta.is_dir(), meta.permissions().readonly()) {
+        (true, false) => 0o755,
+        (true, true) => 0o555,
+        (false, false) => 0o644,
+        (false, true) => 0o444,
+    };
+    let mut mode = String::from(match mock_file_type(meta) {
+        "d" => 'd',
+        "l" => 'l',
+        _ => '-',
+    });
+    for shift in [6, 3, 0] {
+        let bits = bits >> shift;
+        mode.push(if bits & 4 != 0 { 'r' } else { '-' });
+        mode.push(if bits & 2 != 0 { 'w' } else { '-' });
+        mode.push(if bits & 1 != 0 { 'x' } else { '-' });
+    }
+    mode
+}
+
+/// Print LIST_FILES_FORMAT records for the entries under `dir`, down to `depth` levels
+fn mock_list_dir(
+    owner: &str,
+    dir: &Path,
+    root: &str,
+    relative: &str,
+    depth: u32,
+    out: &mut Vec<u8>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = if relative.is_empty() {
+            name
+        } else {
+            format!("{}/{}", relative, name)
+        };
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0.0, |d| d.as_secs_f64());
+        let fields = [
+            mock_file_type(&meta).to_string(),
+            fs::metadata(&path)
+                .map_or("N", |target| mock_file_type(&target))
+                .to_string(),
+            meta.len().to_string(),
+            format!("{:.10}", mtime),
+            mock_mode(&meta),
+            "1001".to_string(),
+            "100".to_string(),
+            owner.to_string(),
+            "users".to_string(),
+            fs::read_link(&path)
+                .map(|link| link.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            relative.clone(),
+            format!("{}/{}", root, relative),
+        ];
+        for field in fields {
+            out.extend_from_slice(field.as_bytes());
+            out.push(0);
+        }
+        if depth > 1 && meta.is_dir() {
+            mock_list_dir(owner, &path, root, &relative, depth - 1, out);
+        }
+    }
+}
+
+fn mock_tree_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| mock_tree_size(&entry.path()))
+        .sum()
+}
+
+#[tauri::command]
+fn check_docker() -> bool {
+    run_command("docker", &["--version"]).is_ok()
+}
+
+#[tauri::command]
+fn get_local_ip() -> String {
+    // Cross-platform: Try different methods to get local IP
+
+    // Method 1: Linux - use hostname -I (GNU extension, not available on macOS)
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(output) = run_command("hostname", &["-I"]) {
+            if let Some(ip) = output.trim().split_whitespace().next() {
+                if !ip.is_empty() && ip != "127.0.0.1" {
+                    return ip.to_string();
+                }
+            }
+        }
+    }
+
+    // Method 2: macOS - use ipconfig getifaddr with dynamic interface discovery
+    #[cfg(target_os = "macos")]
+    {
+        // Get list of network services dynamically
+        if let Ok(output) = run_command("sh", &["-c", "ifconfig -l"]) {
+            for iface in output.trim().split_whitespace() {
+                // Skip loopback and other non-ethernet interfaces
+                if iface.starts_with("lo") || iface.starts_with("gif") || iface.starts_with("stf") {
+                    continue;
+                }
+                if let Ok(ip_output) = run_command("ipconfig", &["getifaddr", iface]) {
+                    let ip = ip_output.trim();
+                    if !ip.is_empty() && !ip.starts_with("127.") {
+                        return ip.to_string();
+                    }
+                }
+            }
+        }
+        // Fallback to common interface names
+        for iface in &["en0", "en1", "en2", "en3", "en4", "en5", "en10", "en11"] {
+            if let Ok(output) = run_command("ipconfig", &["getifaddr", iface]) {
+                let ip = output.trim();
+                if !ip.is_empty() {
+                    return ip.to_string();
+                }
+            }
+        }
+    }
+
+    // Method 3: Windows - use PowerShell (includes both DHCP and static IPs)
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(output) = run_command("powershell", &[
+            "-Command",
+            "(Get-NetIPAddress -AddressFamily IPv4 | Where-Object {$_.InterfaceAlias -notlike '*Loopback*' -and $_.IPAddress -notlike '127.*' -and $_.IPAddress -notlike '169.254.*'}).IPAddress | Select-Object -First 1"
+        ]) {
+            let ip = output.trim().to_string();
+            if !ip.is_empty() {
+                return ip;
+            }
+        }
+    }
+
+    // Fallback
+    "127.0.0.1".to_string()
+}
+
+#[tauri::command]
+fn list_servers() -> Vec<ServerInfo> {
+    // Load stored credentials
+    let stored_creds = load_credentials();
+
+    // List only atmoz/sftp containers
+    let result = run_command(
+        "docker",
+        &[
+            "ps",
+            "-a",
+            "--filter",
+            &format!("ancestor={}", SFTP_IMAGE),
+            "--format",
+            "{{.Names}}|{{.Status}}|{{.Ports}}",
+        ],
+    );
+
+    match result {
+        Ok(output) => {
+            if output.trim().is_empty() {
+                return vec![];
+            }
+
+            let names: Vec<String> = output
+                .trim()
+                .lines()
+                .filter_map(|line| line.split('|').next())
+                .map(|name| name.to_string())
+                .collect();
+            let states = inspect_container_states(&names);
+            let running: Vec<(String, String, u16)> = output
+                .trim()
+                .lines()
+                .filter_map(|line| {
+                    let parts: Vec<&str> = line.split('|').collect();
+                    let name = parts.first()?.to_string();
+                    let bind_ip = stored_creds
+                        .get(&name)
+                        .and_then(|c| c.bind_ip.clone())
+                        .or_else(|| extract_bind_ip(parts.get(2)?))
+                        .unwrap_or_default();
+                    let port = extract_port(parts.get(2)?);
+                    parts
+                        .get(1)?
+                        .contains("Up")
+                        .then_some((name, bind_ip, port))
+                })
+                .collect();
+            let answering = probe_servers(&running);
+
+            output
+                .trim()
+                .lines()
+                .filter_map(|line| {
+                    let parts: Vec<&str> = line.split('|').collect();
+                    if parts.len() >= 3 {
+                        let name = parts[0].to_string();
+                        let status = if parts[1].contains("Up") {
+                            "running"
+                        } else {
+                            "stopped"
+                        };
+                        let ports_str = parts[2];
+                        let port = extract_port(ports_str);
+                        // Extract bind IP from Docker ports info (e.g., "192.168.1.100:2222->22/tcp")
+                        let docker_bind_ip = extract_bind_ip(ports_str);
+
+                        // Get stored credentials for this server
+                        let (username, password, host_path, container_path, stored_bind_ip) =
+                            if let Some(creds) = stored_creds.get(&name) {
+                                (
+                                    creds.username.clone(),
+                                    creds.password.clone(),
+                                    creds.host_path.clone(),
+                                    creds.container_path.clone(),
+                                    creds.bind_ip.clone(),
+                                )
+                            } else {
+                                (String::new(), String::new(), String::new(), String::new(), None)
+                            };
+                        let timezone = stored_creds.get(&name).and_then(|c| c.timezone.clone());
+                        let depends_on = stored_creds
+                            .get(&name)
+                            .map(|c| c.depends_on.clone())
+                            .unwrap_or_default();
+                        let anonymous_read_only = stored_creds
+                            .get(&name)
+                            .is_some_and(|c| c.anonymous_read_only);
+                        let (umask, file_mode, dir_mode) = stored_creds
+                            .get(&name)
+                            .map(|c| (c.umask.clone(), c.file_mode.clone(), c.dir_mode.clone()))
+                            .unwrap_or_default();
+                        let exclude_patterns = stored_creds
+                            .get(&name)
+                            .map(|c| c.exclude_patterns.clone())
+                            .unwrap_or_default();
+                        let maintenance =
+                            stored_creds.get(&name).and_then(|c| c.maintenance.clone());
+                        let on_demand = stored_creds.get(&name).and_then(|c| c.on_demand.clone());
+                        let relay = stored_creds
+                            .get(&name)
+                            .and_then(|c| c.relay.as_ref())
+                            .map(|config| relay_status(&name, config));
+
+                        // Use stored bind_ip if available, otherwise use Docker's bind IP
+                        let bind_ip = stored_bind_ip.or(docker_bind_ip);
+
+                        let state = states.get(&name);
+                        let created_at = state.and_then(|s| s.created_at.clone());
+                        let started_at = state
+                            .filter(|_| status == "running")
+                            .and_then(|s| s.started_at.clone());
+                        let uptime_secs = started_at.as_deref().and_then(seconds_since);
+                        let restart_policy = state.and_then(|s| s.restart_policy);
+                        let server_state = server_state(
+                            state,
+                            &host_path,
+                            port,
+                            answering.get(&name).copied(),
+                            uptime_secs,
+                        );
+
+                        Some(ServerInfo {
+                            id: stored_creds.get(&name).and_then(|c| c.id.clone()),
+                            name,
+                            port,
+                            host_path,
+                            container_path,
+                            username,
+                            password,
+                            status: status.to_string(),
+                            state: server_state,
+                            created_at,
+                            started_at,
+                            uptime_secs,
+                            bind_ip,
+                            timezone,
+                            restart_policy,
+                            depends_on,
+                            anonymous_read_only,
+                            umask,
+                            file_mode,
+                            dir_mode,
+                            exclude_patterns,
+                            relay,
+                            maintenance,
+                            on_demand,
+                        })
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        }
+        Err(_) => vec![],
+    }
+}
+
+fn extract_port(ports_str: &str) -> u16 {
+    // Parse "0.0.0.0:2222->22/tcp" format
+    if let Some(start) = ports_str.find(':') {
+        if let Some(end) = ports_str.find("->") {
+            if let Ok(port) = ports_str[start + 1..end].parse() {
+                return port;
+            }
+        }
+    }
+    0
+}
+
+fn extract_bind_ip(ports_str: &str) -> Option<String> {
+    // Parse "192.168.1.100:2222->22/tcp" or "0.0.0.0:2222->22/tcp" format
+    if let Some(colon_pos) = ports_str.find(':') {
+        let ip = &ports_str[..colon_pos];
+        if !ip.is_empty() {
+            return Some(ip.to_string());
+        }
+    }
+    None
+}
+
+/// Published host ports of every container (running or not), read from HostConfig
+fn docker_port_bindings() -> Vec<PortAllocation> {
+    let ids = match run_command("docker", &["ps", "-aq"]) {
+        Ok(output) => output,
+        Err(_) => return vec![],
+    };
+    let ids: Vec<&str> = ids.split_whitespace().collect();
+    if ids.is_empty() {
+        return vec![];
+    }
+
+    let mut args = vec![
+        "inspect",
+        "--format",
+        "{{.Name}}|{{json .HostConfig.PortBindings}}",
+    ];
+    args.extend(ids);
+
+    let output = match run_command("docker", &args) {
+        Ok(output) => output,
+        Err(_) => return vec![],
+    };
+
+    let mut allocations = Vec::new();
+    for line in output.lines() {
+        let Some((name, bindings)) = line.split_once('|') else {
+            continue;
+        };
+        let bindings: HashMap<String, Option<Vec<HashMap<String, String>>>> =
+            serde_json::from_str(bindings).unwrap_or_default();
+
+        for binding in bindings.values().flatten().flatten() {
+            let port = binding
+                .get("HostPort")
+                .and_then(|p| p.parse::<u16>().ok())
+                .unwrap_or(0);
+            if port == 0 {
+                continue;
+            }
+            let ip = binding
+                .get("HostIp")
+                .filter(|ip| !ip.is_empty())
+                .cloned()
+                .unwrap_or_else(|| "0.0.0.0".to_string());
+            allocations.push(PortAllocation {
+                port,
+                ip,
+                server: name.trim_start_matches('/').to_string(),
+                state: "bound".to_string(),
+            });
+        }
+    }
+    allocations
+}
+
+/// Two bindings collide when they share a port and either side listens on all interfaces
+fn bindings_conflict(ip_a: &str, ip_b: &str) -> bool {
+    ip_a == ip_b || ip_a == "0.0.0.0" || ip_b == "0.0.0.0"
+}
+
+/// Pick or validate a port for a new server and reserve it until the guard is dropped
+fn reserve_port<'a>(
+    reservations: &'a PortReservations,
+    settings: &AppSettings,
+    requested: u16,
+    bind_ip: &str,
+    server: &str,
+) -> Result<PortReservation<'a>, String> {
+    let bound = docker_port_bindings();
+    let mut reserved = reservations.0.lock().map_err(|e| e.to_string())?;
+
+    let is_free = |port: u16| {
+        !reserved.contains_key(&port)
+            && !bound
+                .iter()
+                .any(|a| a.port == port && bindings_conflict(&a.ip, bind_ip))
+    };
+
+    let port = if requested == 0 {
+        let range = settings.port_range.unwrap_or(PortRange {
+            start: 2222,
+            end: 2299,
+        });
+        (range.start..=range.end)
+            .find(|p| is_free(*p))
+            .ok_or_else(|| format!("No free port in range {}-{}", range.start, range.end))?
+    } else {
+        if let Some(range) = settings.port_range {
+            if !range.contains(requested) {
+                return Err(format!(
+                    "Port {} is outside the allowed range {}-{}",
+                    requested, range.start, range.end
+                ));
+            }
+        }
+        if let Some(holder) = reserved.get(&requested) {
+            return Err(format!(
+                "Port {} is reserved by pending server '{}'",
+                requested, holder
+            ));
+        }
+        if let Some(a) = bound
+            .iter()
+            .find(|a| a.port == requested && bindings_conflict(&a.ip, bind_ip))
+        {
+            return Err(format!(
+                "Port {} is already used by '{}' on {}",
+                requested, a.server, a.ip
+            ));
+        }
+        requested
+    };
+
+    reserved.insert(port, server.to_string());
+    Ok(PortReservation { reservations, port })
+}
+
+struct PortReservation<'a> {
+    reservations: &'a PortReservations,
+    port: u16,
+}
+
+impl Drop for PortReservation<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut reserved) = self.reservations.0.lock() {
+            reserved.remove(&self.port);
+        }
+    }
+}
+
+#[tauri::command]
+fn get_port_range() -> Option<PortRange> {
+    load_app_settings().port_range
+}
+
+#[tauri::command]
+fn set_port_range(range: Option<PortRange>) -> CommandResult {
+    if let Some(r) = range {
+        if r.start == 0 || r.start > r.end {
+            return CommandResult {
+                success: false,
+                error: Some(format!("Invalid port range {}-{}", r.start, r.end)),
+            };
+        }
+    }
+
+    update_app_settings(|settings| settings.port_range = range);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+fn current_locale() -> String {
+    load_app_settings().locale.unwrap_or_default()
+}
+
+/// Primary language subtag, lowercased: "ko-KR" -> "ko"
+fn locale_language(locale: &str) -> String {
+    locale.split(['-', '_']).next().unwrap_or("").to_lowercase()
+}
+
+/// Languages that write 1,5 rather than 1.5
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "cs", "da", "de", "es", "fi", "fr", "id", "it", "nb", "nl", "pl", "pt", "ru", "sv", "tr", "uk",
+];
+
+/// Human size in binary units with one decimal, matching the GUI ("1.5 MB")
+fn format_size(bytes: u64, locale: &str) -> String {
+    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let mut number = format!("{:.1}", value);
+    if DECIMAL_COMMA_LANGUAGES.contains(&locale_language(locale).as_str()) {
+        number = number.replace('.', ",");
+    }
+    format!("{} {}", number, UNITS[unit])
+}
+
+/// The two largest non-zero units of a duration: "3d 4h", "3일 4시간"
+fn format_duration(secs: u64, locale: &str) -> String {
+    let units: [&str; 4] = match locale_language(locale).as_str() {
+        "ko" => ["일", "시간", "분", "초"],
+        "ja" => ["日", "時間", "分", "秒"],
+        "zh" => ["天", "小时", "分钟", "秒"],
+        _ => ["d", "h", "m", "s"],
+    };
+    let parts = [
+        secs / 86_400,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+    ];
+    let shown: Vec<String> = parts
+        .iter()
+        .zip(units)
+        .skip_while(|(value, _)| **value == 0)
+        .take(2)
+        .filter(|(value, _)| **value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+    if shown.is_empty() {
+        format!("0{}", units[3])
+    } else {
+        shown.join(" ")
+    }
+}
+
+#[tauri::command]
+fn get_locale() -> Option<String> {
+    load_app_settings().locale
+}
+
+/// Set the language used for formatted sizes and durations, so the CLI and
+/// notifications match the GUI; `None` falls back to English
+#[tauri::command]
+fn set_locale(locale: Option<String>) -> CommandResult {
+    let locale = locale
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty());
+    if let Some(l) = &locale {
+        if !l
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return CommandResult {
+                success: false,
+                error: Some(format!("Invalid locale: {}", l)),
+            };
+        }
+    }
+
+    update_app_settings(|settings| settings.locale = locale);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct StoragePaths {
+    /// Settings, server definitions and secrets
+    pub config_dir: String,
+    /// Backups, history, logs and captured host keys
+    pub data_dir: String,
+    /// The data directory was moved with `set_data_dir`
+    pub custom_data_dir: bool,
+    pub audit_log: String,
+    pub history: String,
+    pub backups: String,
+    pub host_keys: String,
+    pub removed_servers: String,
+    pub snapshots: String,
+}
+
+#[tauri::command]
+fn get_storage_paths() -> StoragePaths {
+    let data_dir = get_data_dir();
+    let path = |p: PathBuf| p.to_string_lossy().to_string();
+    StoragePaths {
+        config_dir: path(get_config_dir()),
+        data_dir: path(data_dir.clone()),
+        custom_data_dir: load_app_settings().data_dir.is_some(),
+        audit_log: path(get_audit_log_path()),
+        history: path(get_history_path()),
+        backups: path(data_dir.join(BACKUP_DIR)),
+        host_keys: path(data_dir.join(HOST_IDENTITY_DIR)),
+        removed_servers: path(data_dir.join(REMOVED_DIR)),
+        snapshots: path(data_dir.join(SNAPSHOTS_DIR)),
+    }
+}
+
+/// Rename `from` to `to`, copying and then deleting when they are on different disks
+fn move_entry(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        copy_tree(from, to)?;
+        fs::remove_dir_all(from)
+    } else {
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    }
+}
+
+/// Move every data entry in `from_dir` that `to_dir` lacks. Links left behind by an
+/// earlier move are not data and stay put.
+fn move_data_entries(from_dir: &Path, to_dir: &Path) -> Result<(), String> {
+    for entry in DATA_ENTRIES {
+        let (from, to) = (from_dir.join(entry), to_dir.join(entry));
+        let is_data = fs::symlink_metadata(&from).is_ok_and(|m| !m.file_type().is_symlink());
+        if !is_data || to.exists() {
+            continue;
+        }
+        move_entry(&from, &to).map_err(|e| format!("Failed to move {}: {}", from.display(), e))?;
+        #[cfg(unix)]
+        if MOUNTED_DATA_ENTRIES.contains(entry) {
+            std::os::unix::fs::symlink(&to, &from).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Move data older versions kept in the config directory into the data directory.
+/// Run once at startup; anything the data directory already has is left alone.
+fn migrate_data_dir() {
+    let (config_dir, data_dir) = (get_config_dir(), get_data_dir());
+    if config_dir == data_dir {
+        return;
+    }
+    let _lock = lock_config(true);
+    if let Err(e) = move_data_entries(&config_dir, &data_dir) {
+        log::error!("Failed to migrate data directory: {}", e);
+    }
+}
+
+/// Move the data directory to `path`, e.g. a bigger disk, or back to the platform
+/// default when `None`. Servers mounting captured host keys or snapshots keep
+/// finding them through a link left at the old place.
+#[tauri::command]
+async fn set_data_dir(path: Option<String>) -> CommandResult {
+    let fail = |error: String| CommandResult {
+        success: false,
+        error: Some(error),
+    };
+    if std::env::var_os(DATA_DIR_ENV).is_some() {
+        return fail(format!("The data directory is set by {}", DATA_DIR_ENV));
+    }
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if path.as_deref().is_some_and(|p| !Path::new(p).is_absolute()) {
+        return fail("The data directory must be an absolute path".to_string());
+    }
+
+    let (from, to) = (get_data_dir(), resolve_data_dir(path.as_deref()));
+    if let Err(e) = fs::create_dir_all(&to) {
+        return fail(format!("Cannot use {}: {}", to.display(), e));
+    }
+    let same = match (fs::canonicalize(&from), fs::canonicalize(&to)) {
+        (Ok(from), Ok(to)) => from == to,
+        _ => false,
+    };
+    if !same {
+        if let Some(entry) = DATA_ENTRIES.iter().find(|e| to.join(e).exists()) {
+            return fail(format!("{} already has a {}", to.display(), entry));
+        }
+        let (source, dest) = (from.clone(), to.clone());
+        let moved = tauri::async_runtime::spawn_blocking(move || {
+            let _lock = lock_config(true);
+            move_data_entries(&source, &dest)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        if let Err(e) = moved {
+            return fail(e);
+        }
+    }
+
+    update_app_settings(|settings| settings.data_dir = path);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+fn list_port_allocations(reservations: State<'_, PortReservations>) -> Vec<PortAllocation> {
+    let mut allocations = docker_port_bindings();
+    if let Ok(reserved) = reservations.0.lock() {
+        for (port, server) in reserved.iter() {
+            allocations.push(PortAllocation {
+                port: *port,
+                ip: String::new(),
+                server: server.clone(),
+                state: "reserved".to_string(),
+            });
+        }
+    }
+    allocations.sort_by(|a, b| a.port.cmp(&b.port).then_with(|| a.ip.cmp(&b.ip)));
+    allocations
+}
+
+#[tauri::command]
+async fn pull_image(
+    app: AppHandle,
+    tag: Option<String>,
+    operation_id: Option<String>,
+) -> CommandResult {
+    let image = image_ref(tag.as_deref());
+    // Pulling can take minutes; keep it off the async worker threads
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        run_operation(operation_id, || pull_image_internal(&app, &image)).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// What dsftp knows about one image it pulled, keyed by reference
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct ImageRecord {
+    /// Registry digest of the local copy, e.g. "sha256:…"
+    pub digest: Option<String>,
+    pub pulled_at: Option<String>,
+    /// Digest the registry served at the last `verify_image`
+    pub registry_digest: Option<String>,
+    pub verified_at: Option<String>,
+    pub scan: Option<VulnerabilityScan>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct VulnerabilityScan {
+    pub scanner: String,
+    pub scanned_at: String,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImageVerification {
+    pub image: String,
+    pub local_digest: Option<String>,
+    pub registry_digest: Option<String>,
+    /// The registry has a different image under this tag
+    pub update_available: bool,
+    pub scan: Option<VulnerabilityScan>,
+    /// Why no scan ran, e.g. trivy isn't installed
+    pub scan_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum SecurityState {
+    UpToDate,
+    /// A newer image exists, in the registry or already pulled but not yet used
+    UpdateAvailable,
+    /// The last scan found critical or high severity vulnerabilities
+    Vulnerable,
+    /// The image was never checked with `verify_image`
+    Unverified,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ServerSecurity {
+    pub server: String,
+    pub image: String,
+    pub state: SecurityState,
+    pub detail: String,
+}
+
+fn get_image_digests_path() -> PathBuf {
+    get_data_dir().join(IMAGE_DIGESTS_FILE)
+}
+
+/// Registry digest of the local copy of `image`
+fn local_image_digest(image: &str) -> Option<String> {
+    let output = run_command(
+        "docker",
+        &[
+            "image",
+            "inspect",
+            "--format",
+            "{{range .RepoDigests}}{{println .}}{{end}}",
+            image,
+        ],
+    )
+    .ok()?;
+    output
+        .lines()
+        .find_map(|line| line.trim().split_once('@').map(|(_, d)| d.to_string()))
+}
+
+/// Remember which digest a pull brought in
+fn record_image_pull(image: &str) {
+    let digest = local_image_digest(image);
+    update_json_file(
+        &get_image_digests_path(),
+        |all: &mut HashMap<String, ImageRecord>| {
+            let record = all.entry(image.to_string()).or_default();
+            record.digest = digest;
+            record.pulled_at = Some(chrono::Local::now().to_rfc3339());
+        },
+    );
+}
+
+/// Digest the registry currently serves for `image`, without pulling it
+fn registry_image_digest(image: &str) -> Result<String, String> {
+    let output = run_command("docker", &["buildx", "imagetools", "inspect", image])?;
+    // "Digest:    sha256:…" in the header
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Digest:"))
+        .map(|d| d.trim().to_string())
+        .ok_or_else(|| format!("No digest in the registry's answer for {}", image))
+}
+
+/// Count vulnerabilities by severity with trivy, if it's installed
+fn scan_image(image: &str) -> Result<VulnerabilityScan, String> {
+    let output = run_command(
+        "trivy",
+        &[
+            "image",
+            "--quiet",
+            "--scanners",
+            "vuln",
+            "--format",
+            "json",
+            image,
+        ],
+    )
+    .map_err(|e| format!("trivy failed: {}", e))?;
+    let report: serde_json::Value = serde_json::from_str(&output).map_err(|e| e.to_string())?;
+    let mut scan = VulnerabilityScan {
+        scanner: "trivy".to_string(),
+        scanned_at: chrono::Local::now().to_rfc3339(),
+        ..Default::default()
+    };
+    let severities = report["Results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|result| result["Vulnerabilities"].as_array().into_iter().flatten())
+        .filter_map(|v| v["Severity"].as_str());
+    for severity in severities {
+        match severity {
+            "CRITICAL" => scan.critical += 1,
+            "HIGH" => scan.high += 1,
+            "MEDIUM" => scan.medium += 1,
+            "LOW" => scan.low += 1,
+            _ => {}
+        }
+    }
+    Ok(scan)
+}
+
+fn verify_image_internal(image: &str, scan: bool) -> Result<ImageVerification, String> {
+    let local_digest = local_image_digest(image);
+    let registry_digest = registry_image_digest(image)?;
+    let (scan, scan_error) = if !scan {
+        (None, None)
+    } else if run_command("trivy", &["--version"]).is_err() {
+        (
+            None,
+            Some("Install trivy to scan for vulnerabilities".to_string()),
+        )
+    } else {
+        match scan_image(image) {
+            Ok(scan) => (Some(scan), None),
+            Err(e) => (None, Some(e)),
+        }
+    };
+
+    update_json_file(
+        &get_image_digests_path(),
+        |all: &mut HashMap<String, ImageRecord>| {
+            let record = all.entry(image.to_string()).or_default();
+            record.digest = local_digest.clone();
+            record.registry_digest = Some(registry_digest.clone());
+            record.verified_at = Some(chrono::Local::now().to_rfc3339());
+            if scan.is_some() {
+                record.scan = scan.clone();
+            }
+        },
+    );
+    Ok(ImageVerification {
+        image: image.to_string(),
+        update_available: local_digest.as_deref() != Some(registry_digest.as_str()),
+        local_digest,
+        registry_digest: Some(registry_digest),
+        scan,
+        scan_error,
+    })
+}
+
+/// Compare the local image with the registry and, with `scan`, look for known
+/// vulnerabilities. The result feeds `get_security_status`.
+#[tauri::command]
+async fn verify_image(
+    tag: Option<String>,
+    scan: Option<bool>,
+) -> Result<ImageVerification, String> {
+    let image = image_ref(tag.as_deref());
+    tauri::async_runtime::spawn_blocking(move || {
+        verify_image_internal(&image, scan.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Per server: whether its image has known problems or a newer version
+#[tauri::command]
+fn get_security_status() -> Vec<ServerSecurity> {
+    let records: HashMap<String, ImageRecord> = {
+        let _lock = lock_config(false);
+        read_json_file(&get_image_digests_path())
+    };
+    list_servers()
+        .into_iter()
+        .filter_map(|server| {
+            let inspect = run_command(
+                "docker",
+                &[
+                    "inspect",
+                    "--format",
+                    "{{.Config.Image}}\t{{.Image}}",
+                    &server.name,
+                ],
+            )
+            .ok()?;
+            let (image, running_id) = inspect.trim().split_once('\t')?;
+            let record = records.get(image);
+            let local_id = run_command(
+                "docker",
+                &["image", "inspect", "--format", "{{.Id}}", image],
+            )
+            .ok();
+            let scan = record.and_then(|r| r.scan.as_ref());
+
+            let (state, detail) = if let Some(scan) = scan.filter(|s| s.critical + s.high > 0) {
+                (
+                    SecurityState::Vulnerable,
+                    format!(
+                        "{} critical and {} high severity vulnerabilities",
+                        scan.critical, scan.high
+                    ),
+                )
+            } else if local_id.as_deref().map(str::trim) != Some(running_id) {
+                (
+                    SecurityState::UpdateAvailable,
+                    "A newer image was pulled; recreate the server to use it".to_string(),
+                )
+            } else if let Some(record) = record.filter(|r| r.verified_at.is_some()) {
+                if record.registry_digest.is_some() && record.registry_digest != record.digest {
+                    (
+                        SecurityState::UpdateAvailable,
+                        format!("The registry has a newer {}", image),
+                    )
+                } else {
+                    (SecurityState::UpToDate, "Matches the registry".to_string())
+                }
+            } else {
+                (
+                    SecurityState::Unverified,
+                    "Run an image check to compare with the registry".to_string(),
+                )
+            };
+            Some(ServerSecurity {
+                server: server.name,
+                image: image.to_string(),
+                state,
+                detail,
+            })
+        })
+        .collect()
+}
+
+/// Best-effort IANA name of the host's zone
+fn host_timezone() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ") {
+        if !tz.trim().is_empty() {
+            return Some(tz.trim().trim_start_matches(':').to_string());
+        }
+    }
+    if let Ok(tz) = fs::read_to_string("/etc/timezone") {
+        if !tz.trim().is_empty() {
+            return Some(tz.trim().to_string());
+        }
+    }
+    // /etc/localtime -> /usr/share/zoneinfo/Asia/Seoul (or /var/db/timezone/zoneinfo/... on macOS)
+    fs::read_link("/etc/localtime").ok().and_then(|target| {
+        let target = target.to_string_lossy().to_string();
+        target
+            .split_once("zoneinfo/")
+            .map(|(_, zone)| zone.to_string())
+    })
+}
+
+/// Validate an octal permission string such as "0644" or "022"
+fn check_mode(field: &str, value: &str) -> Result<(), String> {
+    let valid = (3..=4).contains(&value.len())
+        && u32::from_str_radix(value, 8).is_ok_and(|mode| mode <= 0o7777);
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid {}: {} (expected octal, e.g. 0644)",
+            field, value
+        ))
+    }
+}
+
+fn sftp_hook_path(name: &str) -> PathBuf {
+    get_config_dir()
+        .join(SFTP_HOOKS_DIR)
+        .join(format!("{}.sh", name))
+}
+
+/// Connection limits for one server. The sshd settings go into its config; the
+/// per-client rate is a host firewall rule
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct RateLimit {
+    /// sshd MaxStartups: "10", or "start:rate:full" such as "10:30:60"
+    pub max_startups: Option<String>,
+    pub max_sessions: Option<u32>,
+    pub login_grace_time_secs: Option<u32>,
+    /// New connections per minute from one client address, enforced by iptables on
+    /// Linux hosts; needs root
+    pub connections_per_minute: Option<u32>,
+}
+
+fn check_rate_limit(limit: &RateLimit) -> Result<(), String> {
+    if let Some(value) = &limit.max_startups {
+        let parts: Vec<&str> = value.split(':').collect();
+        let valid = (parts.len() == 1 || parts.len() == 3)
+            && parts
+                .iter()
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+        if !valid {
+            return Err(format!(
+                "Invalid MaxStartups: {} (expected e.g. 10 or 10:30:60)",
+                value
+            ));
+        }
+    }
+    if limit.max_sessions == Some(0) || limit.connections_per_minute == Some(0) {
+        return Err("Limits must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+/// Shell that replaces dsftp's block of rate settings in sshd_config
+fn rate_limit_script(limit: &RateLimit) -> String {
+    let mut settings = String::new();
+    if let Some(value) = &limit.max_startups {
+        settings.push_str(&format!("MaxStartups {}\n", value));
+    }
+    if let Some(value) = limit.max_sessions {
+        settings.push_str(&format!("MaxSessions {}\n", value));
+    }
+    if let Some(value) = limit.login_grace_time_secs {
+        settings.push_str(&format!("LoginGraceTime {}\n", value));
+    }
+    format!(
+        "sed -i '/^# dsftp rate limit$/,/^# dsftp rate limit end$/d' /etc/ssh/sshd_config\n\
+         cat >> /etc/ssh/sshd_config <<'EOF'\n\
+         # dsftp rate limit\n{}# dsftp rate limit end\nEOF\n",
+        settings
+    )
+}
+
+/// DOCKER-USER rules tagged for `name`, as `iptables -S` prints them
+#[cfg(target_os = "linux")]
+fn firewall_rules(name: &str) -> Result<Vec<String>, String> {
+    let tag = format!("\"dsftp:{}\"", name);
+    Ok(run_command("iptables", &["-S", "DOCKER-USER"])?
+        .lines()
+        .filter(|line| line.contains(&tag))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Replace the server's token-bucket rule: connection attempts above the rate are
+/// dropped per source address. Matches the published port as seen before Docker's
+/// DNAT, so it survives the container getting a new IP.
+#[cfg(target_os = "linux")]
+fn apply_firewall_limit(name: &str, port: u16, per_minute: Option<u32>) -> Result<(), String> {
+    for rule in firewall_rules(name)? {
+        let Some(spec) = rule.strip_prefix("-A ") else {
+            continue;
+        };
+        // Container names never contain spaces, so the quoted comment is one token
+        let mut args = vec!["-D"];
+        args.extend(spec.split_whitespace().map(|t| t.trim_matches('"')));
+        run_command("iptables", &args)?;
+    }
+
+    let Some(per_minute) = per_minute else {
+        return Ok(());
+    };
+    let port = port.to_string();
+    let rate = format!("{}/minute", per_minute);
+    let burst = per_minute.to_string();
+    // hashlimit names are capped at 15 characters
+    let bucket = format!("dsftp{}", port);
+    let tag = format!("dsftp:{}", name);
+    run_command(
+        "iptables",
+        &[
+            "-I",
+            "DOCKER-USER",
+            "-p",
+            "tcp",
+            "--syn",
+            "-m",
+            "conntrack",
+            "--ctorigdstport",
+            &port,
+            "--ctdir",
+            "ORIGINAL",
+            "-m",
+            "hashlimit",
+            "--hashlimit-above",
+            &rate,
+            "--hashlimit-burst",
+            &burst,
+            "--hashlimit-mode",
+            "srcip",
+            "--hashlimit-name",
+            &bucket,
+            "-m",
+            "comment",
+            "--comment",
+            &tag,
+            "-j",
+            "DROP",
+        ],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("iptables: {}", e.trim()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_firewall_limit(_name: &str, _port: u16, per_minute: Option<u32>) -> Result<(), String> {
+    if per_minute.is_some() {
+        return Err(
+            "Per-client connection rates need iptables, available on Linux hosts only".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Apply connection limits to a running server and keep them for recreates. The
+/// sshd settings are written into the container and sshd reloaded in place.
+#[tauri::command]
+fn set_rate_limit(name: String, config: Option<RateLimit>) -> CommandResult {
+    // Invalid limits are refused below rather than queued
+    let valid = config.as_ref().is_none_or(|l| check_rate_limit(l).is_ok());
+    if let Some(result) = valid
+        .then(|| {
+            pending::defer(
+                &name,
+                pending::QueuedChange::RateLimit {
+                    config: config.clone(),
+                },
+            )
+        })
+        .flatten()
+    {
+        return result;
+    }
+    let result = find_server(&name).and_then(|server| {
+        if let Some(limit) = &config {
+            check_rate_limit(limit)?;
+        }
+        let limit = config.clone().unwrap_or_default();
+        let script = format!("{}{}", rate_limit_script(&limit), SSHD_RELOAD_SCRIPT);
+        run_command("docker", &["exec", &name, "sh", "-c", &script])?;
+        apply_firewall_limit(&name, server.port, limit.connections_per_minute)?;
+
+        let stored = update_credentials(|all| {
+            all.get_mut(&name).map(|creds| {
+                creds.rate_limit = config.clone();
+                creds.clone()
+            })
+        });
+        // Keep the startup hook in step so a restart doesn't drop the settings
+        if let Some(creds) = stored {
+            let hook_config = ServerConfig::from_info(&server, &creds);
+            if sftp_hook_path(&name).exists() || needs_sftp_hook(&hook_config) {
+                let flags = sftp_server_flags(&hook_config).unwrap_or_default();
+                write_sftp_hook(&hook_config, &flags)?;
+            }
+        }
+        Ok(())
+    });
+    record_audit(
+        &name,
+        "set-rate-limit",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Longest banner accepted; clients print it on every connect
+const MAX_BANNER_BYTES: usize = 4096;
+/// Ends the here-document the banner text is written with
+const BANNER_DELIMITER: &str = "DSFTP_BANNER";
+
+fn check_banner(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("Banner is empty".to_string());
+    }
+    if text.len() > MAX_BANNER_BYTES {
+        return Err(format!("Banner is longer than {} bytes", MAX_BANNER_BYTES));
+    }
+    if text
+        .chars()
+        .any(|c| c.is_control() && c != '\n' && c != '\t')
+    {
+        return Err("Banner can't contain control characters".to_string());
+    }
+    if text.lines().any(|line| line == BANNER_DELIMITER) {
+        return Err(format!("Banner can't contain a line {}", BANNER_DELIMITER));
+    }
+    Ok(())
+}
+
+/// Shell that replaces dsftp's Banner in sshd_config, or just removes it. It goes
+/// after the maintenance block, whose own Banner wins while that is in place.
+fn banner_script(text: Option<&str>) -> String {
+    let mut script = "sed -i '/^# dsftp banner$/,/^# dsftp banner end$/d' /etc/ssh/sshd_config\n\
+                      rm -f /etc/ssh/dsftp-banner\n"
+        .to_string();
+    if let Some(text) = text {
+        script.push_str(&format!(
+            "cat > /etc/ssh/dsftp-banner <<'{delimiter}'\n{}\n{delimiter}\n\
+             cat >> /etc/ssh/sshd_config <<'EOF'\n\
+             # dsftp banner\nBanner /etc/ssh/dsftp-banner\n# dsftp banner end\nEOF\n",
+            text.trim_end_matches('\n'),
+            delimiter = BANNER_DELIMITER
+        ));
+    }
+    script
+}
+
+/// Show `text` to clients before they log in, or stop showing one with `None`.
+/// Written into the running server and kept for restarts and recreates.
+#[tauri::command]
+fn set_banner(name: String, text: Option<String>) -> CommandResult {
+    let text = text.filter(|t| !t.trim().is_empty());
+    let valid = text.as_deref().is_none_or(|t| check_banner(t).is_ok());
+    if let Some(result) = valid
+        .then(|| pending::defer(&name, pending::QueuedChange::Banner { text: text.clone() }))
+        .flatten()
+    {
+        return result;
+    }
+    let result = find_server(&name).and_then(|server| {
+        if let Some(text) = &text {
+            check_banner(text)?;
+        }
+        let script = format!("{}{}", banner_script(text.as_deref()), SSHD_RELOAD_SCRIPT);
+        run_command("docker", &["exec", &name, "sh", "-c", &script])?;
+
+        let stored = update_credentials(|all| {
+            all.get_mut(&name).map(|creds| {
+                creds.banner = text.clone();
+                creds.clone()
+            })
+        });
+        // The startup hook reapplies it when the container starts again
+        if let Some(creds) = stored {
+            let hook_config = ServerConfig::from_info(&server, &creds);
+            if sftp_hook_path(&name).exists() || needs_sftp_hook(&hook_config) {
+                let flags = sftp_server_flags(&hook_config).unwrap_or_default();
+                write_sftp_hook(&hook_config, &flags)?;
+            }
+        }
+        Ok(())
+    });
+    record_audit(
+        &name,
+        "set-banner",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// internal-sftp options for the server, if it needs any beyond atmoz's defaults
+fn sftp_server_flags(config: &ServerConfig) -> Option<String> {
+    let mut flags = String::new();
+    if config.anonymous_read_only {
+        flags.push_str(" -R");
+    }
+    if let Some(umask) = &config.umask {
+        flags.push_str(&format!(" -u {}", umask));
+    }
+    if let Some(mode) = &config.file_mode {
+        flags.push_str(&format!(" -m {}", mode));
+    }
+    Some(flags).filter(|f| !f.is_empty())
+}
+
+fn needs_sftp_hook(config: &ServerConfig) -> bool {
+    sftp_server_flags(config).is_some() || config.rate_limit.is_some() || config.banner.is_some()
+}
+
+/// Write the sshd hook mounted into the server's /etc/sftp.d
+fn write_sftp_hook(config: &ServerConfig, flags: &str) -> Result<(), String> {
+    let path = sftp_hook_path(&config.name);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let mut script = SFTP_HOOK_SCRIPT.replace("{flags}", flags);
+    if config.anonymous_read_only {
+        script.push_str(ANONYMOUS_HARDENING_SCRIPT);
+    }
+    if let Some(limit) = &config.rate_limit {
+        script.push_str(&rate_limit_script(limit));
+    }
+    if let Some(text) = &config.banner {
+        script.push_str(&banner_script(Some(text)));
+    }
+    fs::write(&path, script).map_err(|e| e.to_string())?;
+    // atmoz only runs hooks that are executable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// A user-supplied script atmoz runs at container start
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct InitScript {
+    /// Letters, digits, '-' and '_'
+    pub name: String,
+    pub content: String,
+}
+
+fn init_scripts_dir(name: &str) -> PathBuf {
+    get_config_dir().join(INIT_SCRIPTS_DIR).join(name)
+}
+
+/// Script files in run order; the numeric prefix keeps atmoz's glob order equal to
+/// the order they were given in
+fn init_script_files(name: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(init_scripts_dir(name))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "sh"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+#[tauri::command]
+fn get_init_scripts(name: String) -> Vec<InitScript> {
+    init_script_files(&name)
+        .into_iter()
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            let (_, script_name) = stem.split_once('-')?;
+            Some(InitScript {
+                name: script_name.to_string(),
+                content: fs::read_to_string(&path).ok()?,
+            })
+        })
+        .collect()
+}
+
+fn write_init_scripts(name: &str, scripts: &[InitScript]) -> Result<(), String> {
+    if let Some(script) = scripts.iter().find(|s| {
+        s.name.is_empty()
+            || !s
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }) {
+        return Err(format!("Invalid script name: '{}'", script.name));
+    }
+
+    let dir = init_scripts_dir(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    if scripts.is_empty() {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    for (i, script) in scripts.iter().enumerate() {
+        let path = dir.join(format!("{:02}-{}.sh", i + 1, script.name));
+        // Windows editors may save CRLF, which sh chokes on
+        fs::write(&path, script.content.replace("\r\n", "\n")).map_err(|e| e.to_string())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the server's init scripts. They are mounted into /etc/sftp.d when the
+/// container is created, so changes take effect the next time it is recreated.
+#[tauri::command]
+fn set_init_scripts(name: String, scripts: Vec<InitScript>) -> CommandResult {
+    let result = write_init_scripts(&name, &scripts);
+    record_audit(
+        &name,
+        "set-init-scripts",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// One exclude pattern: matched against the path from the share root when it is
+/// anchored or contains a '/', otherwise against every file and directory name
+struct ExcludeRule {
+    pattern: glob::Pattern,
+    dir_only: bool,
+    anchored: bool,
+}
+
+fn check_exclude_patterns(patterns: &[String]) -> Result<(), String> {
+    parse_exclude_patterns(patterns).map(|_| ())
+}
+
+fn parse_exclude_patterns(patterns: &[String]) -> Result<Vec<ExcludeRule>, String> {
+    patterns
+        .iter()
+        .map(|raw| {
+            let text = raw.trim();
+            let dir_only = text.ends_with('/');
+            let text = text.trim_end_matches('/');
+            let anchored = text.contains('/');
+            let text = text.trim_start_matches('/');
+            if text.is_empty() || text.split('/').any(|part| part == "..") {
+                return Err(format!("Invalid exclude pattern: '{}'", raw));
+            }
+            let pattern = glob::Pattern::new(text)
+                .map_err(|e| format!("Invalid exclude pattern '{}': {}", raw, e))?;
+            Ok(ExcludeRule {
+                pattern,
+                dir_only,
+                anchored,
+            })
+        })
+        .collect()
+}
+
+/// Paths under `root`, relative to it, that the rules hide. Matched directories
+/// aren't descended into, and symlinks are never followed.
+fn excluded_host_paths(root: &Path, rules: &[ExcludeRule]) -> Vec<(String, bool)> {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let mut matches = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, relative)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let path = if relative.is_empty() {
+                file_name.clone()
+            } else {
+                format!("{}/{}", relative, file_name)
+            };
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let hidden = rules.iter().any(|rule| {
+                (is_dir || !rule.dir_only)
+                    && if rule.anchored {
+                        rule.pattern.matches_with(&path, options)
+                    } else {
+                        rule.pattern.matches_with(&file_name, options)
+                    }
+            });
+            if hidden {
+                if matches.len() == MAX_EXCLUDE_MASKS {
+                    log::warn!(
+                        "Exclude patterns match more than {} paths; the rest stay visible",
+                        MAX_EXCLUDE_MASKS
+                    );
+                    return matches;
+                }
+                matches.push((path, is_dir));
+            } else if is_dir {
+                pending.push((entry.path(), path));
+            }
+        }
+    }
+    matches
+}
+
+fn is_exclude_mask(source: &str) -> bool {
+    let source = source.replace('\\', "/");
+    [EXCLUDE_MASK_EMPTY_DIR, EXCLUDE_MASK_EMPTY_FILE]
+        .iter()
+        .any(|mask| source.ends_with(&format!("/{}/{}", EXCLUDE_MASKS_DIR, mask)))
+}
+
+/// `docker run -v` values laying an empty, read-only directory or file over every
+/// path the server's exclude patterns match. Matches are found when the container
+/// is created; later files that match stay visible until it is recreated.
+fn exclude_mounts(config: &ServerConfig) -> Vec<String> {
+    let Ok(rules) = parse_exclude_patterns(&config.exclude_patterns) else {
+        return Vec::new();
+    };
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let masks = get_config_dir().join(EXCLUDE_MASKS_DIR);
+    let empty_dir = masks.join(EXCLUDE_MASK_EMPTY_DIR);
+    let empty_file = masks.join(EXCLUDE_MASK_EMPTY_FILE);
+    if fs::create_dir_all(&empty_dir).is_err()
+        || (!empty_file.exists() && fs::write(&empty_file, b"").is_err())
+    {
+        return Vec::new();
+    }
+
+    excluded_host_paths(Path::new(&config.host_path), &rules)
+        .into_iter()
+        // `-v` splits on ':'
+        .filter(|(path, _)| !path.contains(':'))
+        .map(|(path, is_dir)| {
+            let source = if is_dir { &empty_dir } else { &empty_file };
+            format!(
+                "{}:{}:ro",
+                source.to_string_lossy().replace('\\', "/"),
+                join_container_path(&config.container_path, &path)
+            )
+        })
+        .collect()
+}
+
+/// Replace the server's exclude patterns. Like the mounts they become, they take
+/// effect the next time the server is recreated.
+#[tauri::command]
+fn set_exclude_patterns(name: String, patterns: Vec<String>) -> CommandResult {
+    let patterns: Vec<String> = patterns
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    let result = check_exclude_patterns(&patterns).and_then(|_| {
+        update_credentials(|all| match all.get_mut(&name) {
+            Some(creds) => {
+                creds.exclude_patterns = patterns;
+                Ok(())
+            }
+            None => Err(format!("Unknown server: {}", name)),
+        })
+    });
+    record_audit(
+        &name,
+        "set-exclude-patterns",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Arguments for the `docker run` that creates a server
+fn build_run_args(config: &ServerConfig, host_path: &str, bind_ip: &str, port: u16) -> Vec<String> {
+    let mount_mode = if config.anonymous_read_only {
+        ":ro"
+    } else {
+        ""
+    };
+    let mut args: Vec<String> = vec![
+        "run".into(),
+        "-d".into(),
+        "--name".into(),
+        config.name.clone(),
+        "-p".into(),
+        format!("{}:{}:22", bind_ip, port),
+        "-v".into(),
+        format!("{}:{}{}", host_path, config.container_path, mount_mode),
+    ];
+    if let Some(id) = &config.id {
+        args.extend([
+            "--label".into(),
+            format!("{}={}", SERVER_ID_LABEL, id),
+            "-e".into(),
+            format!("{}={}", SERVER_ID_ENV, id),
+        ]);
+    }
+
+    if needs_sftp_hook(config) {
+        let hook = sftp_hook_path(&config.name)
+            .to_string_lossy()
+            .replace('\\', "/");
+        args.extend(["-v".into(), format!("{}:/etc/sftp.d/dsftp.sh:ro", hook)]);
+    }
+    if let Some((ca_pub, trust)) = ssh_ca_mounts() {
+        args.extend([
+            "-v".into(),
+            format!("{}:/etc/ssh/dsftp_ca.pub:ro", ca_pub),
+            "-v".into(),
+            format!("{}:/etc/sftp.d/dsftp-ca.sh:ro", trust),
+        ]);
+    }
+    for mount in host_identity_mounts(&config.name)
+        .into_iter()
+        .chain(user_key_mounts(&config.name))
+        .chain(exclude_mounts(config))
+    {
+        args.extend(["-v".into(), mount]);
+    }
+    for script in init_script_files(&config.name) {
+        let Some(file_name) = script.file_name() else {
+            continue;
+        };
+        args.extend([
+            "-v".into(),
+            format!(
+                "{}:/etc/sftp.d/{}:ro",
+                script.to_string_lossy().replace('\\', "/"),
+                file_name.to_string_lossy()
+            ),
+        ]);
+    }
+
+    match config.timezone.as_deref().map(str::trim) {
+        None | Some("") => {}
+        Some("local") => {
+            if let Some(tz) = host_timezone() {
+                args.extend(["-e".into(), format!("TZ={}", tz)]);
+            }
+            // Covers images without tzdata, where TZ alone falls back to UTC
+            if cfg!(unix) && Path::new("/etc/localtime").exists() {
+                args.extend(["-v".into(), "/etc/localtime:/etc/localtime:ro".into()]);
+            }
+        }
+        Some(tz) => args.extend(["-e".into(), format!("TZ={}", tz)]),
+    }
+
+    // Options must precede the image name, so user arguments go in before it
+    args.extend(config.extra_docker_args.iter().cloned());
+
+    args.extend([
+        "--restart".into(),
+        config.restart_policy.docker_arg(),
+        match config.image_build {
+            Some(_) => custom_image(&config.name),
+            None => SFTP_IMAGE.into(),
+        },
+        format!("{}:{}:1001", config.username, config.password),
+    ]);
+    // Imported accounts, when an existing server is recreated
+    if let Some(creds) = CREDENTIALS.load().remove(&config.name) {
+        args.extend(creds.users.iter().map(SftpUser::spec));
+    }
+    args
+}
+
+#[tauri::command]
+async fn create_server(
+    app: AppHandle,
+    config: ServerConfig,
+    dry_run: Option<bool>,
+) -> CreateResult {
+    // Creating may pull or build the image for minutes; keep it off the main thread
+    // so the window stays responsive and its progress events render
+    tauri::async_runtime::spawn_blocking(move || {
+        let reservations = app.state::<PortReservations>();
+        if dry_run.unwrap_or(false) {
+            return create_server_dry_run(&reservations, config);
+        }
+        let name = config.name.clone();
+        let result = create_server_internal(&app, &reservations, config, None);
+        record_audit(&name, "create", result.error.as_deref());
+        result
+    })
+    .await
+    .unwrap_or_else(|e| CreateResult {
+        error: Some(e.to_string()),
+        ..Default::default()
+    })
+}
+
+/// Apply the settings some options imply (anonymous servers get a fixed user)
+fn normalize_server_config(config: &mut ServerConfig) {
+    if config.anonymous_read_only {
+        config.username = ANONYMOUS_USER.to_string();
+        if config.password.is_empty() {
+            config.password = ANONYMOUS_PASSWORD.to_string();
+        }
+    }
+}
+
+/// Address new servers are published on: the user's preferred one, or the best guess
+fn default_bind_ip() -> String {
+    let network_config = load_network_config();
+    let interfaces = list_network_interfaces_internal();
+    get_current_ip_internal(&interfaces, &network_config).0
+}
+
+/// Docker container names: a letter or digit, then letters, digits, `_`, `.` or `-`
+fn is_valid_container_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Linux user names atmoz can create
+fn is_valid_username(name: &str) -> bool {
+    name.len() <= 32
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'))
+}
+
+/// Every pre-flight check `create_server` runs, collected instead of stopping at the
+/// first failure
+/// Filesystem free space as seen from one of the places dsftp or Docker writes to
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct VolumeCapacity {
+    pub path: String,
+    /// "data", "docker" or the name of the server sharing it
+    pub used_by: String,
+    #[ts(type = "number")]
+    pub available_bytes: u64,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CapacityReport {
+    /// CPUs and memory Docker can hand out; on Docker Desktop, those of its VM
+    pub cpus: u32,
+    #[ts(type = "number")]
+    pub memory_bytes: u64,
+    /// Memory the running containers use right now
+    #[ts(type = "number")]
+    pub memory_used_bytes: u64,
+    pub containers: usize,
+    pub running_containers: usize,
+    pub volumes: Vec<VolumeCapacity>,
+    /// Reasons another server would likely overcommit the host; empty when there's room
+    pub warnings: Vec<String>,
+}
+
+/// Last capacity report, reused by validation for `CAPACITY_CACHE_TTL`
+static CAPACITY_CACHE: LazyLock<Mutex<Option<(Instant, CapacityReport)>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+fn capacity_report() -> Result<CapacityReport, String> {
+    let info = run_command(
+        "docker",
+        &[
+            "info",
+            "--format",
+            "{{.NCPU}}|{{.MemTotal}}|{{.Containers}}|{{.ContainersRunning}}|{{.DockerRootDir}}",
+        ],
+    )?;
+    let fields: Vec<&str> = info.trim().split('|').collect();
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+    // Counted from the running containers' own usage, not the host's
+    let memory_used_bytes = run_command(
+        "docker",
+        &["stats", "--no-stream", "--format", "{{.MemUsage}}"],
+    )
+    .map(|output| {
+        output
+            .lines()
+            .filter_map(|line| line.split('/').next())
+            .map(parse_docker_size)
+            .sum()
+    })
+    .unwrap_or(0);
+
+    let mut places = vec![(get_data_dir().display().to_string(), "data".to_string())];
+    // Only a path on this machine with a native engine; Docker Desktop keeps it in its VM
+    if Path::new(field(4)).is_dir() {
+        places.push((field(4).to_string(), "docker".to_string()));
+    }
+    places.extend(
+        list_servers()
+            .into_iter()
+            .filter(|s| !s.host_path.is_empty())
+            .map(|s| (s.host_path, s.name)),
+    );
+    let mut seen = HashSet::new();
+    let volumes = places
+        .into_iter()
+        .filter(|(path, _)| seen.insert(path.clone()))
+        .filter_map(|(path, used_by)| {
+            Some(VolumeCapacity {
+                available_bytes: fs2::available_space(&path).ok()?,
+                total_bytes: fs2::total_space(&path).ok()?,
+                path,
+                used_by,
+            })
+        })
+        .collect();
+
+    let mut report = CapacityReport {
+        cpus: field(0).parse().unwrap_or(0),
+        memory_bytes: field(1).parse().unwrap_or(0),
+        memory_used_bytes,
+        containers: field(2).parse().unwrap_or(0),
+        running_containers: field(3).parse().unwrap_or(0),
+        volumes,
+        warnings: Vec::new(),
+    };
+    report.warnings = capacity_warnings(&report);
+    if let Ok(mut cache) = CAPACITY_CACHE.lock() {
+        *cache = Some((Instant::now(), report.clone()));
+    }
+    Ok(report)
+}
+
+fn capacity_warnings(report: &CapacityReport) -> Vec<String> {
+    let locale = current_locale();
+    let mut warnings = Vec::new();
+    let memory_limit = (report.memory_bytes as f64 * MEMORY_COMMIT_LIMIT) as u64;
+    if report.memory_bytes > 0 && report.memory_used_bytes + SERVER_MEMORY_ESTIMATE > memory_limit {
+        warnings.push(format!(
+            "Containers already use {} of the {} Docker can give them",
+            format_size(report.memory_used_bytes, &locale),
+            format_size(report.memory_bytes, &locale)
+        ));
+    }
+    if report.cpus > 0 && report.running_containers >= report.cpus as usize * CONTAINERS_PER_CPU {
+        warnings.push(format!(
+            "{} containers are already running on {} CPUs",
+            report.running_containers, report.cpus
+        ));
+    }
+    let headroom = load_app_settings().disk_guard.min_free_mb * 1024 * 1024;
+    for volume in report
+        .volumes
+        .iter()
+        .filter(|v| v.available_bytes < headroom)
+    {
+        warnings.push(format!(
+            "{} ({}) has only {} free",
+            volume.path,
+            volume.used_by,
+            format_size(volume.available_bytes, &locale)
+        ));
+    }
+    warnings
+}
+
+/// A capacity report at most `CAPACITY_CACHE_TTL` old, so validating as the user
+/// types doesn't run `docker stats` each time
+fn recent_capacity_report() -> Result<CapacityReport, String> {
+    let cached = CAPACITY_CACHE.lock().ok().and_then(|cache| {
+        cache
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < CAPACITY_CACHE_TTL)
+            .map(|(_, report)| report.clone())
+    });
+    match cached {
+        Some(report) => Ok(report),
+        None => capacity_report(),
+    }
+}
+
+/// How much room Docker and the disks have left: CPUs and memory, container
+/// counts and free space where servers and dsftp's data live, with warnings when
+/// another server would likely overcommit the host
+#[tauri::command]
+async fn get_capacity_report() -> Result<CapacityReport, String> {
+    tauri::async_runtime::spawn_blocking(capacity_report)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn validate_config(config: &ServerConfig, bind_ip: &str) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    if !is_valid_container_name(&config.name) {
+        report.error(
+            "name",
+            "Use letters, digits, '_', '.' or '-', starting with a letter or digit",
+        );
+    } else if run_command(
+        "docker",
+        &[
+            "inspect",
+            "--type",
+            "container",
+            "--format",
+            "{{.Id}}",
+            &config.name,
+        ],
+    )
+    .is_ok()
+    {
+        report.error(
+            "name",
+            format!("A container named {} already exists", config.name),
+        );
+    }
+
+    if config.port != 0 {
+        let settings = load_app_settings();
+        if let Some(range) = settings.port_range.filter(|r| !r.contains(config.port)) {
+            report.error(
+                "port",
+                format!(
+                    "Port {} is outside the allowed range {}-{}",
+                    config.port, range.start, range.end
+                ),
+            );
+        }
+        if let Some(a) = docker_port_bindings()
+            .iter()
+            .find(|a| a.port == config.port && bindings_conflict(&a.ip, bind_ip))
+        {
+            report.error(
+                "port",
+                format!(
+                    "Port {} is already used by '{}' on {}",
+                    config.port, a.server, a.ip
+                ),
+            );
+        }
+        if config.port < 1024 {
+            report.warn(
+                "port",
+                "Ports below 1024 need elevated privileges on some hosts",
+            );
+        }
+    }
+
+    let host_path = Path::new(&config.host_path);
+    if config.host_path.trim().is_empty() {
+        report.error("host_path", "Host folder is required");
+    } else if !host_path.exists() {
+        report.warn(
+            "host_path",
+            "Host folder does not exist; Docker will create it owned by root",
+        );
+    } else if !host_path.is_dir() {
+        report.error("host_path", "Host path is not a folder");
+    }
+
+    if !config.container_path.starts_with('/') {
+        report.error("container_path", "Container path must be absolute");
+    } else if !config
+        .container_path
+        .starts_with(&format!("/home/{}/", config.username))
+    {
+        report.warn(
+            "container_path",
+            format!(
+                "Users are chrooted to /home/{}; files mounted elsewhere are not visible over SFTP",
+                config.username
+            ),
+        );
+    }
+
+    if config.username == "root" || !is_valid_username(&config.username) {
+        report.error(
+            "username",
+            "Use lowercase letters, digits, '_' or '-', starting with a letter (not root)",
+        );
+    }
+    if config.password.is_empty() {
+        report.error("password", "Password is required");
+    } else if config.password.contains(':') {
+        report.error("password", "Password cannot contain ':'");
+    }
+
+    for (field, value) in [
+        ("umask", &config.umask),
+        ("file_mode", &config.file_mode),
+        ("dir_mode", &config.dir_mode),
+    ] {
+        if let Some(Err(e)) = value.as_deref().map(|v| check_mode(field, v)) {
+            report.error(field, e);
+        }
+    }
+    if let Err(e) = docker_args::check_extra_docker_args(&config.extra_docker_args) {
+        report.error("extra_docker_args", e);
+    }
+    if let Some(Err(e)) = config.rate_limit.as_ref().map(check_rate_limit) {
+        report.error("rate_limit", e);
+    }
+    if let Some(Err(e)) = config.banner.as_deref().map(check_banner) {
+        report.error("banner", e);
+    }
+    if let Some(Err(e)) = config.image_build.as_ref().map(check_image_build) {
+        report.error("image_build", e);
+    }
+    if let Err(e) = check_exclude_patterns(&config.exclude_patterns) {
+        report.error("exclude_patterns", e);
+    }
+    if let Err(e) = check_dependencies(&config.name, &config.depends_on, &load_credentials()) {
+        report.error("depends_on", e);
+    }
+
+    if !image_exists(&image_ref(None)) {
+        report.warn(
+            "image",
+            format!("{} is not present locally and will be pulled", SFTP_IMAGE),
+        );
+    }
+    for warning in recent_capacity_report()
+        .map(|c| c.warnings)
+        .unwrap_or_default()
+    {
+        report.warn("capacity", warning);
+    }
+
+    report.valid = report.errors.is_empty();
+    report
+}
+
+#[tauri::command]
+fn validate_server_config(mut config: ServerConfig) -> ValidationReport {
+    normalize_server_config(&mut config);
+    validate_config(&config, &default_bind_ip())
+}
+
+/// Everything `create_server` would do short of running the container
+fn create_server_dry_run(
+    reservations: &PortReservations,
+    mut config: ServerConfig,
+) -> CreateResult {
+    normalize_server_config(&mut config);
+    let bind_ip = default_bind_ip();
+    let report = validate_config(&config, &bind_ip);
+    if let Some(issue) = report.errors.first() {
+        return CreateResult {
+            error: Some(format!("{}: {}", issue.field, issue.message)),
+            ..Default::default()
+        };
+    }
+
+    // Resolve an automatic port the same way a real create would, then let it go
+    let port = match reserve_port(
+        reservations,
+        &load_app_settings(),
+        config.port,
+        &bind_ip,
+        &config.name,
+    ) {
+        Ok(reservation) => reservation.port,
+        Err(e) => {
+            return CreateResult {
+                error: Some(e),
+                ..Default::default()
+            }
+        }
+    };
+
+    let host_path = config.host_path.replace('\\', "/");
+    let mut command = vec!["docker".to_string()];
+    command.extend(build_run_args(&config, &host_path, &bind_ip, port));
+    CreateResult {
+        success: true,
+        docker_command: Some(command),
+        ..Default::default()
+    }
+}
+
+/// Create the container; `bind_ip` overrides the preferred network address
+fn create_server_internal<R: Runtime>(
+    app: &AppHandle<R>,
+    reservations: &PortReservations,
+    mut config: ServerConfig,
+    bind_ip: Option<String>,
+) -> CreateResult {
+    let host_path = config.host_path.replace('\\', "/");
+    normalize_server_config(&mut config);
+    let name = config.name.clone();
+    // A recreate keeps the server's identity
+    if config.id.is_none() {
+        config.id = Some(server_id(&name).unwrap_or_else(new_server_id));
+    }
+
+    let emit = |step: CreateStep, state: CreateStepState, error: Option<&str>| {
+        let _ = app.emit(
+            "create-progress",
+            CreateProgress {
+                server: name.clone(),
+                step,
+                state,
+                error: error.map(str::to_string),
+            },
+        );
+    };
+    let fail = |step: CreateStep, error: String| {
+        emit(step, CreateStepState::Failed, Some(&error));
+        CreateResult {
+            error: Some(error),
+            failed_step: Some(step),
+            ..Default::default()
+        }
+    };
+
+    emit(CreateStep::Validate, CreateStepState::Started, None);
+    // Get network config to bind to specific IP
+    let bind_ip = bind_ip.unwrap_or_else(default_bind_ip);
+    let report = validate_config(&config, &bind_ip);
+    if let Some(issue) = report.errors.first() {
+        return fail(
+            CreateStep::Validate,
+            format!("{}: {}", issue.field, issue.message),
+        );
+    }
+    emit(CreateStep::Validate, CreateStepState::Completed, None);
+
+    // Pull the image up front so `docker run` doesn't silently block on it
+    emit(CreateStep::PullImage, CreateStepState::Started, None);
+    if let Err(e) = ensure_image(app, &image_ref(None)) {
+        return fail(
+            CreateStep::PullImage,
+            format!("Failed to pull {}: {}", SFTP_IMAGE, e),
+        );
+    }
+    if let Some(build) = &config.image_build {
+        if let Err(e) = ensure_custom_image(&config.name, build) {
+            return fail(
+                CreateStep::PullImage,
+                format!("Failed to build the server's image: {}", e),
+            );
+        }
+    }
+    emit(CreateStep::PullImage, CreateStepState::Completed, None);
+
+    emit(CreateStep::Prepare, CreateStepState::Started, None);
+    if needs_sftp_hook(&config) {
+        let flags = sftp_server_flags(&config).unwrap_or_default();
+        if let Err(e) = write_sftp_hook(&config, &flags) {
+            return fail(
+                CreateStep::Prepare,
+                format!("Failed to write sshd hook script: {}", e),
+            );
+        }
+    }
+    // Hold the port until the container is running (or creation failed)
+    let reservation = match reserve_port(
+        reservations,
+        &load_app_settings(),
+        config.port,
+        &bind_ip,
+        &config.name,
+    ) {
+        Ok(reservation) => reservation,
+        Err(e) => return fail(CreateStep::Prepare, e),
+    };
+    let port = reservation.port;
+    emit(CreateStep::Prepare, CreateStepState::Completed, None);
+
+    emit(CreateStep::RunContainer, CreateStepState::Started, None);
+    let args = build_run_args(&config, &host_path, &bind_ip, port);
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    release_on_demand(&config.name);
+    if let Err(e) = run_command("docker", &args) {
+        return fail(CreateStep::RunContainer, e);
+    }
+    let now = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    emit(CreateStep::RunContainer, CreateStepState::Completed, None);
+
+    emit(CreateStep::WaitReady, CreateStepState::Started, None);
+    if let Err(e) = wait_until_ready(&config.name, Some((bind_ip.as_str(), port))) {
+        run_command("docker", &["rm", "-f", &config.name]).ok();
+        return fail(CreateStep::WaitReady, e);
+    }
+    emit(CreateStep::WaitReady, CreateStepState::Completed, None);
+
+    emit(CreateStep::StoreCredentials, CreateStepState::Started, None);
+    // A recreate (e.g. rebind) keeps the rotation schedule
+    let previous = CREDENTIALS.load().remove(&config.name);
+    // Store credentials for later retrieval
+    if let Err(e) = store_server_credentials(
+        &config.name,
+        StoredCredentials {
+            id: config.id.clone(),
+            username: config.username.clone(),
+            password: config.password.clone(),
+            host_path: config.host_path.clone(),
+            container_path: config.container_path.clone(),
+            bind_ip: Some(bind_ip.clone()),
+            timezone: config.timezone.clone(),
+            depends_on: config.depends_on.clone(),
+            extra_docker_args: config.extra_docker_args.clone(),
+            anonymous_read_only: config.anonymous_read_only,
+            umask: config.umask.clone(),
+            file_mode: config.file_mode.clone(),
+            dir_mode: config.dir_mode.clone(),
+            exclude_patterns: config.exclude_patterns.clone(),
+            rotate_password_days: previous.as_ref().and_then(|p| p.rotate_password_days),
+            password_rotated_at: previous
+                .as_ref()
+                .and_then(|p| p.password_rotated_at.clone()),
+            password_self_service: previous.as_ref().is_some_and(|p| p.password_self_service),
+            // A fresh container has the stock sshd config
+            maintenance: None,
+            rate_limit: config.rate_limit.clone(),
+            banner: config.banner.clone(),
+            image_build: config.image_build.clone(),
+            virus_scan: previous.as_ref().is_some_and(|p| p.virus_scan),
+            file_policy: previous.as_ref().and_then(|p| p.file_policy.clone()),
+            relay: previous.as_ref().and_then(|p| p.relay.clone()),
+            tunnel: previous.as_ref().and_then(|p| p.tunnel.clone()),
+            on_demand: previous.as_ref().and_then(|p| p.on_demand.clone()),
+            idle_stop_minutes: previous.as_ref().and_then(|p| p.idle_stop_minutes),
+            ingest_token_sha256: previous
+                .as_ref()
+                .and_then(|p| p.ingest_token_sha256.clone()),
+            ingest_token: None,
+            notification_channels: previous
+                .as_ref()
+                .and_then(|p| p.notification_channels.clone()),
+            users: previous.map(|p| p.users).unwrap_or_default(),
+        },
+    ) {
+        run_command("docker", &["rm", "-f", &config.name]).ok();
+        return fail(
+            CreateStep::StoreCredentials,
+            format!("Failed to store the password: {}", e),
+        );
+    }
+    emit(
+        CreateStep::StoreCredentials,
+        CreateStepState::Completed,
+        None,
+    );
+
+    CreateResult {
+        success: true,
+        server: Some(ServerInfo {
+            id: config.id,
+            name: config.name,
+            port,
+            host_path: config.host_path,
+            container_path: config.container_path,
+            username: config.username,
+            password: config.password,
+            status: "running".to_string(),
+            state: ServerState::Running,
+            created_at: Some(now.clone()),
+            started_at: Some(now),
+            uptime_secs: Some(0),
+            bind_ip: Some(bind_ip),
+            timezone: config.timezone,
+            restart_policy: Some(config.restart_policy),
+            depends_on: config.depends_on,
+            anonymous_read_only: config.anonymous_read_only,
+            umask: config.umask,
+            file_mode: config.file_mode,
+            dir_mode: config.dir_mode,
+            exclude_patterns: config.exclude_patterns,
+            relay: None,
+            maintenance: None,
+            on_demand: None,
+        }),
+        ..Default::default()
+    }
+}
+
+#[tauri::command]
+fn start_server(name: String) -> CommandResult {
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return CommandResult {
+            success: false,
+            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
+        };
+    }
+
+    if let Ok(mut stops) = MANUAL_STOPS.lock() {
+        stops.remove(&name);
+    }
+    release_on_demand(&name);
+
+    let result = match run_command("docker", &["start", &name]) {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    };
+    record_audit(&name, "start", result.error.as_deref());
+    result
+}
+
+#[tauri::command]
+fn stop_server(name: String) -> CommandResult {
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return CommandResult {
+            success: false,
+            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
+        };
+    }
+
+    if let Ok(mut stops) = MANUAL_STOPS.lock() {
+        stops.insert(name.clone());
+    }
+
+    let result = match run_command("docker", &["stop", &name]) {
+        Ok(_) => {
+            tunnels::close(&name);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    };
+    record_audit(&name, "stop", result.error.as_deref());
+    result
+}
+
+/// Freeze a server's processes without stopping it. Open sessions stay connected
+/// but stall, and new ones can't log in, until `resume_server`.
+#[tauri::command]
+fn pause_server(name: String) -> CommandResult {
+    set_paused(&name, true)
+}
+
+#[tauri::command]
+fn resume_server(name: String) -> CommandResult {
+    set_paused(&name, false)
+}
+
+fn set_paused(name: &str, paused: bool) -> CommandResult {
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(name) {
+        return CommandResult {
+            success: false,
+            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
+        };
+    }
+
+    let (command, action) = if paused {
+        ("pause", "pause")
+    } else {
+        ("unpause", "resume")
+    };
+    let result = match run_command("docker", &[command, name]) {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    };
+    record_audit(name, action, result.error.as_deref());
+    result
+}
+
+/// Change the restart policy of an existing server in place
+#[tauri::command]
+fn set_restart_policy(name: String, policy: RestartPolicy) -> CommandResult {
+    if let Some(result) = pending::defer(&name, pending::QueuedChange::RestartPolicy { policy }) {
+        return result;
+    }
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return CommandResult {
+            success: false,
+            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
+        };
+    }
+
+    let result = match run_command(
+        "docker",
+        &["update", "--restart", &policy.docker_arg(), &name],
+    ) {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    };
+    record_audit(&name, "set-restart-policy", result.error.as_deref());
+    result
+}
+
+/// Remove a server. With `archive`, its definition, credentials and host keys
+/// (plus the host folder with `include_volume`) are kept for `restore_removed_server`.
+#[tauri::command]
+fn remove_server(
+    name: String,
+    archive: Option<bool>,
+    include_volume: Option<bool>,
+    operation_id: Option<String>,
+) -> CommandResult {
+    if let Some(result) = pending::defer(
+        &name,
+        pending::QueuedChange::Remove {
+            archive,
+            include_volume,
+        },
+    ) {
+        return result;
+    }
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return CommandResult {
+            success: false,
+            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
+        };
+    }
+
+    let archived = if archive.unwrap_or(false) {
+        let archived = run_operation(operation_id, || {
+            archive_server(&name, include_volume.unwrap_or(false))
+        });
+        match archived {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                let error = format!("Archiving failed, nothing was removed: {}", e);
+                record_audit(&name, "remove", Some(&error));
+                return CommandResult {
+                    success: false,
+                    error: Some(error),
+                };
+            }
+        }
+    } else {
+        None
+    };
+
+    // Docker's copy of the logs goes with the container
+    persist_logs(&name).ok();
+    // Takes the container's anonymous volumes along; host folders stay
+    let result = match run_command("docker", &["rm", "-f", "--volumes", &name]) {
+        Ok(_) => {
+            // Remove stored credentials and browsing state
+            remove_server_credentials(&name);
+            fs::remove_file(sftp_hook_path(&name)).ok();
+            fs::remove_dir_all(volume_index_dir(&name)).ok();
+            fs::remove_dir_all(image_build_dir(&name)).ok();
+            run_command("docker", &["image", "rm", &custom_image(&name)]).ok();
+            release_on_demand(&name);
+            for (dir, subdir) in [
+                (init_scripts_dir(&name), INIT_SCRIPTS_DIR),
+                (host_identity_dir(&name), HOST_IDENTITY_DIR),
+                (user_keys_dir(&name), USER_KEYS_DIR),
+            ] {
+                match &archived {
+                    Some(archive) if dir.is_dir() => {
+                        fs::rename(&dir, archive.join(subdir)).ok();
+                    }
+                    _ => {
+                        fs::remove_dir_all(&dir).ok();
+                    }
+                }
+            }
+            update_bookmarks(|all| all.remove(&name));
+            tunnels::close(&name);
+            drop_snapshot_share(&name);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        Err(e) => {
+            if let Some(archive) = &archived {
+                fs::remove_dir_all(archive).ok();
+            }
+            CommandResult {
+                success: false,
+                error: Some(e),
+            }
+        }
+    };
+    record_audit(&name, "remove", result.error.as_deref());
+    result
+}
+
+/// A server `remove_server` archived instead of destroying
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct RemovedServer {
+    pub id: String,
+    pub name: String,
+    pub removed_at: String,
+    pub server: SharedServer,
+    /// The host folder was archived too and can be unpacked on restore
+    pub has_volume: bool,
+}
+
+fn get_removed_dir() -> PathBuf {
+    get_data_dir().join(REMOVED_DIR)
+}
+
+/// Snapshot everything needed to bring `name` back into a fresh archive dir: its
+/// definition, credentials, host keys and optionally the host folder
+fn archive_server(name: &str, include_volume: bool) -> Result<PathBuf, String> {
+    let server = find_server(name)?;
+    let creds = load_credentials().remove(name);
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let id = format!("{}-{}", name, stamp);
+    let dir = get_removed_dir().join(&id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let result = (|| {
+        capture_host_identity(name)?;
+        if include_volume {
+            let host_path = Path::new(&server.host_path);
+            if !host_path.is_dir() {
+                return Err(format!("Host folder not found: {}", server.host_path));
+            }
+            check_disk_space(&dir, dir_size(host_path)).map_err(|e| e.to_string())?;
+            let archive = dir.join(REMOVED_VOLUME_FILE).to_string_lossy().to_string();
+            run_command("tar", &["-czf", &archive, "-C", &server.host_path, "."])
+                .map_err(|e| format!("Snapshot failed: {}", e.trim()))?;
+        }
+        let record = RemovedServer {
+            id,
+            name: name.to_string(),
+            removed_at: chrono::Local::now().to_rfc3339(),
+            server: SharedServer::new(server, creds.as_ref()),
+            has_volume: include_volume,
+        };
+        write_json_file(&dir.join(REMOVED_SERVER_FILE), &record);
+        // Holds the password in the clear, like the inline credential store
+        if let Some(creds) = &creds {
+            write_private_json_file(&dir.join(CONFIG_FILE), creds);
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) => Ok(dir),
+        Err(e) => {
+            fs::remove_dir_all(&dir).ok();
+            Err(e)
+        }
+    }
+}
+
+fn read_removed_server(dir: &Path) -> Option<RemovedServer> {
+    let content = fs::read_to_string(dir.join(REMOVED_SERVER_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Archived servers, most recently removed first
+#[tauri::command]
+fn list_removed_servers() -> Vec<RemovedServer> {
+    let Ok(entries) = fs::read_dir(get_removed_dir()) else {
+        return Vec::new();
+    };
+    let mut removed: Vec<RemovedServer> = entries
+        .flatten()
+        .filter_map(|e| read_removed_server(&e.path()))
+        .collect();
+    removed.sort_by(|a, b| b.removed_at.cmp(&a.removed_at));
+    removed
+}
+
+/// Recreate an archived server with its old password, address, host keys and
+/// settings. The host folder is unpacked only when it is missing or empty, so
+/// newer files are never overwritten.
+#[tauri::command]
+fn restore_removed_server(
+    app: AppHandle,
+    reservations: State<'_, PortReservations>,
+    archive_id: String,
+) -> CreateResult {
+    let Some(record) = list_removed_servers()
+        .into_iter()
+        .find(|r| r.id == archive_id)
+    else {
+        return CreateResult {
+            error: Some(format!("No removed server {}", archive_id)),
+            ..Default::default()
+        };
+    };
+    let name = record.name.clone();
+    let result = restore_removed_server_internal(&app, &reservations, record).unwrap_or_else(|e| {
+        CreateResult {
+            error: Some(e),
+            ..Default::default()
+        }
+    });
+    record_audit(&name, "restore", result.error.as_deref());
+    result
+}
+
+fn restore_removed_server_internal<R: Runtime>(
+    app: &AppHandle<R>,
+    reservations: &PortReservations,
+    record: RemovedServer,
+) -> Result<CreateResult, String> {
+    let name = record.name.clone();
+    if find_server(&name).is_ok() {
+        return Err(format!("A server named {} already exists", name));
+    }
+    let dir = get_removed_dir().join(&record.id);
+    let creds: StoredCredentials = fs::read_to_string(dir.join(CONFIG_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .ok_or_else(|| format!("The archive of {} has no credentials", name))?;
+
+    let host_path = Path::new(&record.server.host_path);
+    let empty = fs::read_dir(host_path).map_or(true, |mut e| e.next().is_none());
+    if record.has_volume && empty {
+        fs::create_dir_all(host_path).map_err(|e| e.to_string())?;
+        let archive = dir.join(REMOVED_VOLUME_FILE).to_string_lossy().to_string();
+        run_command("tar", &["-xzf", &archive, "-C", &record.server.host_path])
+            .map_err(|e| format!("Unpacking the host folder failed: {}", e.trim()))?;
+    }
+    // Back in place before the create, which mounts them into the new container
+    for (archived, live) in [
+        (dir.join(INIT_SCRIPTS_DIR), init_scripts_dir(&name)),
+        (dir.join(HOST_IDENTITY_DIR), host_identity_dir(&name)),
+        (dir.join(USER_KEYS_DIR), user_keys_dir(&name)),
+    ] {
+        if archived.is_dir() && !live.exists() {
+            if let Some(parent) = live.parent() {
+                fs::create_dir_all(parent).ok();
+            }
+            fs::rename(&archived, &live).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let config = ServerConfig {
+        rate_limit: creds.rate_limit.clone(),
+        banner: creds.banner.clone(),
+        image_build: creds.image_build.clone(),
+        id: creds.id.clone(),
+        ..ServerConfig::from_shared(record.server, creds.password.clone())
+    };
+    let result = create_server_internal(app, reservations, config, creds.bind_ip.clone());
+    if result.success {
+        // Brings back what create doesn't know about: rotation, relay, tunnel, tokens
+        store_server_credentials(&name, creds)?;
+        fs::remove_dir_all(&dir).ok();
+    }
+    Ok(result)
+}
+
+/// Drop an archived server for good
+#[tauri::command]
+fn delete_removed_server(archive_id: String) -> CommandResult {
+    let found = list_removed_servers()
+        .into_iter()
+        .any(|r| r.id == archive_id);
+    let result = if found {
+        fs::remove_dir_all(get_removed_dir().join(&archive_id)).map_err(|e| e.to_string())
+    } else {
+        Err(format!("No removed server {}", archive_id))
+    };
+    CommandResult {
+        success: result.is_ok(),
+        error: result.err(),
+    }
+}
+
+/// Order `names` and their transitive dependencies so every container comes after
+/// everything it depends on
+fn start_order(
+    names: &[String],
+    graph: &HashMap<String, Vec<String>>,
+) -> Result<Vec<String>, String> {
+    fn visit(
+        name: &str,
+        graph: &HashMap<String, Vec<String>>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if order.iter().any(|n| n == name) {
+            return Ok(());
+        }
+        if let Some(pos) = path.iter().position(|n| n == name) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(name.to_string());
+            return Err(format!("Dependency cycle: {}", cycle.join(" -> ")));
+        }
+        path.push(name.to_string());
+        for dep in graph.get(name).into_iter().flatten() {
+            visit(dep, graph, path, order)?;
+        }
+        path.pop();
+        order.push(name.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    for name in names {
+        visit(name, graph, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+fn dependency_graph(creds: &HashMap<String, StoredCredentials>) -> HashMap<String, Vec<String>> {
+    creds
+        .iter()
+        .map(|(name, c)| (name.clone(), c.depends_on.clone()))
+        .collect()
+}
+
+/// Reject dependency lists that name the server itself or close a cycle
+fn check_dependencies(
+    name: &str,
+    depends_on: &[String],
+    creds: &HashMap<String, StoredCredentials>,
+) -> Result<(), String> {
+    if depends_on.iter().any(|d| d == name) {
+        return Err(format!("{} cannot depend on itself", name));
+    }
+    let mut graph = dependency_graph(creds);
+    graph.insert(name.to_string(), depends_on.to_vec());
+    start_order(&[name.to_string()], &graph).map(|_| ())
+}
+
+/// Poll until `name` is running and, where that can be told, healthy: the image's
+/// HEALTHCHECK when it has one, otherwise the SSH banner for managed servers
+fn wait_until_ready(name: &str, ssh: Option<(&str, u16)>) -> Result<(), String> {
+    let deadline = Instant::now() + DEPENDENCY_READY_TIMEOUT;
+    loop {
+        let Some(state) = inspect_container_states(&[name.to_string()]).remove(name) else {
+            return Err(format!("Container {} does not exist", name));
+        };
+        let waiting_on = if state.status != "running" {
+            state.status
+        } else {
+            match state.health.as_deref() {
+                Some("healthy") => return Ok(()),
+                Some(health) => health.to_string(),
+                None => match ssh {
+                    Some((ip, port)) if port != 0 && !probe_ssh(ip, port) => {
+                        "not accepting SSH connections".to_string()
+                    }
+                    _ => return Ok(()),
+                },
+            }
+        };
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "{} still {} after {}s",
+                name,
+                waiting_on,
+                DEPENDENCY_READY_TIMEOUT.as_secs()
+            ));
+        }
+        std::thread::sleep(DEPENDENCY_POLL_INTERVAL);
+    }
+}
+
+fn start_and_wait(name: &str, server: Option<&ServerInfo>) -> Result<(), String> {
+    match server {
+        Some(server) => {
+            if let Some(e) = start_server(name.to_string()).error {
+                return Err(e);
+            }
+            wait_until_ready(
+                name,
+                Some((server.bind_ip.as_deref().unwrap_or(""), server.port)),
+            )
+        }
+        None => {
+            // Not ours (VPN, reverse proxy, ...): start it only if it is down
+            let running = inspect_container_states(&[name.to_string()])
+                .get(name)
+                .is_some_and(|s| s.status == "running");
+            if !running {
+                run_command("docker", &["start", name])?;
+            }
+            wait_until_ready(name, None)
+        }
+    }
+}
+
+fn start_servers_internal(names: &[String]) -> Vec<BatchStartResult> {
+    let creds = load_credentials();
+    let graph = dependency_graph(&creds);
+    let order = match start_order(names, &graph) {
+        Ok(order) => order,
+        Err(e) => {
+            return names
+                .iter()
+                .map(|name| BatchStartResult {
+                    server: name.clone(),
+                    success: false,
+                    error: Some(e.clone()),
+                })
+                .collect()
+        }
+    };
+    let servers: HashMap<String, ServerInfo> = list_servers()
+        .into_iter()
+        .map(|s| (s.name.clone(), s))
+        .collect();
+
+    let mut failed: HashSet<String> = HashSet::new();
+    order
+        .into_iter()
+        .map(|name| {
+            let blocked_by = graph
+                .get(&name)
+                .into_iter()
+                .flatten()
+                .find(|d| failed.contains(*d));
+            let result = match blocked_by {
+                Some(dep) => Err(format!("Dependency {} did not start", dep)),
+                None => start_and_wait(&name, servers.get(&name)),
+            };
+            if result.is_err() {
+                failed.insert(name.clone());
+            }
+            BatchStartResult {
+                server: name,
+                success: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect()
+}
+
+/// Start several servers, bringing up their dependencies first and waiting for each
+/// to be ready before starting anything that depends on it
+#[tauri::command]
+async fn start_servers(names: Vec<String>) -> Vec<BatchStartResult> {
+    let requested = names.clone();
+    tauri::async_runtime::spawn_blocking(move || start_servers_internal(&names))
+        .await
+        .unwrap_or_else(|e| {
+            requested
+                .into_iter()
+                .map(|name| BatchStartResult {
+                    server: name,
+                    success: false,
+                    error: Some(e.to_string()),
+                })
+                .collect()
+        })
+}
+
+#[tauri::command]
+fn set_server_dependencies(name: String, depends_on: Vec<String>) -> CommandResult {
+    let result = update_credentials(|all| {
+        if !all.contains_key(&name) {
+            return Err(format!("Unknown server: {}", name));
+        }
+        check_dependencies(&name, &depends_on, all)?;
+        if let Some(creds) = all.get_mut(&name) {
+            creds.depends_on = depends_on;
+        }
+        Ok(())
+    });
+    record_audit(
+        &name,
+        "set-dependencies",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PasswordRotation {
+    pub server: String,
+    pub username: String,
+    pub rotated_at: String,
+    /// Whether the rotation was scheduled rather than requested
+    pub automatic: bool,
+}
+
+fn generate_password() -> String {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789";
+    (0..GENERATED_PASSWORD_LEN)
+        .map(|_| ALPHABET[OsRng.next_u32() as usize % ALPHABET.len()] as char)
+        .collect()
+}
+
+/// The server's own account; anonymous servers have a fixed, public one
+fn check_account(server: &ServerInfo, username: &str) -> Result<(), String> {
+    if server.anonymous_read_only {
+        return Err(format!("{} only has the anonymous account", server.name));
+    }
+    if server.username != username {
+        return Err(format!("{} has no user {}", server.name, username));
+    }
+    Ok(())
+}
+
+fn set_container_password(name: &str, username: &str, password: &str) -> Result<(), String> {
+    // chpasswd reads user:password from stdin, keeping it out of the process list
+    let output = run_process_with_input(
+        "docker",
+        &["exec", "-i", name, "chpasswd"],
+        Some(format!("{}:{}\n", username, password).into_bytes()),
+    )
+    .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+/// Store `creds`, which hold `username`'s new `password`, then set it in the
+/// container. If the container refuses it, `previous` is stored again, so the stored
+/// password never drifts from the one that works.
+fn change_password(
+    name: &str,
+    username: &str,
+    password: &str,
+    creds: StoredCredentials,
+    previous: StoredCredentials,
+) -> Result<(), String> {
+    store_server_credentials(name, creds)?;
+    if let Err(e) = set_container_password(name, username, password) {
+        return Err(match store_server_credentials(name, previous) {
+            Ok(()) => e,
+            Err(restore) => format!(
+                "{}; the previous stored password could not be put back: {}",
+                e, restore
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Set a fresh random password in the running container and in stored credentials,
+/// so a later recreate keeps it
+fn rotate_password_internal(name: &str, username: &str) -> Result<String, String> {
+    let server = find_server(name)?;
+    check_account(&server, username)?;
+    if server.status != "running" {
+        return Err(format!("{} is not running", name));
+    }
+    let mut creds = load_credentials()
+        .remove(name)
+        .ok_or_else(|| format!("No stored configuration for {}", name))?;
+    let previous = creds.clone();
+    let password = generate_password();
+
+    let rotated_at = chrono::Local::now().to_rfc3339();
+    creds.password = password.clone();
+    creds.password_rotated_at = Some(rotated_at.clone());
+    change_password(name, username, &password, creds, previous)?;
+    Ok(rotated_at)
+}
+
+/// Replace the user's password with a generated one
+#[tauri::command]
+fn rotate_password(name: String, username: String) -> CommandResult {
+    let result = rotate_password_internal(&name, &username);
+    record_audit(
+        &name,
+        "rotate-password",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(rotated_at) => {
+            events::publish(events::Event::PasswordRotated(PasswordRotation {
+                server: name,
+                username,
+                rotated_at,
+                automatic: false,
+            }));
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Rotate the server's password every `days` days; `None` turns it off
+#[tauri::command]
+fn set_password_rotation(name: String, days: Option<u32>) -> CommandResult {
+    if days == Some(0) {
+        return CommandResult {
+            success: false,
+            error: Some("Rotation interval must be at least one day".to_string()),
+        };
+    }
+    let result = update_credentials(|all| match all.get_mut(&name) {
+        Some(creds) => {
+            creds.rotate_password_days = days;
+            Ok(())
+        }
+        None => Err(format!("Unknown server: {}", name)),
+    });
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Let the server's users change their own password through the self-service form
+/// on the HTTP API
+#[tauri::command]
+fn set_password_self_service(name: String, enabled: bool) -> CommandResult {
+    let result = update_credentials(|all| match all.get_mut(&name) {
+        Some(creds) if creds.anonymous_read_only && enabled => {
+            Err(format!("{} only has the anonymous account", name))
+        }
+        Some(creds) => {
+            creds.password_self_service = enabled;
+            Ok(())
+        }
+        None => Err(format!("Unknown server: {}", name)),
+    });
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Failed self-service password changes per server, account and client address:
+/// how many, and when the first of them happened
+static PASSWORD_CHANGE_FAILURES: LazyLock<
+    Mutex<HashMap<(String, String, String), (u32, Instant)>>,
+> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Change an account's password once its current one checks out: the main account
+/// or one added with `import_users`. Failures lock out only the account they were
+/// made against, from the address `peer` they came from.
+fn change_own_password(
+    name: &str,
+    username: &str,
+    peer: &str,
+    current: &str,
+    new: &str,
+) -> Result<(), String> {
+    let key = (name.to_string(), username.to_string(), peer.to_string());
+    let locked = PASSWORD_CHANGE_FAILURES
+        .lock()
+        .map(|mut failures| {
+            failures.retain(|_, (_, since)| since.elapsed() < PASSWORD_CHANGE_LOCKOUT);
+            failures
+                .get(&key)
+                .is_some_and(|(count, _)| *count >= MAX_PASSWORD_CHANGE_FAILURES)
+        })
+        .unwrap_or(false);
+    if locked {
+        return Err("Too many failed attempts; try again later".to_string());
+    }
+
+    let mut creds = load_credentials()
+        .remove(name)
+        .filter(|c| c.password_self_service)
+        .ok_or_else(|| format!("{} does not allow password changes", name))?;
+    let previous = creds.clone();
+    let main = creds.username == username;
+    let stored = if main {
+        Some(&mut creds.password)
+    } else {
+        creds
+            .users
+            .iter_mut()
+            .find(|u| u.username == username)
+            .map(|u| &mut u.password)
+    };
+    // Key-only accounts have no password to check against. Compared in constant
+    // time, so the response time doesn't leak the password
+    let Some(stored) =
+        stored.filter(|p| !p.is_empty() && bool::from(p.as_bytes().ct_eq(current.as_bytes())))
+    else {
+        if let Ok(mut failures) = PASSWORD_CHANGE_FAILURES.lock() {
+            failures.entry(key).or_insert((0, Instant::now())).0 += 1;
+        }
+        return Err("Wrong user name or password".to_string());
+    };
+
+    if new.chars().count() < MIN_SELF_SERVICE_PASSWORD_LEN {
+        return Err(format!(
+            "The new password needs at least {} characters",
+            MIN_SELF_SERVICE_PASSWORD_LEN
+        ));
+    }
+    if new.contains(':') || new.contains(['\n', '\r']) {
+        return Err("The new password cannot contain ':' or line breaks".to_string());
+    }
+    if new == current {
+        return Err("The new password is the same as the current one".to_string());
+    }
+    *stored = new.to_string();
+
+    let rotated_at = chrono::Local::now().to_rfc3339();
+    if main {
+        creds.password_rotated_at = Some(rotated_at.clone());
+    }
+    change_password(name, username, new, creds, previous)?;
+    events::publish(events::Event::PasswordRotated(PasswordRotation {
+        server: name.to_string(),
+        username: username.to_string(),
+        rotated_at,
+        automatic: false,
+    }));
+    Ok(())
+}
+
+/// An account beyond the server's main user, created by `import_users`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SftpUser {
+    pub username: String,
+    /// Empty for key-only accounts
+    #[serde(default)]
+    pub password: String,
+    /// Directory created under the user's home, like atmoz's `dir` field
+    #[serde(default)]
+    pub home_dir: Option<String>,
+}
+
+impl SftpUser {
+    /// The `user:pass:uid:gid:dir` spec atmoz's create-sftp-user takes
+    fn spec(&self) -> String {
+        format!(
+            "{}:{}:::{}",
+            self.username,
+            self.password,
+            self.home_dir.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+fn user_keys_dir(name: &str) -> PathBuf {
+    get_config_dir().join(USER_KEYS_DIR).join(name)
+}
+
+/// `docker run -v` values queueing each extra user's key where atmoz picks it up
+fn user_key_mounts(name: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(user_keys_dir(name)) else {
+        return Vec::new();
+    };
+    let mut mounts: Vec<String> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "pub"))
+        .filter_map(|path| {
+            let user = path.file_stem()?.to_string_lossy().to_string();
+            Some(format!(
+                "{}:/home/{}/.ssh/keys/dsftp.pub:ro",
+                path.to_string_lossy().replace('\\', "/"),
+                user
+            ))
+        })
+        .collect();
+    mounts.sort();
+    mounts
+}
+
+/// Outcome of one CSV row
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ImportUserRow {
+    /// 1-based line in the file
+    pub line: usize,
+    pub username: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Split one CSV line, honouring double-quoted fields with `""` escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("fields is never empty");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => field.push(c),
+        }
+    }
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+fn is_public_key(value: &str) -> bool {
+    ["ssh-", "ecdsa-", "sk-"]
+        .iter()
+        .any(|p| value.starts_with(p))
+}
+
+/// Check one `username,password-or-key,home-dir` row
+fn parse_user_row(
+    fields: &[String],
+    taken: &HashSet<String>,
+) -> Result<(SftpUser, Option<String>), String> {
+    let username = fields.first().cloned().unwrap_or_default();
+    if !is_valid_username(&username) {
+        return Err(format!("Invalid username: {}", username));
+    }
+    if taken.contains(&username) {
+        return Err(format!("{} already exists", username));
+    }
+    let secret = fields.get(1).cloned().unwrap_or_default();
+    if secret.is_empty() {
+        return Err("A password or public key is required".to_string());
+    }
+    let (password, key) = if is_public_key(&secret) {
+        (String::new(), Some(secret))
+    } else if secret.contains(':') {
+        return Err("Passwords can't contain ':'".to_string());
+    } else {
+        (secret, None)
+    };
+    let home_dir = fields.get(2).filter(|d| !d.is_empty()).cloned();
+    if let Some(dir) = &home_dir {
+        let plain = Path::new(dir)
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+        if !plain || dir.contains([':', ',']) {
+            return Err(format!("Invalid home directory: {}", dir));
+        }
+    }
+    Ok((
+        SftpUser {
+            username,
+            password,
+            home_dir,
+        },
+        key,
+    ))
+}
+
+/// Create one account in the running container. The spec and key go in on
+/// stdin, keeping the password out of the process list.
+fn create_container_user(name: &str, user: &SftpUser, key: Option<&str>) -> Result<(), String> {
+    let input = format!("{}\n{}\n", user.spec(), key.unwrap_or_default());
+    let output = run_process_with_input(
+        "docker",
+        &["exec", "-i", name, "sh", "-c", CREATE_USER_SCRIPT],
+        Some(input.into_bytes()),
+    )
+    .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+/// Add every account in a `username,password-or-key,home-dir` CSV to a running
+/// server. Rows are checked first and bad ones reported without stopping the rest;
+/// the accounts are kept in stored credentials so a recreate brings them back.
+#[tauri::command]
+async fn import_users(name: String, csv_path: String) -> Result<Vec<ImportUserRow>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let server = find_server(&name)?;
+        if server.status != "running" {
+            return Err(format!("{} is not running", name));
+        }
+        let content = fs::read_to_string(&csv_path).map_err(|e| e.to_string())?;
+        let mut taken: HashSet<String> = load_credentials()
+            .remove(&name)
+            .map(|c| c.users.into_iter().map(|u| u.username).collect())
+            .unwrap_or_default();
+        taken.insert(server.username.clone());
+
+        let mut report = Vec::new();
+        let mut added = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = split_csv_line(line);
+            if index == 0 && fields[0].eq_ignore_ascii_case("username") {
+                continue;
+            }
+            let result = parse_user_row(&fields, &taken).and_then(|(user, key)| {
+                create_container_user(&name, &user, key.as_deref())?;
+                if let Some(key) = &key {
+                    let dir = user_keys_dir(&name);
+                    fs::create_dir_all(&dir)
+                        .and_then(|_| fs::write(dir.join(format!("{}.pub", user.username)), key))
+                        .map_err(|e| format!("Created, but keeping the key failed: {}", e))?;
+                }
+                Ok(user)
+            });
+            let username = fields[0].clone();
+            match result {
+                Ok(user) => {
+                    taken.insert(user.username.clone());
+                    added.push(user);
+                    report.push(ImportUserRow {
+                        line: index + 1,
+                        username,
+                        success: true,
+                        error: None,
+                    });
+                }
+                Err(e) => report.push(ImportUserRow {
+                    line: index + 1,
+                    username,
+                    success: false,
+                    error: Some(e),
+                }),
+            }
+        }
+
+        let count = added.len();
+        if count > 0 {
+            update_credentials(|all| {
+                if let Some(creds) = all.get_mut(&name) {
+                    creds.users.extend(added);
+                }
+            });
+        }
+        let failed = report.len() - count;
+        record_audit(
+            &name,
+            "import-users",
+            (failed > 0)
+                .then(|| format!("{} of {} rows failed", failed, report.len()))
+                .as_deref(),
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Lock or unlock the user's password so logins are refused without deleting the account
+#[tauri::command]
+fn set_account_locked(name: String, username: String, locked: bool) -> CommandResult {
+    let result = find_server(&name)
+        .and_then(|server| check_account(&server, &username))
+        .and_then(|_| {
+            let flag = if locked { "-L" } else { "-U" };
+            run_command("docker", &["exec", &name, "usermod", flag, &username])
+        });
+    record_audit(
+        &name,
+        if locked {
+            "lock-account"
+        } else {
+            "unlock-account"
+        },
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Rotate passwords whose schedule has come due
+fn rotation_tick(servers: &[ServerInfo]) {
+    let creds = load_credentials();
+    let running = servers
+        .iter()
+        .filter(|s| s.status == "running" && s.state != ServerState::Paused);
+    for server in running {
+        let Some(entry) = creds.get(&server.name) else {
+            continue;
+        };
+        let Some(days) = entry.rotate_password_days else {
+            continue;
+        };
+        let last = entry
+            .password_rotated_at
+            .as_deref()
+            .or(server.created_at.as_deref())
+            .and_then(seconds_since);
+        if last.is_some_and(|secs| secs < days as u64 * 24 * 60 * 60) {
+            continue;
+        }
+        let result = rotate_password_internal(&server.name, &server.username);
+        record_audit(
+            &server.name,
+            "rotate-password",
+            result.as_ref().err().map(|e| e.as_str()),
+        );
+        if let Ok(rotated_at) = result {
+            events::publish(events::Event::PasswordRotated(PasswordRotation {
+                server: server.name.clone(),
+                username: server.username.clone(),
+                rotated_at,
+                automatic: true,
+            }));
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct IssuedCertificate {
+    /// OpenSSH certificate, saved next to the private key as `<key>-cert.pub`
+    pub certificate: String,
+    /// Only set when dsftp generated the key pair
+    pub private_key: Option<String>,
+    pub key_id: String,
+    pub valid_until: String,
+}
+
+fn ssh_ca_key_path() -> PathBuf {
+    get_config_dir().join(SSH_CA_DIR).join("ca")
+}
+
+/// Write the trust hook mounted into every server once the CA exists
+fn write_ssh_ca_trust(trust: &Path) -> Result<(), String> {
+    fs::write(trust, SSH_CA_TRUST_SCRIPT).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(trust, fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Host paths of the CA public key and trust hook, once the CA exists
+fn ssh_ca_mounts() -> Option<(String, String)> {
+    let ca_pub = ssh_ca_key_path().with_extension("pub");
+    let trust = get_config_dir().join(SSH_CA_DIR).join("trust.sh");
+    if !ca_pub.is_file() {
+        return None;
+    }
+    // A hook written by an older version is brought up to date
+    if fs::read_to_string(&trust).ok().as_deref() != Some(SSH_CA_TRUST_SCRIPT) {
+        write_ssh_ca_trust(&trust).ok()?;
+    }
+    let docker_path = |p: PathBuf| p.to_string_lossy().replace('\\', "/");
+    Some((docker_path(ca_pub), docker_path(trust)))
+}
+
+/// Create the CA key pair (once) and return its public key. Servers created from now
+/// on trust certificates it signs for them; existing ones pick it up when recreated.
+#[tauri::command]
+fn init_ssh_ca() -> Result<String, String> {
+    let key = ssh_ca_key_path();
+    let dir = key.parent().map(Path::to_path_buf).unwrap_or_default();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    if !key.is_file() {
+        let key_arg = key.to_string_lossy();
+        run_command(
+            "ssh-keygen",
+            &[
+                "-q", "-t", "ed25519", "-N", "", "-C", "dsftp-ca", "-f", &*key_arg,
+            ],
+        )
+        .map_err(|e| format!("ssh-keygen: {}", e.trim()))?;
+    }
+
+    write_ssh_ca_trust(&dir.join("trust.sh"))?;
+    fs::read_to_string(key.with_extension("pub"))
+        .map(|k| k.trim().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_ssh_ca_public_key() -> Option<String> {
+    fs::read_to_string(ssh_ca_key_path().with_extension("pub"))
+        .ok()
+        .map(|k| k.trim().to_string())
+}
+
+/// Sign (and if needed first generate) the user's key inside `scratch`. The
+/// certificate's only principal is `user@<server id>`, which no other server accepts.
+fn sign_certificate(
+    scratch: &Path,
+    server: &ServerInfo,
+    user: &str,
+    ttl_minutes: u32,
+    public_key: Option<String>,
+) -> Result<IssuedCertificate, String> {
+    let name = server.name.as_str();
+    let id = server
+        .id
+        .as_deref()
+        .ok_or_else(|| format!("{} has no server id yet; recreate it first", name))?;
+    let principal = format!("{}@{}", user, id);
+    let key = scratch.join("key");
+    let key_arg = key.to_string_lossy().to_string();
+    let generated = match public_key {
+        Some(public_key) => {
+            fs::write(key.with_extension("pub"), public_key.trim()).map_err(|e| e.to_string())?;
+            false
+        }
+        None => {
+            run_command(
+                "ssh-keygen",
+                &["-q", "-t", "ed25519", "-N", "", "-C", user, "-f", &key_arg],
+            )
+            .map_err(|e| format!("ssh-keygen: {}", e.trim()))?;
+            true
+        }
+    };
+
+    let key_id = format!("dsftp:{}:{}:{}", name, user, chrono::Utc::now().timestamp());
+    let validity = format!("+{}m", ttl_minutes);
+    let ca_arg = ssh_ca_key_path().to_string_lossy().to_string();
+    let pub_arg = format!("{}.pub", key_arg);
+    run_command(
+        "ssh-keygen",
+        &[
+            "-q", "-s", &ca_arg, "-I", &key_id, "-n", &principal, "-V", &validity, &pub_arg,
+        ],
+    )
+    .map_err(|e| format!("ssh-keygen: {}", e.trim()))?;
+
+    Ok(IssuedCertificate {
+        certificate: fs::read_to_string(scratch.join("key-cert.pub"))
+            .map_err(|e| e.to_string())?
+            .trim()
+            .to_string(),
+        private_key: if generated {
+            Some(fs::read_to_string(&key).map_err(|e| e.to_string())?)
+        } else {
+            None
+        },
+        key_id,
+        valid_until: (chrono::Local::now() + chrono::Duration::minutes(ttl_minutes as i64))
+            .to_rfc3339(),
+    })
+}
+
+fn issue_certificate_internal(
+    name: &str,
+    user: &str,
+    ttl_minutes: u32,
+    public_key: Option<String>,
+) -> Result<IssuedCertificate, String> {
+    if !ssh_ca_key_path().is_file() {
+        return Err("The SSH certificate authority hasn't been set up".to_string());
+    }
+    let server = find_server(name)?;
+    check_account(&server, user)?;
+
+    // Work in a scratch dir so ssh-keygen can write key, pub and cert side by side
+    let scratch = get_config_dir()
+        .join(SSH_CA_DIR)
+        .join(format!("issue-{}", random_token()));
+    fs::create_dir_all(&scratch).map_err(|e| e.to_string())?;
+    let result = sign_certificate(&scratch, &server, user, ttl_minutes, public_key);
+    // Never leave generated private keys lying around
+    fs::remove_dir_all(&scratch).ok();
+    result
+}
+
+/// Sign a short-lived certificate for `user` on `name`. Signs `public_key` when
+/// given; otherwise generates a key pair and returns its private key too.
+#[tauri::command]
+async fn issue_certificate(
+    name: String,
+    user: String,
+    ttl_minutes: Option<u32>,
+    public_key: Option<String>,
+) -> Result<IssuedCertificate, String> {
+    let ttl = ttl_minutes
+        .unwrap_or(DEFAULT_CERTIFICATE_TTL_MINUTES)
+        .max(1);
+    let audit_name = name.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        issue_certificate_internal(&name, &user, ttl, public_key)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    record_audit(
+        &audit_name,
+        "issue-certificate",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    result
+}
+
+/// Refuse a write of `bytes` to `path` that would eat into the disk guard's
+/// headroom. Passes when free space can't be read, rather than block on it.
+fn check_disk_space(path: &Path, bytes: u64) -> Result<(), InsufficientSpace> {
+    let headroom = load_app_settings().disk_guard.min_free_mb * 1024 * 1024;
+    // Destinations may not exist yet; their filesystem is the nearest ancestor's
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    let Ok(available) = fs2::available_space(existing) else {
+        return Ok(());
+    };
+    let required = bytes.saturating_add(headroom);
+    if available < required {
+        return Err(InsufficientSpace {
+            path: path.display().to_string(),
+            required,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Warn once per dip when a share's filesystem falls below the guard's headroom
+fn disk_space_tick(servers: &[ServerInfo], low: &mut HashSet<String>) {
+    let threshold = load_app_settings().disk_guard.min_free_mb * 1024 * 1024;
+    for server in servers.iter().filter(|s| !s.host_path.is_empty()) {
+        let Ok(available) = fs2::available_space(&server.host_path) else {
+            continue;
+        };
+        if available >= threshold {
+            low.remove(&server.name);
+        } else if low.insert(server.name.clone()) {
+            events::publish(events::Event::LowDiskSpace(LowDiskSpace {
+                server: server.name.clone(),
+                path: server.host_path.clone(),
+                available,
+                threshold,
+            }));
+        }
+    }
+}
+
+/// Total size of regular files under `path`, without following symlinks
+fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return if meta.is_file() { meta.len() } else { 0 };
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Size and times of one file in a host-folder file list
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+struct IndexedFile {
+    size: u64,
+    /// Unix seconds
+    modified: i64,
+    /// Unix seconds; not every filesystem records it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<i64>,
+}
+
+/// Every file under a server's host folder at one point in time, which a later
+/// `diff_volume` compares against to find deleted files
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct VolumeIndex {
+    taken_at: String,
+    label: Option<String>,
+    files: HashMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct VolumeIndexInfo {
+    /// `None` for the ones the watcher takes
+    pub label: Option<String>,
+    pub taken_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct FileChange {
+    /// Relative to the host folder, with forward slashes
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    pub modified_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct VolumeDiff {
+    pub since: String,
+    pub added: Vec<FileChange>,
+    pub modified: Vec<FileChange>,
+    pub deleted: Vec<FileChange>,
+    /// Time of the file list deletions were counted from, which may be before
+    /// `since`; `None` when there was no list and deletions can't be told
+    pub deletions_since: Option<String>,
+    /// Some list hit `MAX_DIFF_ENTRIES` and was cut short
+    pub truncated: bool,
+}
+
+fn unix_seconds(time: std::io::Result<SystemTime>) -> Option<i64> {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// Every regular file under `root` by its relative path; symlinks aren't followed
+fn index_tree(root: &Path) -> HashMap<String, IndexedFile> {
+    let mut files = HashMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if meta.is_dir() {
+                pending.push(path);
+            } else if meta.is_file() {
+                let Ok(relative) = path.strip_prefix(root) else {
+                    continue;
+                };
+                let file = IndexedFile {
+                    size: meta.len(),
+                    modified: unix_seconds(meta.modified()).unwrap_or(0),
+                    created: unix_seconds(meta.created()),
+                };
+                files.insert(relative.to_string_lossy().replace('\\', "/"), file);
+            }
+        }
+    }
+    files
+}
+
+fn volume_index_dir(name: &str) -> PathBuf {
+    get_data_dir().join(VOLUME_INDEX_DIR).join(name)
+}
+
+fn is_valid_index_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 64
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !label.starts_with('.')
+}
+
+/// The watcher's file lists of a server with their times, oldest first
+fn auto_volume_indexes(name: &str) -> Vec<(chrono::DateTime<chrono::FixedOffset>, PathBuf)> {
+    let Ok(entries) = fs::read_dir(volume_index_dir(name)) else {
+        return Vec::new();
+    };
+    let mut indexes: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = file_name
+                .strip_prefix(AUTO_INDEX_PREFIX)?
+                .strip_suffix(".json")?;
+            let time = chrono::DateTime::parse_from_str(stamp, INDEX_STAMP_FORMAT).ok()?;
+            Some((time, entry.path()))
+        })
+        .collect();
+    indexes.sort_by_key(|(time, _)| *time);
+    indexes
+}
+
+/// Record the current file list of a server's host folder, under `label` or as
+/// one of the watcher's, dropping the oldest of those past `MAX_AUTO_VOLUME_INDEXES`
+fn take_volume_index(name: &str, label: Option<&str>) -> Result<String, String> {
+    let server = find_server(name)?;
+    let root = Path::new(&server.host_path);
+    if server.host_path.is_empty() || !root.is_dir() {
+        return Err(format!("Host folder not found: {}", server.host_path));
+    }
+    let now = chrono::Local::now();
+    let index = VolumeIndex {
+        taken_at: now.to_rfc3339(),
+        label: label.map(str::to_string),
+        files: index_tree(root),
+    };
+    let file_name = match label {
+        Some(label) => format!("{}{}.json", NAMED_INDEX_PREFIX, label),
+        None => format!(
+            "{}{}.json",
+            AUTO_INDEX_PREFIX,
+            now.format(INDEX_STAMP_FORMAT)
+        ),
+    };
+    let dir = volume_index_dir(name);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    write_json_file(&dir.join(file_name), &index);
+    if label.is_none() {
+        let autos = auto_volume_indexes(name);
+        let excess = autos.len().saturating_sub(MAX_AUTO_VOLUME_INDEXES);
+        for (_, path) in &autos[..excess] {
+            fs::remove_file(path).ok();
+        }
+    }
+    Ok(index.taken_at)
+}
+
+/// Record the host folder's file list under a name, so `diff_volume` can later
+/// report changes since exactly this point. An existing list of that name is replaced.
+#[tauri::command]
+async fn create_volume_index(name: String, label: String) -> Result<String, String> {
+    if !is_valid_index_label(&label) {
+        return Err(format!(
+            "Invalid name {}: use letters, digits, '-', '_' and '.'",
+            label
+        ));
+    }
+    tauri::async_runtime::spawn_blocking(move || take_volume_index(&name, Some(&label)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// File lists kept for a server, newest first
+#[tauri::command]
+fn list_volume_indexes(name: String) -> Vec<VolumeIndexInfo> {
+    let Ok(entries) = fs::read_dir(volume_index_dir(&name)) else {
+        return Vec::new();
+    };
+    let mut indexes: Vec<VolumeIndexInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let stem = file_name.strip_suffix(".json")?;
+            if let Some(label) = stem.strip_prefix(NAMED_INDEX_PREFIX) {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                return Some(VolumeIndexInfo {
+                    label: Some(label.to_string()),
+                    taken_at: chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339(),
+                });
+            }
+            let stamp = stem.strip_prefix(AUTO_INDEX_PREFIX)?;
+            let time = chrono::DateTime::parse_from_str(stamp, INDEX_STAMP_FORMAT).ok()?;
+            Some(VolumeIndexInfo {
+                label: None,
+                taken_at: time.to_rfc3339(),
+            })
+        })
+        .collect();
+    indexes.sort_by(|a, b| b.taken_at.cmp(&a.taken_at));
+    indexes
+}
+
+fn file_change(path: &str, file: &IndexedFile) -> FileChange {
+    FileChange {
+        path: path.to_string(),
+        size: file.size,
+        modified_at: chrono::DateTime::from_timestamp(file.modified, 0)
+            .map(|t| t.with_timezone(&chrono::Local).to_rfc3339()),
+    }
+}
+
+fn diff_volume_internal(name: &str, since: &str) -> Result<VolumeDiff, String> {
+    let server = find_server(name)?;
+    let root = Path::new(&server.host_path);
+    if server.host_path.is_empty() || !root.is_dir() {
+        return Err(format!("Host folder not found: {}", server.host_path));
+    }
+    let named = volume_index_dir(name).join(format!("{}{}.json", NAMED_INDEX_PREFIX, since));
+    let (since_time, baseline) = if is_valid_index_label(since) && named.is_file() {
+        let index: VolumeIndex = read_json_file(&named);
+        let time = chrono::DateTime::parse_from_rfc3339(&index.taken_at)
+            .map_err(|_| format!("File list {} is damaged", since))?;
+        (time, Some(index))
+    } else {
+        let time = chrono::DateTime::parse_from_rfc3339(since).map_err(|_| {
+            format!(
+                "{} is neither a time (RFC 3339) nor a saved file list",
+                since
+            )
+        })?;
+        // The latest list from before `since`, else the earliest there is, so
+        // at least the later deletions show
+        let autos = auto_volume_indexes(name);
+        let chosen = autos
+            .iter()
+            .rev()
+            .find(|(taken, _)| *taken <= time)
+            .or(autos.first());
+        (time, chosen.map(|(_, path)| read_json_file(path)))
+    };
+    let since_secs = since_time.timestamp();
+
+    let current = index_tree(root);
+    let mut diff = VolumeDiff {
+        since: since_time.to_rfc3339(),
+        added: Vec::new(),
+        modified: Vec::new(),
+        deleted: Vec::new(),
+        deletions_since: baseline.as_ref().map(|b| b.taken_at.clone()),
+        truncated: false,
+    };
+    for (path, file) in &current {
+        // A copy that kept its original mtime still has a fresh creation time
+        let created_after = file.created.is_some_and(|c| c > since_secs);
+        if file.modified <= since_secs && !created_after {
+            continue;
+        }
+        let is_new = match &baseline {
+            Some(baseline) => !baseline.files.contains_key(path),
+            None => created_after,
+        };
+        let list = if is_new {
+            &mut diff.added
+        } else {
+            &mut diff.modified
+        };
+        list.push(file_change(path, file));
+    }
+    if let Some(baseline) = &baseline {
+        diff.deleted = baseline
+            .files
+            .iter()
+            .filter(|(path, _)| !current.contains_key(*path))
+            .map(|(path, file)| file_change(path, file))
+            .collect();
+    }
+    for list in [&mut diff.added, &mut diff.modified, &mut diff.deleted] {
+        list.sort_by(|a, b| a.path.cmp(&b.path));
+        if list.len() > MAX_DIFF_ENTRIES {
+            list.truncate(MAX_DIFF_ENTRIES);
+            diff.truncated = true;
+        }
+    }
+    Ok(diff)
+}
+
+/// Files added, modified and deleted under a server's host folder since `since`,
+/// an RFC 3339 time or the name of a saved file list. Additions and changes come
+/// from file times; deletions need a file list from before, which the watcher
+/// records every few hours.
+#[tauri::command]
+async fn diff_volume(name: String, since: String) -> Result<VolumeDiff, String> {
+    tauri::async_runtime::spawn_blocking(move || diff_volume_internal(&name, &since))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Servers whose host folder is being listed by the watcher right now
+static VOLUME_INDEXING: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Record each server's file list every `VOLUME_INDEX_INTERVAL`, each on a thread
+/// of its own since large shares take a while to walk
+fn volume_index_tick(servers: &[ServerInfo]) {
+    let now = chrono::Local::now().fixed_offset();
+    for server in servers {
+        if server.host_path.is_empty() || !Path::new(&server.host_path).is_dir() {
+            continue;
+        }
+        let due = auto_volume_indexes(&server.name)
+            .last()
+            .is_none_or(|(taken, _)| {
+                (now - *taken)
+                    .to_std()
+                    .map_or(true, |age| age >= VOLUME_INDEX_INTERVAL)
+            });
+        let Ok(mut indexing) = VOLUME_INDEXING.lock() else {
+            continue;
+        };
+        if !due || !indexing.insert(server.name.clone()) {
+            continue;
+        }
+        let name = server.name.clone();
+        std::thread::spawn(move || {
+            take_volume_index(&name, None).ok();
+            if let Ok(mut indexing) = VOLUME_INDEXING.lock() {
+                indexing.remove(&name);
+            }
+        });
+    }
+}
+
+/// Count authenticated SSH sessions; sshd keeps one "[priv]" monitor process per session
+fn count_sessions(name: &str) -> usize {
+    run_command("docker", &["top", name])
+        .map(|output| {
+            output
+                .lines()
+                .skip(1)
+                .filter(|line| line.contains("[priv]"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// One logged-in SFTP client
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SftpSession {
+    /// pid of the session's sshd monitor inside the container; pass to `kill_session`
+    pub id: u32,
+    pub user: String,
+    /// Client address and port, when the socket could be matched
+    pub client: Option<String>,
+    pub started_at: Option<String>,
+}
+
+/// "0100007F:D431" from /proc/net/tcp{,6} -> "127.0.0.1:54321"
+fn parse_proc_net_address(value: &str) -> Option<String> {
+    let (ip, port) = value.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    // The kernel prints each 32-bit word in host (little-endian) order
+    let words: Vec<u32> = (0..ip.len() / 8)
+        .map(|i| u32::from_str_radix(&ip[i * 8..i * 8 + 8], 16).map(u32::swap_bytes))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let ip: std::net::IpAddr = match words.as_slice() {
+        [a] => std::net::Ipv4Addr::from(*a).into(),
+        [a, b, c, d] => {
+            let v6 = std::net::Ipv6Addr::from(
+                (u128::from(*a) << 96)
+                    | (u128::from(*b) << 64)
+                    | (u128::from(*c) << 32)
+                    | u128::from(*d),
+            );
+            v6.to_ipv4_mapped().map(Into::into).unwrap_or(v6.into())
+        }
+        _ => return None,
+    };
+    Some(std::net::SocketAddr::new(ip, port).to_string())
+}
+
+/// Authenticated sessions on the server, one per sshd "[priv]" monitor
+#[tauri::command]
+fn list_sessions(name: String) -> Result<Vec<SftpSession>, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let output = run_command("docker", &["exec", &name, "sh", "-c", LIST_SESSIONS_SCRIPT])?;
+    let mut lines = output.lines();
+    let uptime: f64 = lines
+        .next()
+        .and_then(|l| l.split_whitespace().next())
+        .and_then(|u| u.parse().ok())
+        .unwrap_or(0.0);
+    let now = chrono::Local::now();
+
+    Ok(lines
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(4, '|').collect();
+            let [pid, cmd, peer, start] = parts.as_slice() else {
+                return None;
+            };
+            // "sshd: alice [priv]"
+            let user = cmd.split_whitespace().nth(1)?.to_string();
+            let started_at = start.trim().parse::<f64>().ok().map(|ticks| {
+                let age = (uptime - ticks / CLOCK_TICKS_PER_SEC).max(0.0);
+                (now - chrono::Duration::milliseconds((age * 1000.0) as i64)).to_rfc3339()
+            });
+            Some(SftpSession {
+                id: pid.parse().ok()?,
+                user,
+                client: parse_proc_net_address(peer.trim()),
+                started_at,
+            })
+        })
+        .collect())
+}
+
+/// Algorithms an older client can negotiate, one list per negotiation step
+struct LegacyClient {
+    name: &'static str,
+    kex: &'static [&'static str],
+    ciphers: &'static [&'static str],
+    macs: &'static [&'static str],
+    host_keys: &'static [&'static str],
+}
+
+/// Clients still found in the wild whose algorithm support stopped years ago.
+/// Each only needs one entry of every list to be offered to connect.
+const LEGACY_CLIENTS: &[LegacyClient] = &[
+    LegacyClient {
+        name: "WinSCP 5.8 and older (PuTTY 0.67 and older)",
+        kex: &[
+            "diffie-hellman-group-exchange-sha256",
+            "diffie-hellman-group-exchange-sha1",
+            "diffie-hellman-group14-sha1",
+            "diffie-hellman-group1-sha1",
+        ],
+        ciphers: &[
+            "aes256-ctr",
+            "aes192-ctr",
+            "aes128-ctr",
+            "aes256-cbc",
+            "aes128-cbc",
+            "3des-cbc",
+        ],
+        macs: &["hmac-sha2-256", "hmac-sha1"],
+        host_keys: &["ssh-rsa", "ssh-dss"],
+    },
+    LegacyClient {
+        name: "Java clients on JSch 0.1.54 and older",
+        kex: &[
+            "ecdh-sha2-nistp256",
+            "diffie-hellman-group-exchange-sha256",
+            "diffie-hellman-group-exchange-sha1",
+            "diffie-hellman-group14-sha1",
+            "diffie-hellman-group1-sha1",
+        ],
+        ciphers: &["aes128-ctr", "aes256-ctr", "aes128-cbc", "3des-cbc"],
+        macs: &["hmac-sha2-256", "hmac-sha1"],
+        host_keys: &["ssh-rsa", "ssh-dss", "ecdsa-sha2-nistp256"],
+    },
+    LegacyClient {
+        name: "Embedded devices (old Dropbear or libssh2 before 1.9)",
+        kex: &["diffie-hellman-group14-sha1", "diffie-hellman-group1-sha1"],
+        ciphers: &["aes128-ctr", "aes256-ctr", "aes128-cbc", "3des-cbc"],
+        macs: &["hmac-sha1"],
+        host_keys: &["ssh-rsa", "ssh-dss"],
+    },
+];
+
+/// A client that won't get past the handshake, and the step where it fails
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CompatIssue {
+    pub client: String,
+    /// "kex", "cipher", "mac", "host-key" or "sftp"
+    pub setting: String,
+    pub message: String,
+}
+
+/// What the server's sshd offers connecting clients
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ClientCompatReport {
+    /// "OpenSSH_9.2p1 Debian-2+deb12u3"
+    pub openssh_version: Option<String>,
+    pub kex_algorithms: Vec<String>,
+    pub ciphers: Vec<String>,
+    pub macs: Vec<String>,
+    pub host_key_algorithms: Vec<String>,
+    /// Command behind the sftp subsystem, None when SFTP is not offered at all
+    pub sftp_subsystem: Option<String>,
+    /// SFTP protocol versions the subsystem speaks
+    pub sftp_versions: Vec<u32>,
+    pub issues: Vec<CompatIssue>,
+}
+
+/// Compare the offered algorithms against `LEGACY_CLIENTS`
+fn client_compat_issues(report: &ClientCompatReport) -> Vec<CompatIssue> {
+    let mut issues = Vec::new();
+    for client in LEGACY_CLIENTS {
+        let steps = [
+            ("kex", "key exchange", client.kex, &report.kex_algorithms),
+            ("cipher", "cipher", client.ciphers, &report.ciphers),
+            ("mac", "MAC", client.macs, &report.macs),
+            (
+                "host-key",
+                "host key algorithm",
+                client.host_keys,
+                &report.host_key_algorithms,
+            ),
+        ];
+        for (setting, label, supported, offered) in steps {
+            if !supported.iter().any(|a| offered.iter().any(|o| o == a)) {
+                issues.push(CompatIssue {
+                    client: client.name.to_string(),
+                    setting: setting.to_string(),
+                    message: format!(
+                        "No common {}; the client offers {}",
+                        label,
+                        supported.join(", ")
+                    ),
+                });
+            }
+        }
+    }
+    if report.sftp_subsystem.is_none() {
+        issues.push(CompatIssue {
+            client: "All SFTP clients".to_string(),
+            setting: "sftp".to_string(),
+            message: "sshd has no sftp subsystem configured".to_string(),
+        });
+    } else if report.sftp_versions.is_empty() {
+        issues.push(CompatIssue {
+            client: "All SFTP clients".to_string(),
+            setting: "sftp".to_string(),
+            message: "The sftp subsystem is not OpenSSH's; its protocol versions are unknown"
+                .to_string(),
+        });
+    }
+    issues
+}
+
+/// Which key exchanges, ciphers, MACs and SFTP versions the server offers, read
+/// from sshd's effective configuration, with the older clients that can't connect
+#[tauri::command]
+fn probe_client_compat(name: String) -> Result<ClientCompatReport, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let output = run_command("docker", &["exec", &name, "sh", "-c", PROBE_SSHD_SCRIPT])?;
+    let mut lines = output.lines();
+    let openssh_version = lines
+        .next()
+        .and_then(|l| l.split(',').next())
+        .filter(|v| v.starts_with("OpenSSH"))
+        .map(|v| v.trim().to_string());
+
+    let mut settings: HashMap<String, String> = HashMap::new();
+    for line in lines {
+        if let Some((keyword, value)) = line.trim().split_once(' ') {
+            // `sshd -T` repeats keywords like subsystem; the sftp one is all that matters
+            if keyword == "subsystem" && !value.starts_with("sftp ") {
+                continue;
+            }
+            settings.insert(keyword.to_string(), value.trim().to_string());
+        }
+    }
+    let list = |keyword: &str| -> Vec<String> {
+        settings
+            .get(keyword)
+            .map(|v| v.split(',').map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    let sftp_subsystem = settings
+        .get("subsystem")
+        .and_then(|v| v.strip_prefix("sftp "))
+        .map(|v| v.trim().to_string());
+    // OpenSSH's sftp-server and internal-sftp speak version 3 and nothing else
+    let sftp_versions = match &sftp_subsystem {
+        Some(command)
+            if command.starts_with("internal-sftp")
+                || command
+                    .split_whitespace()
+                    .next()
+                    .is_some_and(|c| c.ends_with("/sftp-server")) =>
+        {
+            vec![3]
+        }
+        _ => Vec::new(),
+    };
+
+    let mut report = ClientCompatReport {
+        openssh_version,
+        kex_algorithms: list("kexalgorithms"),
+        ciphers: list("ciphers"),
+        macs: list("macs"),
+        host_key_algorithms: list("hostkeyalgorithms"),
+        sftp_subsystem,
+        sftp_versions,
+        issues: Vec::new(),
+    };
+    report.issues = client_compat_issues(&report);
+    Ok(report)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct HardeningCheck {
+    /// Account the check is about; `None` for server-wide ones
+    pub user: Option<String>,
+    pub check: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Whether a server only offers SFTP, as verified inside the container
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct HardeningReport {
+    pub passed: bool,
+    pub checks: Vec<HardeningCheck>,
+}
+
+/// A login account as `VERIFY_HARDENING_SCRIPT` reports it
+#[derive(Default)]
+struct AccountHardening {
+    user: String,
+    shell: String,
+    settings: HashMap<String, String>,
+    /// (owner, mode, path) of the chroot and each directory above it
+    chroot_dirs: Vec<(String, u32, String)>,
+    missing_dirs: Vec<String>,
+}
+
+fn account_checks(account: &AccountHardening) -> Vec<HardeningCheck> {
+    let setting = |keyword: &str| account.settings.get(keyword).map(String::as_str);
+    let check = |label: &str, passed: bool, detail: String| HardeningCheck {
+        user: Some(account.user.clone()),
+        check: label.to_string(),
+        passed,
+        detail,
+    };
+    let mut checks = Vec::new();
+
+    checks.push(match setting("forcecommand") {
+        Some("internal-sftp") => check(
+            "Shell access denied",
+            true,
+            "ForceCommand internal-sftp".to_string(),
+        ),
+        other => check(
+            "Shell access denied",
+            false,
+            format!(
+                "ForceCommand is {}; the account can run commands with {}",
+                other.unwrap_or("none"),
+                account.shell
+            ),
+        ),
+    });
+
+    let forwarding = ["allowtcpforwarding", "allowstreamlocalforwarding"];
+    let allowed: Vec<&str> = forwarding
+        .iter()
+        .copied()
+        .filter(|k| setting(*k) != Some("no"))
+        .collect();
+    checks.push(check(
+        "TCP forwarding disabled",
+        allowed.is_empty(),
+        if allowed.is_empty() {
+            "AllowTcpForwarding and AllowStreamLocalForwarding are off".to_string()
+        } else {
+            format!("Enabled: {}", allowed.join(", "))
+        },
+    ));
+
+    let tunnels = ["x11forwarding", "permittunnel", "allowagentforwarding"];
+    let allowed: Vec<&str> = tunnels
+        .iter()
+        .copied()
+        .filter(|k| setting(*k) != Some("no"))
+        .collect();
+    checks.push(check(
+        "X11, tunnel and agent forwarding disabled",
+        allowed.is_empty(),
+        if allowed.is_empty() {
+            "X11Forwarding, PermitTunnel and AllowAgentForwarding are off".to_string()
+        } else {
+            format!("Enabled: {}", allowed.join(", "))
+        },
+    ));
+
+    // sshd refuses a chroot unless every directory up to / is root-owned and
+    // writable by nobody else
+    let chroot = setting("chrootdirectory").filter(|c| *c != "none");
+    let unsafe_dirs: Vec<String> = account
+        .chroot_dirs
+        .iter()
+        .filter(|(owner, mode, _)| owner != "root" || mode & 0o022 != 0)
+        .map(|(owner, mode, path)| format!("{} ({} {:o})", path, owner, mode))
+        .collect();
+    checks.push(match chroot {
+        None => check(
+            "Chrooted",
+            false,
+            "No ChrootDirectory; the whole filesystem is visible".to_string(),
+        ),
+        Some(_) if !account.missing_dirs.is_empty() => check(
+            "Chrooted",
+            false,
+            format!("{} does not exist", account.missing_dirs.join(", ")),
+        ),
+        Some(_) if !unsafe_dirs.is_empty() => check(
+            "Chrooted",
+            false,
+            format!(
+                "Must be root-owned and not group or world writable: {}",
+                unsafe_dirs.join(", ")
+            ),
+        ),
+        Some(dir) => check("Chrooted", true, format!("ChrootDirectory {}", dir)),
+    });
+    checks
+}
+
+/// Check inside the container that its users get SFTP and nothing else: no shell,
+/// no forwarding, internal-sftp, and a chroot sshd will actually apply
+#[tauri::command]
+fn verify_hardening(name: String) -> Result<HardeningReport, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let output = run_command(
+        "docker",
+        &["exec", &name, "sh", "-c", VERIFY_HARDENING_SCRIPT],
+    )?;
+
+    let mut global: HashMap<String, String> = HashMap::new();
+    let mut accounts: Vec<AccountHardening> = Vec::new();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("user ") {
+            let (user, shell) = rest.split_once(' ').unwrap_or((rest, ""));
+            accounts.push(AccountHardening {
+                user: user.to_string(),
+                shell: shell.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(account) = accounts.last_mut() else {
+            if let Some((keyword, value)) = line.trim().split_once(' ') {
+                // `sshd -T` repeats keywords like subsystem; the sftp one is all that matters
+                if keyword == "subsystem" && !value.starts_with("sftp ") {
+                    continue;
+                }
+                global.insert(keyword.to_string(), value.trim().to_string());
+            }
+            continue;
+        };
+        if let Some(rest) = line.strip_prefix("stat ") {
+            let mut fields = rest.splitn(3, ' ');
+            if let (Some(owner), Some(mode), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                let mode = u32::from_str_radix(mode, 8).unwrap_or(0o777);
+                account
+                    .chroot_dirs
+                    .push((owner.to_string(), mode, path.to_string()));
+            }
+        } else if let Some(path) = line.strip_prefix("missing ") {
+            account.missing_dirs.push(path.to_string());
+        } else if let Some((keyword, value)) = line.trim().split_once(' ') {
+            account
+                .settings
+                .insert(keyword.to_string(), value.trim().to_string());
+        }
+    }
+
+    let global_check = |label: &str, passed: bool, detail: String| HardeningCheck {
+        user: None,
+        check: label.to_string(),
+        passed,
+        detail,
+    };
+    let subsystem = global
+        .get("subsystem")
+        .and_then(|v| v.strip_prefix("sftp "))
+        .map(str::trim);
+    let mut checks = vec![
+        global_check(
+            "SFTP served by internal-sftp",
+            subsystem.is_some_and(|s| s.starts_with("internal-sftp")),
+            format!(
+                "Subsystem sftp {}",
+                subsystem.unwrap_or("is not configured")
+            ),
+        ),
+        global_check(
+            "Root login disabled",
+            global.get("permitrootlogin").map(String::as_str) == Some("no"),
+            format!(
+                "PermitRootLogin {}",
+                global
+                    .get("permitrootlogin")
+                    .map_or("unknown", |v| v.as_str())
+            ),
+        ),
+        global_check(
+            "Has SFTP accounts",
+            !accounts.is_empty(),
+            format!("{} login accounts", accounts.len()),
+        ),
+    ];
+    checks.extend(accounts.iter().flat_map(account_checks));
+    Ok(HardeningReport {
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+    })
+}
+
+/// Disconnect one client by ending its sshd monitor process
+#[tauri::command]
+fn kill_session(name: String, session_id: u32) -> CommandResult {
+    let result = list_sessions(name.clone()).and_then(|sessions| {
+        let session = sessions
+            .iter()
+            .find(|s| s.id == session_id)
+            .ok_or_else(|| format!("No session {} on {}", session_id, name))?;
+        run_command("docker", &["exec", &name, "kill", &session.id.to_string()])
+    });
+    record_audit(
+        &name,
+        "kill-session",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Message clients see while a server is in maintenance, unless given another
+const DEFAULT_MAINTENANCE_MESSAGE: &str = "This server is down for maintenance.";
+
+/// Closes the server to new logins and shows $1 as the pre-login banner. The block
+/// goes first in sshd_config, where its Banner wins and DenyUsers applies globally;
+/// on SIGHUP sshd re-reads the config and sessions already open carry on.
+const ENTER_MAINTENANCE_SCRIPT: &str = r#"set -e
+printf '%s\n' "$1" > /etc/ssh/dsftp-maintenance
+if ! grep -q '^# dsftp maintenance begin$' /etc/ssh/sshd_config; then
+    {
+        printf '# dsftp maintenance begin\nDenyUsers *\nBanner /etc/ssh/dsftp-maintenance\n# dsftp maintenance end\n'
+        cat /etc/ssh/sshd_config
+    } > /etc/ssh/sshd_config.dsftp
+    cat /etc/ssh/sshd_config.dsftp > /etc/ssh/sshd_config
+    rm -f /etc/ssh/sshd_config.dsftp
+fi
+if ! sshd -t; then
+    sed -i '/^# dsftp maintenance begin$/,/^# dsftp maintenance end$/d' /etc/ssh/sshd_config
+    exit 1
+fi
+"#;
+
+const EXIT_MAINTENANCE_SCRIPT: &str = r#"set -e
+sed -i '/^# dsftp maintenance begin$/,/^# dsftp maintenance end$/d' /etc/ssh/sshd_config
+rm -f /etc/ssh/dsftp-maintenance
+"#;
+
+/// A server closed to new logins until `until`
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct MaintenanceWindow {
+    pub started_at: String,
+    pub until: String,
+    /// Shown to connecting clients as the SSH banner
+    pub message: String,
+    /// When sessions still open get disconnected; `None` leaves them be
+    pub drop_sessions_at: Option<String>,
+    #[serde(default)]
+    pub sessions_dropped: bool,
+}
+
+/// Disconnect every open session
+fn drop_sessions(name: &str) -> Result<(), String> {
+    let ids: Vec<String> = list_sessions(name.to_string())?
+        .iter()
+        .map(|s| s.id.to_string())
+        .collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let mut args = vec!["exec", name, "kill"];
+    args.extend(ids.iter().map(String::as_str));
+    run_command("docker", &args).map(|_| ())
+}
+
+fn enter_maintenance_internal(
+    name: &str,
+    duration_minutes: u64,
+    message: Option<String>,
+    grace_minutes: Option<u64>,
+) -> Result<(), String> {
+    if !is_sftp_container(name) {
+        return Err("Not an SFTP container (atmoz/sftp)".to_string());
+    }
+    if !(1..=MAX_MAINTENANCE_MINUTES).contains(&duration_minutes) {
+        return Err(format!(
+            "Maintenance lasts from one minute to {} days",
+            MAX_MAINTENANCE_MINUTES / (24 * 60)
+        ));
+    }
+    let message = message
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| DEFAULT_MAINTENANCE_MESSAGE.to_string());
+    run_command(
+        "docker",
+        &[
+            "exec",
+            name,
+            "sh",
+            "-c",
+            &format!("{}{}", ENTER_MAINTENANCE_SCRIPT, SSHD_RELOAD_SCRIPT),
+            "sh",
+            &message,
+        ],
+    )?;
+
+    let now = chrono::Local::now();
+    let minutes = |m: u64| chrono::Duration::minutes(m.min(MAX_MAINTENANCE_MINUTES) as i64);
+    let window = MaintenanceWindow {
+        started_at: now.to_rfc3339(),
+        until: (now + minutes(duration_minutes)).to_rfc3339(),
+        message,
+        drop_sessions_at: grace_minutes.map(|g| (now + minutes(g)).to_rfc3339()),
+        sessions_dropped: false,
+    };
+    update_credentials(|all| match all.get_mut(name) {
+        Some(creds) => {
+            creds.maintenance = Some(window);
+            Ok(())
+        }
+        None => Err(format!("Unknown server: {}", name)),
+    })?;
+    if grace_minutes == Some(0) {
+        maintenance_tick(&list_servers());
+    }
+    Ok(())
+}
+
+/// Stop taking new logins for `duration_minutes`, showing `message` to clients that
+/// try. Open sessions are disconnected after `grace_minutes`, or kept when `None`.
+/// The server reopens by itself once the time is up.
+#[tauri::command]
+fn enter_maintenance(
+    name: String,
+    duration_minutes: u64,
+    message: Option<String>,
+    grace_minutes: Option<u64>,
+) -> CommandResult {
+    let result = enter_maintenance_internal(&name, duration_minutes, message, grace_minutes);
+    record_audit(
+        &name,
+        "maintenance",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+fn exit_maintenance_internal(name: &str) -> Result<(), String> {
+    run_command(
+        "docker",
+        &[
+            "exec",
+            name,
+            "sh",
+            "-c",
+            &format!("{}{}", EXIT_MAINTENANCE_SCRIPT, SSHD_RELOAD_SCRIPT),
+        ],
+    )?;
+    update_credentials(|all| {
+        if let Some(creds) = all.get_mut(name) {
+            creds.maintenance = None;
+        }
+    });
+    Ok(())
+}
+
+/// Reopen a server before its maintenance window is over
+#[tauri::command]
+fn exit_maintenance(name: String) -> CommandResult {
+    let result = exit_maintenance_internal(&name);
+    record_audit(
+        &name,
+        "maintenance-end",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Disconnect sessions whose grace period ran out and reopen servers whose window
+/// is over. Stopped servers are handled once they run again.
+fn maintenance_tick(servers: &[ServerInfo]) {
+    for server in servers.iter().filter(|s| s.status == "running") {
+        let Some(window) = &server.maintenance else {
+            continue;
+        };
+        if seconds_since(&window.until).is_some() {
+            let result = exit_maintenance_internal(&server.name);
+            record_audit(
+                &server.name,
+                "maintenance-end",
+                result.as_ref().err().map(|e| e.as_str()),
+            );
+            continue;
+        }
+        let drop_due = window
+            .drop_sessions_at
+            .as_deref()
+            .is_some_and(|at| seconds_since(at).is_some());
+        if drop_due && !window.sessions_dropped {
+            let result = drop_sessions(&server.name);
+            record_audit(
+                &server.name,
+                "maintenance-drop-sessions",
+                result.as_ref().err().map(|e| e.as_str()),
+            );
+            update_credentials(|all| {
+                if let Some(window) = all
+                    .get_mut(&server.name)
+                    .and_then(|c| c.maintenance.as_mut())
+                {
+                    window.sessions_dropped = true;
+                }
+            });
+        }
+    }
+}
+
+/// Walks every share and asks Docker about each running server, so it runs off
+/// the main thread
+#[tauri::command]
+async fn get_dashboard_summary() -> Result<DashboardSummary, String> {
+    tauri::async_runtime::spawn_blocking(dashboard_summary)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn dashboard_summary() -> DashboardSummary {
+    let servers = list_servers();
+    let running = servers.iter().filter(|s| s.status == "running").count();
+
+    let total_exported_bytes = servers
+        .iter()
+        .filter(|s| !s.host_path.is_empty())
+        .map(|s| dir_size(Path::new(&s.host_path)))
+        .sum();
+
+    let active_sessions = servers
+        .iter()
+        .filter(|s| s.status == "running")
+        .map(|s| count_sessions(&s.name))
+        .sum();
+
+    let network_config = load_network_config();
+    let interfaces = list_network_interfaces_internal();
+    let (bind_ip, bind_interface, is_vpn) = get_current_ip_internal(&interfaces, &network_config);
+
+    // Newest first
+    let recent_errors: Vec<AuditEntry> = load_audit_log()
+        .into_iter()
+        .rev()
+        .filter(|e| !e.success)
+        .take(DASHBOARD_RECENT_ERRORS)
+        .collect();
+
+    DashboardSummary {
+        total_servers: servers.len(),
+        running,
+        stopped: servers.len() - running,
+        total_exported_bytes,
+        total_exported_display: format_size(total_exported_bytes, &current_locale()),
+        active_sessions,
+        docker_available: check_docker(),
+        bind_ip,
+        bind_interface,
+        is_vpn,
+        recent_errors,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum SearchKind {
+    Server,
+    Bookmark,
+    RecentPath,
+    Setting,
+}
+
+/// What the palette can do with a result, in the order it should offer them
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum SearchAction {
+    Open,
+    Start,
+    Stop,
+    CopyConnection,
+    Browse,
+    EditSetting,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub title: String,
+    pub subtitle: String,
+    pub server: Option<String>,
+    /// Container path for bookmarks and recent paths, dotted key for settings
+    pub path: Option<String>,
+    pub actions: Vec<SearchAction>,
+    pub score: i64,
+}
+
+/// Subsequence match of `query` in `text`, ignoring case. Consecutive characters
+/// and ones starting a word score higher; `None` if `query` doesn't fit.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        if wanted.is_whitespace() {
+            continue;
+        }
+        let found = next + text[next..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    // Shorter texts match more specifically
+    Some(score * 100 - text.len() as i64)
+}
+
+/// Dotted keys of every setting, e.g. "alerts.cpu_percent"
+fn setting_keys(value: &serde_json::Value, prefix: &str, keys: &mut Vec<String>) {
+    let serde_json::Value::Object(fields) = value else {
+        return;
+    };
+    for (field, inner) in fields {
+        let key = if prefix.is_empty() {
+            field.clone()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+        keys.push(key.clone());
+        // Backup targets are named by the user; their inner fields aren't settings
+        if field != "backup_targets" {
+            setting_keys(inner, &key, keys);
+        }
+    }
+}
+
+/// Fuzzy search over servers, bookmarks, recently browsed paths and settings
+/// for the command palette, best matches first
+#[tauri::command]
+fn global_search(query: String, limit: Option<usize>) -> Vec<SearchResult> {
+    let query = query.trim();
+    if query.is_empty() {
+        return vec![];
+    }
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut push = |result: SearchResult, haystacks: &[&str]| {
+        if let Some(score) = haystacks.iter().filter_map(|h| fuzzy_score(query, h)).max() {
+            results.push(SearchResult { score, ..result });
+        }
+    };
+
+    for server in list_servers() {
+        let running = server.status == "running";
+        let actions = vec![
+            SearchAction::Open,
+            if running {
+                SearchAction::Stop
+            } else {
+                SearchAction::Start
+            },
+            SearchAction::CopyConnection,
+            SearchAction::Browse,
+        ];
+        push(
+            SearchResult {
+                kind: SearchKind::Server,
+                subtitle: format!("{} · port {}", server.status, server.port),
+                title: server.name.clone(),
+                server: Some(server.name.clone()),
+                path: None,
+                actions,
+                score: 0,
+            },
+            &[server.name.as_str(), server.host_path.as_str()],
+        );
+    }
+
+    for (server, saved) in load_bookmarks() {
+        for bookmark in &saved.bookmarks {
+            push(
+                SearchResult {
+                    kind: SearchKind::Bookmark,
+                    title: bookmark.label.clone(),
+                    subtitle: format!("{}:{}", server, bookmark.path),
+                    server: Some(server.clone()),
+                    path: Some(bookmark.path.clone()),
+                    actions: vec![SearchAction::Browse],
+                    score: 0,
+                },
+                &[bookmark.label.as_str(), bookmark.path.as_str()],
+            );
+        }
+        for path in &saved.recent_paths {
+            push(
+                SearchResult {
+                    kind: SearchKind::RecentPath,
+                    title: split_container_path(path).1,
+                    subtitle: format!("{}:{}", server, path),
+                    server: Some(server.clone()),
+                    path: Some(path.clone()),
+                    actions: vec![SearchAction::Browse],
+                    score: 0,
+                },
+                &[path.as_str()],
+            );
+        }
+    }
+
+    let mut keys = Vec::new();
+    let settings = serde_json::to_value(load_app_settings()).unwrap_or_default();
+    setting_keys(&settings, "", &mut keys);
+    for key in keys {
+        push(
+            SearchResult {
+                kind: SearchKind::Setting,
+                title: key.replace(['_', '.'], " "),
+                subtitle: key.clone(),
+                server: None,
+                path: Some(key.clone()),
+                actions: vec![SearchAction::EditSetting],
+                score: 0,
+            },
+            &[key.as_str()],
+        );
+    }
+
+    results.sort_by_key(|r| Reverse(r.score));
+    results.truncate(limit.unwrap_or(SEARCH_RESULT_LIMIT));
+    results
+}
+
+/// A hint the GUI can show with a button that runs `command` with `args`
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Suggestion {
+    /// Stable for the same situation, so a dismissal sticks
+    pub id: String,
+    pub server: Option<String>,
+    pub message: String,
+    /// Tauri command that acts on the hint
+    pub command: String,
+    pub args: serde_json::Value,
+}
+
+/// Hints derived from the servers' state and the local audit log, which doubles
+/// as the record of how often each operation is used; nothing leaves the machine
+#[tauri::command]
+fn get_suggestions() -> Vec<Suggestion> {
+    let settings = load_app_settings();
+    let servers = list_servers();
+    let audit = load_audit_log();
+    let names: Vec<String> = servers.iter().map(|s| s.name.clone()).collect();
+    let states = inspect_container_states(&names);
+    let addresses: HashSet<String> = list_network_interfaces_internal()
+        .into_iter()
+        .map(|i| i.address)
+        .collect();
+    // Backups go to the first target by name until the user picks another
+    let target = settings.backup_targets.keys().min().cloned();
+    let mut suggestions = Vec::new();
+
+    for server in &servers {
+        let name = &server.name;
+        let idle_days = states
+            .get(name)
+            .filter(|_| server.status != "running")
+            .and_then(|s| s.finished_at.as_deref())
+            .and_then(seconds_since)
+            .map(|secs| secs / (24 * 60 * 60));
+        if let (Some(days), Some(target)) = (
+            idle_days.filter(|d| *d >= SUGGEST_ARCHIVE_AFTER_DAYS),
+            &target,
+        ) {
+            suggestions.push(Suggestion {
+                id: format!("archive:{}", name),
+                server: Some(name.clone()),
+                message: format!("{} has been stopped for {} days. Archive it?", name, days),
+                command: "backup_server".to_string(),
+                args: serde_json::json!({ "name": name, "target": target }),
+            });
+        }
+
+        if let Some(ip) = server
+            .bind_ip
+            .as_deref()
+            .filter(|ip| *ip != "0.0.0.0" && !addresses.contains(*ip))
+        {
+            suggestions.push(Suggestion {
+                id: format!("rebind:{}:{}", name, ip),
+                server: Some(name.clone()),
+                message: format!(
+                    "{} is bound to {}, which this machine no longer has. Your VPN address may have changed; rebind?",
+                    name, ip
+                ),
+                command: "rebind_servers".to_string(),
+                args: serde_json::json!({ "fromIp": ip }),
+            });
+        }
+
+        let is_this_server = |e: &AuditEntry| {
+            is_same_server(
+                &e.server,
+                e.server_id.as_deref(),
+                name,
+                server.id.as_deref(),
+            )
+        };
+        let backed_up = audit
+            .iter()
+            .any(|e| is_this_server(e) && e.action == "backup" && e.success);
+        if !backed_up {
+            let (message, command, args) = match &target {
+                None => (
+                    format!("No backup is configured for {}. Add a backup target?", name),
+                    "set_backup_target",
+                    serde_json::json!({}),
+                ),
+                Some(target) => (
+                    format!("{} has never been backed up. Back it up now?", name),
+                    "backup_server",
+                    serde_json::json!({ "name": name, "target": target }),
+                ),
+            };
+            suggestions.push(Suggestion {
+                id: format!("backup:{}", name),
+                server: Some(name.clone()),
+                message,
+                command: command.to_string(),
+                args,
+            });
+        }
+
+        let manual_starts = audit
+            .iter()
+            .filter(|e| is_this_server(e) && e.action == "start")
+            .filter_map(|e| seconds_since(&e.timestamp))
+            .filter(|secs| *secs < SUGGEST_WINDOW_DAYS * 24 * 60 * 60)
+            .count();
+        if manual_starts >= SUGGEST_FREQUENT_STARTS
+            && server.restart_policy == Some(RestartPolicy::No)
+        {
+            suggestions.push(Suggestion {
+                id: format!("restart-policy:{}", name),
+                server: Some(name.clone()),
+                message: format!(
+                    "You started {} by hand {} times in the last {} days. Let Docker restart it?",
+                    name, manual_starts, SUGGEST_WINDOW_DAYS
+                ),
+                command: "set_restart_policy".to_string(),
+                args: serde_json::json!({ "name": name, "policy": RestartPolicy::UnlessStopped }),
+            });
+        }
+    }
+
+    suggestions.retain(|s| !settings.dismissed_suggestions.contains(&s.id));
+    suggestions
+}
+
+#[tauri::command]
+fn dismiss_suggestion(id: String) -> CommandResult {
+    update_app_settings(|settings| {
+        if !settings.dismissed_suggestions.contains(&id) {
+            settings.dismissed_suggestions.push(id);
+        }
+    });
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+struct ContainerState {
+    status: String,
+    exit_code: i64,
+    oom_killed: bool,
+    /// Docker HEALTHCHECK status, when the image defines one
+    health: Option<String>,
+    created_at: Option<String>,
+    started_at: Option<String>,
+    restart_policy: Option<RestartPolicy>,
+    /// When it last stopped
+    finished_at: Option<String>,
+    /// Why Docker couldn't start it, e.g. a port already in use
+    error: String,
+}
+
+fn inspect_container_states(names: &[String]) -> HashMap<String, ContainerState> {
+    if names.is_empty() {
+        return HashMap::new();
+    }
+    let mut args = vec![
+        "inspect",
+        "--format",
+        "{{.Name}}|{{.State.Status}}|{{.State.ExitCode}}|{{.State.OOMKilled}}|{{if .State.Health}}{{.State.Health.Status}}{{end}}|{{.Created}}|{{.State.StartedAt}}|{{.HostConfig.RestartPolicy.Name}}|{{.HostConfig.RestartPolicy.MaximumRetryCount}}|{{.State.FinishedAt}}|{{json .State.Error}}",
+    ];
+    args.extend(names.iter().map(|n| n.as_str()));
+
+    // inspect fails as a whole if any container vanished; the others still print
+    let output = if mock_runtime() {
+        run_command("docker", &args).unwrap_or_default()
+    } else {
+        match run_process("docker", &args) {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+            Err(_) => return HashMap::new(),
+        }
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            // The error comes last, as JSON, since it may contain the separator
+            let parts: Vec<&str> = line.splitn(11, '|').collect();
+            if parts.len() < 10 {
+                return None;
+            }
+            Some((
+                parts[0].trim_start_matches('/').to_string(),
+                ContainerState {
+                    status: parts[1].to_string(),
+                    exit_code: parts[2].parse().unwrap_or(0),
+                    oom_killed: parts[3] == "true",
+                    health: Some(parts[4].to_string()).filter(|h| !h.is_empty()),
+                    created_at: parse_docker_time(parts[5]).map(to_local_rfc3339),
+                    started_at: parse_docker_time(parts[6]).map(to_local_rfc3339),
+                    restart_policy: RestartPolicy::from_inspect(
+                        parts[7],
+                        parts[8].parse().unwrap_or(0),
+                    ),
+                    finished_at: parse_docker_time(parts[9]).map(to_local_rfc3339),
+                    error: parts
+                        .get(10)
+                        .and_then(|e| serde_json::from_str(e).ok())
+                        .unwrap_or_default(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// What a server is doing, with enough detail for the GUI to explain it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum ServerState {
+    Running,
+    /// Up, but sshd isn't answering yet
+    Starting,
+    /// Up, but failing its health check or not answering SSH
+    Unhealthy {
+        reason: String,
+    },
+    Stopped,
+    /// Frozen with `pause_server`: connections hang until it is resumed
+    Paused,
+    /// Exited on its own with a failure
+    ExitedError {
+        #[ts(type = "number")]
+        exit_code: i64,
+        oom_killed: bool,
+        /// Docker's own explanation, when it gave one
+        message: Option<String>,
+    },
+    /// Docker couldn't publish the port because something else holds it
+    PortConflict {
+        port: u16,
+    },
+    /// The shared host folder no longer exists
+    MissingVolume {
+        path: String,
+    },
+}
+
+/// Recent SSH probe results, so listing servers doesn't dial every port each time
+static SSH_PROBES: LazyLock<Mutex<HashMap<String, (Instant, bool)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Whether each (name, bind ip, port) answers SSH, probing in parallel the ones
+/// without a fresh result
+fn probe_servers(targets: &[(String, String, u16)]) -> HashMap<String, bool> {
+    let cached: HashMap<String, bool> = SSH_PROBES
+        .lock()
+        .map(|probes| {
+            probes
+                .iter()
+                .filter(|(_, (at, _))| at.elapsed() < SERVER_WATCH_INTERVAL)
+                .map(|(name, (_, ok))| (name.clone(), *ok))
+                .collect()
+        })
+        .unwrap_or_default();
+    let fresh: Vec<(String, bool)> = std::thread::scope(|scope| {
+        let probes: Vec<_> = targets
+            .iter()
+            .filter(|(name, _, _)| !cached.contains_key(name))
+            .map(|(name, ip, port)| scope.spawn(move || (name.clone(), probe_ssh(ip, *port))))
+            .collect();
+        probes.into_iter().filter_map(|p| p.join().ok()).collect()
+    });
+    if let Ok(mut probes) = SSH_PROBES.lock() {
+        for (name, ok) in &fresh {
+            probes.insert(name.clone(), (Instant::now(), *ok));
+        }
+    }
+    cached.into_iter().chain(fresh).collect()
+}
+
+fn server_state(
+    state: Option<&ContainerState>,
+    host_path: &str,
+    port: u16,
+    answering: Option<bool>,
+    uptime_secs: Option<u64>,
+) -> ServerState {
+    if !host_path.is_empty() && !Path::new(host_path).exists() {
+        return ServerState::MissingVolume {
+            path: host_path.to_string(),
+        };
+    }
+    let Some(state) = state else {
+        return ServerState::Stopped;
+    };
+    let error = Some(state.error.clone()).filter(|e| !e.is_empty());
+    if error.as_deref().is_some_and(|e| {
+        e.contains("port is already allocated") || e.contains("address already in use")
+    }) {
+        return ServerState::PortConflict { port };
+    }
+    match state.status.as_str() {
+        "paused" => ServerState::Paused,
+        "running" if state.health.as_deref() == Some("unhealthy") => ServerState::Unhealthy {
+            reason: "Failing its health check".to_string(),
+        },
+        "running" if answering == Some(false) => {
+            if uptime_secs.is_some_and(|secs| secs < STARTING_GRACE.as_secs()) {
+                ServerState::Starting
+            } else {
+                ServerState::Unhealthy {
+                    reason: format!("Not answering SSH on port {}", port),
+                }
+            }
+        }
+        "running" if state.health.as_deref() == Some("starting") => ServerState::Starting,
+        "running" => ServerState::Running,
+        "restarting" if state.exit_code == 0 => ServerState::Starting,
+        // 137 and 143 are SIGKILL and SIGTERM, i.e. `docker stop`
+        "exited" | "dead" | "restarting" | "created"
+            if state.oom_killed || error.is_some() || !matches!(state.exit_code, 0 | 137 | 143) =>
+        {
+            ServerState::ExitedError {
+                exit_code: state.exit_code,
+                oom_killed: state.oom_killed,
+                message: error,
+            }
+        }
+        _ => ServerState::Stopped,
+    }
+}
+
+/// Address a local client should dial for a server bound to `bind_ip`
+fn client_host(bind_ip: &str) -> &str {
+    if bind_ip.is_empty() || bind_ip == "0.0.0.0" {
+        "127.0.0.1"
+    } else {
+        bind_ip
+    }
+}
+
+/// Connect to the published port and wait for the SSH identification banner
+fn probe_ssh(bind_ip: &str, port: u16) -> bool {
+    // Simulated containers have no sshd behind the port
+    if mock_runtime() {
+        return true;
+    }
+    let ip = client_host(bind_ip);
+    let Ok(addr) = format!("{}:{}", ip, port).parse::<std::net::SocketAddr>() else {
+        return false;
+    };
+    let Ok(mut stream) = std::net::TcpStream::connect_timeout(&addr, HEALTH_PROBE_TIMEOUT) else {
+        return false;
+    };
+    stream.set_read_timeout(Some(HEALTH_PROBE_TIMEOUT)).ok();
+    let mut banner = [0u8; 4];
+    stream.read_exact(&mut banner).is_ok() && &banner == b"SSH-"
+}
+
+#[derive(Default)]
+struct HealState {
+    failed_probes: u32,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    gave_up: bool,
+}
+
+/// Why a server should be considered down, if it is
+fn down_reason(
+    server: &ServerInfo,
+    state: &ContainerState,
+    heal: &mut HealState,
+    policy: &AutoHealPolicy,
+) -> Option<String> {
+    match state.status.as_str() {
+        // docker stop ends sshd with SIGTERM (143); anything else is a crash
+        "exited" | "dead" => {
+            if state.oom_killed {
+                Some("killed by the OOM killer".to_string())
+            } else if state.exit_code != 0 && state.exit_code != 143 {
+                Some(format!("exited with code {}", state.exit_code))
+            } else {
+                None
+            }
+        }
+        "running" => {
+            let healthy = match state.health.as_deref() {
+                Some("unhealthy") => false,
+                _ => {
+                    server.port == 0
+                        || probe_ssh(server.bind_ip.as_deref().unwrap_or(""), server.port)
+                }
+            };
+            heal.failed_probes = if healthy { 0 } else { heal.failed_probes + 1 };
+            if heal.failed_probes >= policy.unhealthy_threshold.max(1) {
+                Some(format!("failed {} health checks", heal.failed_probes))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// One auto-heal pass: restart servers that went down, with exponential backoff
+fn auto_heal_tick(servers: &[ServerInfo], heal_states: &mut HashMap<String, HealState>) {
+    let policy = load_app_settings().auto_heal;
+    if !policy.enabled {
+        heal_states.clear();
+        return;
+    }
+
+    let names: Vec<String> = servers.iter().map(|s| s.name.clone()).collect();
+    let mut watched = names.clone();
+    for dep in servers.iter().flat_map(|s| &s.depends_on) {
+        if !watched.contains(dep) {
+            watched.push(dep.clone());
+        }
+    }
+    let states = inspect_container_states(&watched);
+    let mut manual_stops = MANUAL_STOPS.lock().map(|s| s.clone()).unwrap_or_default();
+    // Down is where on-demand servers rest; a connection starts them
+    manual_stops.extend(
+        load_credentials()
+            .into_iter()
+            .filter(|(_, c)| c.on_demand.is_some())
+            .map(|(name, _)| name),
+    );
+    heal_states.retain(|name, _| names.contains(name));
+
+    for server in servers {
+        let Some(state) = states.get(&server.name) else {
+            continue;
+        };
+        if manual_stops.contains(&server.name) {
+            heal_states.remove(&server.name);
+            continue;
+        }
+        let heal = heal_states.entry(server.name.clone()).or_default();
+
+        let Some(reason) = down_reason(server, state, heal, &policy) else {
+            if heal.attempts > 0 {
+                events::publish(events::Event::AutoHeal(AutoHealEvent {
+                    server: server.name.clone(),
+                    attempt: heal.attempts,
+                    max_retries: policy.max_retries,
+                    reason: String::new(),
+                    outcome: "recovered".to_string(),
+                    error: None,
+                }));
+                *heal = HealState::default();
+            }
+            continue;
+        };
+
+        if heal.gave_up || heal.next_attempt.is_some_and(|t| Instant::now() < t) {
+            continue;
+        }
+
+        // Restarting ahead of a dependency that is itself down would only burn retries
+        if server
+            .depends_on
+            .iter()
+            .any(|d| !states.get(d).is_some_and(|s| s.status == "running"))
+        {
+            continue;
+        }
+
+        if heal.attempts >= policy.max_retries {
+            heal.gave_up = true;
+            let message = format!(
+                "still down after {} restart attempts: {}",
+                heal.attempts, reason
+            );
+            record_audit(&server.name, "auto-heal", Some(&message));
+            let event = AutoHealEvent {
+                server: server.name.clone(),
+                attempt: heal.attempts,
+                max_retries: policy.max_retries,
+                reason,
+                outcome: "gave_up".to_string(),
+                error: Some(message),
+            };
+            events::publish(events::Event::AutoHeal(event.clone()));
+            events::publish(events::Event::ServerDown(event));
+            continue;
+        }
+
+        heal.attempts += 1;
+        heal.failed_probes = 0;
+        let backoff = policy
+            .initial_backoff_secs
+            .max(1)
+            .saturating_mul(1 << (heal.attempts - 1).min(16))
+            .min(policy.max_backoff_secs.max(1));
+        heal.next_attempt = Some(Instant::now() + Duration::from_secs(backoff));
+
+        let error = run_command("docker", &["restart", &server.name]).err();
+        record_audit(&server.name, "auto-heal", error.as_deref());
+        events::publish(events::Event::AutoHeal(AutoHealEvent {
+            server: server.name.clone(),
+            attempt: heal.attempts,
+            max_retries: policy.max_retries,
+            reason,
+            outcome: "restarting".to_string(),
+            error,
+        }));
+    }
+}
+
+/// Record runs that ended since the last pass; `sessions` maps running servers to
+/// their start time
+fn track_uptime(servers: &[ServerInfo], sessions: &mut HashMap<String, String>) {
+    let running: HashMap<String, String> = servers
+        .iter()
+        .filter_map(|s| Some((s.name.clone(), s.started_at.clone()?)))
+        .collect();
+
+    for (name, started_at) in sessions.iter() {
+        if running.get(name) == Some(started_at) {
+            continue;
+        }
+        let session = UptimeSession {
+            started_at: started_at.clone(),
+            ended_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            seconds: seconds_since(started_at).unwrap_or(0),
+        };
+        record_history(
+            Some(name),
+            "uptime",
+            serde_json::to_value(&session).unwrap_or_default(),
+        );
+    }
+    *sessions = running;
+}
+
+fn uptime_summary(server: &ServerInfo) -> UptimeSummary {
+    let sessions: Vec<UptimeSession> = load_history()
+        .into_iter()
+        .filter(|r| r.kind == "uptime" && r.server.as_deref() == Some(server.name.as_str()))
+        .filter_map(|r| serde_json::from_value(r.data).ok())
+        .collect();
+    let total_uptime_secs =
+        sessions.iter().map(|s| s.seconds).sum::<u64>() + server.uptime_secs.unwrap_or(0);
+    UptimeSummary {
+        total_uptime_secs,
+        total_uptime_display: format_duration(total_uptime_secs, &current_locale()),
+        sessions: sessions.len(),
+        current_uptime_secs: server.uptime_secs,
+        tracked_since: sessions
+            .first()
+            .map(|s| s.started_at.clone())
+            .or_else(|| server.started_at.clone()),
+    }
+}
+
+#[tauri::command]
+fn get_uptime_summary(name: String) -> Result<UptimeSummary, String> {
+    find_server(&name).map(|server| uptime_summary(&server))
+}
+
+/// Emit `network-changed` for interfaces that appeared or went away since the last
+/// pass (VPNs connecting and disconnecting) and keep them in the history store
+fn track_interfaces(servers: &[ServerInfo], known: &mut Option<Vec<NetworkInterface>>) {
+    let current = list_network_interfaces_internal();
+    let Some(previous) = known.replace(current.clone()) else {
+        return;
+    };
+    let same =
+        |a: &NetworkInterface, b: &NetworkInterface| a.name == b.name && a.address == b.address;
+
+    let gone = previous
+        .iter()
+        .filter(|old| !current.iter().any(|new| same(*old, new)))
+        .map(|iface| ("disappeared", iface));
+    let added = current
+        .iter()
+        .filter(|new| !previous.iter().any(|old| same(old, *new)))
+        .map(|iface| ("appeared", iface));
+
+    for (change, iface) in gone.chain(added) {
+        let affected_servers = if change == "disappeared" {
+            servers
+                .iter()
+                .filter(|s| s.bind_ip.as_deref() == Some(iface.address.as_str()))
+                .map(|s| s.name.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let event = NetworkChangeEvent {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            change: change.to_string(),
+            interface: iface.clone(),
+            affected_servers,
+        };
+        events::publish(events::Event::NetworkChanged(event));
+    }
+}
+
+/// Files the server's cleanup rules would delete; a file matched by several rules
+/// is listed once
+fn cleanup_candidates(name: &str, rules: &[CleanupRule]) -> Result<Vec<CleanupCandidate>, String> {
+    let now = chrono::Utc::now().timestamp();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut candidates = Vec::new();
+
+    for rule in rules {
+        check_exec_path(rule.path())?;
+        let path_arg = exec_path_arg(rule.path());
+        let output = run_command_bytes(
+            "docker",
+            &[
+                "exec",
+                name,
+                "sh",
+                "-c",
+                CLEANUP_FILES_SCRIPT,
+                "sh",
+                &path_arg,
+            ],
+        )?;
+        let fields: Vec<&[u8]> = output.split(|b| *b == 0).collect();
+        // (size, mtime, path), skipping names that can't be addressed again
+        let mut files: Vec<(u64, i64, String)> = fields
+            .chunks_exact(3)
+            .filter_map(|f| {
+                let size = String::from_utf8_lossy(f[0]).parse().ok()?;
+                let mtime = String::from_utf8_lossy(f[1]).parse::<f64>().ok()? as i64;
+                let path = std::str::from_utf8(f[2]).ok()?;
+                Some((size, mtime, path.to_string()))
+            })
+            .collect();
+
+        let selected: Vec<(u64, i64, String)> = match rule {
+            CleanupRule::MaxAge { days, .. } => {
+                let cutoff = now - *days as i64 * 24 * 60 * 60;
+                files.into_iter().filter(|(_, m, _)| *m < cutoff).collect()
+            }
+            CleanupRule::MaxSize { max_bytes, .. } => {
+                files.sort_by_key(|(_, m, _)| *m);
+                let mut excess = files
+                    .iter()
+                    .map(|(size, _, _)| size)
+                    .sum::<u64>()
+                    .saturating_sub(*max_bytes);
+                files
+                    .into_iter()
+                    .take_while(|(size, _, _)| {
+                        let take = excess > 0;
+                        excess = excess.saturating_sub(*size);
+                        take
+                    })
+                    .collect()
+            }
+        };
+        let reason = match rule {
+            CleanupRule::MaxAge { path, days } => format!("older than {} days in {}", days, path),
+            CleanupRule::MaxSize { path, max_bytes } => {
+                format!("over the {} byte cap of {}", max_bytes, path)
+            }
+        };
+        for (size, modified, path) in selected {
+            if seen.insert(path.clone()) {
+                candidates.push(CleanupCandidate {
+                    path,
+                    size,
+                    modified,
+                    reason: reason.clone(),
+                });
+            }
+        }
+    }
+    Ok(candidates)
+}
+
+/// Apply (or with `dry_run`, preview) the server's cleanup rules. Every deleted file
+/// gets its own audit entry.
+fn run_cleanup(name: &str, dry_run: bool) -> Result<CleanupReport, String> {
+    let rules = load_cleanup_rules().remove(name).unwrap_or_default();
+    let candidates = cleanup_candidates(name, &rules)?;
+    let mut report = CleanupReport {
+        server: name.to_string(),
+        dry_run,
+        files: Vec::new(),
+        bytes: 0,
+        errors: Vec::new(),
+    };
+    if dry_run {
+        report.bytes = candidates.iter().map(|c| c.size).sum();
+        report.files = candidates;
+        return Ok(report);
+    }
+
+    for batch in candidates.chunks(CLEANUP_BATCH_SIZE) {
+        let mut args = vec!["exec", name, "rm", "-f", "--"];
+        args.extend(batch.iter().map(|c| c.path.as_str()));
+        let error = run_command("docker", &args).err();
+        for candidate in batch {
+            write_audit_entry(AuditEntry {
+                timestamp: chrono::Local::now().to_rfc3339(),
+                server: name.to_string(),
+                server_id: None,
+                action: "cleanup-delete".to_string(),
+                success: error.is_none(),
+                detail: Some(format!("{} ({})", candidate.path, candidate.reason)),
+            });
+        }
+        match error {
+            Some(e) => report.errors.push(e.trim().to_string()),
+            None => {
+                report.bytes += batch.iter().map(|c| c.size).sum::<u64>();
+                report.files.extend(batch.iter().cloned());
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+fn get_cleanup_rules(name: String) -> Vec<CleanupRule> {
+    load_cleanup_rules().remove(&name).unwrap_or_default()
+}
+
+#[tauri::command]
+fn set_cleanup_rules(name: String, rules: Vec<CleanupRule>) -> CommandResult {
+    if rules.iter().any(|r| r.path().trim().is_empty()) {
+        return CommandResult {
+            success: false,
+            error: Some("Every cleanup rule needs a path".to_string()),
+        };
+    }
+    if let Err(e) = rules.iter().try_for_each(|r| check_exec_path(r.path())) {
+        return CommandResult {
+            success: false,
+            error: Some(e),
+        };
+    }
+    update_json_file(
+        &get_cleanup_rules_path(),
+        |all: &mut HashMap<String, Vec<CleanupRule>>| {
+            if rules.is_empty() {
+                all.remove(&name);
+            } else {
+                all.insert(name, rules);
+            }
+        },
+    );
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// What the next cleanup pass would delete, without touching anything
+#[tauri::command]
+async fn preview_cleanup(
+    name: String,
+    operation_id: Option<String>,
+) -> Result<CleanupReport, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        run_operation(operation_id, || run_cleanup(&name, true))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn run_cleanup_now(
+    name: String,
+    operation_id: Option<String>,
+) -> Result<CleanupReport, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        run_operation(operation_id, || run_cleanup(&name, false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Hourly cleanup pass over running servers that have rules
+fn cleanup_tick(servers: &[ServerInfo], last_run: &mut Option<Instant>) {
+    if last_run.is_some_and(|t| t.elapsed() < CLEANUP_INTERVAL) {
+        return;
+    }
+    *last_run = Some(Instant::now());
+    let rules = load_cleanup_rules();
+    for server in servers {
+        if server.status == "running" && rules.get(&server.name).is_some_and(|r| !r.is_empty()) {
+            if let Err(e) = run_cleanup(&server.name, false) {
+                record_audit(&server.name, "cleanup", Some(&e));
+            }
+        }
+    }
+}
+
+/// Files seen in one server's watched directories
+#[derive(Default)]
+struct IngestState {
+    /// path -> (size, mtime) from the previous scan
+    pending: HashMap<String, (u64, String)>,
+    /// Files already acted on, or present before watching began
+    handled: HashSet<String>,
+}
+
+/// Container path -> host path, for files under the server's share
+fn host_path_of(server: &ServerInfo, path: &str) -> Option<PathBuf> {
+    let relative = path
+        .strip_prefix(server.container_path.trim_end_matches('/'))?
+        .trim_start_matches('/');
+    Some(Path::new(&server.host_path).join(relative))
+}
+
+fn run_ingest_action(
+    server: &ServerInfo,
+    path: &str,
+    size: u64,
+    action: &IngestAction,
+) -> Result<(), String> {
+    let file_name = split_container_path(path).1;
+    match action {
+        IngestAction::Move { to } => {
+            check_exec_path(to)?;
+            run_command(
+                "docker",
+                &[
+                    "exec",
+                    &server.name,
+                    "sh",
+                    "-c",
+                    INGEST_MOVE_SCRIPT,
+                    "sh",
+                    path,
+                    to,
+                ],
+            )
+            .map(|_| ())
+        }
+        IngestAction::Command { program, args } => {
+            let host_path = host_path_of(server, path)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string());
+            let args: Vec<String> = args
+                .iter()
+                .map(|a| {
+                    a.replace("{path}", &host_path)
+                        .replace("{name}", &file_name)
+                        .replace("{server}", &server.name)
+                })
+                .collect();
+            let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+            run_command(program, &args).map(|_| ())
+        }
+        IngestAction::Webhook { url } => ureq::post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .send_json(serde_json::json!({
+                "server": server.name,
+                "path": path,
+                "name": file_name,
+                "size": size,
+            }))
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn has_extension(file_name: &str, extensions: &[String]) -> bool {
+    let file_name = file_name.to_lowercase();
+    extensions
+        .iter()
+        .any(|ext| file_name.ends_with(&format!(".{}", ext.trim_start_matches('.').to_lowercase())))
+}
+
+/// The rule a file breaks, if any. `dir_files` counts the files in its directory,
+/// itself included.
+fn policy_violation(
+    policy: &FilePolicy,
+    file_name: &str,
+    size: u64,
+    dir_files: usize,
+) -> Option<String> {
+    if let Some(max) = policy.max_file_bytes.filter(|max| size > *max) {
+        return Some(format!("Larger than the {} byte limit", max));
+    }
+    if has_extension(file_name, &policy.blocked_extensions) {
+        return Some("Blocked extension".to_string());
+    }
+    if !policy.allowed_extensions.is_empty()
+        && !has_extension(file_name, &policy.allowed_extensions)
+    {
+        return Some("Extension not in the allowed list".to_string());
+    }
+    if let Some(max) = policy
+        .max_files_per_dir
+        .filter(|max| dir_files > *max as usize)
+    {
+        return Some(format!("Directory already holds {} files", max));
+    }
+    None
+}
+
+fn check_file_policy(policy: &FilePolicy) -> Result<(), String> {
+    let extensions = policy
+        .allowed_extensions
+        .iter()
+        .chain(&policy.blocked_extensions);
+    for ext in extensions {
+        let trimmed = ext.trim_start_matches('.');
+        if trimmed.is_empty() || trimmed.contains(['/', '\\']) {
+            return Err(format!("Invalid extension: {}", ext));
+        }
+    }
+    if policy.max_file_bytes == Some(0) || policy.max_files_per_dir == Some(0) {
+        return Err("Limits must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+/// Quarantine or delete a refused upload, then report it
+fn enforce_file_policy(
+    server: &ServerInfo,
+    path: &str,
+    size: u64,
+    rule: String,
+    action: PolicyAction,
+) {
+    let result = match (action, host_path_of(server, path)) {
+        (PolicyAction::Quarantine, Some(host_path)) => {
+            antivirus::quarantine(server, path, &host_path, &rule).map(Some)
+        }
+        (PolicyAction::Quarantine, None) => {
+            Err("Outside the share, so it can't be quarantined".to_string())
+        }
+        (PolicyAction::Delete, _) => {
+            run_command("docker", &["exec", &server.name, "rm", "-f", "--", path]).map(|_| None)
+        }
+    };
+    let event = PolicyViolation {
+        server: server.name.clone(),
+        path: path.to_string(),
+        size,
+        rule,
+        action,
+        quarantine_id: result.as_ref().ok().cloned().flatten(),
+        error: result.err(),
+    };
+    write_audit_entry(AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        server: server.name.clone(),
+        server_id: None,
+        action: "policy-violation".to_string(),
+        success: event.error.is_none(),
+        detail: Some(match &event.error {
+            None => format!("{}: {}", path, event.rule),
+            Some(e) => format!("{}: {}, left in place: {}", path, event.rule, e),
+        }),
+    });
+    events::publish(events::Event::PolicyViolation(event));
+}
+
+/// One ingest pass: a file counts as arrived once two scans in a row see the same
+/// size and mtime, so uploads still in progress aren't picked up half-written
+fn ingest_tick(servers: &[ServerInfo], states: &mut HashMap<String, IngestState>) {
+    let all_rules = load_ingest_rules();
+    let (engine, scanned) = antivirus::scanned_servers().unzip();
+    let scanned = scanned.unwrap_or_default();
+    let policies: HashMap<String, FilePolicy> = load_credentials()
+        .into_iter()
+        .filter_map(|(name, creds)| Some((name, creds.file_policy?)))
+        .collect();
+    states.retain(|name, _| {
+        all_rules.contains_key(name) || scanned.contains(name) || policies.contains_key(name)
+    });
+
+    for server in servers.iter().filter(|s| s.status == "running") {
+        let rules = all_rules
+            .get(&server.name)
+            .map_or(&[][..], |r| r.as_slice());
+        let engine = engine.as_ref().filter(|_| scanned.contains(&server.name));
+        let policy = policies.get(&server.name);
+        if rules.is_empty() && engine.is_none() && policy.is_none() {
+            continue;
+        }
+        let watched = |rule: &IngestRule| {
+            rule.path
+                .clone()
+                .unwrap_or_else(|| server.container_path.clone())
+        };
+        let mut dirs: Vec<String> = rules.iter().map(watched).collect();
+        if engine.is_some() || policy.is_some() {
+            dirs.push(server.container_path.clone());
+        }
+        dirs.sort();
+        dirs.dedup();
+
+        // (path, size, mtime) of every file currently in a watched directory
+        let mut files: Vec<(String, u64, String)> = Vec::new();
+        for dir in &dirs {
+            if check_exec_path(dir).is_err() {
+                continue;
+            }
+            let dir_arg = exec_path_arg(dir);
+            let Ok(output) = run_command_bytes(
+                "docker",
+                &[
+                    "exec",
+                    &server.name,
+                    "sh",
+                    "-c",
+                    INGEST_FILES_SCRIPT,
+                    "sh",
+                    &dir_arg,
+                ],
+            ) else {
+                continue;
+            };
+            let fields: Vec<&[u8]> = output.split(|b| *b == 0).collect();
+            files.extend(fields.chunks_exact(3).filter_map(|f| {
+                let size = String::from_utf8_lossy(f[0]).parse().ok()?;
+                let mtime = String::from_utf8_lossy(f[1]).into_owned();
+                let path = std::str::from_utf8(f[2]).ok()?;
+                Some((path.to_string(), size, mtime))
+            }));
+        }
+
+        let first_scan = !states.contains_key(&server.name);
+        let state = states.entry(server.name.clone()).or_default();
+        let present: HashSet<&str> = files.iter().map(|(p, _, _)| p.as_str()).collect();
+        state.handled.retain(|p| present.contains(p.as_str()));
+        if first_scan {
+            state.handled.extend(present.iter().map(|p| p.to_string()));
+            continue;
+        }
+
+        let mut dir_files: HashMap<String, usize> = HashMap::new();
+        for (path, _, _) in &files {
+            *dir_files.entry(split_container_path(path).0).or_default() += 1;
+        }
+
+        let mut pending = HashMap::new();
+        for (path, size, mtime) in files {
+            if state.handled.contains(&path) {
+                continue;
+            }
+            if state.pending.get(&path) != Some(&(size, mtime.clone())) {
+                pending.insert(path, (size, mtime));
+                continue;
+            }
+
+            state.handled.insert(path.clone());
+            let (dir, file_name) = split_container_path(&path);
+            if let Some(policy) = policy {
+                let count = dir_files.get(&dir).copied().unwrap_or(0);
+                if let Some(rule) = policy_violation(policy, &file_name, size, count) {
+                    enforce_file_policy(server, &path, size, rule, policy.action);
+                    if let Some(count) = dir_files.get_mut(&dir) {
+                        *count = count.saturating_sub(1);
+                    }
+                    continue;
+                }
+            }
+            if engine.is_some_and(|engine| !antivirus::admit_arrival(server, &path, engine)) {
+                continue;
+            }
+            for rule in rules {
+                let matches =
+                    glob::Pattern::new(&rule.pattern).is_ok_and(|p| p.matches(&file_name));
+                if !matches || watched(rule).trim_end_matches('/') != dir {
+                    continue;
+                }
+                let result = run_ingest_action(server, &path, size, &rule.action);
+                let event = IngestEvent {
+                    server: server.name.clone(),
+                    path: path.clone(),
+                    size,
+                    action: rule.action.clone(),
+                    success: result.is_ok(),
+                    error: result.err(),
+                };
+                record_audit(&server.name, "ingest", event.error.as_deref());
+                events::publish(events::Event::FileIngested(event));
+            }
+        }
+        if !pending.is_empty() {
+            invalidate_listings(&server.name);
+        }
+        state.pending = pending;
+    }
+}
+
+#[tauri::command]
+fn get_ingest_rules(name: String) -> Vec<IngestRule> {
+    load_ingest_rules().remove(&name).unwrap_or_default()
+}
+
+#[tauri::command]
+fn set_ingest_rules(name: String, rules: Vec<IngestRule>) -> CommandResult {
+    let invalid = rules.iter().find_map(|rule| {
+        if let Err(e) = glob::Pattern::new(&rule.pattern) {
+            return Some(format!("Invalid pattern '{}': {}", rule.pattern, e));
+        }
+        match &rule.action {
+            IngestAction::Move { to } if to.trim().is_empty() => {
+                Some("Move needs a destination".to_string())
+            }
+            IngestAction::Command { program, .. } if program.trim().is_empty() => {
+                Some("Command needs a program".to_string())
+            }
+            IngestAction::Webhook { url }
+                if !url.starts_with("http://") && !url.starts_with("https://") =>
+            {
+                Some(format!("Invalid webhook URL: {}", url))
+            }
+            _ => None,
+        }
+    });
+    if let Some(error) = invalid {
+        return CommandResult {
+            success: false,
+            error: Some(error),
+        };
+    }
+
+    update_json_file(
+        &get_ingest_rules_path(),
+        |all: &mut HashMap<String, Vec<IngestRule>>| {
+            if rules.is_empty() {
+                all.remove(&name);
+            } else {
+                all.insert(name, rules);
+            }
+        },
+    );
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+fn get_file_policy(name: String) -> Option<FilePolicy> {
+    load_credentials().remove(&name)?.file_policy
+}
+
+/// Limit what the server accepts from now on, or lift the limits with `None`
+#[tauri::command]
+fn set_file_policy(name: String, policy: Option<FilePolicy>) -> CommandResult {
+    let result = policy
+        .as_ref()
+        .map_or(Ok(()), check_file_policy)
+        .and_then(|_| {
+            update_credentials(|all| {
+                all.get_mut(&name)
+                    .map(|creds| creds.file_policy = policy.clone())
+            })
+            .ok_or_else(|| format!("No stored configuration for {}", name))
+        });
+    record_audit(
+        &name,
+        "set-file-policy",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Background loop that watches managed containers while the app is running
+fn spawn_server_watcher(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut heal_states: HashMap<String, HealState> = HashMap::new();
+        let mut cpu_high_since: HashMap<String, Instant> = HashMap::new();
+        let mut run_sessions: HashMap<String, String> = HashMap::new();
+        let mut interfaces: Option<Vec<NetworkInterface>> = None;
+        let mut last_cleanup: Option<Instant> = None;
+        let mut ingest_states: HashMap<String, IngestState> = HashMap::new();
+        let mut low_disk: HashSet<String> = HashSet::new();
+        let mut idle_since: HashMap<String, Instant> = HashMap::new();
+        let mut last_log_persist: Option<Instant> = None;
+        let mut last_tiering: Option<Instant> = None;
+        loop {
+            std::thread::sleep(SERVER_WATCH_INTERVAL);
+            follow_renames();
+            pending::tick();
+            let servers = list_servers();
+            track_uptime(&servers, &mut run_sessions);
+            track_interfaces(&servers, &mut interfaces);
+            auto_heal_tick(&servers, &mut heal_states);
+            sample_stats(&app, &servers);
+            evaluate_alerts(&app, &mut cpu_high_since);
+            cleanup_tick(&servers, &mut last_cleanup);
+            ingest_tick(&servers, &mut ingest_states);
+            rotation_tick(&servers);
+            relay_tick(&servers);
+            tunnels::tick(&servers);
+            on_demand_tick(&servers);
+            idle_stop_tick(&servers, &mut idle_since);
+            disk_space_tick(&servers, &mut low_disk);
+            maintenance_tick(&servers);
+            volume_index_tick(&servers);
+            snapshot_share_tick();
+            log_persist_tick(&servers, &mut last_log_persist);
+            tiering::tick(&servers, &mut last_tiering);
+        }
+    });
+}
+
+/// Parse docker's human sizes ("12.5MiB", "1.2GB", "512B") into bytes
+fn parse_docker_size(value: &str) -> u64 {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().unwrap_or(0.0);
+    let multiplier: f64 = match unit.trim() {
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => 1.0,
+    };
+    (number * multiplier) as u64
+}
+
+/// Use% of the filesystem backing `path` inside the container
+fn volume_used_percent(name: &str, path: &str) -> Option<f64> {
+    let output = run_command("docker", &["exec", name, "df", "-P", "--", path]).ok()?;
+    // Filesystem 1024-blocks Used Available Capacity Mounted-on
+    let line = output.lines().nth(1)?;
+    let capacity = line.split_whitespace().nth(4)?;
+    capacity.trim_end_matches('%').parse().ok()
+}
+
+/// Take one `docker stats` sample of every running server into the StatsCache
+fn sample_stats(app: &AppHandle, servers: &[ServerInfo]) {
+    let running: Vec<&ServerInfo> = servers.iter().filter(|s| s.status == "running").collect();
+    let mut samples: HashMap<String, ContainerStats> = HashMap::new();
+    let locale = current_locale();
+
+    if !running.is_empty() {
+        let mut args = vec![
+            "stats",
+            "--no-stream",
+            "--format",
+            "{{.Name}}|{{.CPUPerc}}|{{.MemUsage}}",
+        ];
+        args.extend(running.iter().map(|s| s.name.as_str()));
+        let output = run_command("docker", &args).unwrap_or_default();
+
+        for line in output.lines() {
+            let parts: Vec<&str> = line.split('|').collect();
+            if parts.len() < 3 {
+                continue;
+            }
+            let (used, limit) = parts[2].split_once('/').unwrap_or((parts[2], "0B"));
+            let server = running.iter().find(|s| s.name == parts[0]);
+            let volume_used_percent = server
+                .filter(|s| !s.container_path.is_empty())
+                .and_then(|s| volume_used_percent(&s.name, &s.container_path));
+
+            samples.insert(
+                parts[0].to_string(),
+                ContainerStats {
+                    cpu_percent: parts[1].trim().trim_end_matches('%').parse().unwrap_or(0.0),
+                    memory_bytes: parse_docker_size(used),
+                    memory_display: format_size(parse_docker_size(used), &locale),
+                    memory_limit_bytes: parse_docker_size(limit),
+                    volume_used_percent,
+                    sampled_at: chrono::Local::now().to_rfc3339(),
+                },
+            );
+        }
+    }
+
+    if let Ok(mut cache) = app.state::<StatsCache>().0.lock() {
+        *cache = samples;
+    }
+}
+
+/// Compare the latest samples with the thresholds and raise/clear alerts
+fn evaluate_alerts(app: &AppHandle, cpu_high_since: &mut HashMap<String, Instant>) {
+    let thresholds = load_app_settings().alerts;
+    let samples = app
+        .state::<StatsCache>()
+        .0
+        .lock()
+        .map(|c| c.clone())
+        .unwrap_or_default();
+    let alerts_state = app.state::<ActiveAlerts>();
+    let Ok(mut active) = alerts_state.0.lock() else {
+        return;
+    };
+
+    cpu_high_since.retain(|name, _| samples.contains_key(name));
+
+    // (server, kind) -> (value, threshold, message) for every breached threshold
+    let mut breached: HashMap<(String, String), (f64, f64, String)> = HashMap::new();
+    for (name, stats) in &samples {
+        if let Some(limit_mb) = thresholds.memory_mb {
+            let used_mb = stats.memory_bytes as f64 / (1024.0 * 1024.0);
+            if used_mb > limit_mb as f64 {
+                breached.insert(
+                    (name.clone(), "memory".to_string()),
+                    (
+                        used_mb,
+                        limit_mb as f64,
+                        format!(
+                            "{} is using {:.0} MB of memory (limit {} MB)",
+                            name, used_mb, limit_mb
+                        ),
+                    ),
+                );
+            }
+        }
+
+        if let Some(limit) = thresholds.cpu_percent {
+            if stats.cpu_percent > limit {
+                let since = *cpu_high_since
+                    .entry(name.clone())
+                    .or_insert_with(Instant::now);
+                let sustained = Duration::from_secs(thresholds.cpu_minutes * 60);
+                if since.elapsed() >= sustained {
+                    breached.insert(
+                        (name.clone(), "cpu".to_string()),
+                        (
+                            stats.cpu_percent,
+                            limit,
+                            format!(
+                                "{} has used over {:.0}% CPU for {} minutes",
+                                name, limit, thresholds.cpu_minutes
+                            ),
+                        ),
+                    );
+                }
+            } else {
+                cpu_high_since.remove(name);
+            }
+        }
+
+        if let (Some(limit), Some(used)) = (thresholds.volume_percent, stats.volume_used_percent) {
+            if used > limit {
+                breached.insert(
+                    (name.clone(), "volume".to_string()),
+                    (
+                        used,
+                        limit,
+                        format!("{}'s volume is {:.0}% full", name, used),
+                    ),
+                );
+            }
+        }
+    }
+
+    for (key, (value, threshold, message)) in &breached {
+        if active.contains_key(key) {
+            continue;
+        }
+        let alert = ResourceAlert {
+            server: key.0.clone(),
+            kind: key.1.clone(),
+            value: *value,
+            threshold: *threshold,
+            message: message.clone(),
+            raised_at: chrono::Local::now().to_rfc3339(),
+        };
+        events::publish(events::Event::AlertRaised(alert.clone()));
+        active.insert(key.clone(), alert);
+    }
+
+    let cleared: Vec<(String, String)> = active
+        .keys()
+        .filter(|key| !breached.contains_key(*key))
+        .cloned()
+        .collect();
+    for key in cleared {
+        if let Some(alert) = active.remove(&key) {
+            events::publish(events::Event::AlertCleared(alert));
+        }
+    }
+}
+
+#[tauri::command]
+fn get_server_stats(stats: State<'_, StatsCache>, name: String) -> Option<ContainerStats> {
+    stats.0.lock().ok()?.get(&name).cloned()
+}
+
+#[tauri::command]
+fn list_active_alerts(alerts: State<'_, ActiveAlerts>) -> Vec<ResourceAlert> {
+    alerts
+        .0
+        .lock()
+        .map(|a| a.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_alert_thresholds() -> AlertThresholds {
+    load_app_settings().alerts
+}
+
+#[tauri::command]
+fn set_alert_thresholds(thresholds: AlertThresholds) -> CommandResult {
+    update_app_settings(|settings| settings.alerts = thresholds);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+fn get_auto_heal_policy() -> AutoHealPolicy {
+    load_app_settings().auto_heal
+}
+
+#[tauri::command]
+fn set_auto_heal_policy(policy: AutoHealPolicy) -> CommandResult {
+    update_app_settings(|settings| settings.auto_heal = policy);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+fn get_container_status(name: String) -> String {
+    // Only check atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return "not sftp".to_string();
+    }
+
+    match run_command(
+        "docker",
+        &["inspect", "--format", "{{.State.Status}}", &name],
+    ) {
+        Ok(status) => status.trim().to_string(),
+        Err(_) => "not created".to_string(),
+    }
+}
+
+/// Parse a docker RFC3339Nano time; unset times come back as the zero time
+fn parse_docker_time(value: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    chrono::DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .filter(|t| t.timestamp() > 0)
+}
+
+fn docker_time(value: &serde_json::Value) -> Option<String> {
+    value
+        .as_str()
+        .and_then(parse_docker_time)
+        .map(to_local_rfc3339)
+}
+
+/// Seconds since an RFC3339 timestamp
+fn seconds_since(time: &str) -> Option<u64> {
+    let time = chrono::DateTime::parse_from_rfc3339(time).ok()?;
+    u64::try_from((chrono::Local::now().fixed_offset() - time).num_seconds()).ok()
+}
+
+fn inspect_container(name: &str) -> Result<ContainerInspect, String> {
+    let output = run_command("docker", &["inspect", "--type", "container", name])?;
+    let parsed: Vec<serde_json::Value> =
+        serde_json::from_str(&output).map_err(|e| format!("Bad docker inspect output: {}", e))?;
+    let data = parsed
+        .first()
+        .ok_or_else(|| format!("No such container: {}", name))?;
+    let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+
+    Ok(ContainerInspect {
+        id: text(&data["Id"]),
+        image: text(&data["Config"]["Image"]),
+        status: text(&data["State"]["Status"]),
+        health: data["State"]["Health"]["Status"]
+            .as_str()
+            .map(|h| h.to_string()),
+        created_at: docker_time(&data["Created"]),
+        started_at: docker_time(&data["State"]["StartedAt"]),
+        restart_policy: RestartPolicy::from_inspect(
+            data["HostConfig"]["RestartPolicy"]["Name"]
+                .as_str()
+                .unwrap_or_default(),
+            data["HostConfig"]["RestartPolicy"]["MaximumRetryCount"]
+                .as_u64()
+                .unwrap_or(0) as u32,
+        ),
+        mounts: data["Mounts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|m| MountInfo {
+                source: text(&m["Source"]),
+                destination: text(&m["Destination"]),
+                read_only: !m["RW"].as_bool().unwrap_or(true),
+            })
+            .collect(),
+        env: data["Config"]["Env"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(text)
+            .collect(),
+        server_id: data["Config"]["Labels"][SERVER_ID_LABEL]
+            .as_str()
+            .map(str::to_string),
+    })
+}
+
+/// Container filesystem changes outside the mounted volumes, from `docker diff`
+#[derive(Debug, Serialize, Deserialize, Default, TS)]
+#[ts(export)]
+pub struct ContainerDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Changes left out because the image or dsftp makes them on every start
+    #[ts(type = "number")]
+    pub ignored: usize,
+}
+
+/// Whether atmoz/sftp or dsftp touches `path` as part of a normal start: user
+/// accounts, host keys, runtime files, and the directories mounts are made in
+fn expected_container_change(path: &str, mounts: &[MountInfo]) -> bool {
+    let under = |prefix: &str| path == prefix || path.starts_with(&format!("{}/", prefix));
+    EXPECTED_CONTAINER_CHANGES.iter().any(|p| under(p))
+        || mounts.iter().any(|m| {
+            let destination = m.destination.trim_end_matches('/');
+            destination == path || destination.starts_with(&format!("{}/", path))
+        })
+}
+
+/// What changed inside the container beyond its volumes, to spot tampering or
+/// stray writes that a recreate would throw away
+#[tauri::command]
+fn get_container_diff(name: String) -> Result<ContainerDiff, String> {
+    let mounts = inspect_container(&name)?.mounts;
+    let output = run_command("docker", &["diff", &name])?;
+    let mut diff = ContainerDiff::default();
+    for line in output.lines() {
+        let Some((kind, path)) = line.split_once(' ') else {
+            continue;
+        };
+        if expected_container_change(path, &mounts) {
+            diff.ignored += 1;
+            continue;
+        }
+        match kind {
+            "A" => diff.added.push(path.to_string()),
+            "C" => diff.changed.push(path.to_string()),
+            "D" => diff.deleted.push(path.to_string()),
+            _ => {}
+        }
+    }
+    Ok(diff)
+}
+
+/// Something Docker keeps around that no server uses any more
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct DockerArtifact {
+    /// "volume" or "image"
+    pub kind: String,
+    pub id: String,
+    #[ts(type = "number")]
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DockerCleanupReport {
+    pub dry_run: bool,
+    /// Artifacts found, or removed when not a dry run
+    pub artifacts: Vec<DockerArtifact>,
+    /// Reclaimable, or reclaimed when not a dry run
+    #[ts(type = "number")]
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+/// Docker names anonymous volumes with 64 hex digits
+fn is_anonymous_volume(name: &str) -> bool {
+    name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Anonymous volumes no container uses, as `docker rm` without `-v` leaves behind.
+/// Docker Desktop keeps volumes inside its VM, so they are measured from a
+/// throwaway container.
+fn orphaned_volumes() -> Result<Vec<DockerArtifact>, String> {
+    let output = run_command(
+        "docker",
+        &["volume", "ls", "-q", "--filter", "dangling=true"],
+    )?;
+    let names: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|n| is_anonymous_volume(n))
+        .collect();
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut args = vec!["run".to_string(), "--rm".to_string()];
+    for name in &names {
+        args.extend(["-v".to_string(), format!("{}:/v/{}:ro", name, name)]);
+    }
+    args.extend(["--entrypoint", "du", SFTP_IMAGE, "-s", "-b"].map(String::from));
+    args.extend(names.iter().map(|n| format!("/v/{}", n)));
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let sizes: HashMap<String, u64> = run_command("docker", &args)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (size, path) = line.split_once('\t')?;
+            Some((
+                path.trim_start_matches("/v/").to_string(),
+                size.parse().ok()?,
+            ))
+        })
+        .collect();
+
+    Ok(names
+        .into_iter()
+        .map(|name| DockerArtifact {
+            kind: "volume".to_string(),
+            id: name.to_string(),
+            size: sizes.get(name).copied().unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Untagged atmoz/sftp images left by pulls of a newer version, unless a
+/// container still runs them
+fn dangling_sftp_images() -> Result<Vec<DockerArtifact>, String> {
+    let output = run_command(
+        "docker",
+        &["images", "-q", "--no-trunc", "--filter", "dangling=true"],
+    )?;
+    let ids: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let containers = run_command("docker", &["ps", "-aq", "--no-trunc"])?;
+    let mut in_use = HashSet::new();
+    if !containers.trim().is_empty() {
+        let mut args = vec!["inspect", "--format", "{{.Image}}"];
+        args.extend(containers.lines().map(str::trim));
+        in_use.extend(run_command("docker", &args)?.lines().map(String::from));
+    }
+
+    let mut args = vec![
+        "image",
+        "inspect",
+        "--format",
+        "{{.Id}}|{{.Size}}|{{join .RepoDigests \",\"}}",
+    ];
+    args.extend(ids);
+    let prefix = format!("{}@", SFTP_IMAGE);
+    Ok(run_command("docker", &args)?
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '|');
+            let id = parts.next()?.to_string();
+            let size = parts.next()?.parse().unwrap_or(0);
+            let ours = parts.next()?.split(',').any(|d| d.starts_with(&prefix));
+            (ours && !in_use.contains(&id)).then_some(DockerArtifact {
+                kind: "image".to_string(),
+                id,
+                size,
+            })
+        })
+        .collect())
+}
+
+/// Find anonymous volumes left by removed containers and old atmoz/sftp images,
+/// and remove them unless `dry_run`. dsftp creates no networks of its own, so
+/// there are none to collect.
+#[tauri::command]
+async fn cleanup_docker_artifacts(dry_run: bool) -> Result<DockerCleanupReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut candidates = orphaned_volumes()?;
+        candidates.extend(dangling_sftp_images()?);
+        let mut report = DockerCleanupReport {
+            dry_run,
+            artifacts: Vec::new(),
+            bytes: 0,
+            errors: Vec::new(),
+        };
+        if dry_run {
+            report.bytes = candidates.iter().map(|a| a.size).sum();
+            report.artifacts = candidates;
+            return Ok(report);
+        }
+
+        for artifact in candidates {
+            let args = [artifact.kind.as_str(), "rm", artifact.id.as_str()];
+            match run_command("docker", &args) {
+                Ok(_) => {
+                    report.bytes += artifact.size;
+                    report.artifacts.push(artifact);
+                }
+                Err(e) => report.errors.push(format!("{}: {}", artifact.id, e.trim())),
+            }
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn connection_info(server: &ServerInfo) -> ConnectionInfo {
+    let host = match server.bind_ip.as_deref() {
+        Some(ip) if !ip.is_empty() && ip != "0.0.0.0" => ip.to_string(),
+        _ => get_local_ip(),
+    };
+    ConnectionInfo {
+        url: format!("sftp://{}@{}:{}", server.username, host, server.port),
+        command: format!("sftp -P {} {}@{}", server.port, server.username, host),
+        host,
+        port: server.port,
+        username: server.username.clone(),
+        public_address: tunnels::public_address(&server.name),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// A server's login, the only command that hands out its password. Every call is
+/// audited.
+#[tauri::command]
+fn reveal_credentials(name: String) -> Result<Credentials, String> {
+    let creds = load_credentials()
+        .remove(&name)
+        .ok_or_else(|| format!("Unknown server: {}", name))?;
+    record_audit(&name, "reveal-credentials", None);
+    Ok(Credentials {
+        username: creds.username,
+        password: creds.password,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LandingPageInfo {
+    pub server: String,
+    pub port: u16,
+    /// Link to hand out; carries the one-time token only in the response that
+    /// started the page
+    pub url: String,
+}
+
+const LANDING_PAGE_HTML: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width">
+<title>{title}</title>
+<style>body{font-family:system-ui,sans-serif;max-width:40em;margin:2em auto;padding:0 1em}
+dt{font-weight:600;margin-top:.8em}dd{margin:0;font-family:monospace;font-size:1.1em}</style>
+</head><body><h1>{title}</h1>
+<dl><dt>Host</dt><dd>{host}</dd><dt>Port</dt><dd>{port}</dd>
+<dt>Username</dt><dd>{username}</dd><dt>Password</dt><dd>{password}</dd>
+<dt>Address</dt><dd>{url}</dd><dt>Command line</dt><dd>{command}</dd></dl>
+<h2>Get a client</h2><ul>
+<li><a href="https://winscp.net/eng/download.php">WinSCP</a> (Windows)</li>
+<li><a href="https://cyberduck.io/download/">Cyberduck</a> (macOS, Windows)</li>
+<li><a href="https://filezilla-project.org/download.php?type=client">FileZilla</a> (Windows, macOS, Linux)</li>
+</ul></body></html>"#;
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Random (version 4) UUID, identifying a server for as long as it exists
+fn new_server_id() -> String {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn random_token() -> String {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn render_landing_page(name: &str) -> String {
+    let Ok(server) = find_server(name) else {
+        return format!("<p>{} no longer exists.</p>", html_escape(name));
+    };
+    let info = connection_info(&server);
+    LANDING_PAGE_HTML
+        .replace(
+            "{title}",
+            &html_escape(&format!("Connect to {}", server.name)),
+        )
+        .replace("{host}", &html_escape(&info.host))
+        .replace("{port}", &info.port.to_string())
+        .replace("{username}", &html_escape(&info.username))
+        .replace("{password}", &html_escape(&server.password))
+        .replace("{url}", &html_escape(&info.url))
+        .replace("{command}", &html_escape(&info.command))
+}
+
+const PASSWORD_FORM_HTML: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width">
+<title>Change your password on {server}</title>
+<style>body{font-family:system-ui,sans-serif;max-width:28em;margin:2em auto;padding:0 1em}
+label{display:block;margin-top:.8em;font-weight:600}input{display:block;width:100%;padding:.4em;font-size:1em}
+button{margin-top:1.2em;padding:.5em 1.2em;font-size:1em}.message{padding:.6em;background:#eee}</style>
+</head><body><h1>Change your password on {server}</h1>{message}
+<form method="post">
+<label>User name<input name="username" autocomplete="username" required></label>
+<label>Current password<input name="current" type="password" autocomplete="current-password" required></label>
+<label>New password<input name="new" type="password" autocomplete="new-password" minlength="{min_len}" required></label>
+<label>New password again<input name="confirm" type="password" autocomplete="new-password" required></label>
+<button>Change password</button>
+</form></body></html>"#;
+
+/// `GET|POST /password/<server>`: the form where the server's users change their
+/// own password. Servers that don't allow it answer 404.
+fn handle_password_page(
+    request: &mut tiny_http::Request,
+    method: &tiny_http::Method,
+    server: &str,
+) -> Result<tiny_http::ResponseBox, (u16, String)> {
+    let server = percent_decode(server).ok_or((400, "Invalid server name".to_string()))?;
+    let peer = request
+        .remote_addr()
+        .map(|a| a.ip().to_string())
+        .unwrap_or_default();
+    if !load_credentials()
+        .get(&server)
+        .is_some_and(|c| c.password_self_service)
+    {
+        return Err((404, "Not found".to_string()));
+    }
+
+    let message = match method {
+        tiny_http::Method::Get => String::new(),
+        tiny_http::Method::Post => {
+            let mut body = String::new();
+            request
+                .as_reader()
+                .take(MAX_FORM_BYTES)
+                .read_to_string(&mut body)
+                .map_err(|e| (400, e.to_string()))?;
+            let field = |key: &str| {
+                body.split('&')
+                    .find_map(|kv| {
+                        let (k, v) = kv.split_once('=')?;
+                        if k == key {
+                            percent_decode(v)
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or_default()
+            };
+            let (username, new) = (field("username"), field("new"));
+            let result = if new != field("confirm") {
+                Err("The new passwords don't match".to_string())
+            } else {
+                change_own_password(&server, &username, &peer, &field("current"), &new)
+            };
+            record_audit(
+                &server,
+                "self-service-password",
+                result
+                    .as_ref()
+                    .err()
+                    .map(|e| format!("{}: {}", username, e))
+                    .as_deref(),
+            );
+            match result {
+                Ok(()) => "<p class=\"message\">Your password was changed.</p>".to_string(),
+                Err(e) => format!("<p class=\"message\">{}</p>", html_escape(&e)),
+            }
+        }
+        _ => return Err((405, "Method not allowed".to_string())),
+    };
+
+    let page = PASSWORD_FORM_HTML
+        .replace("{server}", &html_escape(&server))
+        .replace("{min_len}", &MIN_SELF_SERVICE_PASSWORD_LEN.to_string())
+        .replace("{message}", &message);
+    let mut response = tiny_http::Response::from_string(page);
+    if let Ok(header) =
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
+    {
+        response.add_header(header);
+    }
+    Ok(response.boxed())
+}
+
+/// Serve the landing page. The token in the shared link works once: the first visit
+/// trades it for a session cookie, so the link is useless to anyone it's forwarded to.
+fn serve_landing_page(http: Arc<tiny_http::Server>, name: String, token: String) {
+    let mut token = Some(token);
+    let mut sessions: HashSet<String> = HashSet::new();
+    let header = |field: &str, value: &str| {
+        tiny_http::Header::from_bytes(field.as_bytes(), value.as_bytes()).ok()
+    };
+
+    for request in http.incoming_requests() {
+        let query_token = request
+            .url()
+            .split_once('?')
+            .and_then(|(_, query)| query.split('&').find_map(|kv| kv.strip_prefix("token=")))
+            .map(str::to_string);
+        let session = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Cookie"))
+            .and_then(|h| {
+                h.value
+                    .as_str()
+                    .split(';')
+                    .find_map(|c| c.trim().strip_prefix("dsftp_session="))
+                    .map(str::to_string)
+            });
+
+        let response = if session.is_some_and(|s| sessions.contains(&s)) {
+            let mut response = tiny_http::Response::from_string(render_landing_page(&name));
+            if let Some(h) = header("Content-Type", "text/html; charset=utf-8") {
+                response.add_header(h);
+            }
+            response
+        } else if query_token.is_some() && query_token == token {
+            token = None;
+            let session = random_token();
+            sessions.insert(session.clone());
+            let mut response = tiny_http::Response::from_string("").with_status_code(303);
+            for h in [
+                header("Location", "/"),
+                header(
+                    "Set-Cookie",
+                    &format!(
+                        "dsftp_session={}; HttpOnly; SameSite=Strict; Path=/",
+                        session
+                    ),
+                ),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                response.add_header(h);
+            }
+            response
+        } else {
+            tiny_http::Response::from_string("This link has expired or was already used.")
+                .with_status_code(403)
+        };
+        request.respond(response).ok();
+    }
+}
+
+/// Start a small HTTP page next to the server's port with its connection details
+/// and client download links, for recipients on the LAN
+#[tauri::command]
+fn start_landing_page(
+    pages: State<'_, LandingPages>,
+    name: String,
+    port: Option<u16>,
+) -> Result<LandingPageInfo, String> {
+    let server = find_server(&name)?;
+    let port = match port {
+        Some(port) => port,
+        None => server
+            .port
+            .checked_add(LANDING_PAGE_PORT_OFFSET)
+            .ok_or_else(|| "No port available above the server's".to_string())?,
+    };
+    let mut pages = pages.0.lock().map_err(|e| e.to_string())?;
+    if let Some(old) = pages.remove(&name) {
+        old.http.unblock();
+    }
+
+    let bind_ip = server.bind_ip.clone().unwrap_or_else(default_bind_ip);
+    let http = tiny_http::Server::http(format!("{}:{}", bind_ip, port))
+        .map(Arc::new)
+        .map_err(|e| format!("Cannot listen on {}:{}: {}", bind_ip, port, e))?;
+    let token = random_token();
+    let host = connection_info(&server).host;
+    let url = format!("http://{}:{}/?token={}", host, port, token);
+
+    let worker = Arc::clone(&http);
+    let page_name = name.clone();
+    std::thread::spawn(move || serve_landing_page(worker, page_name, token));
+    pages.insert(name.clone(), LandingPage { port, http });
+    record_audit(&name, "start-landing-page", None);
+    Ok(LandingPageInfo {
+        server: name,
+        port,
+        url,
+    })
+}
+
+#[tauri::command]
+fn stop_landing_page(pages: State<'_, LandingPages>, name: String) -> CommandResult {
+    let removed = pages.0.lock().ok().and_then(|mut p| p.remove(&name));
+    match removed {
+        Some(page) => {
+            page.http.unblock();
+            record_audit(&name, "stop-landing-page", None);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        None => CommandResult {
+            success: false,
+            error: Some(format!("No landing page is running for {}", name)),
+        },
+    }
+}
+
+/// Running landing pages; the token isn't repeated, start again for a fresh link
+#[tauri::command]
+fn list_landing_pages(pages: State<'_, LandingPages>) -> Vec<LandingPageInfo> {
+    let pages = pages.0.lock().map(|p| {
+        p.iter()
+            .map(|(name, page)| (name.clone(), page.port))
+            .collect::<Vec<_>>()
+    });
+    let servers = list_servers();
+    pages
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, port)| {
+            let host = servers
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| connection_info(s).host)
+                .unwrap_or_else(get_local_ip);
+            LandingPageInfo {
+                url: format!("http://{}:{}/", host, port),
+                server: name,
+                port,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PairingInfo {
+    /// Name the other machine sees when it discovers this one
+    pub instance: String,
+    pub port: u16,
+    /// Short code to read out to whoever pairs with this machine
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct DiscoveredPeer {
+    pub instance: String,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct PairedPeer {
+    pub instance: String,
+    pub host: String,
+    pub port: u16,
+    #[serde(skip)]
+    token: String,
+}
+
+/// The host's short name, which doubles as the advertised mDNS instance
+fn pairing_instance_name() -> String {
+    run_command("hostname", &[])
+        .ok()
+        .and_then(|h| h.trim().split('.').next().map(str::to_string))
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "dsftp".to_string())
+}
+
+fn pairing_code() -> String {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    format!("{:06}", OsRng.next_u32() % 1_000_000)
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3)?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn query_param(url: &str, key: &str) -> Option<String> {
+    url.split_once('?')?.1.split('&').find_map(|kv| {
+        let (k, v) = kv.split_once('=')?;
+        if k == key {
+            percent_decode(v)
+        } else {
+            None
+        }
+    })
+}
+
+/// A file under a server's host folder, named by a peer or API client and checked
+/// by `host_file_path`
+struct SharePath {
+    /// The host folder with symlinks resolved
+    root: PathBuf,
+    path: PathBuf,
+}
+
+impl SharePath {
+    /// Open the file for reading; it may not have become a symlink since the check
+    fn open(&self) -> Result<fs::File, String> {
+        no_follow(fs::OpenOptions::new().read(true))
+            .open(&self.path)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Create `path`, this file or one beside it, making its folders first. They're
+    /// checked again once they exist and the file is opened without following a
+    /// symlink, so nothing swapped in since the first check redirects the write.
+    fn create(&self, path: &Path) -> Result<fs::File, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        check_in_share(&self.root, path)?;
+        no_follow(
+            fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true),
+        )
+        .open(path)
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// A server's host folder joined with a path from a peer or API client, which may
+/// not climb out of it, by `..` or through a symlink
+fn host_file_path(server: &str, relative: &str) -> Result<SharePath, String> {
+    let server = find_server(server)?;
+    let relative = Path::new(relative.trim_start_matches('/'));
+    let contained = relative
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if relative.as_os_str().is_empty() || !contained {
+        return Err(format!("Invalid path {}", relative.display()));
+    }
+    let root = fs::canonicalize(&server.host_path).map_err(|e| e.to_string())?;
+    let path = root.join(relative);
+    check_in_share(&root, &path)?;
+    Ok(SharePath { root, path })
+}
+
+/// Refuse `path` when the deepest part of it that exists resolves outside `root`, or
+/// when it is itself a symlink
+fn check_in_share(root: &Path, path: &Path) -> Result<(), String> {
+    let outside = || format!("{} is outside the share", path.display());
+    // A dangling symlink counts as existing, so canonicalizing it below fails
+    let existing = path
+        .ancestors()
+        .find(|p| fs::symlink_metadata(p).is_ok())
+        .ok_or_else(outside)?;
+    if fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) {
+        return Err(outside());
+    }
+    match fs::canonicalize(existing) {
+        Ok(resolved) if resolved.starts_with(root) => Ok(()),
+        _ => Err(outside()),
+    }
+}
+
+/// Don't follow a symlink in the last component of the opened path
+fn no_follow(options: &mut fs::OpenOptions) -> &mut fs::OpenOptions {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    options
+}
+
+fn pairing_response(status: u16, body: String) -> tiny_http::ResponseBox {
+    tiny_http::Response::from_string(body)
+        .with_status_code(status)
+        .boxed()
+}
+
+/// Answers peers: `POST /pair` trades the code for a token, which every other
+/// route then requires
+fn serve_pairing(http: Arc<tiny_http::Server>, code: String) {
+    let mut code = Some(code);
+    let mut attempts = 0;
+    let mut tokens: HashSet<String> = HashSet::new();
+
+    for mut request in http.incoming_requests() {
+        let url = request.url().to_string();
+        let route = url.split('?').next().unwrap_or_default();
+        let authorized = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+            .is_some_and(|t| tokens.contains(t));
+        let target = query_param(&url, "server").zip(query_param(&url, "path"));
+
+        let response = match (request.method().clone(), route) {
+            (tiny_http::Method::Post, "/pair") => {
+                let mut offered = String::new();
+                request
+                    .as_reader()
+                    .take(64)
+                    .read_to_string(&mut offered)
+                    .ok();
+                match &code {
+                    Some(expected) if offered.trim() == expected.as_str() => {
+                        let token = random_token();
+                        tokens.insert(token.clone());
+                        pairing_response(200, serde_json::json!({ "token": token }).to_string())
+                    }
+                    Some(_) => {
+                        attempts += 1;
+                        if attempts >= PAIRING_MAX_ATTEMPTS {
+                            code = None;
+                        }
+                        pairing_response(403, "Wrong pairing code".to_string())
+                    }
+                    None => pairing_response(403, "Pairing is closed; start it again".to_string()),
+                }
+            }
+            _ if !authorized => pairing_response(401, "Not paired".to_string()),
+            (tiny_http::Method::Get, "/servers") => {
+                let creds = load_credentials();
+                let servers: Vec<SharedServer> = list_servers()
+                    .into_iter()
+                    .filter(|s| creds.contains_key(&s.name))
+                    .map(|s| {
+                        let entry = creds.get(&s.name);
+                        SharedServer::new(s, entry)
+                    })
+                    .collect();
+                pairing_response(200, serde_json::to_string(&servers).unwrap_or_default())
+            }
+            (tiny_http::Method::Get, "/files") => {
+                let file = target
+                    .ok_or_else(|| "Missing server or path".to_string())
+                    .and_then(|(server, path)| host_file_path(&server, &path))
+                    .and_then(|dest| dest.open());
+                match file {
+                    Ok(file) => tiny_http::Response::from_file(file).boxed(),
+                    Err(e) => pairing_response(404, e),
+                }
+            }
+            (tiny_http::Method::Put, "/files") => {
+                let Some((server, path)) = target else {
+                    request
+                        .respond(pairing_response(400, "Missing server or path".to_string()))
+                        .ok();
+                    continue;
+                };
+                let length = request.body_length().unwrap_or(0) as u64;
+                let written = host_file_path(&server, &path).and_then(|dest| {
+                    check_disk_space(&dest.path, length).map_err(|short| short.to_string())?;
+                    let mut file = dest.create(&dest.path)?;
+                    std::io::copy(request.as_reader(), &mut file).map_err(|e| e.to_string())
+                });
+                record_audit(
+                    &server,
+                    "peer-push",
+                    written.as_ref().err().map(String::as_str),
+                );
+                match written {
+                    Ok(_) => pairing_response(204, String::new()),
+                    Err(e) => pairing_response(400, e),
+                }
+            }
+            _ => pairing_response(404, "Not found".to_string()),
+        };
+        request.respond(response).ok();
+    }
+}
+
+fn advertise_pairing(
+    instance: &str,
+    ip: &str,
+    port: u16,
+) -> Result<mdns_sd::ServiceDaemon, String> {
+    let mdns = mdns_sd::ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let service = mdns_sd::ServiceInfo::new(
+        PAIRING_SERVICE,
+        instance,
+        &format!("{}.local.", instance),
+        ip,
+        port,
+        None::<HashMap<String, String>>,
+    )
+    .map_err(|e| e.to_string())?;
+    mdns.register(service).map_err(|e| e.to_string())?;
+    Ok(mdns)
+}
+
+fn withdraw_pairing(offer: PairingOffer) {
+    offer
+        .mdns
+        .unregister(&format!("{}.{}", offer.instance, PAIRING_SERVICE))
+        .ok();
+    offer.mdns.shutdown().ok();
+    offer.http.unblock();
+}
+
+/// Advertise this machine on the LAN until `stop_pairing`; peers that enter the
+/// returned code can push and pull files and copy server definitions
+#[tauri::command]
+fn start_pairing(host: State<'_, PairingHost>) -> Result<PairingInfo, String> {
+    let mut host = host.0.lock().map_err(|e| e.to_string())?;
+    if let Some(old) = host.take() {
+        withdraw_pairing(old);
+    }
+
+    let bind_ip = default_bind_ip();
+    let http = tiny_http::Server::http(format!("{}:0", bind_ip))
+        .map(Arc::new)
+        .map_err(|e| format!("Cannot listen on {}: {}", bind_ip, e))?;
+    let Some(port) = http.server_addr().to_ip().map(|addr| addr.port()) else {
+        http.unblock();
+        return Err("Pairing needs a TCP port".to_string());
+    };
+    let advertised = if bind_ip == "0.0.0.0" {
+        get_local_ip()
+    } else {
+        bind_ip
+    };
+    let instance = pairing_instance_name();
+    let mdns = match advertise_pairing(&instance, &advertised, port) {
+        Ok(mdns) => mdns,
+        Err(e) => {
+            http.unblock();
+            return Err(format!("Cannot advertise on the LAN: {}", e));
+        }
+    };
+
+    let code = pairing_code();
+    let worker = Arc::clone(&http);
+    let worker_code = code.clone();
+    std::thread::spawn(move || serve_pairing(worker, worker_code));
+    *host = Some(PairingOffer {
+        port,
+        code: code.clone(),
+        instance: instance.clone(),
+        http,
+        mdns,
+    });
+    Ok(PairingInfo {
+        instance,
+        port,
+        code,
+    })
+}
+
+/// Stop advertising; peers paired so far lose access too
+#[tauri::command]
+fn stop_pairing(host: State<'_, PairingHost>) -> CommandResult {
+    match host.0.lock().ok().and_then(|mut h| h.take()) {
+        Some(offer) => {
+            withdraw_pairing(offer);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        None => CommandResult {
+            success: false,
+            error: Some("Pairing isn't running".to_string()),
+        },
+    }
+}
+
+/// The running offer, so the code can be shown again
+#[tauri::command]
+fn get_pairing(host: State<'_, PairingHost>) -> Option<PairingInfo> {
+    let host = host.0.lock().ok()?;
+    host.as_ref().map(|offer| PairingInfo {
+        instance: offer.instance.clone(),
+        port: offer.port,
+        code: offer.code.clone(),
+    })
+}
+
+fn browse_peers(own: Option<&str>) -> Result<Vec<DiscoveredPeer>, String> {
+    let mdns = mdns_sd::ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let events = mdns.browse(PAIRING_SERVICE).map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + PAIRING_BROWSE_TIME;
+    let mut peers: Vec<DiscoveredPeer> = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = events.recv_timeout(left) else {
+            break;
+        };
+        let mdns_sd::ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        let instance = info
+            .get_fullname()
+            .trim_end_matches(PAIRING_SERVICE)
+            .trim_end_matches('.')
+            .to_string();
+        let Some(host) = info.get_addresses().iter().next().map(|ip| ip.to_string()) else {
+            continue;
+        };
+        if own == Some(instance.as_str()) || peers.iter().any(|p| p.instance == instance) {
+            continue;
+        }
+        peers.push(DiscoveredPeer {
+            instance,
+            host,
+            port: info.get_port(),
+        });
+    }
+    mdns.shutdown().ok();
+    Ok(peers)
+}
+
+/// Other dsftp instances currently advertising a pairing offer on the LAN
+#[tauri::command]
+async fn discover_peers(host: State<'_, PairingHost>) -> Result<Vec<DiscoveredPeer>, String> {
+    let own = host
+        .0
+        .lock()
+        .ok()
+        .and_then(|h| h.as_ref().map(|offer| offer.instance.clone()));
+    tauri::async_runtime::spawn_blocking(move || browse_peers(own.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn peer_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(PAIRING_CONNECT_TIMEOUT)
+        .build()
+}
+
+fn peer_request(peer: &PairedPeer, method: &str, route: &str) -> ureq::Request {
+    peer_agent()
+        .request(
+            method,
+            &format!("http://{}:{}{}", peer.host, peer.port, route),
+        )
+        .set("Authorization", &format!("Bearer {}", peer.token))
+}
+
+fn peer_error(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(_, response) => response.into_string().unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+fn paired_peer(peers: &PairedPeers, instance: &str) -> Result<PairedPeer, String> {
+    peers
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(instance)
+        .cloned()
+        .ok_or_else(|| format!("Not paired with {}", instance))
+}
+
+fn peer_files_route(server: &str, path: &str) -> String {
+    format!(
+        "/files?server={}&path={}",
+        percent_encode(server),
+        percent_encode(path)
+    )
+}
+
+/// Trade the code shown on the other machine for access to it
+#[tauri::command]
+async fn pair_with_peer(
+    peers: State<'_, PairedPeers>,
+    peer: DiscoveredPeer,
+    code: String,
+) -> Result<PairedPeer, String> {
+    let url = format!("http://{}:{}/pair", peer.host, peer.port);
+    let reply = tauri::async_runtime::spawn_blocking(move || {
+        peer_agent()
+            .post(&url)
+            .send_string(code.trim())
+            .map_err(peer_error)?
+            .into_json::<serde_json::Value>()
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let token = reply["token"]
+        .as_str()
+        .ok_or_else(|| format!("{} didn't hand out a token", peer.instance))?;
+    let paired = PairedPeer {
+        instance: peer.instance,
+        host: peer.host,
+        port: peer.port,
+        token: token.to_string(),
+    };
+    peers
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(paired.instance.clone(), paired.clone());
+    Ok(paired)
+}
+
+#[tauri::command]
+fn list_paired_peers(peers: State<'_, PairedPeers>) -> Vec<PairedPeer> {
+    peers
+        .0
+        .lock()
+        .map(|p| p.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Server definitions a paired peer offers for replication
+#[tauri::command]
+async fn list_peer_servers(
+    peers: State<'_, PairedPeers>,
+    instance: String,
+) -> Result<Vec<SharedServer>, String> {
+    let peer = paired_peer(&peers, &instance)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        peer_request(&peer, "GET", "/servers")
+            .call()
+            .map_err(peer_error)?
+            .into_json()
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Copy a local file into one of the peer's servers; `path` is relative to that
+/// server's host folder
+#[tauri::command]
+async fn push_file_to_peer(
+    peers: State<'_, PairedPeers>,
+    instance: String,
+    local_path: String,
+    server: String,
+    path: String,
+) -> Result<(), String> {
+    let peer = paired_peer(&peers, &instance)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let result = fs::File::open(&local_path)
+            .and_then(|file| Ok((file.metadata()?.len(), file)))
+            .map_err(|e| format!("{}: {}", local_path, e))
+            .and_then(|(len, file)| {
+                peer_request(&peer, "PUT", &peer_files_route(&server, &path))
+                    .set("Content-Length", &len.to_string())
+                    .send(file)
+                    .map(|_| len)
+                    .map_err(peer_error)
+            });
+        record_transfer(
+            None,
+            TransferRecord {
+                direction: TransferDirection::PeerPush,
+                source: local_path,
+                destination: format!("{}:{}", server, path),
+                peer: Some(peer.instance.clone()),
+                bytes: *result.as_ref().unwrap_or(&0),
+                duration_ms: started.elapsed().as_millis() as u64,
+                success: result.is_ok(),
+                error: result.as_ref().err().cloned(),
+            },
+        );
+        result.map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Download a file from one of the peer's servers to `destination`
+#[tauri::command]
+async fn pull_file_from_peer(
+    peers: State<'_, PairedPeers>,
+    instance: String,
+    server: String,
+    path: String,
+    destination: String,
+) -> Result<(), String> {
+    let peer = paired_peer(&peers, &instance)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let result = peer_request(&peer, "GET", &peer_files_route(&server, &path))
+            .call()
+            .map_err(peer_error)
+            .and_then(|response| {
+                let mut file = fs::File::create(&destination)
+                    .map_err(|e| format!("{}: {}", destination, e))?;
+                std::io::copy(&mut response.into_reader(), &mut file).map_err(|e| e.to_string())
+            });
+        record_transfer(
+            None,
+            TransferRecord {
+                direction: TransferDirection::PeerPull,
+                source: format!("{}:{}", server, path),
+                destination,
+                peer: Some(peer.instance.clone()),
+                bytes: *result.as_ref().unwrap_or(&0),
+                duration_ms: started.elapsed().as_millis() as u64,
+                success: result.is_ok(),
+                error: result.as_ref().err().cloned(),
+            },
+        );
+        result.map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Recreate one of the peer's servers here, serving `host_path` with a password
+/// of your own
+#[tauri::command]
+async fn replicate_server_from_peer(
+    app: AppHandle,
+    reservations: State<'_, PortReservations>,
+    peers: State<'_, PairedPeers>,
+    instance: String,
+    name: String,
+    host_path: String,
+    password: String,
+) -> Result<CreateResult, String> {
+    let peer = paired_peer(&peers, &instance)?;
+    let offered: Vec<SharedServer> = tauri::async_runtime::spawn_blocking(move || {
+        peer_request(&peer, "GET", "/servers")
+            .call()
+            .map_err(peer_error)?
+            .into_json()
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let shared = offered
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("{} doesn't offer {}", instance, name))?;
+
+    let config = ServerConfig {
+        host_path,
+        ..ServerConfig::from_shared(shared, password)
+    };
+    let result = create_server_internal(&app, &reservations, config, None);
+    record_audit(&name, "replicate-from-peer", result.error.as_deref());
+    Ok(result)
+}
+
+/// Reverse SSH tunnel that publishes a server through a jump host the user
+/// controls, for machines that can't open ports or join a VPN
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct RelayConfig {
+    /// `user@host` of the jump host
+    pub jump_host: String,
+    #[serde(default)]
+    pub ssh_port: Option<u16>,
+    /// Port opened on the jump host. Reaching it from other machines needs
+    /// `GatewayPorts yes` in the jump host's sshd_config
+    pub remote_port: u16,
+    /// Private key for the jump host; ssh's defaults and agent otherwise
+    #[serde(default)]
+    pub identity_file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum RelayState {
+    Connecting,
+    Connected,
+    Down,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct RelayStatus {
+    /// Address clients connect to, e.g. "jump.example.com:2222"
+    pub endpoint: String,
+    pub state: RelayState,
+    /// Why the tunnel is down, usually ssh's own message
+    pub error: Option<String>,
+    /// Bytes from SFTP clients to the server
+    #[ts(type = "number")]
+    pub bytes_in: u64,
+    #[ts(type = "number")]
+    pub bytes_out: u64,
+    /// Both directions, averaged since the previous status read
+    pub bytes_per_sec: f64,
+}
+
+#[derive(Default)]
+struct RelayTraffic {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    closed: AtomicBool,
+}
+
+/// A running relay: ssh forwards the jump host's port to a local proxy, which
+/// counts the bytes on their way to the server
+struct RelayTunnel {
+    config: RelayConfig,
+    ssh: std::process::Child,
+    stderr: Option<std::thread::JoinHandle<Vec<u8>>>,
+    started_at: Instant,
+    error: Option<String>,
+    proxy_port: u16,
+    traffic: Arc<RelayTraffic>,
+    last_sample: (Instant, u64),
+}
+
+static RELAYS: LazyLock<Mutex<HashMap<String, RelayTunnel>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn check_relay_config(config: &RelayConfig) -> Result<(), String> {
+    let host = config.jump_host.rsplit('@').next().unwrap_or_default();
+    if host.is_empty() || config.jump_host.starts_with('-') || config.jump_host.contains(' ') {
+        return Err(format!("Invalid jump host: {}", config.jump_host));
+    }
+    if config.remote_port == 0 {
+        return Err("The relay needs a port on the jump host".to_string());
+    }
+    Ok(())
+}
+
+fn relay_endpoint(config: &RelayConfig) -> String {
+    let host = config.jump_host.rsplit('@').next().unwrap_or_default();
+    format!("{}:{}", host, config.remote_port)
+}
+
+/// Copy one direction of a relayed connection, counting into `counter`
+fn relay_copy(mut from: std::net::TcpStream, mut to: std::net::TcpStream, counter: &AtomicU64) {
+    let mut buf = [0u8; 16 * 1024];
+    while let Ok(n) = from.read(&mut buf) {
+        if n == 0 || to.write_all(&buf[..n]).is_err() {
+            break;
+        }
+        counter.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    to.shutdown(std::net::Shutdown::Write).ok();
+}
+
+fn spawn_relay_proxy(
+    listener: std::net::TcpListener,
+    upstream: String,
+    traffic: Arc<RelayTraffic>,
+) {
+    std::thread::spawn(move || {
+        for client in listener.incoming() {
+            if traffic.closed.load(Ordering::Relaxed) {
+                break;
+            }
+            let Ok(client) = client else {
+                continue;
+            };
+            let Ok(server) = std::net::TcpStream::connect(&upstream) else {
+                continue;
+            };
+            let (Ok(client_read), Ok(server_read)) = (client.try_clone(), server.try_clone())
+            else {
+                continue;
+            };
+            let inbound = Arc::clone(&traffic);
+            std::thread::spawn(move || relay_copy(client_read, server, &inbound.bytes_in));
+            let outbound = Arc::clone(&traffic);
+            std::thread::spawn(move || relay_copy(server_read, client, &outbound.bytes_out));
+        }
+    });
+}
+
+fn start_relay(server: &ServerInfo, config: &RelayConfig) -> Result<RelayTunnel, String> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let proxy_port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let bind_ip = server.bind_ip.clone().unwrap_or_else(default_bind_ip);
+    let upstream = format!("{}:{}", client_host(&bind_ip), server.port);
+    let traffic = Arc::new(RelayTraffic::default());
+
+    let forward = format!("{}:127.0.0.1:{}", config.remote_port, proxy_port);
+    let ssh_port = config.ssh_port.unwrap_or(22).to_string();
+    let mut args = vec![
+        "-N",
+        "-R",
+        forward.as_str(),
+        "-p",
+        ssh_port.as_str(),
+        "-o",
+        "BatchMode=yes",
+        "-o",
+        "ExitOnForwardFailure=yes",
+        "-o",
+        "ServerAliveInterval=15",
+        "-o",
+        "ServerAliveCountMax=3",
+    ];
+    if let Some(identity) = &config.identity_file {
+        args.extend(["-i", identity.as_str()]);
+    }
+    args.push(&config.jump_host);
+    let mut ssh = new_command("ssh")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run ssh: {}", e))?;
+
+    spawn_relay_proxy(listener, upstream, Arc::clone(&traffic));
+    Ok(RelayTunnel {
+        config: config.clone(),
+        stderr: ssh.stderr.take().map(read_to_end_in_background),
+        ssh,
+        started_at: Instant::now(),
+        error: None,
+        proxy_port,
+        traffic,
+        last_sample: (Instant::now(), 0),
+    })
+}
+
+fn stop_relay(mut tunnel: RelayTunnel) {
+    tunnel.ssh.kill().ok();
+    tunnel.ssh.wait().ok();
+    tunnel.traffic.closed.store(true, Ordering::Relaxed);
+    // Wake the proxy's accept so it sees the flag
+    std::net::TcpStream::connect(("127.0.0.1", tunnel.proxy_port)).ok();
+}
+
+impl RelayTunnel {
+    /// Whether ssh has exited, keeping its last words as the error
+    fn exited(&mut self) -> bool {
+        match self.ssh.try_wait() {
+            Ok(None) => false,
+            Ok(Some(status)) => {
+                if self.error.is_none() {
+                    let stderr = self
+                        .stderr
+                        .take()
+                        .and_then(|handle| handle.join().ok())
+                        .unwrap_or_default();
+                    let message = String::from_utf8_lossy(&stderr).trim().to_string();
+                    self.error = Some(if message.is_empty() {
+                        format!("ssh exited with {}", status)
+                    } else {
+                        message
+                    });
+                }
+                true
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                true
+            }
+        }
+    }
+
+    fn status(&mut self) -> RelayStatus {
+        let state = if self.exited() {
+            RelayState::Down
+        } else if self.started_at.elapsed() < RELAY_SETTLE_TIME {
+            RelayState::Connecting
+        } else {
+            RelayState::Connected
+        };
+        let bytes_in = self.traffic.bytes_in.load(Ordering::Relaxed);
+        let bytes_out = self.traffic.bytes_out.load(Ordering::Relaxed);
+        let (sampled_at, sampled_total) = self.last_sample;
+        let elapsed = sampled_at.elapsed().as_secs_f64();
+        let bytes_per_sec = if elapsed > 0.0 {
+            (bytes_in + bytes_out).saturating_sub(sampled_total) as f64 / elapsed
+        } else {
+            0.0
+        };
+        self.last_sample = (Instant::now(), bytes_in + bytes_out);
+        RelayStatus {
+            endpoint: relay_endpoint(&self.config),
+            state,
+            error: self.error.clone(),
+            bytes_in,
+            bytes_out,
+            bytes_per_sec,
+        }
+    }
+}
+
+/// Status of a configured relay; `Down` if this process isn't running it
+fn relay_status(name: &str, config: &RelayConfig) -> RelayStatus {
+    let tunnel = RELAYS
+        .lock()
+        .ok()
+        .and_then(|mut relays| relays.get_mut(name).map(RelayTunnel::status));
+    tunnel.unwrap_or_else(|| RelayStatus {
+        endpoint: relay_endpoint(config),
+        state: RelayState::Down,
+        error: Some("Relay isn't running".to_string()),
+        bytes_in: 0,
+        bytes_out: 0,
+        bytes_per_sec: 0.0,
+    })
+}
+
+/// Start, keep or stop one server's relay to match `wanted`. A dropped tunnel
+/// is restarted once it has had a pause, so a bad jump host isn't hammered.
+fn sync_relay(
+    relays: &mut HashMap<String, RelayTunnel>,
+    server: &ServerInfo,
+    wanted: Option<RelayConfig>,
+) {
+    let current = relays.remove(&server.name);
+    let Some(config) = wanted else {
+        if let Some(tunnel) = current {
+            stop_relay(tunnel);
+        }
+        return;
+    };
+    if let Some(mut tunnel) = current {
+        let retry_due = tunnel.exited() && tunnel.started_at.elapsed() >= RELAY_RETRY_INTERVAL;
+        if tunnel.config == config && !retry_due {
+            relays.insert(server.name.clone(), tunnel);
+            return;
+        }
+        stop_relay(tunnel);
+    }
+    match start_relay(server, &config) {
+        Ok(tunnel) => {
+            record_audit(&server.name, "start-relay", None);
+            relays.insert(server.name.clone(), tunnel);
+        }
+        Err(e) => record_audit(&server.name, "start-relay", Some(&e)),
+    }
+}
+
+/// Bring every relay in line with the config; only running servers get one
+fn relay_tick(servers: &[ServerInfo]) {
+    let creds = load_credentials();
+    let Ok(mut relays) = RELAYS.lock() else {
+        return;
+    };
+    for server in servers {
+        let wanted = creds
+            .get(&server.name)
+            .and_then(|c| c.relay.clone())
+            .filter(|_| server.status == "running");
+        sync_relay(&mut relays, server, wanted);
+    }
+    // Servers that were removed altogether
+    let gone: Vec<String> = relays
+        .keys()
+        .filter(|name| !servers.iter().any(|s| &s.name == *name))
+        .cloned()
+        .collect();
+    for name in gone {
+        if let Some(tunnel) = relays.remove(&name) {
+            stop_relay(tunnel);
+        }
+    }
+}
+
+/// Publish a server through an SSH jump host, or stop with `None`. The tunnel
+/// comes up right away if the server is running and is kept up from then on.
+#[tauri::command]
+fn set_relay(name: String, config: Option<RelayConfig>) -> CommandResult {
+    let result = find_server(&name).and_then(|server| {
+        if let Some(config) = &config {
+            check_relay_config(config)?;
+        }
+        update_credentials(|all| all.get_mut(&name).map(|creds| creds.relay = config.clone()))
+            .ok_or_else(|| format!("{} has no stored settings", name))?;
+        let wanted = config.clone().filter(|_| server.status == "running");
+        let mut relays = RELAYS.lock().map_err(|e| e.to_string())?;
+        sync_relay(&mut relays, &server, wanted);
+        Ok(())
+    });
+    record_audit(
+        &name,
+        "set-relay",
+        result.as_ref().err().map(String::as_str),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Start a stopped server when a client connects to its port, and stop it again
+/// once it has sat without sessions for a while
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct OnDemandConfig {
+    /// Minutes without sessions before the server is stopped again; 0 leaves it running
+    pub idle_minutes: u32,
+}
+
+/// Stop flags of the listeners standing in for stopped on-demand servers
+static ON_DEMAND_LISTENERS: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Close a server's on-demand listener so Docker can publish the port again
+fn release_on_demand(name: &str) {
+    let stop = ON_DEMAND_LISTENERS
+        .lock()
+        .ok()
+        .and_then(|mut listeners| listeners.remove(name));
+    if let Some(stop) = stop {
+        stop.store(true, Ordering::Relaxed);
+        // Let the thread see the flag and drop the socket
+        std::thread::sleep(ON_DEMAND_POLL_INTERVAL * 2);
+    }
+}
+
+/// Shuttle bytes both ways until either side closes
+fn splice(client: std::net::TcpStream, upstream: std::net::TcpStream) {
+    let (Ok(mut client_reader), Ok(mut upstream_writer)) =
+        (client.try_clone(), upstream.try_clone())
+    else {
+        return;
+    };
+    std::thread::spawn(move || {
+        std::io::copy(&mut client_reader, &mut upstream_writer).ok();
+        upstream_writer.shutdown(std::net::Shutdown::Write).ok();
+    });
+    let (mut upstream, mut client) = (upstream, client);
+    std::io::copy(&mut upstream, &mut client).ok();
+    client.shutdown(std::net::Shutdown::Write).ok();
+}
+
+/// Start the container for the client that knocked, then hand its connection over
+fn start_on_demand(name: &str, bind_ip: &str, port: u16, client: std::net::TcpStream) {
+    let result = run_command("docker", &["start", name])
+        .and_then(|_| wait_until_ready(name, Some((bind_ip, port))));
+    record_audit(
+        name,
+        "on-demand-start",
+        result.as_ref().err().map(String::as_str),
+    );
+    if result.is_err() {
+        return;
+    }
+    if let Ok(mut stops) = MANUAL_STOPS.lock() {
+        stops.remove(name);
+    }
+    let upstream = format!("{}:{}", client_host(bind_ip), port)
+        .parse::<std::net::SocketAddr>()
+        .ok()
+        .and_then(|addr| std::net::TcpStream::connect_timeout(&addr, HEALTH_PROBE_TIMEOUT).ok());
+    if let Some(upstream) = upstream {
+        client.set_nonblocking(false).ok();
+        splice(client, upstream);
+    }
+}
+
+/// Listen on a stopped server's published address until a client connects or
+/// the listener is released
+fn listen_on_demand(name: &str, bind_ip: &str, port: u16) -> Result<(), String> {
+    let mut listeners = ON_DEMAND_LISTENERS.lock().map_err(|e| e.to_string())?;
+    if listeners.contains_key(name) {
+        return Ok(());
+    }
+    let listen_ip = if bind_ip.is_empty() {
+        "0.0.0.0"
+    } else {
+        bind_ip
+    };
+    let listener = std::net::TcpListener::bind((listen_ip, port))
+        .map_err(|e| format!("Cannot listen on {}:{}: {}", listen_ip, port, e))?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let stop = Arc::new(AtomicBool::new(false));
+    listeners.insert(name.to_string(), stop.clone());
+
+    let (name, bind_ip) = (name.to_string(), bind_ip.to_string());
+    std::thread::spawn(move || {
+        let client = loop {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            match listener.accept() {
+                Ok((client, _)) => break client,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(ON_DEMAND_POLL_INTERVAL);
+                }
+                Err(_) => return,
+            }
+        };
+        drop(listener);
+        if let Ok(mut listeners) = ON_DEMAND_LISTENERS.lock() {
+            if listeners.get(&name).is_some_and(|s| Arc::ptr_eq(s, &stop)) {
+                listeners.remove(&name);
+            }
+        }
+        start_on_demand(&name, &bind_ip, port, client);
+    });
+    Ok(())
+}
+
+/// Keep a listener on every stopped on-demand server
+fn on_demand_tick(servers: &[ServerInfo]) {
+    let creds = load_credentials();
+    let on_demand: HashSet<&str> = servers
+        .iter()
+        .filter(|s| creds.get(&s.name).is_some_and(|c| c.on_demand.is_some()))
+        .map(|s| s.name.as_str())
+        .collect();
+    let listening: Vec<String> = ON_DEMAND_LISTENERS
+        .lock()
+        .map(|l| l.keys().cloned().collect())
+        .unwrap_or_default();
+    for name in listening {
+        let stopped = servers
+            .iter()
+            .any(|s| s.name == name && s.state == ServerState::Stopped);
+        if !on_demand.contains(name.as_str()) || !stopped {
+            release_on_demand(&name);
+        }
+    }
+
+    // Stopped containers don't show their ports in `docker ps`
+    let mut bindings: Option<Vec<PortAllocation>> = None;
+    for server in servers {
+        if !on_demand.contains(server.name.as_str()) || server.state != ServerState::Stopped {
+            continue;
+        }
+        let bindings = bindings.get_or_insert_with(docker_port_bindings);
+        let Some(binding) = bindings.iter().find(|b| b.server == server.name) else {
+            continue;
+        };
+        // Retried every tick while something else holds the port
+        listen_on_demand(&server.name, &binding.ip, binding.port).ok();
+    }
+}
+
+/// Have a server started by the first connection to its port while it is
+/// stopped, or turn that off with `None`
+#[tauri::command]
+fn set_on_demand(name: String, config: Option<OnDemandConfig>) -> CommandResult {
+    let result = find_server(&name).and_then(|_| {
+        update_credentials(|all| {
+            all.get_mut(&name)
+                .map(|creds| creds.on_demand = config.clone())
+        })
+        .ok_or_else(|| format!("{} has no stored settings", name))
+    });
+    if config.is_none() {
+        release_on_demand(&name);
+    }
+    record_audit(
+        &name,
+        "set-on-demand",
+        result.as_ref().err().map(String::as_str),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// A server stopped for having been idle
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IdleStop {
+    pub server: String,
+    pub idle_minutes: u32,
+    /// The next connection starts it again
+    pub on_demand: bool,
+    pub error: Option<String>,
+}
+
+/// Minutes without sessions after which a server is stopped: the shorter of its
+/// idle policy and its on-demand `idle_minutes`
+fn idle_limit(creds: &StoredCredentials) -> Option<u32> {
+    let on_demand = creds
+        .on_demand
+        .as_ref()
+        .map(|c| c.idle_minutes)
+        .filter(|m| *m > 0);
+    [creds.idle_stop_minutes, on_demand]
+        .into_iter()
+        .flatten()
+        .min()
+}
+
+/// Whether one of dsftp's own uploads or downloads is using the server
+fn has_active_transfer(name: &str) -> bool {
+    let prefix = format!("{}:", name);
+    ACTIVE_TRANSFERS.lock().is_ok_and(|active| {
+        active.values().any(|t| {
+            !t.done && (t.source.starts_with(&prefix) || t.destination.starts_with(&prefix))
+        })
+    })
+}
+
+/// Stop running servers that have had no sessions and no transfers for their idle limit
+fn idle_stop_tick(servers: &[ServerInfo], idle_since: &mut HashMap<String, Instant>) {
+    let creds = load_credentials();
+    idle_since.retain(|name, _| servers.iter().any(|s| &s.name == name));
+    for server in servers {
+        let stored = creds.get(&server.name);
+        let Some(limit) = stored.and_then(idle_limit) else {
+            idle_since.remove(&server.name);
+            continue;
+        };
+        if server.state != ServerState::Running
+            || count_sessions(&server.name) > 0
+            || has_active_transfer(&server.name)
+        {
+            idle_since.remove(&server.name);
+            continue;
+        }
+        let since = *idle_since
+            .entry(server.name.clone())
+            .or_insert_with(Instant::now);
+        if since.elapsed() < Duration::from_secs(u64::from(limit) * 60) {
+            continue;
+        }
+        idle_since.remove(&server.name);
+        if let Ok(mut stops) = MANUAL_STOPS.lock() {
+            stops.insert(server.name.clone());
+        }
+        let result = run_command("docker", &["stop", &server.name]);
+        if result.is_ok() {
+            tunnels::close(&server.name);
+        }
+        let error = result.err();
+        record_audit(&server.name, "idle-stop", error.as_deref());
+        events::publish(events::Event::IdleStopped(IdleStop {
+            server: server.name.clone(),
+            idle_minutes: limit,
+            on_demand: stored.is_some_and(|c| c.on_demand.is_some()),
+            error,
+        }));
+    }
+}
+
+/// Stop a server once it has gone `minutes` without sessions or transfers, or
+/// keep it running with `None`. Start it again by hand or through `set_on_demand`.
+#[tauri::command]
+fn set_idle_policy(name: String, minutes: Option<u32>) -> CommandResult {
+    let result = find_server(&name).and_then(|_| {
+        if minutes == Some(0) {
+            return Err("Idle time must be at least a minute".to_string());
+        }
+        update_credentials(|all| {
+            all.get_mut(&name)
+                .map(|creds| creds.idle_stop_minutes = minutes)
+        })
+        .ok_or_else(|| format!("{} has no stored settings", name))
+    });
+    record_audit(
+        &name,
+        "set-idle-policy",
+        result.as_ref().err().map(String::as_str),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Token for the embedded HTTP API. API tokens act on every server as far as
+/// their role in api-policy.json allows; per-server ingest tokens only write into
+/// their own.
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    /// Only filled in the response that created the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// What api-tokens.json keeps instead of the secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(skip)]
+    pub secret_sha256: Option<String>,
+}
+
+static API_SERVER: LazyLock<Mutex<Option<Arc<tiny_http::Server>>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+fn get_api_tokens_path() -> PathBuf {
+    get_config_dir().join(API_TOKENS_FILE)
+}
+
+fn load_api_tokens() -> Vec<ApiToken> {
+    let _lock = lock_config(false);
+    read_json_file(&get_api_tokens_path())
+}
+
+/// Hex SHA-256 of a bearer token, which is all dsftp keeps of it
+fn token_digest(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Whether `token` hashes to `digest`, compared in constant time
+fn token_matches(token: &str, digest: &str) -> bool {
+    token_digest(token)
+        .as_bytes()
+        .ct_eq(digest.as_bytes())
+        .into()
+}
+
+/// Replace API and ingest tokens stored in the clear by their digests
+fn migrate_token_digests() {
+    update_private_json_file(&get_api_tokens_path(), |all: &mut Vec<ApiToken>| {
+        for token in all.iter_mut() {
+            if let Some(secret) = token.secret.take() {
+                token.secret_sha256 = Some(token_digest(&secret));
+            }
+        }
+    });
+    update_credentials(|all| {
+        for creds in all.values_mut() {
+            if let Some(token) = creds.ingest_token.take() {
+                creds.ingest_token_sha256 = Some(token_digest(&token));
+            }
+        }
+    });
+}
+
+fn bearer_token(request: &tiny_http::Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(str::to_string)
+}
+
+/// `PUT /ingest/<server>/<path>`: write the body to `path` under the server's
+/// host folder. The file appears under its final name only once complete.
+fn handle_ingest(request: &mut tiny_http::Request, route: &str) -> Result<u64, (u16, String)> {
+    let (server, path) = route
+        .split_once('/')
+        .and_then(|(server, path)| Some((percent_decode(server)?, percent_decode(path)?)))
+        .ok_or((400, "Expected /ingest/<server>/<path>".to_string()))?;
+    let token = bearer_token(request);
+    let server_token = load_credentials()
+        .get(&server)
+        .and_then(|c| c.ingest_token_sha256.clone());
+    // The server's own ingest token needs no policy; API tokens go by their role
+    let own = token
+        .as_deref()
+        .zip(server_token)
+        .is_some_and(|(token, digest)| token_matches(token, &digest));
+    if !own {
+        authorize_api(token.as_deref(), &server, "ingest")?;
+    }
+    let dest = host_file_path(&server, &path).map_err(|e| (400, e))?;
+    if let Some(size) = request.body_length() {
+        check_disk_space(&dest.path, size as u64).map_err(|short| (507, short.to_string()))?;
+    }
+    let file_name = dest
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let partial = dest.path.with_file_name(format!(".{}.part", file_name));
+
+    let started = Instant::now();
+    // The rename replaces a symlink at the final name rather than following it
+    let written = dest.create(&partial).and_then(|mut file| {
+        std::io::copy(request.as_reader(), &mut file)
+            .and_then(|size| fs::rename(&partial, &dest.path).map(|_| size))
+            .map_err(|e| e.to_string())
+    });
+    if written.is_err() {
+        fs::remove_file(&partial).ok();
+    }
+    let error = written.as_ref().err().cloned();
+    record_transfer(
+        Some(&server),
+        TransferRecord {
+            direction: TransferDirection::Ingest,
+            source: "HTTP API".to_string(),
+            destination: path,
+            peer: request.remote_addr().map(|a| a.ip().to_string()),
+            bytes: *written.as_ref().unwrap_or(&0),
+            duration_ms: started.elapsed().as_millis() as u64,
+            success: written.is_ok(),
+            error: error.clone(),
+        },
+    );
+    record_audit(&server, "api-ingest", error.as_deref());
+    written.map_err(|e| (500, e))
+}
+
+/// What an API token may do. Each role includes the ones before it. Tokens without
+/// a policy entry may only ingest, as every token could before roles.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ApiRole {
+    ReadOnly,
+    Operator,
+    /// Also whatever command isn't listed in `API_COMMANDS`
+    Admin,
+}
+
+/// The least role each API command needs
+const API_COMMANDS: &[(&str, ApiRole)] = &[
+    ("list_servers", ApiRole::ReadOnly),
+    ("describe_server", ApiRole::ReadOnly),
+    ("ingest", ApiRole::Operator),
+    ("start_server", ApiRole::Operator),
+    ("stop_server", ApiRole::Operator),
+    ("pause_server", ApiRole::Operator),
+    ("resume_server", ApiRole::Operator),
+];
+/// The one command a token without a policy entry may run
+const API_UNMAPPED_COMMAND: &str = "ingest";
+
+fn get_api_policy_path() -> PathBuf {
+    get_config_dir().join(API_POLICY_FILE)
+}
+
+/// Role per API token id
+fn load_api_policy() -> HashMap<String, ApiRole> {
+    let _lock = lock_config(false);
+    read_json_file(&get_api_policy_path())
+}
+
+/// Check the request's API token against the policy for `command`, auditing
+/// refusals against `server` ("" for commands about no one server)
+fn authorize_api(token: Option<&str>, server: &str, command: &str) -> Result<(), (u16, String)> {
+    let required = API_COMMANDS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or(ApiRole::Admin, |(_, role)| *role);
+    let Some(found) = token.and_then(|token| {
+        load_api_tokens().into_iter().find(|t| {
+            t.secret_sha256
+                .as_deref()
+                .is_some_and(|digest| token_matches(token, digest))
+        })
+    }) else {
+        record_audit(
+            server,
+            "api-denied",
+            Some(&format!("{}: invalid token", command)),
+        );
+        return Err((401, "Invalid token".to_string()));
+    };
+    let allowed = match load_api_policy().get(&found.id) {
+        Some(role) => *role >= required,
+        None => command == API_UNMAPPED_COMMAND,
+    };
+    if !allowed {
+        let reason = format!("{}: token {} needs {:?}", command, found.name, required);
+        record_audit(server, "api-denied", Some(&reason));
+        return Err((403, format!("This token may not {}", command)));
+    }
+    Ok(())
+}
+
+fn json_response(value: &impl Serialize) -> tiny_http::ResponseBox {
+    let body = serde_json::to_string(value).unwrap_or_default();
+    let mut response = tiny_http::Response::from_string(body);
+    if let Ok(header) =
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+    {
+        response.add_header(header);
+    }
+    response.boxed()
+}
+
+/// `GET /servers` and `POST /servers/<name>/start|stop|pause|resume`.
+/// Passwords never leave through the API.
+fn handle_servers_route(
+    request: &tiny_http::Request,
+    method: &tiny_http::Method,
+    route: &str,
+) -> Result<tiny_http::ResponseBox, (u16, String)> {
+    let token = bearer_token(request);
+    let (name, action) = match route.split_once('/') {
+        Some((name, action)) => (name, action),
+        None => (route, ""),
+    };
+    let name = percent_decode(name).ok_or((400, "Invalid server name".to_string()))?;
+    let command = match (method, name.is_empty(), action) {
+        (tiny_http::Method::Get, true, "") => "list_servers",
+        (tiny_http::Method::Get, false, "description") => "describe_server",
+        (tiny_http::Method::Post, false, "start") => "start_server",
+        (tiny_http::Method::Post, false, "stop") => "stop_server",
+        (tiny_http::Method::Post, false, "pause") => "pause_server",
+        (tiny_http::Method::Post, false, "resume") => "resume_server",
+        _ => return Err((404, "Not found".to_string())),
+    };
+    authorize_api(token.as_deref(), &name, command)?;
+
+    let result = match command {
+        "list_servers" => {
+            let servers: Vec<serde_json::Value> = list_servers()
+                .into_iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "name": s.name,
+                        "status": s.status,
+                        "state": s.state,
+                        "port": s.port,
+                        "bind_ip": s.bind_ip,
+                    })
+                })
+                .collect();
+            return Ok(json_response(&servers));
+        }
+        "describe_server" => {
+            let server = find_server(&name).map_err(|e| (404, e))?;
+            let text = server_description(&server, &current_locale());
+            return Ok(tiny_http::Response::from_string(text).boxed());
+        }
+        "start_server" => start_server(name),
+        "stop_server" => stop_server(name),
+        "pause_server" => pause_server(name),
+        _ => resume_server(name),
+    };
+    match result.error {
+        None => Ok(json_response(&result)),
+        Some(e) => Err((500, e)),
+    }
+}
+
+#[tauri::command]
+fn get_api_policy() -> HashMap<String, ApiRole> {
+    load_api_policy()
+}
+
+/// Set what the API token `id` may do
+#[tauri::command]
+fn set_api_token_role(id: String, role: ApiRole) -> CommandResult {
+    if !load_api_tokens().iter().any(|t| t.id == id) {
+        return CommandResult {
+            success: false,
+            error: Some(format!("No API token {}", id)),
+        };
+    }
+    update_json_file(
+        &get_api_policy_path(),
+        |all: &mut HashMap<String, ApiRole>| all.insert(id.clone(), role),
+    );
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+fn handle_api_request(mut request: tiny_http::Request) {
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default();
+    let method = request.method().clone();
+    let result = if let (tiny_http::Method::Put, Some(route)) =
+        (&method, path.strip_prefix("/ingest/"))
+    {
+        handle_ingest(&mut request, route).map(|size| {
+            tiny_http::Response::from_string(serde_json::json!({ "size": size }).to_string())
+                .with_status_code(201)
+                .boxed()
+        })
+    } else if let Some(route) = path
+        .strip_prefix("/servers")
+        .and_then(|r| r.strip_prefix('/').or(r.is_empty().then_some("")))
+    {
+        handle_servers_route(&request, &method, route)
+    } else if let Some(server) = path.strip_prefix("/password/") {
+        handle_password_page(&mut request, &method, server)
+    } else if let (tiny_http::Method::Get, Some(token)) = (&method, path.strip_prefix("/share/")) {
+        handle_share_download(token).map(|(link, file)| {
+            let file_name = split_container_path(&link.path).1.replace('"', "");
+            let disposition = format!("attachment; filename=\"{}\"", file_name);
+            let mut response = tiny_http::Response::from_file(file);
+            if let Ok(header) =
+                tiny_http::Header::from_bytes(&b"Content-Disposition"[..], disposition.as_bytes())
+            {
+                response.add_header(header);
+            }
+            response.boxed()
+        })
+    } else {
+        Err((404, "Not found".to_string()))
+    };
+    let response = result.unwrap_or_else(|(status, message)| {
+        tiny_http::Response::from_string(message)
+            .with_status_code(status)
+            .boxed()
+    });
+    request.respond(response).ok();
+}
+
+fn start_api_server(settings: &ApiSettings) -> Result<(), String> {
+    let mut running = API_SERVER.lock().map_err(|e| e.to_string())?;
+    if let Some(old) = running.take() {
+        old.unblock();
+    }
+    let bind_ip = settings.bind_ip.as_deref().unwrap_or("0.0.0.0");
+    let http = tiny_http::Server::http(format!("{}:{}", bind_ip, settings.port))
+        .map(Arc::new)
+        .map_err(|e| format!("Cannot listen on {}:{}: {}", bind_ip, settings.port, e))?;
+    let worker = Arc::clone(&http);
+    std::thread::spawn(move || {
+        // One thread per request so a slow upload doesn't hold up the others
+        for request in worker.incoming_requests() {
+            std::thread::spawn(move || handle_api_request(request));
+        }
+    });
+    *running = Some(http);
+    Ok(())
+}
+
+fn stop_api_server() {
+    if let Some(old) = API_SERVER.lock().ok().and_then(|mut s| s.take()) {
+        old.unblock();
+    }
+}
+
+/// Start the API if it's enabled in the settings
+fn spawn_api_server() {
+    if let Some(settings) = load_app_settings().api {
+        if let Err(e) = start_api_server(&settings) {
+            log::error!("dsftp API not started: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+fn get_api_settings() -> Option<ApiSettings> {
+    load_app_settings().api
+}
+
+/// Enable the HTTP API on the given address, or turn it off with `None`
+#[tauri::command]
+fn set_api_settings(settings: Option<ApiSettings>) -> CommandResult {
+    let result = match &settings {
+        Some(api) => start_api_server(api),
+        None => {
+            stop_api_server();
+            Ok(())
+        }
+    };
+    if result.is_ok() {
+        update_app_settings(|app_settings| app_settings.api = settings);
+    }
+    CommandResult {
+        success: result.is_ok(),
+        error: result.err(),
+    }
+}
+
+/// New API token; its secret is only ever returned here
+#[tauri::command]
+fn create_api_token(name: String) -> Result<ApiToken, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Give the token a name".to_string());
+    }
+    let secret = random_token();
+    let token = ApiToken {
+        id: random_token()[..8].to_string(),
+        name,
+        created_at: chrono::Local::now().to_rfc3339(),
+        secret: None,
+        secret_sha256: Some(token_digest(&secret)),
+    };
+    update_private_json_file(&get_api_tokens_path(), |all: &mut Vec<ApiToken>| {
+        all.push(token.clone())
+    });
+    Ok(ApiToken {
+        secret: Some(secret),
+        secret_sha256: None,
+        ..token
+    })
+}
+
+#[tauri::command]
+fn list_api_tokens() -> Vec<ApiToken> {
+    load_api_tokens()
+        .into_iter()
+        .map(|t| ApiToken {
+            secret: None,
+            secret_sha256: None,
+            ..t
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn revoke_api_token(id: String) -> CommandResult {
+    let removed = update_private_json_file(&get_api_tokens_path(), |all: &mut Vec<ApiToken>| {
+        let before = all.len();
+        all.retain(|t| t.id != id);
+        all.len() != before
+    });
+    update_json_file(
+        &get_api_policy_path(),
+        |all: &mut HashMap<String, ApiRole>| all.remove(&id),
+    );
+    CommandResult {
+        success: removed,
+        error: (!removed).then(|| format!("No API token {}", id)),
+    }
+}
+
+/// Replace the server's ingest token, for `PUT /ingest/<server>/<path>`; the old
+/// one stops working immediately
+#[tauri::command]
+fn rotate_ingest_token(name: String) -> Result<String, String> {
+    let token = random_token();
+    update_credentials(|all| {
+        all.get_mut(&name)
+            .map(|creds| creds.ingest_token_sha256 = Some(token_digest(&token)))
+    })
+    .ok_or_else(|| format!("{} has no stored settings", name))?;
+    record_audit(&name, "rotate-ingest-token", None);
+    Ok(token)
+}
+
+#[tauri::command]
+fn clear_ingest_token(name: String) -> CommandResult {
+    let cleared = update_credentials(|all| {
+        all.get_mut(&name)
+            .map(|creds| creds.ingest_token_sha256 = None)
+    });
+    record_audit(&name, "clear-ingest-token", None);
+    CommandResult {
+        success: cleared.is_some(),
+        error: cleared
+            .is_none()
+            .then(|| format!("{} has no stored settings", name)),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ShareLink {
+    pub token: String,
+    pub server: String,
+    /// Container path of the shared file
+    pub path: String,
+    pub url: String,
+    pub created_at: String,
+    pub expires_at: String,
+    /// `None` allows any number of downloads until the link expires
+    pub max_downloads: Option<u32>,
+    pub downloads: u32,
+}
+
+fn get_share_links_path() -> PathBuf {
+    get_config_dir().join(SHARE_LINKS_FILE)
+}
+
+fn share_link_live(link: &ShareLink) -> bool {
+    let unexpired = chrono::DateTime::parse_from_rfc3339(&link.expires_at)
+        .is_ok_and(|expires| expires > chrono::Local::now());
+    unexpired && link.max_downloads.is_none_or(|max| link.downloads < max)
+}
+
+/// `GET /share/<token>`: the linked file as a download, counted against the
+/// link's limit once it could be opened
+fn handle_share_download(token: &str) -> Result<(ShareLink, fs::File), (u16, String)> {
+    let link = list_share_links(None)
+        .into_iter()
+        .find(|l| l.token == token)
+        .ok_or((404, "This link has expired or doesn't exist.".to_string()))?;
+    let opened = find_server(&link.server)
+        .and_then(|server| {
+            let relative = link
+                .path
+                .strip_prefix(server.container_path.trim_end_matches('/'))
+                .ok_or_else(|| format!("{} is outside the share", link.path))?;
+            host_file_path(&server.name, relative)
+        })
+        .and_then(|dest| dest.open());
+    // Claim the download under the lock so two requests can't both take the last one
+    let claimed = opened.and_then(|file| {
+        update_json_file(&get_share_links_path(), |all: &mut Vec<ShareLink>| {
+            all.retain(share_link_live);
+            let link = all.iter_mut().find(|l| l.token == token)?;
+            link.downloads += 1;
+            Some(link.clone())
+        })
+        .map(|link| (link, file))
+        .ok_or_else(|| "This link has expired or doesn't exist.".to_string())
+    });
+    record_audit(
+        &link.server,
+        "share-link-download",
+        claimed.as_ref().err().map(String::as_str),
+    );
+    claimed.map_err(|e| (404, e))
+}
+
+fn share_link_url(token: &str) -> Option<String> {
+    let api = load_app_settings().api?;
+    let host = match api.bind_ip.as_deref() {
+        Some(ip) if !ip.is_empty() && ip != "0.0.0.0" => ip.to_string(),
+        _ => get_local_ip(),
+    };
+    Some(format!("http://{}:{}/share/{}", host, api.port, token))
+}
+
+/// Hand out one file over the HTTP API through an unguessable link that stops
+/// working after `ttl_minutes` or `max_downloads` downloads
+#[tauri::command]
+fn create_share_link(
+    name: String,
+    path: String,
+    ttl_minutes: u32,
+    max_downloads: Option<u32>,
+) -> Result<ShareLink, String> {
+    let server = find_server(&name)?;
+    let relative = path
+        .strip_prefix(server.container_path.trim_end_matches('/'))
+        .ok_or_else(|| format!("Only files under {} can be shared", server.container_path))?;
+    let file = host_file_path(&name, relative)?.open()?;
+    if !file.metadata().is_ok_and(|m| m.is_file()) {
+        return Err(format!("{} is not a file", path));
+    }
+    if ttl_minutes == 0 || max_downloads == Some(0) {
+        return Err("The link would expire right away".to_string());
+    }
+    let token = random_token();
+    let url = share_link_url(&token)
+        .ok_or_else(|| "Enable the HTTP API in the settings to share links".to_string())?;
+    let now = chrono::Local::now();
+    let link = ShareLink {
+        token,
+        server: name.clone(),
+        path,
+        url,
+        created_at: now.to_rfc3339(),
+        expires_at: (now + chrono::Duration::minutes(ttl_minutes as i64)).to_rfc3339(),
+        max_downloads,
+        downloads: 0,
+    };
+    update_json_file(&get_share_links_path(), |all: &mut Vec<ShareLink>| {
+        all.retain(share_link_live);
+        all.push(link.clone());
+    });
+    record_audit(&name, "create-share-link", None);
+    Ok(link)
+}
+
+/// Links that still work, optionally only one server's
+#[tauri::command]
+fn list_share_links(name: Option<String>) -> Vec<ShareLink> {
+    update_json_file(&get_share_links_path(), |all: &mut Vec<ShareLink>| {
+        all.retain(share_link_live);
+        all.iter()
+            .filter(|l| name.as_ref().is_none_or(|n| &l.server == n))
+            .cloned()
+            .collect()
+    })
+}
+
+#[tauri::command]
+fn revoke_share_link(token: String) -> CommandResult {
+    let removed = update_json_file(&get_share_links_path(), |all: &mut Vec<ShareLink>| {
+        let index = all.iter().position(|l| l.token == token)?;
+        Some(all.remove(index))
+    });
+    match removed {
+        Some(link) => {
+            record_audit(&link.server, "revoke-share-link", None);
+            CommandResult {
+                success: true,
+                error: None,
+            }
+        }
+        None => CommandResult {
+            success: false,
+            error: Some("No such share link".to_string()),
+        },
+    }
+}
+
+/// A temporary read-only server over a frozen copy of a folder, so the original
+/// can keep changing
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SnapshotShare {
+    /// The server serving the copy
+    pub name: String,
+    pub source: String,
+    pub path: String,
+    /// The copy shares blocks with the source (reflink or clonefile) instead of
+    /// duplicating them
+    pub copy_on_write: bool,
+    pub created_at: String,
+    /// The server and its copy are removed after this
+    pub expires_at: Option<String>,
+}
+
+fn get_snapshot_shares_path() -> PathBuf {
+    get_config_dir().join(SNAPSHOT_SHARES_FILE)
+}
+
+/// Plain recursive copy, for filesystems without copy-on-write clones
+fn copy_tree(source: &Path, dest: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            copy_tree(&entry.path(), &target)?;
+        } else if kind.is_file() {
+            fs::copy(entry.path(), &target)?;
+        } else if kind.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy `source`'s contents into `dest`, cloning where the filesystem allows.
+/// Returns whether the copy is copy-on-write.
+fn snapshot_tree(source: &Path, dest: &Path) -> Result<bool, String> {
+    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+    let dest_arg = dest.to_string_lossy().to_string();
+    let cloned = if cfg!(target_os = "macos") {
+        let source_arg = format!("{}/", source.to_string_lossy());
+        run_command("cp", &["-cR", &source_arg, &dest_arg]).is_ok()
+    } else if cfg!(unix) {
+        let source_arg = format!("{}/.", source.to_string_lossy());
+        run_command("cp", &["-a", "--reflink=always", &source_arg, &dest_arg]).is_ok()
+    } else {
+        false
+    };
+    if cloned {
+        return Ok(true);
+    }
+
+    // A failed clone may have left part of the tree behind
+    fs::remove_dir_all(dest).ok();
+    check_disk_space(dest, dir_size(source)).map_err(|e| e.to_string())?;
+    copy_tree(source, dest).map_err(|e| {
+        fs::remove_dir_all(dest).ok();
+        format!("Copy failed: {}", e)
+    })?;
+    Ok(false)
+}
+
+/// Freeze `source` and serve the copy read-only on a new server, removed after
+/// `ttl_minutes` if given
+#[tauri::command]
+async fn create_snapshot_share(
+    app: AppHandle,
+    reservations: State<'_, PortReservations>,
+    source: String,
+    port: Option<u16>,
+    ttl_minutes: Option<u32>,
+    operation_id: Option<String>,
+) -> Result<SnapshotShare, String> {
+    if ttl_minutes == Some(0) {
+        return Err("Expiry must be at least one minute".to_string());
+    }
+    if !Path::new(&source).is_dir() {
+        return Err(format!("Not a folder: {}", source));
+    }
+    let now = chrono::Local::now();
+    let name = format!("snapshot-{}", now.format("%Y%m%d-%H%M%S"));
+    let path = get_data_dir().join(SNAPSHOTS_DIR).join(&name);
+
+    let (copy_source, copy_path) = (PathBuf::from(&source), path.clone());
+    let copy_on_write = tauri::async_runtime::spawn_blocking(move || {
+        run_operation(operation_id, || snapshot_tree(&copy_source, &copy_path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let host_path = path.to_string_lossy().to_string();
+    let config = ServerConfig {
+        name: name.clone(),
+        port: port.unwrap_or(0),
+        host_path: host_path.clone(),
+        container_path: format!("/home/{}/snapshot", ANONYMOUS_USER),
+        username: ANONYMOUS_USER.to_string(),
+        password: generate_password(),
+        restart_policy: RestartPolicy::No,
+        anonymous_read_only: true,
+        ..Default::default()
+    };
+    let result = create_server_internal(&app, &reservations, config, None);
+    record_audit(&name, "create-snapshot-share", result.error.as_deref());
+    if let Some(e) = result.error {
+        fs::remove_dir_all(&path).ok();
+        return Err(e);
+    }
+
+    let share = SnapshotShare {
+        name,
+        source,
+        path: host_path,
+        copy_on_write,
+        created_at: now.to_rfc3339(),
+        expires_at: ttl_minutes
+            .map(|ttl| (now + chrono::Duration::minutes(ttl as i64)).to_rfc3339()),
+    };
+    update_json_file(
+        &get_snapshot_shares_path(),
+        |all: &mut Vec<SnapshotShare>| all.push(share.clone()),
+    );
+    Ok(share)
+}
+
+#[tauri::command]
+fn list_snapshot_shares() -> Vec<SnapshotShare> {
+    let _lock = lock_config(false);
+    read_json_file(&get_snapshot_shares_path())
+}
+
+/// Forget a snapshot share whose server is gone and delete its copy
+fn drop_snapshot_share(name: &str) {
+    let share = update_json_file(
+        &get_snapshot_shares_path(),
+        |all: &mut Vec<SnapshotShare>| {
+            let index = all.iter().position(|s| s.name == name)?;
+            Some(all.remove(index))
+        },
+    );
+    if let Some(share) = share {
+        fs::remove_dir_all(&share.path).ok();
+    }
+}
+
+/// Remove snapshot shares past their expiry
+fn snapshot_share_tick() {
+    let now = chrono::Local::now();
+    let expired: Vec<String> = list_snapshot_shares()
+        .into_iter()
+        .filter(|s| {
+            s.expires_at
+                .as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| t <= now)
+        })
+        .map(|s| s.name)
+        .collect();
+    for name in expired {
+        let result = remove_server(name.clone(), None, None, None);
+        if !result.success && find_server(&name).is_err() {
+            // The server went some other way; the copy still has to go
+            drop_snapshot_share(&name);
+        }
+    }
+}
+
+/// Everything about one server in a single response
+#[tauri::command]
+fn get_server_details(
+    stats: State<'_, StatsCache>,
+    alerts: State<'_, ActiveAlerts>,
+    name: String,
+) -> Result<ServerDetails, String> {
+    let server = find_server(&name)?;
+    let container = inspect_container(&name)?;
+    let stats = stats.0.lock().ok().and_then(|s| s.get(&name).cloned());
+    let alerts = alerts
+        .0
+        .lock()
+        .map(|all| all.values().filter(|a| a.server == name).cloned().collect())
+        .unwrap_or_default();
+    let id = server_id(&name);
+    let recent_events = load_audit_log()
+        .into_iter()
+        .rev()
+        .filter(|e| is_same_server(&e.server, e.server_id.as_deref(), &name, id.as_deref()))
+        .take(DETAIL_RECENT_EVENTS)
+        .collect();
+
+    let excluded_paths = container
+        .mounts
+        .iter()
+        .filter(|m| is_exclude_mask(&m.source))
+        .map(|m| m.destination.clone())
+        .collect();
+
+    Ok(ServerDetails {
+        connection: connection_info(&server),
+        uptime: uptime_summary(&server),
+        excluded_paths,
+        server,
+        container,
+        stats,
+        alerts,
+        recent_events,
+    })
+}
+
+/// Render a timestamp as RFC3339 in the host's local offset
+fn to_local_rfc3339(time: chrono::DateTime<chrono::FixedOffset>) -> String {
+    time.with_timezone(&chrono::Local)
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+/// Rewrite docker's `--timestamps` prefix (RFC3339Nano in UTC) as local RFC3339 with offset
+fn normalize_log_timestamps(logs: &str) -> String {
+    logs.lines()
+        .map(|line| {
+            let Some((stamp, rest)) = line.split_once(' ') else {
+                return line.to_string();
+            };
+            match chrono::DateTime::parse_from_rfc3339(stamp) {
+                Ok(time) => format!("{} {}", to_local_rfc3339(time), rest),
+                Err(_) => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[tauri::command]
+fn get_container_logs(name: String, lines: u32, timestamps: Option<bool>) -> String {
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return "Not an SFTP container".to_string();
+    }
+
+    let lines = lines.to_string();
+    let mut args = vec!["logs", "--tail", lines.as_str()];
+    let timestamps = timestamps.unwrap_or(false);
+    if timestamps {
+        args.push("--timestamps");
+    }
+    args.push(&name);
+
+    match run_command("docker", &args) {
+        Ok(logs) if timestamps => redact_secrets(&normalize_log_timestamps(&logs)),
+        Ok(logs) => redact_secrets(&logs),
+        Err(e) => e,
+    }
+}
+
+/// One container log line kept by `persist_logs`
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PersistedLogLine {
+    /// Local RFC3339 with docker's nanoseconds, which orders lines logged in the
+    /// same second
+    pub timestamp: String,
+    pub stderr: bool,
+    pub line: String,
+}
+
+fn persisted_logs_path(name: &str) -> PathBuf {
+    get_data_dir()
+        .join(CONTAINER_LOGS_DIR)
+        .join(format!("{}.jsonl", name))
+}
+
+fn load_persisted_logs(path: &Path) -> Vec<PersistedLogLine> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
                 .lines()
-                .filter_map(|line| {
-                    let parts: Vec<&str> = line.split('|').collect();
-                    if parts.len() >= 3 {
-                        let name = parts[0].to_string();
-                        let status = if parts[1].contains("Up") {
-                            "running"
-                        } else {
-                            "stopped"
-                        };
-                        let ports_str = parts[2];
-                        let port = extract_port(ports_str);
-                        // Extract bind IP from Docker ports info (e.g., "192.168.1.100:2222->22/tcp")
-                        let docker_bind_ip = extract_bind_ip(ports_str);
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Time of the newest line persisted per server. Held for the whole of a
+/// `persist_logs`, so the watcher and a removal don't copy the same lines twice.
+static LOG_CURSORS: LazyLock<Mutex<HashMap<String, chrono::DateTime<chrono::FixedOffset>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Copy the lines Docker logged since the last call into the server's log file,
+/// then apply the retention settings. Does nothing while persistence is off.
+fn persist_logs(name: &str) -> Result<usize, String> {
+    let Some(retention) = load_app_settings().log_retention else {
+        return Ok(0);
+    };
+    let mut cursors = LOG_CURSORS.lock().map_err(|e| e.to_string())?;
+    let path = persisted_logs_path(name);
+    let cutoff =
+        (chrono::Local::now() - chrono::Duration::days(retention.days as i64)).fixed_offset();
+    let cursor = match cursors.get(name) {
+        Some(cursor) => Some(*cursor),
+        None => load_persisted_logs(&path)
+            .last()
+            .and_then(|l| chrono::DateTime::parse_from_rfc3339(&l.timestamp).ok()),
+    };
+    let since = cursor
+        .filter(|c| *c > cutoff)
+        .unwrap_or(cutoff)
+        .to_rfc3339_opts(chrono::SecondsFormat::Nanos, true);
+
+    let output = run_process("docker", &["logs", "--timestamps", "--since", &since, name])
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(redact_secrets(&String::from_utf8_lossy(&output.stderr)));
+    }
+    let redactor = Redactor::current();
+    let mut lines: Vec<(chrono::DateTime<chrono::FixedOffset>, bool, String)> = Vec::new();
+    for (bytes, stderr) in [(&output.stdout, false), (&output.stderr, true)] {
+        for line in String::from_utf8_lossy(bytes).lines() {
+            let (stamp, text) = line.split_once(' ').unwrap_or((line, ""));
+            let Ok(time) = chrono::DateTime::parse_from_rfc3339(stamp) else {
+                continue;
+            };
+            // --since includes lines logged at the cursor itself
+            if cursor.is_some_and(|c| time <= c) {
+                continue;
+            }
+            lines.push((time, stderr, redactor.apply(text)));
+        }
+    }
+    // docker logs keeps the container's stdout and stderr apart
+    lines.sort_by_key(|(time, _, _)| *time);
+
+    if let Some((newest, _, _)) = lines.last() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| e.to_string())?;
+        for (time, stderr, line) in &lines {
+            let record = PersistedLogLine {
+                timestamp: time
+                    .with_timezone(&chrono::Local)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Nanos, false),
+                stderr: *stderr,
+                line: line.clone(),
+            };
+            if let Ok(json) = serde_json::to_string(&record) {
+                writeln!(file, "{}", json).map_err(|e| e.to_string())?;
+            }
+        }
+        cursors.insert(name.to_string(), *newest);
+    } else if let Some(cursor) = cursor {
+        cursors.insert(name.to_string(), cursor);
+    }
+    prune_persisted_logs(&path, &retention);
+    Ok(lines.len())
+}
+
+/// Drop lines older than the retention, then the oldest of what is left until the
+/// file fits in `max_mb`. A file left empty is removed.
+fn prune_persisted_logs(path: &Path, retention: &LogRetention) {
+    let max_bytes = retention.max_mb * 1024 * 1024;
+    let cutoff = chrono::Local::now() - chrono::Duration::days(retention.days as i64);
+    let expired = |line: &PersistedLogLine| {
+        chrono::DateTime::parse_from_rfc3339(&line.timestamp).map_or(true, |t| t < cutoff)
+    };
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let oldest_expired = fs::File::open(path)
+        .ok()
+        .and_then(|file| BufReader::new(file).lines().next()?.ok())
+        .and_then(|line| serde_json::from_str::<PersistedLogLine>(&line).ok())
+        .is_some_and(|line| expired(&line));
+    if size <= max_bytes && !oldest_expired {
+        return;
+    }
+
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+    let mut kept: Vec<&str> = content
+        .lines()
+        .filter(|line| serde_json::from_str::<PersistedLogLine>(line).is_ok_and(|l| !expired(&l)))
+        .collect();
+    let mut total: u64 = kept.iter().map(|l| l.len() as u64 + 1).sum();
+    let excess = kept
+        .iter()
+        .take_while(|line| {
+            let over = total > max_bytes;
+            total -= line.len() as u64 + 1;
+            over
+        })
+        .count();
+    kept.drain(..excess);
+    if kept.is_empty() {
+        fs::remove_file(path).ok();
+        return;
+    }
+    let tmp_path = path.with_extension("jsonl.tmp");
+    if fs::write(&tmp_path, kept.join("\n") + "\n").is_ok() {
+        fs::rename(&tmp_path, path).ok();
+    }
+}
+
+/// Every `LOG_PERSIST_INTERVAL`, copy running servers' new log lines and prune
+/// the files of every server, removed ones included
+fn log_persist_tick(servers: &[ServerInfo], last_run: &mut Option<Instant>) {
+    if last_run.is_some_and(|at| at.elapsed() < LOG_PERSIST_INTERVAL) {
+        return;
+    }
+    *last_run = Some(Instant::now());
+    let Some(retention) = load_app_settings().log_retention else {
+        return;
+    };
+    for server in servers.iter().filter(|s| s.status == "running") {
+        if let Err(e) = persist_logs(&server.name) {
+            log::warn!("Persisting logs of {} failed: {}", server.name, e.trim());
+        }
+    }
+    if let Ok(entries) = fs::read_dir(get_data_dir().join(CONTAINER_LOGS_DIR)) {
+        for entry in entries.flatten() {
+            if entry.path().extension().is_some_and(|e| e == "jsonl") {
+                prune_persisted_logs(&entry.path(), &retention);
+            }
+        }
+    }
+}
+
+/// Servers with persisted logs, including removed ones
+#[tauri::command]
+fn list_persisted_logs() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(get_data_dir().join(CONTAINER_LOGS_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.strip_suffix(".jsonl").map(str::to_string)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// The newest `limit` persisted lines of a server, oldest first, optionally only
+/// those after `since` (RFC3339). Works for servers that have been removed.
+#[tauri::command]
+fn get_persisted_logs(
+    name: String,
+    since: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<PersistedLogLine>, String> {
+    if !is_valid_container_name(&name) {
+        return Err(format!("Invalid server name: {}", name));
+    }
+    let since = since
+        .map(|s| chrono::DateTime::parse_from_rfc3339(&s).map_err(|e| e.to_string()))
+        .transpose()?;
+    let lines: Vec<PersistedLogLine> = load_persisted_logs(&persisted_logs_path(&name))
+        .into_iter()
+        .filter(|line| {
+            since.is_none_or(|since| {
+                chrono::DateTime::parse_from_rfc3339(&line.timestamp).is_ok_and(|t| t > since)
+            })
+        })
+        .collect();
+    let skip = lines
+        .len()
+        .saturating_sub(limit.unwrap_or(DEFAULT_PERSISTED_LOG_LINES));
+    Ok(lines.into_iter().skip(skip).collect())
+}
+
+#[tauri::command]
+fn get_log_retention() -> Option<LogRetention> {
+    load_app_settings().log_retention
+}
+
+/// Turn log persistence on with `retention`, or off with `None`. Logs already kept
+/// stay until removed by hand.
+#[tauri::command]
+fn set_log_retention(retention: Option<LogRetention>) -> CommandResult {
+    if retention
+        .as_ref()
+        .is_some_and(|r| !(1..=MAX_LOG_RETENTION_DAYS).contains(&r.days) || r.max_mb == 0)
+    {
+        return CommandResult {
+            success: false,
+            error: Some(format!(
+                "Retention must be 1 to {} days and at least 1 MB",
+                MAX_LOG_RETENTION_DAYS
+            )),
+        };
+    }
+    update_app_settings(|settings| settings.log_retention = retention);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+// Lists everything under $1 up to depth $2 using the -printf format in $3.
+// Unreadable subdirectories are ignored rather than failing the whole listing.
+const LIST_FILES_SCRIPT: &str = r#"test -d "$1" || { echo "No such directory: $1" >&2; exit 1; }
+find -H "$1" -mindepth 1 -maxdepth "$2" -printf "$3" 2>/dev/null
+exit 0"#;
+
+// Streaming variant: prints its own pid (NUL-terminated) first, then execs find so that
+// pid can be killed on cancel
+const LIST_FILES_STREAM_SCRIPT: &str = r#"test -d "$1" || { echo "No such directory: $1" >&2; exit 1; }
+printf '%s\0' "$$"
+exec find -H "$1" -mindepth 1 -maxdepth "$2" -printf "$3" 2>/dev/null"#;
+
+// NUL-separated fields per entry: type, target type, size, mtime, mode, uid, gid, owner,
+// group, link target, relative path, full path. NUL is the only byte that can't appear in
+// a file name. find prints the numeric id for %u/%g when there is no matching name.
+const LIST_FILES_FORMAT: &str = "%y\\0%Y\\0%s\\0%T@\\0%M\\0%U\\0%G\\0%u\\0%g\\0%l\\0%P\\0%p\\0";
+const LIST_FILES_FIELDS: usize = 12;
+
+// Prints the number of direct children of each argument, one per line
+// Counts bytes rather than lines so names containing newlines are counted once
+const COUNT_CHILDREN_SCRIPT: &str =
+    r#"for d; do find "$d" -mindepth 1 -maxdepth 1 -printf x 2>/dev/null | wc -c; done"#;
+
+// Size and path of every regular file under $1, NUL-separated
+const LIST_REGULAR_FILES_SCRIPT: &str = r#"test -d "$1" || { echo "No such directory: $1" >&2; exit 1; }
+find "$1" -type f -printf '%s\0%p\0' 2>/dev/null
+exit 0"#;
+
+// Size and path relative to $1 of every regular file under it, NUL-separated
+const SIZE_FILES_SCRIPT: &str = r#"test -d "$1" || { echo "No such directory: $1" >&2; exit 1; }
+find "$1" -type f -printf '%s\0%P\0' 2>/dev/null
+exit 0"#;
+
+// Size, mtime and path of every regular file under $1, NUL-separated; a missing
+// directory simply has nothing to clean
+const CLEANUP_FILES_SCRIPT: &str = r#"test -d "$1" || exit 0
+find "$1" -type f -printf '%s\0%T@\0%p\0' 2>/dev/null
+exit 0"#;
+
+// Size, mtime and path of the regular files directly in $1, NUL-separated
+const INGEST_FILES_SCRIPT: &str = r#"test -d "$1" || exit 0
+find "$1" -mindepth 1 -maxdepth 1 -type f -printf '%s\0%T@\0%p\0' 2>/dev/null
+exit 0"#;
+
+// Moves $1 into directory $2, creating it if needed
+const INGEST_MOVE_SCRIPT: &str = r#"mkdir -p "$2" && mv -- "$1" "$2"/"#;
+
+/// Reads a create-sftp-user spec and an optional public key from stdin; atmoz
+/// authorizes keys queued in ~/.ssh/keys when it creates the user
+const CREATE_USER_SCRIPT: &str = r#"read -r spec
+read -r key
+user="${spec%%:*}"
+if [ -n "$key" ]; then
+    mkdir -p "/home/$user/.ssh/keys"
+    printf '%s\n' "$key" > "/home/$user/.ssh/keys/dsftp.pub"
+fi
+create-sftp-user "$spec" || exit
+# Not in atmoz's users.conf, so the CA trust hook learns about the account here
+echo "$user" >> /etc/ssh/dsftp_users
+if [ -d /etc/ssh/dsftp_principals ] && [ -n "$DSFTP_SERVER_ID" ]; then
+    echo "$user@$DSFTP_SERVER_ID" > "/etc/ssh/dsftp_principals/$user"
+fi"#;
+
+// Seconds since boot, then "pid|title|peer|start ticks" per sshd session monitor.
+// The peer comes from matching the monitor's socket inode against /proc/net/tcp*.
+// Uses /proc rather than ps, which the atmoz image doesn't ship.
+const LIST_SESSIONS_SCRIPT: &str = r#"cat /proc/uptime
+for d in /proc/[0-9]*; do
+    cmd=$(tr '\0' ' ' < "$d/cmdline" 2>/dev/null)
+    case "$cmd" in "sshd: "*"[priv]"*) ;; *) continue ;; esac
+    inode=$(ls -l "$d/fd" 2>/dev/null | sed -n 's/.*socket:\[\([0-9]*\)\].*/\1/p' | head -n 1)
+    peer=$(awk -v i="$inode" '$10 == i { print $3; exit }' /proc/net/tcp /proc/net/tcp6 2>/dev/null)
+    printf '%s|%s|%s|%s\n' "${d#/proc/}" "$cmd" "$peer" "$(cut -d' ' -f22 "$d/stat")"
+done"#;
+
+// The OpenSSH version, then the effective sshd configuration as "keyword value" lines
+const PROBE_SSHD_SCRIPT: &str = r#"ssh -V 2>&1
+sshd -T"#;
+
+// The global effective sshd configuration, then for each login account (uid 1000 and
+// up) a "user <name> <shell>" line, its configuration with Match blocks applied
+// (indented), and owner and mode of every directory from its chroot up to /
+const VERIFY_HARDENING_SCRIPT: &str = r#"sshd -T 2>&1
+awk -F: '$3 >= 1000 && $3 < 65534 { print $1 ":" $6 ":" $7 }' /etc/passwd |
+while IFS=: read -r user home shell; do
+    echo "user $user $shell"
+    settings=$(sshd -T -C "user=$user,host=localhost,addr=127.0.0.1" 2>&1)
+    echo "$settings" | sed 's/^/ /'
+    chroot=$(echo "$settings" | sed -n 's/^chrootdirectory //p' | sed "s|%h|$home|g; s|%u|$user|g")
+    case "$chroot" in
+    /*)
+        dir=$chroot
+        while :; do
+            stat -c 'stat %U %a %n' "$dir" 2>/dev/null || echo "missing $dir"
+            [ "$dir" = / ] && break
+            dir=$(dirname "$dir")
+        done
+        ;;
+    esac
+done"#;
+
+// Prints the SHA-256 of each argument, one per line in argument order (empty when a
+// file can't be read); reading through stdin keeps sha256sum from escaping odd names
+const HASH_FILES_SCRIPT: &str =
+    r#"for f; do h=$(sha256sum 2>/dev/null < "$f"); echo "${h%% *}"; done"#;
+
+/// Fill `entry_count` for directory entries with one extra exec
+fn count_directory_entries(name: &str, entries: &mut [(FileEntry, f64)]) {
+    let dirs: Vec<usize> = (0..entries.len())
+        .filter(|i| entries[*i].0.is_dir)
+        .collect();
+    if dirs.is_empty() {
+        return;
+    }
+
+    let mut args = vec!["exec", name, "sh", "-c", COUNT_CHILDREN_SCRIPT, "sh"];
+    args.extend(dirs.iter().map(|i| entries[*i].0.path.as_str()));
+    let Ok(output) = run_command("docker", &args) else {
+        return;
+    };
+    for (i, count) in dirs.into_iter().zip(output.lines()) {
+        entries[i].0.entry_count = count.trim().parse().ok();
+    }
+}
+
+/// find falls back to the numeric id when a uid/gid has no name
+fn id_name(name: &str, id: &str) -> Option<String> {
+    if name.is_empty() || name == id {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+fn is_hidden_path(relative: &str) -> bool {
+    relative.split('/').any(|part| part.starts_with('.'))
+}
+
+fn compile_globs(patterns: &[String]) -> Result<Vec<glob::Pattern>, String> {
+    patterns
+        .iter()
+        .map(|p| glob::Pattern::new(p).map_err(|e| format!("Invalid pattern '{}': {}", p, e)))
+        .collect()
+}
+
+/// The name filters of `ListFilesOptions`, compiled once per listing
+struct ListingFilter {
+    include_hidden: bool,
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl ListingFilter {
+    fn new(options: &ListFilesOptions) -> Result<Self, String> {
+        Ok(Self {
+            include_hidden: options.include_hidden,
+            include: compile_globs(&options.include)?,
+            exclude: compile_globs(&options.exclude)?,
+        })
+    }
+}
+
+/// Turn one `LIST_FILES_FORMAT` record into an entry and its exact mtime, or `None`
+/// if the filter rejects it
+fn parse_file_record(
+    raw: &[&[u8]],
+    filter: &ListingFilter,
+    locale: &str,
+) -> Option<(FileEntry, f64)> {
+    let lossy_name = std::str::from_utf8(raw[LIST_FILES_FIELDS - 1]).is_err();
+    let record: Vec<String> = raw
+        .iter()
+        .map(|field| String::from_utf8_lossy(field).into_owned())
+        .collect();
+    let [kind, target_kind, size, mtime, mode, uid, gid, owner, group, link, relative, full_path] =
+        record.as_slice()
+    else {
+        return None;
+    };
+
+    if !filter.include_hidden && is_hidden_path(relative) {
+        return None;
+    }
+    let file_name = relative.rsplit('/').next().unwrap_or(relative);
+    if !filter.include.is_empty() && !filter.include.iter().any(|p| p.matches(file_name)) {
+        return None;
+    }
+    if filter.exclude.iter().any(|p| p.matches(file_name)) {
+        return None;
+    }
+
+    let is_symlink = *kind == "l";
+    Some((
+        FileEntry {
+            name: relative.to_string(),
+            path: full_path.to_string(),
+            // find reports the link target's type as %Y; N means it doesn't exist
+            is_dir: *target_kind == "d",
+            size: size.parse().unwrap_or(0),
+            size_display: format_size(size.parse().unwrap_or(0), locale),
+            is_symlink,
+            link_target: if is_symlink {
+                Some(link.to_string())
+            } else {
+                None
+            },
+            is_broken_link: is_symlink && *target_kind == "N",
+            modified: mtime.parse::<f64>().ok().map(|t| t as i64),
+            permissions: mode.to_string(),
+            uid: uid.parse().unwrap_or(0),
+            gid: gid.parse().unwrap_or(0),
+            owner: id_name(owner, uid),
+            group: id_name(group, gid),
+            entry_count: None,
+            lossy_name,
+        },
+        mtime.parse().unwrap_or(0.0),
+    ))
+}
+
+/// Raw listing output by (server, path, depth). Filtering, sorting and paging run
+/// on top of it, so every page and sort order of a directory shares one entry.
+struct CachedListing {
+    fetched: Instant,
+    output: Arc<Vec<u8>>,
+}
+
+type ListingKey = (String, String, String);
+
+fn cached_listing(key: &ListingKey) -> Option<Arc<Vec<u8>>> {
+    let cache = LISTING_CACHE.lock().ok()?;
+    cache
+        .get(key)
+        .filter(|c| c.fetched.elapsed() < LISTING_CACHE_TTL)
+        .map(|c| Arc::clone(&c.output))
+}
+
+fn cache_listing(key: ListingKey, output: Arc<Vec<u8>>) {
+    let Ok(mut cache) = LISTING_CACHE.lock() else {
+        return;
+    };
+    cache.retain(|_, c| c.fetched.elapsed() < LISTING_CACHE_TTL);
+    if cache.len() >= LISTING_CACHE_ENTRIES {
+        let oldest = cache
+            .iter()
+            .min_by_key(|(_, c)| c.fetched)
+            .map(|(k, _)| k.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(
+        key,
+        CachedListing {
+            fetched: Instant::now(),
+            output,
+        },
+    );
+}
+
+/// Forget every cached listing of a server whose files may have changed
+fn invalidate_listings(server: &str) {
+    if let Ok(mut cache) = LISTING_CACHE.lock() {
+        cache.retain(|(name, _, _), _| name != server);
+    }
+}
+
+/// Large directories take a while to list, parse and sort, so it runs off the
+/// main thread
+#[tauri::command]
+async fn list_files(
+    name: String,
+    path: String,
+    options: Option<ListFilesOptions>,
+) -> Result<Vec<FileEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_files_internal(name, path, options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn list_files_internal(
+    name: String,
+    path: String,
+    options: Option<ListFilesOptions>,
+) -> Result<Vec<FileEntry>, String> {
+    // Only allow atmoz/sftp containers
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+
+    check_exec_path(&path)?;
+    let options = options.unwrap_or_default();
+    let depth = options.depth.clamp(1, MAX_LIST_DEPTH).to_string();
+    let path_arg = exec_path_arg(&path);
+    let filter = ListingFilter::new(&options)?;
+    let locale = current_locale();
+
+    let key = (name.clone(), path_arg.clone(), depth.clone());
+    let cached = (!options.refresh).then(|| cached_listing(&key)).flatten();
+    let output = match cached {
+        Some(output) => output,
+        None => {
+            // Use docker exec to list files inside the container. Arguments are passed
+            // positionally, never spliced into the script, so names need no quoting.
+            let output = Arc::new(run_command_bytes(
+                "docker",
+                &[
+                    "exec",
+                    &name,
+                    "sh",
+                    "-c",
+                    LIST_FILES_SCRIPT,
+                    "sh",
+                    &path_arg,
+                    &depth,
+                    LIST_FILES_FORMAT,
+                ],
+            )?);
+            cache_listing(key, Arc::clone(&output));
+            output
+        }
+    };
+
+    // Entries paired with their mtime, which is only needed for sorting
+    let raw_fields: Vec<&[u8]> = output.split(|b| *b == 0).collect();
+    let mut entries: Vec<(FileEntry, f64)> = raw_fields
+        .chunks_exact(LIST_FILES_FIELDS)
+        .filter_map(|raw| parse_file_record(raw, &filter, &locale))
+        .collect();
+
+    // Sort: directories first, then by the requested key
+    entries.sort_by(|(a, a_mtime), (b, b_mtime)| match (a.is_dir, b.is_dir) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => {
+            let ordering = match options.sort_by {
+                FileSortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                FileSortKey::Size => a.size.cmp(&b.size),
+                FileSortKey::Mtime => a_mtime.total_cmp(b_mtime),
+            };
+            if options.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+    });
+
+    record_recent_path(&name, &path);
+
+    // Only count children for the page actually returned
+    let mut page: Vec<(FileEntry, f64)> = entries
+        .into_iter()
+        .skip(options.offset)
+        .take(options.limit.unwrap_or(usize::MAX))
+        .collect();
+    count_directory_entries(&name, &mut page);
+
+    Ok(page.into_iter().map(|(entry, _)| entry).collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ListingSummary {
+    pub listing_id: String,
+    #[ts(type = "number")]
+    pub total_entries: u64,
+    #[ts(type = "number")]
+    pub total_size: u64,
+    /// Stopped early by `cancel_listing`
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct FileListingChunk {
+    pub listing_id: String,
+    pub entries: Vec<FileEntry>,
+    /// Present on the last chunk only
+    pub summary: Option<ListingSummary>,
+}
+
+fn take_listing_cancel(listing_id: &str) -> bool {
+    CANCELLED_LISTINGS
+        .lock()
+        .map(|mut cancelled| cancelled.remove(listing_id))
+        .unwrap_or(false)
+}
+
+fn list_files_streaming_internal(
+    app: &AppHandle,
+    window_label: &str,
+    name: &str,
+    path: &str,
+    options: &ListFilesOptions,
+    listing_id: &str,
+) -> Result<ListingSummary, String> {
+    check_exec_path(path)?;
+    let filter = ListingFilter::new(options)?;
+    let locale = current_locale();
+    let depth = options.depth.clamp(1, MAX_LIST_DEPTH).to_string();
+    let path_arg = exec_path_arg(path);
+
+    let mut child = new_command("docker")
+        .args([
+            "exec",
+            name,
+            "sh",
+            "-c",
+            LIST_FILES_STREAM_SCRIPT,
+            "sh",
+            path_arg.as_str(),
+            depth.as_str(),
+            LIST_FILES_FORMAT,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let Some(stdout) = child.stdout.take() else {
+        child.kill().ok();
+        return Err("Failed to read the listing".to_string());
+    };
+    let mut reader = BufReader::new(stdout);
+
+    let read_field = |reader: &mut BufReader<_>| -> Option<Vec<u8>> {
+        let mut field = Vec::new();
+        match reader.read_until(0, &mut field) {
+            Ok(n) if n > 0 && field.last() == Some(&0) => {
+                field.pop();
+                Some(field)
+            }
+            _ => None,
+        }
+    };
+
+    // The script reports its pid first so a cancel can stop find inside the container;
+    // killing the local docker CLI alone would leave it running
+    let pid = read_field(&mut reader).map(|f| String::from_utf8_lossy(&f).into_owned());
+    let mut summary = ListingSummary {
+        listing_id: listing_id.to_string(),
+        total_entries: 0,
+        total_size: 0,
+        cancelled: false,
+        error: None,
+    };
+    let mut chunk: Vec<FileEntry> = Vec::new();
+    let mut last_emit = Instant::now();
+    // Only the window that asked gets the chunks
+    let emit = |entries: Vec<FileEntry>, summary: Option<ListingSummary>| {
+        let _ = app.emit_to(
+            window_label,
+            "file-listing-chunk",
+            FileListingChunk {
+                listing_id: listing_id.to_string(),
+                entries,
+                summary,
+            },
+        );
+    };
+
+    if pid.is_some() {
+        'records: loop {
+            let mut raw: Vec<Vec<u8>> = Vec::with_capacity(LIST_FILES_FIELDS);
+            while raw.len() < LIST_FILES_FIELDS {
+                match read_field(&mut reader) {
+                    Some(field) => raw.push(field),
+                    None => break 'records,
+                }
+            }
+            let fields: Vec<&[u8]> = raw.iter().map(|f| f.as_slice()).collect();
+            if let Some((entry, _)) = parse_file_record(&fields, &filter, &locale) {
+                summary.total_entries += 1;
+                summary.total_size += entry.size;
+                chunk.push(entry);
+            }
+
+            if chunk.len() >= LISTING_CHUNK_SIZE || last_emit.elapsed() >= LISTING_CHUNK_INTERVAL {
+                if take_listing_cancel(listing_id) {
+                    summary.cancelled = true;
+                    break;
+                }
+                if !chunk.is_empty() {
+                    emit(std::mem::take(&mut chunk), None);
+                }
+                last_emit = Instant::now();
+            }
+        }
+    }
+
+    // A cancel may also arrive after the last chunk went out
+    summary.cancelled = summary.cancelled || take_listing_cancel(listing_id);
+    if summary.cancelled {
+        if let Some(pid) = &pid {
+            run_command("docker", &["exec", name, "kill", pid.as_str()]).ok();
+        }
+        child.kill().ok();
+        chunk.clear();
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if pid.is_none() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        summary.error = Some(if stderr.is_empty() {
+            "Listing failed".to_string()
+        } else {
+            stderr
+        });
+    }
+
+    emit(chunk, Some(summary.clone()));
+    Ok(summary)
+}
+
+/// List a directory as a stream of `file-listing-chunk` events instead of one response,
+/// for folders too large to sort and send at once. Entries arrive in `find` order;
+/// `sort_by`, `offset` and `limit` don't apply. The last chunk carries the summary.
+#[tauri::command]
+async fn list_files_streaming(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    name: String,
+    path: String,
+    options: Option<ListFilesOptions>,
+    listing_id: String,
+) -> Result<ListingSummary, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let options = options.unwrap_or_default();
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Ok(mut channels) = WINDOW_CHANNELS.lock() {
+            channels.listings.insert(listing_id.clone(), label.clone());
+        }
+        let result =
+            list_files_streaming_internal(&app, &label, &name, &path, &options, &listing_id);
+        if let Ok(mut channels) = WINDOW_CHANNELS.lock() {
+            channels.listings.remove(&listing_id);
+        }
+        if result.as_ref().is_ok_and(|s| s.error.is_none()) {
+            record_recent_path(&name, &path);
+        }
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Streams that report to one window and end when it closes
+#[derive(Default)]
+struct WindowChannels {
+    /// Log followers by stream id, with their window's label
+    logs: HashMap<String, (String, std::process::Child)>,
+    /// Streaming listings by listing id, with their window's label
+    listings: HashMap<String, String>,
+}
+
+static WINDOW_CHANNELS: LazyLock<Mutex<WindowChannels>> =
+    LazyLock::new(|| Mutex::new(WindowChannels::default()));
+
+/// Stop everything a window was being sent
+fn close_window_channels(label: &str) {
+    let Ok(mut channels) = WINDOW_CHANNELS.lock() else {
+        return;
+    };
+    let streams: Vec<String> = channels
+        .logs
+        .iter()
+        .filter(|(_, (window, _))| window == label)
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in streams {
+        if let Some((_, mut child)) = channels.logs.remove(&id) {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+    let listings: Vec<String> = channels
+        .listings
+        .iter()
+        .filter(|(_, window)| *window == label)
+        .map(|(id, _)| id.clone())
+        .collect();
+    drop(channels);
+    for id in listings {
+        cancel_listing(id);
+    }
+}
+
+fn server_window_label(name: &str) -> String {
+    // Window labels allow letters, digits and -/:_, container names also '.'
+    format!("server-{}", name.replace('.', "_"))
+}
+
+/// Open a window dedicated to one server, or focus it if it is already open.
+/// The frontend reads the server from the URL fragment (`#server=<name>`).
+#[tauri::command]
+async fn open_server_window(app: AppHandle, name: String) -> Result<String, String> {
+    find_server(&name)?;
+    let label = server_window_label(&name);
+    if let Some(window) = app.get_webview_window(&label) {
+        window.unminimize().ok();
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(label);
+    }
+    let url = format!("index.html#server={}", percent_encode(&name));
+    tauri::WebviewWindowBuilder::new(&app, &label, tauri::WebviewUrl::App(url.into()))
+        .title(format!("{} - SFTP Manager", name))
+        .inner_size(900.0, 640.0)
+        .min_inner_size(640.0, 480.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(label)
+}
+
+/// One line of a followed container log
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct LogStreamLine {
+    pub stream_id: String,
+    /// Empty on the event that says the stream ended
+    pub line: String,
+    pub stderr: bool,
+    pub ended: bool,
+}
+
+fn follow_logs_internal(
+    app: &AppHandle,
+    label: &str,
+    name: &str,
+    stream_id: &str,
+    tail: u32,
+) -> Result<(), String> {
+    if !is_sftp_container(name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let mut child = new_command("docker")
+        .args([
+            "logs",
+            "--follow",
+            "--timestamps",
+            "--tail",
+            &tail.to_string(),
+            name,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    // docker logs keeps the container's stdout and stderr apart
+    let readers: [(Box<dyn Read + Send>, bool); 2] = [
+        (Box::new(child.stdout.take().ok_or("No log output")?), false),
+        (Box::new(child.stderr.take().ok_or("No log output")?), true),
+    ];
+    {
+        let mut channels = WINDOW_CHANNELS.lock().map_err(|e| e.to_string())?;
+        let replaced = channels
+            .logs
+            .insert(stream_id.to_string(), (label.to_string(), child));
+        if let Some((_, mut previous)) = replaced {
+            previous.kill().ok();
+            previous.wait().ok();
+        }
+    }
+
+    let redactor = Redactor::current();
+    for (reader, stderr) in readers {
+        let (app, label, stream_id) = (app.clone(), label.to_string(), stream_id.to_string());
+        let redactor = redactor.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                let line = LogStreamLine {
+                    stream_id: stream_id.clone(),
+                    line: redactor.apply(&normalize_log_timestamps(&line)),
+                    stderr,
+                    ended: false,
+                };
+                if app
+                    .emit_to(label.as_str(), "log-stream-line", line)
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            // stdout closing means docker logs exited
+            if !stderr {
+                stop_log_stream(stream_id.clone());
+                let _ = app.emit_to(
+                    label.as_str(),
+                    "log-stream-line",
+                    LogStreamLine {
+                        stream_id,
+                        line: String::new(),
+                        stderr,
+                        ended: true,
+                    },
+                );
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Follow a server's log, sending `log-stream-line` events to the calling window
+/// only. The stream stops with `stop_log_stream` or when that window closes.
+#[tauri::command]
+fn follow_logs(
+    app: AppHandle,
+    window: tauri::WebviewWindow,
+    name: String,
+    stream_id: String,
+    tail: Option<u32>,
+) -> CommandResult {
+    match follow_logs_internal(&app, window.label(), &name, &stream_id, tail.unwrap_or(100)) {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// End a log stream started with `follow_logs`; unknown ids are ignored
+#[tauri::command]
+fn stop_log_stream(stream_id: String) {
+    let child = WINDOW_CHANNELS
+        .lock()
+        .ok()
+        .and_then(|mut channels| channels.logs.remove(&stream_id));
+    if let Some((_, mut child)) = child {
+        child.kill().ok();
+        child.wait().ok();
+    }
+}
+
+/// Stop a streaming listing, e.g. when the user navigates away
+#[tauri::command]
+fn cancel_listing(listing_id: String) {
+    if let Ok(mut cancelled) = CANCELLED_LISTINGS.lock() {
+        cancelled.insert(listing_id);
+    }
+}
+
+/// Stop a long-running operation started with `operation_id`, killing the command it
+/// is waiting on. Unknown or finished ids are ignored.
+#[tauri::command]
+fn cancel_operation(operation_id: String) {
+    if let Ok(mut cancelled) = CANCELLED_OPERATIONS.lock() {
+        cancelled.insert(operation_id);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    /// Size of each copy
+    #[ts(type = "number")]
+    pub size: u64,
+    pub paths: Vec<String>,
+    /// Bytes freed by keeping a single copy
+    #[ts(type = "number")]
+    pub reclaimable: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateReport {
+    /// Largest savings first
+    pub groups: Vec<DuplicateGroup>,
+    #[ts(type = "number")]
+    pub files_scanned: u64,
+    /// Files that shared a size with another and had to be hashed
+    #[ts(type = "number")]
+    pub files_hashed: u64,
+    #[ts(type = "number")]
+    pub reclaimable_bytes: u64,
+}
+
+/// Find identical files under `root`. Only files sharing a size are hashed, so
+/// shares full of unique files cost one `find` and little else.
+#[tauri::command]
+async fn find_duplicates(
+    name: String,
+    root: String,
+    operation_id: Option<String>,
+) -> Result<DuplicateReport, CommandError> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string().into());
+    }
+    check_exec_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        run_operation(operation_id, || find_duplicates_internal(&name, &root))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn find_duplicates_internal(name: &str, root: &str) -> Result<DuplicateReport, String> {
+    let root_arg = exec_path_arg(root);
+    let output = run_command_bytes(
+        "docker",
+        &[
+            "exec",
+            name,
+            "sh",
+            "-c",
+            LIST_REGULAR_FILES_SCRIPT,
+            "sh",
+            &root_arg,
+        ],
+    )?;
+
+    let mut by_size: HashMap<u64, Vec<String>> = HashMap::new();
+    let mut files_scanned = 0;
+    let fields: Vec<&[u8]> = output.split(|b| *b == 0).collect();
+    for pair in fields.chunks_exact(2) {
+        files_scanned += 1;
+        // Names that aren't valid UTF-8 can't be passed back to the container
+        let (Ok(size), Ok(path)) = (
+            String::from_utf8_lossy(pair[0]).parse::<u64>(),
+            std::str::from_utf8(pair[1]),
+        ) else {
+            continue;
+        };
+        // Empty files are all "identical" but free nothing
+        if size > 0 {
+            by_size.entry(size).or_default().push(path.to_string());
+        }
+    }
+
+    let candidates: Vec<(u64, String)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .flat_map(|(size, paths)| paths.into_iter().map(move |p| (size, p)))
+        .collect();
+
+    let mut by_hash: HashMap<(u64, String), Vec<String>> = HashMap::new();
+    for batch in candidates.chunks(HASH_BATCH_SIZE) {
+        let mut args = vec!["exec", name, "sh", "-c", HASH_FILES_SCRIPT, "sh"];
+        args.extend(batch.iter().map(|(_, path)| path.as_str()));
+        let output = run_command("docker", &args)?;
+        for ((size, path), hash) in batch.iter().zip(output.lines()) {
+            // Unreadable files print an empty line
+            let hash = hash.trim();
+            if !hash.is_empty() {
+                by_hash
+                    .entry((*size, hash.to_string()))
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|((size, hash), mut paths)| {
+            paths.sort();
+            DuplicateGroup {
+                reclaimable: size * (paths.len() as u64 - 1),
+                hash,
+                size,
+                paths,
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| b.reclaimable.cmp(&a.reclaimable));
+
+    Ok(DuplicateReport {
+        reclaimable_bytes: groups.iter().map(|g| g.reclaimable).sum(),
+        files_scanned,
+        files_hashed: candidates.len() as u64,
+        groups,
+    })
+}
+
+/// One directory in the storage treemap; sizes include everything beneath it
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StorageNode {
+    pub name: String,
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    #[ts(type = "number")]
+    pub file_count: u64,
+    /// Largest first; empty below the requested depth
+    pub children: Vec<StorageNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LargeFile {
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StorageAnalysis {
+    pub tree: StorageNode,
+    /// Largest first
+    pub largest_files: Vec<LargeFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct StorageAnalysisProgress {
+    pub server: String,
+    pub root: String,
+    #[ts(type = "number")]
+    pub files_scanned: u64,
+    #[ts(type = "number")]
+    pub bytes_scanned: u64,
+    pub done: bool,
+}
+
+#[derive(Default)]
+struct DirSizes {
+    size: u64,
+    file_count: u64,
+    children: HashMap<String, DirSizes>,
+}
+
+impl DirSizes {
+    /// Count a file against this directory and each ancestor down to `depth` levels
+    fn add(&mut self, dirs: &[&str], size: u64, depth: u32) {
+        self.size += size;
+        self.file_count += 1;
+        if let (Some(first), true) = (dirs.first(), depth > 0) {
+            self.children
+                .entry(first.to_string())
+                .or_default()
+                .add(&dirs[1..], size, depth - 1);
+        }
+    }
+
+    fn into_node(self, name: String, path: String) -> StorageNode {
+        let mut children: Vec<StorageNode> = self
+            .children
+            .into_iter()
+            .map(|(child, sizes)| {
+                let child_path = join_container_path(&path, &child);
+                sizes.into_node(child, child_path)
+            })
+            .collect();
+        children.sort_by(|a, b| b.size.cmp(&a.size));
+        StorageNode {
+            name,
+            path,
+            size: self.size,
+            file_count: self.file_count,
+            children,
+        }
+    }
+}
+
+/// Per-directory totals to `depth` levels under `root` plus the `top_n` largest files,
+/// with `storage-analysis-progress` events while the scan runs
+#[tauri::command]
+async fn analyze_storage(
+    app: AppHandle,
+    name: String,
+    root: String,
+    depth: Option<u32>,
+    top_n: Option<usize>,
+) -> Result<StorageAnalysis, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    check_exec_path(&root)?;
+    let depth = depth.unwrap_or(3).clamp(1, MAX_LIST_DEPTH);
+    let top_n = top_n.unwrap_or(DEFAULT_LARGEST_FILES);
+    tauri::async_runtime::spawn_blocking(move || {
+        analyze_storage_internal(&app, &name, &root, depth, top_n)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn analyze_storage_internal(
+    app: &AppHandle,
+    name: &str,
+    root: &str,
+    depth: u32,
+    top_n: usize,
+) -> Result<StorageAnalysis, String> {
+    let root_arg = exec_path_arg(root);
+    let mut child = new_command("docker")
+        .args([
+            "exec",
+            name,
+            "sh",
+            "-c",
+            SIZE_FILES_SCRIPT,
+            "sh",
+            root_arg.as_str(),
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let mut tree = DirSizes::default();
+    // Min-heap of the largest files seen so far
+    let mut largest: BinaryHeap<Reverse<(u64, String)>> = BinaryHeap::new();
+    let mut progress = StorageAnalysisProgress {
+        server: name.to_string(),
+        root: root.to_string(),
+        files_scanned: 0,
+        bytes_scanned: 0,
+        done: false,
+    };
+    let mut last_emit = Instant::now();
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut fields = BufReader::new(stdout).split(0).map_while(Result::ok);
+        while let (Some(size), Some(relative)) = (fields.next(), fields.next()) {
+            let size: u64 = String::from_utf8_lossy(&size).parse().unwrap_or(0);
+            let relative = String::from_utf8_lossy(&relative).into_owned();
+            let mut dirs: Vec<&str> = relative.split('/').collect();
+            dirs.pop();
+            tree.add(&dirs, size, depth);
+
+            largest.push(Reverse((size, relative.clone())));
+            if largest.len() > top_n {
+                largest.pop();
+            }
+
+            progress.files_scanned += 1;
+            progress.bytes_scanned += size;
+            if last_emit.elapsed() >= TRANSFER_PROGRESS_INTERVAL {
+                let _ = app.emit("storage-analysis-progress", progress.clone());
+                last_emit = Instant::now();
+            }
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    progress.done = true;
+    let _ = app.emit("storage-analysis-progress", progress);
+
+    let root_name = split_container_path(root).1;
+    Ok(StorageAnalysis {
+        tree: tree.into_node(root_name, root.to_string()),
+        largest_files: largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, relative))| LargeFile {
+                path: join_container_path(root, &relative),
+                size,
+            })
+            .collect(),
+    })
+}
+
+/// Split a container path into (parent dir, final component)
+fn split_container_path(path: &str) -> (String, String) {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some(("", base)) => ("/".to_string(), base.to_string()),
+        Some((parent, base)) => (parent.to_string(), base.to_string()),
+        None => (".".to_string(), trimmed.to_string()),
+    }
+}
+
+fn join_container_path(dir: &str, name: &str) -> String {
+    if dir == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir.trim_end_matches('/'), name)
+    }
+}
+
+fn container_path_exists(name: &str, path: &str) -> bool {
+    run_command("docker", &["exec", name, "test", "-e", path]).is_ok()
+}
+
+/// First free "name (n).ext" variant of `path` inside the container
+fn unique_container_path(name: &str, path: &str) -> String {
+    let (dir, base) = split_container_path(path);
+    let (stem, ext) = match base.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (base.clone(), String::new()),
+    };
+    (1..)
+        .map(|n| join_container_path(&dir, &format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !container_path_exists(name, candidate))
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Apparent size in bytes of a path inside the container, used as the progress total
+fn container_path_size(name: &str, path: &str) -> u64 {
+    run_command("docker", &["exec", name, "du", "-sb", "--", path])
+        .ok()
+        .and_then(|out| out.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0)
+}
+
+fn container_mtime(name: &str, path: &str) -> Option<i64> {
+    run_command("docker", &["exec", name, "stat", "-c", "%Y", "--", path])
+        .ok()
+        .and_then(|out| out.trim().parse().ok())
+}
+
+fn local_mtime(meta: &fs::Metadata) -> Option<i64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// Decide what to do with a destination that already exists
+fn resolve_conflict(
+    server: &str,
+    target: &str,
+    policy: ConflictPolicy,
+    source_mtime: Option<i64>,
+    dest_mtime: Option<i64>,
+) -> Result<ConflictAction, String> {
+    match policy {
+        ConflictPolicy::Fail => Err(format!("{}:{} already exists", server, target)),
+        ConflictPolicy::Skip => Ok(ConflictAction::Skip),
+        ConflictPolicy::Overwrite => Ok(ConflictAction::Write {
+            path: target.to_string(),
+            overwrite: true,
+        }),
+        ConflictPolicy::Rename => Ok(ConflictAction::Write {
+            path: unique_container_path(server, target),
+            overwrite: false,
+        }),
+        // Without both timestamps we can't prove the source is newer, so keep what's there
+        ConflictPolicy::NewerWins => match (source_mtime, dest_mtime) {
+            (Some(src), Some(dst)) if src > dst => Ok(ConflictAction::Write {
+                path: target.to_string(),
+                overwrite: true,
+            }),
+            _ => Ok(ConflictAction::Skip),
+        },
+    }
+}
+
+struct UploadItem {
+    local_path: PathBuf,
+    remote_path: String,
+    size: u64,
+    modified: Option<i64>,
+}
+
+#[derive(Default)]
+struct UploadPlan {
+    /// Remote directories to create
+    dirs: Vec<String>,
+    items: Vec<UploadItem>,
+    /// Local symlinks left out because links aren't being followed
+    skipped_links: Vec<String>,
+}
+
+/// Expand local files/directories into the remote directories to create and files to copy
+fn collect_upload_items(
+    local_paths: &[String],
+    remote_dir: &str,
+    follow_symlinks: bool,
+) -> Result<UploadPlan, String> {
+    fn walk(
+        local: &Path,
+        remote: String,
+        follow_symlinks: bool,
+        plan: &mut UploadPlan,
+    ) -> Result<(), String> {
+        let link_meta =
+            fs::symlink_metadata(local).map_err(|e| format!("{}: {}", local.display(), e))?;
+        if link_meta.file_type().is_symlink() && !follow_symlinks {
+            plan.skipped_links.push(local.display().to_string());
+            return Ok(());
+        }
+        let meta = fs::metadata(local).map_err(|e| format!("{}: {}", local.display(), e))?;
+        if meta.is_dir() {
+            plan.dirs.push(remote.clone());
+            let entries = fs::read_dir(local).map_err(|e| format!("{}: {}", local.display(), e))?;
+            for entry in entries.filter_map(Result::ok) {
+                let child = entry.file_name().to_string_lossy().to_string();
+                walk(
+                    &entry.path(),
+                    join_container_path(&remote, &child),
+                    follow_symlinks,
+                    plan,
+                )?;
+            }
+        } else if meta.is_file() {
+            plan.items.push(UploadItem {
+                local_path: local.to_path_buf(),
+                remote_path: remote,
+                size: meta.len(),
+                modified: local_mtime(&meta),
+            });
+        }
+        Ok(())
+    }
+
+    let mut plan = UploadPlan::default();
+    for local in local_paths {
+        let local = Path::new(local);
+        let Some(file_name) = local.file_name() else {
+            return Err(format!("Invalid local path: {}", local.display()));
+        };
+        let remote = join_container_path(remote_dir, &file_name.to_string_lossy());
+        walk(local, remote, follow_symlinks, &mut plan)?;
+    }
+    Ok(plan)
+}
+
+// Prints "size|mtime|path\0" for every argument that exists
+const STAT_EXISTING_SCRIPT: &str =
+    r#"for p; do [ -e "$p" ] && stat --printf '%s|%Y|%n\0' -- "$p"; done; exit 0"#;
+
+/// Size and mtime of each path that already exists in the container
+fn stat_existing(name: &str, paths: &[&str]) -> Result<HashMap<String, (u64, i64)>, String> {
+    let mut existing = HashMap::new();
+    // Keep each exec's argument list comfortably below ARG_MAX
+    for chunk in paths.chunks(500) {
+        let mut args = vec!["exec", name, "sh", "-c", STAT_EXISTING_SCRIPT, "sh"];
+        args.extend_from_slice(chunk);
+        let output = run_command("docker", &args)?;
+        for record in output.split('\0').filter(|r| !r.is_empty()) {
+            let mut parts = record.splitn(3, '|');
+            if let (Some(size), Some(mtime), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            {
+                existing.insert(
+                    path.to_string(),
+                    (size.parse().unwrap_or(0), mtime.parse().unwrap_or(0)),
+                );
+            }
+        }
+    }
+    Ok(existing)
+}
+
+// Writes stdin to $1 and hands it to the owner of the containing directory
+const RECEIVE_FILE_SCRIPT: &str = r#"set -e
+cat > "$1"
+chown --reference="$(dirname -- "$1")" -- "$1" 2>/dev/null || true
+if [ -n "$2" ]; then chmod "$2" -- "$1"; fi
+"#;
+
+fn upload_file(
+    name: &str,
+    local: &Path,
+    remote: &str,
+    file_mode: Option<&str>,
+) -> Result<u64, String> {
+    let mut file = fs::File::open(local).map_err(|e| format!("{}: {}", local.display(), e))?;
+    let mut child = new_command("docker")
+        .args([
+            "exec",
+            "-i",
+            name,
+            "sh",
+            "-c",
+            RECEIVE_FILE_SCRIPT,
+            "sh",
+            remote,
+            file_mode.unwrap_or(""),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let copied = match child.stdin.take() {
+        Some(mut stdin) => std::io::copy(&mut file, &mut stdin).map_err(|e| e.to_string()),
+        None => Err("Failed to open docker exec stdin".to_string()),
+    };
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    copied
+}
+
+fn upload_files_internal<R: Runtime>(
+    app: &AppHandle<R>,
+    transfer_id: &str,
+    name: &str,
+    local_paths: &[String],
+    remote_dir: &str,
+    policy: ConflictPolicy,
+    follow_symlinks: bool,
+) -> Result<UploadResult, String> {
+    if !is_sftp_container(name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let remote_dir = &confine_container_path(name, remote_dir, true)?;
+    let UploadPlan {
+        dirs,
+        items,
+        skipped_links,
+    } = collect_upload_items(local_paths, remote_dir, follow_symlinks)?;
+    let remote_paths: Vec<&str> = items.iter().map(|i| i.remote_path.as_str()).collect();
+    let existing = stat_existing(name, &remote_paths)?;
+
+    let creds = load_credentials().remove(name);
+    let file_mode = creds.as_ref().and_then(|c| c.file_mode.as_deref());
+    let dir_mode = creds.as_ref().and_then(|c| c.dir_mode.as_deref());
+
+    let total_bytes: u64 = items.iter().map(|i| i.size).sum();
+    let share = creds.as_ref().map_or("", |c| c.host_path.as_str());
+    if !share.is_empty() {
+        if let Err(short) = check_disk_space(Path::new(share), total_bytes) {
+            return Ok(UploadResult {
+                transfer_id: transfer_id.to_string(),
+                errors: vec![short.to_string()],
+                insufficient_space: Some(short),
+                ..Default::default()
+            });
+        }
+    }
+
+    if !dirs.is_empty() {
+        let mut args = vec!["exec", name, "mkdir", "-p", "--"];
+        args.extend(dirs.iter().map(|d| d.as_str()));
+        run_command("docker", &args)?;
+        if let Some(mode) = dir_mode {
+            let mut args = vec!["exec", name, "chmod", mode, "--"];
+            args.extend(dirs.iter().map(|d| d.as_str()));
+            run_command("docker", &args)?;
+        }
+    }
+
+    let mut tracker = TransferTracker::new(
+        transfer_id,
+        String::new(),
+        format!("{}:{}", name, remote_dir),
+        total_bytes,
+        items.len(),
+    );
+    let mut result = UploadResult {
+        transfer_id: transfer_id.to_string(),
+        skipped: skipped_links,
+        ..Default::default()
+    };
+
+    for item in &items {
+        let target = match existing.get(&item.remote_path) {
+            None => item.remote_path.clone(),
+            Some((_, remote_mtime)) => {
+                match resolve_conflict(
+                    name,
+                    &item.remote_path,
+                    policy,
+                    item.modified,
+                    Some(*remote_mtime),
+                ) {
+                    Ok(ConflictAction::Write { path, .. }) => path,
+                    Ok(ConflictAction::Skip) => {
+                        result.skipped.push(item.remote_path.clone());
+                        continue;
+                    }
+                    Err(e) => {
+                        result.errors.push(e);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let started = Instant::now();
+        let uploaded = upload_file(name, &item.local_path, &target, file_mode);
+        record_transfer(
+            Some(name),
+            TransferRecord {
+                direction: TransferDirection::Upload,
+                source: item.local_path.display().to_string(),
+                destination: target.clone(),
+                peer: None,
+                bytes: *uploaded.as_ref().unwrap_or(&0),
+                duration_ms: started.elapsed().as_millis() as u64,
+                success: uploaded.is_ok(),
+                error: uploaded.as_ref().err().cloned(),
+            },
+        );
+        match uploaded {
+            Ok(bytes) => {
+                result.bytes_transferred += bytes;
+                result.uploaded.push(target);
+            }
+            Err(e) => result
+                .errors
+                .push(format!("{}: {}", item.local_path.display(), e)),
+        }
+
+        tracker.progress.source = item.local_path.display().to_string();
+        tracker.progress.files_done += 1;
+        tracker.report(app, result.bytes_transferred);
+    }
+
+    result.success = result.errors.is_empty();
+    tracker.progress.source = String::new();
+    tracker.finish(
+        app,
+        result.bytes_transferred,
+        result.errors.first().cloned(),
+    );
+    Ok(result)
+}
+
+/// Report which files of a planned upload already exist on the server
+#[tauri::command]
+async fn preflight_upload(
+    name: String,
+    local_paths: Vec<String>,
+    remote_dir: String,
+    follow_symlinks: Option<bool>,
+) -> Result<Vec<UploadConflict>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        preflight_upload_internal(name, local_paths, remote_dir, follow_symlinks)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn preflight_upload_internal(
+    name: String,
+    local_paths: Vec<String>,
+    remote_dir: String,
+    follow_symlinks: Option<bool>,
+) -> Result<Vec<UploadConflict>, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let remote_dir = confine_container_path(&name, &remote_dir, true)?;
+    let items =
+        collect_upload_items(&local_paths, &remote_dir, follow_symlinks.unwrap_or(false))?.items;
+    let remote_paths: Vec<&str> = items.iter().map(|i| i.remote_path.as_str()).collect();
+    let existing = stat_existing(&name, &remote_paths)?;
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let (remote_size, remote_mtime) = existing.get(&item.remote_path)?;
+            Some(UploadConflict {
+                local_path: item.local_path.display().to_string(),
+                remote_path: item.remote_path.clone(),
+                local_size: item.size,
+                remote_size: *remote_size,
+                local_modified: item.modified,
+                remote_modified: Some(*remote_mtime),
+            })
+        })
+        .collect())
+}
+
+#[tauri::command]
+async fn upload_files(
+    app: AppHandle,
+    name: String,
+    local_paths: Vec<String>,
+    remote_dir: String,
+    policy: Option<ConflictPolicy>,
+    follow_symlinks: Option<bool>,
+) -> UploadResult {
+    let transfer_id = new_transfer_id();
+    let id = transfer_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = upload_files_internal(
+            &app,
+            &id,
+            &name,
+            &local_paths,
+            &remote_dir,
+            policy.unwrap_or_default(),
+            follow_symlinks.unwrap_or(false),
+        );
+        let error = match &result {
+            Ok(r) => r.errors.first().cloned(),
+            Err(e) => Some(e.clone()),
+        };
+        record_audit(&name, "upload", error.as_deref());
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    result.unwrap_or_else(|e| UploadResult {
+        transfer_id,
+        errors: vec![e],
+        ..Default::default()
+    })
+}
+
+/// The clipboard as a file: an image as PNG, otherwise text
+fn clipboard_file<R: Runtime>(app: &AppHandle<R>) -> Result<(Vec<u8>, &'static str), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    if let Ok(image) = app.clipboard().read_image() {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(image.rgba()))
+            .map_err(|e| format!("Failed to encode the clipboard image: {}", e))?;
+        return Ok((png, "png"));
+    }
+    match app.clipboard().read_text() {
+        Ok(text) if !text.is_empty() => Ok((text.into_bytes(), "txt")),
+        _ => Err("The clipboard holds no image or text".to_string()),
+    }
+}
+
+fn upload_clipboard_internal(
+    app: &AppHandle,
+    name: &str,
+    remote_dir: &str,
+) -> Result<String, String> {
+    if !is_sftp_container(name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let remote_dir = confine_container_path(name, remote_dir, true)?;
+    let (data, extension) = clipboard_file(app)?;
+    let file_name = format!(
+        "clipboard-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        extension
+    );
+    let mut target = join_container_path(&remote_dir, &file_name);
+    if container_path_exists(name, &target) {
+        target = unique_container_path(name, &target);
+    }
+
+    let local = std::env::temp_dir().join(format!("dsftp-{}", file_name));
+    fs::write(&local, &data).map_err(|e| e.to_string())?;
+    let file_mode = load_credentials().remove(name).and_then(|c| c.file_mode);
+    let started = Instant::now();
+    let result = upload_file(name, &local, &target, file_mode.as_deref());
+    fs::remove_file(&local).ok();
+    record_transfer(
+        Some(name),
+        TransferRecord {
+            direction: TransferDirection::Upload,
+            source: "clipboard".to_string(),
+            destination: target.clone(),
+            peer: None,
+            bytes: *result.as_ref().unwrap_or(&0),
+            duration_ms: started.elapsed().as_millis() as u64,
+            success: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+        },
+    );
+    result.map(|_| target)
+}
+
+/// Write the clipboard (an image as PNG, or text) into `remote_dir` on the server
+/// and return the path of the new file
+#[tauri::command]
+async fn upload_clipboard(
+    app: AppHandle,
+    name: String,
+    remote_dir: String,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = upload_clipboard_internal(&app, &name, &remote_dir);
+        record_audit(&name, "upload", result.as_ref().err().map(|e| e.as_str()));
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn eta_secs(done: u64, total: u64, bytes_per_sec: f64) -> Option<u64> {
+    (bytes_per_sec > 0.0 && total >= done)
+        .then(|| ((total - done) as f64 / bytes_per_sec).ceil() as u64)
+}
+
+/// Progress of one running transfer: emits `transfer-progress` and keeps
+/// `get_active_transfers` current until it is dropped
+struct TransferTracker {
+    progress: TransferProgress,
+    sampled_at: Instant,
+    sampled_bytes: u64,
+}
+
+impl TransferTracker {
+    fn new(
+        transfer_id: &str,
+        source: String,
+        destination: String,
+        total_bytes: u64,
+        files_total: usize,
+    ) -> Self {
+        TransferTracker {
+            progress: TransferProgress {
+                transfer_id: transfer_id.to_string(),
+                source,
+                destination,
+                bytes_transferred: 0,
+                total_bytes,
+                files_done: 0,
+                files_total,
+                bytes_per_sec: 0.0,
+                eta_secs: None,
+                started_at: chrono::Local::now().to_rfc3339(),
+                done: false,
+                error: None,
+            },
+            sampled_at: Instant::now(),
+            sampled_bytes: 0,
+        }
+    }
 
-                        // Get stored credentials for this server
-                        let (username, password, host_path, container_path, stored_bind_ip) =
-                            if let Some(creds) = stored_creds.get(&name) {
-                                (
-                                    creds.username.clone(),
-                                    creds.password.clone(),
-                                    creds.host_path.clone(),
-                                    creds.container_path.clone(),
-                                    creds.bind_ip.clone(),
-                                )
-                            } else {
-                                (String::new(), String::new(), String::new(), String::new(), None)
-                            };
+    fn report<R: Runtime>(&mut self, app: &AppHandle<R>, bytes: u64) {
+        let elapsed = self.sampled_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 && bytes >= self.sampled_bytes {
+            let rate = (bytes - self.sampled_bytes) as f64 / elapsed;
+            let average = &mut self.progress.bytes_per_sec;
+            *average = if *average == 0.0 {
+                rate
+            } else {
+                THROUGHPUT_SMOOTHING * rate + (1.0 - THROUGHPUT_SMOOTHING) * *average
+            };
+            self.sampled_at = Instant::now();
+            self.sampled_bytes = bytes;
+        }
+        self.progress.bytes_transferred = bytes;
+        self.progress.eta_secs = eta_secs(
+            bytes,
+            self.progress.total_bytes,
+            self.progress.bytes_per_sec,
+        );
+        if let Ok(mut active) = ACTIVE_TRANSFERS.lock() {
+            active.insert(self.progress.transfer_id.clone(), self.progress.clone());
+        }
+        let _ = app.emit("transfer-progress", self.progress.clone());
+    }
+
+    fn finish<R: Runtime>(mut self, app: &AppHandle<R>, bytes: u64, error: Option<String>) {
+        self.progress.done = true;
+        self.progress.error = error;
+        self.report(app, bytes);
+    }
+}
+
+impl Drop for TransferTracker {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE_TRANSFERS.lock() {
+            active.remove(&self.progress.transfer_id);
+        }
+    }
+}
+
+/// Transfers still running, with their combined progress
+#[tauri::command]
+fn get_active_transfers() -> ActiveTransfers {
+    let mut transfers: Vec<TransferProgress> = ACTIVE_TRANSFERS
+        .lock()
+        .map(|active| active.values().cloned().collect())
+        .unwrap_or_default();
+    transfers.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    let bytes_transferred = transfers.iter().map(|t| t.bytes_transferred).sum();
+    let total_bytes = transfers.iter().map(|t| t.total_bytes).sum();
+    let bytes_per_sec = transfers.iter().map(|t| t.bytes_per_sec).sum();
+    ActiveTransfers {
+        eta_secs: eta_secs(bytes_transferred, total_bytes, bytes_per_sec),
+        transfers,
+        bytes_transferred,
+        total_bytes,
+        bytes_per_sec,
+    }
+}
+
+fn new_transfer_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("tx-{:x}", nanos)
+}
+
+// Extracts the incoming tar into a scratch dir next to the destination, then moves
+// the single entry into place. Args: $1 parent dir, $2 entry name, $3 final path, $4 overwrite
+const RECEIVE_TAR_SCRIPT: &str = r#"set -e
+tmp=$(mktemp -d "$1/.dsftp-transfer.XXXXXX")
+trap 'rm -rf "$tmp"' EXIT
+tar -C "$tmp" -xf -
+if [ "$4" = 1 ]; then rm -rf -- "$3"; fi
+mv -- "$tmp/$2" "$3"
+"#;
+
+fn transfer_between_servers_internal(
+    app: &AppHandle,
+    transfer_id: &str,
+    src_server: &str,
+    src_path: &str,
+    dst_server: &str,
+    dst_path: &str,
+    on_conflict: ConflictPolicy,
+    follow_symlinks: bool,
+) -> Result<TransferResult, String> {
+    for server in [src_server, dst_server] {
+        if !is_sftp_container(server) {
+            return Err(format!("'{}' is not an SFTP container", server));
+        }
+        if get_container_status(server.to_string()) != "running" {
+            return Err(format!("Server '{}' is not running", server));
+        }
+    }
+    let src_path = &confine_container_path(src_server, src_path, follow_symlinks)?;
+    if !container_path_exists(src_server, src_path) {
+        return Err(format!("{}:{} does not exist", src_server, src_path));
+    }
+
+    let (src_parent, src_base) = split_container_path(src_path);
+    // A trailing slash means "into this directory"
+    let mut target = if dst_path.ends_with('/') {
+        join_container_path(dst_path, &src_base)
+    } else {
+        dst_path.to_string()
+    };
+    target = confine_container_path(dst_server, &target, false)?;
+
+    let mut overwrite = false;
+    if container_path_exists(dst_server, &target) {
+        let action = resolve_conflict(
+            dst_server,
+            &target,
+            on_conflict,
+            container_mtime(src_server, src_path),
+            container_mtime(dst_server, &target),
+        )?;
+        match action {
+            ConflictAction::Skip => {
+                return Ok(TransferResult {
+                    success: true,
+                    transfer_id: transfer_id.to_string(),
+                    destination_path: Some(target),
+                    bytes_transferred: 0,
+                    skipped: true,
+                    error: None,
+                });
+            }
+            ConflictAction::Write { path, overwrite: o } => {
+                target = path;
+                overwrite = o;
+            }
+        }
+    }
+    let (dst_parent, _) = split_container_path(&target);
+
+    let source = format!("{}:{}", src_server, src_path);
+    let destination = format!("{}:{}", dst_server, target);
+    let total_bytes = container_path_size(src_server, src_path);
+
+    // tar archives links as links unless asked to dereference them (-h)
+    let mut tar_args = vec![
+        "exec",
+        src_server,
+        "tar",
+        "-C",
+        src_parent.as_str(),
+        "-cf",
+        "-",
+    ];
+    if follow_symlinks {
+        tar_args.push("-h");
+    }
+    tar_args.extend(["--", src_base.as_str()]);
+    let mut sender = new_command("docker")
+        .args(&tar_args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut receiver = new_command("docker")
+        .args([
+            "exec",
+            "-i",
+            dst_server,
+            "sh",
+            "-c",
+            RECEIVE_TAR_SCRIPT,
+            "sh",
+            dst_parent.as_str(),
+            src_base.as_str(),
+            target.as_str(),
+            if overwrite { "1" } else { "0" },
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let mut tracker = TransferTracker::new(transfer_id, source, destination, total_bytes, 1);
+
+    // Both tars may warn at length; drained on their own threads so a full stderr
+    // pipe can't stall the stream
+    let sender_stderr = sender.stderr.take().map(read_to_end_in_background);
+    let receiver_stderr = receiver.stderr.take().map(read_to_end_in_background);
+
+    // Pump the tar stream through the host without touching the disk
+    let mut bytes: u64 = 0;
+    let mut pump_error = None;
+    if let (Some(mut reader), Some(mut writer)) = (sender.stdout.take(), receiver.stdin.take()) {
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut last_emit = Instant::now();
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) => {
+                    pump_error = Some(e.to_string());
+                    break;
+                }
+            };
+            if let Err(e) = writer.write_all(&buf[..n]) {
+                pump_error = Some(e.to_string());
+                break;
+            }
+            bytes += n as u64;
+            if last_emit.elapsed() >= TRANSFER_PROGRESS_INTERVAL {
+                tracker.report(app, bytes);
+                last_emit = Instant::now();
+            }
+        }
+        // Dropping the writer closes the receiver's stdin so tar sees EOF
+    }
+
+    let sent = sender.wait().map_err(|e| e.to_string())?;
+    let received = receiver.wait().map_err(|e| e.to_string())?;
+    let stderr = |handle: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        let output = handle.and_then(|h| h.join().ok()).unwrap_or_default();
+        String::from_utf8_lossy(&output).trim().to_string()
+    };
+    let (sender_stderr, receiver_stderr) = (stderr(sender_stderr), stderr(receiver_stderr));
+
+    let error = if !sent.success() {
+        Some(sender_stderr)
+    } else if !received.success() {
+        Some(receiver_stderr)
+    } else {
+        pump_error
+    };
+    tracker.progress.files_done = 1;
+    tracker.finish(app, bytes, error.clone());
+
+    Ok(TransferResult {
+        success: error.is_none(),
+        transfer_id: transfer_id.to_string(),
+        destination_path: Some(target),
+        bytes_transferred: bytes,
+        skipped: false,
+        error,
+    })
+}
+
+#[tauri::command]
+async fn transfer_between_servers(
+    app: AppHandle,
+    src_server: String,
+    src_path: String,
+    dst_server: String,
+    dst_path: String,
+    on_conflict: Option<ConflictPolicy>,
+    follow_symlinks: Option<bool>,
+) -> TransferResult {
+    let transfer_id = new_transfer_id();
+    let id = transfer_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let result = transfer_between_servers_internal(
+            &app,
+            &id,
+            &src_server,
+            &src_path,
+            &dst_server,
+            &dst_path,
+            on_conflict.unwrap_or_default(),
+            follow_symlinks.unwrap_or(false),
+        );
+        let error = match &result {
+            Ok(r) => r.error.clone(),
+            Err(e) => Some(e.clone()),
+        };
+        if !result.as_ref().is_ok_and(|r| r.skipped) {
+            let (bytes, destination) = match &result {
+                Ok(r) => (r.bytes_transferred, r.destination_path.clone()),
+                Err(_) => (0, None),
+            };
+            record_transfer(
+                Some(&dst_server),
+                TransferRecord {
+                    direction: TransferDirection::ServerToServer,
+                    source: format!("{}:{}", src_server, src_path),
+                    destination: format!(
+                        "{}:{}",
+                        dst_server,
+                        destination.as_deref().unwrap_or(&dst_path)
+                    ),
+                    peer: Some(src_server.clone()),
+                    bytes,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                    success: error.is_none(),
+                    error: error.clone(),
+                },
+            );
+        }
+        record_audit(&dst_server, "transfer", error.as_deref());
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    result.unwrap_or_else(|e| TransferResult {
+        success: false,
+        transfer_id,
+        destination_path: None,
+        bytes_transferred: 0,
+        skipped: false,
+        error: Some(e),
+    })
+}
+
+/// Directory a server's file operations are confined to
+fn share_root(name: &str) -> String {
+    load_credentials()
+        .get(name)
+        .map(|c| c.container_path.clone())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| "/home".to_string())
+}
+
+/// Canonicalize `path` inside the container and refuse anything that resolves outside the
+/// server's share root, so a symlink can't redirect a write to e.g. /etc. With
+/// `follow_final` false the last component is kept as-is (a link is operated on, not its target).
+fn confine_container_path(name: &str, path: &str, follow_final: bool) -> Result<String, String> {
+    let (to_resolve, last) = if follow_final {
+        (path.to_string(), None)
+    } else {
+        let (parent, base) = split_container_path(path);
+        (parent, Some(base))
+    };
+
+    check_exec_path(path)?;
+    let root = share_root(name);
+    let output = run_command(
+        "docker",
+        &[
+            "exec",
+            name,
+            "realpath",
+            "-m",
+            "-z",
+            "--",
+            &root,
+            &to_resolve,
+        ],
+    )?;
+    let mut lines = output.split('\0');
+    let (Some(root), Some(resolved)) = (lines.next(), lines.next()) else {
+        return Err(format!("Failed to resolve {}", path));
+    };
+
+    let resolved = match last {
+        Some(base) if base != ".." => join_container_path(resolved, &base),
+        Some(_) => return Err(format!("{} escapes the share root {}", path, root)),
+        None => resolved.to_string(),
+    };
+
+    let inside = resolved == root
+        || root == "/"
+        || resolved.starts_with(&format!("{}/", root.trim_end_matches('/')));
+    if inside {
+        Ok(resolved)
+    } else {
+        Err(format!("{} resolves outside the share root {}", path, root))
+    }
+}
+
+/// Resolve the source and destination of a move or copy within one server. A
+/// trailing slash on `dst` means "into this directory", as for transfers.
+fn plan_local_move(name: &str, src: &str, dst: &str) -> Result<(String, String), String> {
+    if !is_sftp_container(name) {
+        return Err(format!("'{}' is not an SFTP container", name));
+    }
+    if get_container_status(name.to_string()) != "running" {
+        return Err(format!("Server '{}' is not running", name));
+    }
+    // Links are moved or copied as links, never followed out of the share
+    let src = confine_container_path(name, src, false)?;
+    if !container_path_exists(name, &src) {
+        return Err(format!("{}:{} does not exist", name, src));
+    }
+    let target = if dst.ends_with('/') {
+        join_container_path(dst, &split_container_path(&src).1)
+    } else {
+        dst.to_string()
+    };
+    let target = confine_container_path(name, &target, false)?;
+    if target == src || target.starts_with(&format!("{}/", src)) {
+        return Err(format!("Can't put {} inside itself", src));
+    }
+    if container_path_exists(name, &target) {
+        return Err(format!("{}:{} already exists", name, target));
+    }
+    Ok((src, target))
+}
+
+/// Move or rename a file or directory inside the server, without the data
+/// leaving the container
+#[tauri::command]
+async fn move_path(name: String, src: String, dst: String) -> CommandResult {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = plan_local_move(&name, &src, &dst).and_then(|(src, target)| {
+            run_command("docker", &["exec", &name, "mv", "-T", "--", &src, &target])
+        });
+        record_audit(&name, "move", result.as_ref().err().map(|e| e.as_str()));
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Copy a file, or a directory with `recursive`, inside the server. Ownership,
+/// modes and timestamps are kept.
+#[tauri::command]
+async fn copy_path(name: String, src: String, dst: String, recursive: bool) -> CommandResult {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = plan_local_move(&name, &src, &dst).and_then(|(src, target)| {
+            // -a copies trees; -pP a single file, keeping a link a link
+            let flags = if recursive { "-aT" } else { "-pPT" };
+            run_command("docker", &["exec", &name, "cp", flags, "--", &src, &target])
+        });
+        record_audit(&name, "copy", result.as_ref().err().map(|e| e.as_str()));
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+// Prints the SHA-256 of $1, then its content, both from one snapshot so they match.
+// Refuses anything but a regular file of at most $2 bytes.
+const READ_TEXT_SCRIPT: &str = r#"set -e
+if [ ! -f "$1" ]; then echo "Not a regular file: $1" >&2; exit 1; fi
+if [ "$(stat -c %s -- "$1")" -gt "$2" ]; then echo "Too large to edit: $1" >&2; exit 1; fi
+tmp=$(mktemp)
+trap 'rm -f "$tmp"' EXIT
+cat -- "$1" > "$tmp"
+h=$(sha256sum < "$tmp")
+echo "${h%% *}"
+cat "$tmp"
+"#;
+
+// Replaces $1 with stdin unless its SHA-256 is no longer $2 (empty: the file must not
+// exist yet), then prints the new hash. Existing files keep their owner and mode; new
+// ones go to the directory's owner, with mode $3 when set. Exits 3 on a conflict.
+const WRITE_TEXT_SCRIPT: &str = r#"set -e
+dir=$(dirname -- "$1")
+tmp=$(mktemp "$dir/.dsftp-edit.XXXXXX")
+trap 'rm -f "$tmp"' EXIT
+cat > "$tmp"
+if [ -e "$1" ]; then
+    h=$(sha256sum < "$1")
+    if [ "${h%% *}" != "$2" ]; then echo "$1 changed since it was opened" >&2; exit 3; fi
+    chown --reference="$1" -- "$tmp"
+    chmod --reference="$1" -- "$tmp"
+elif [ -n "$2" ]; then
+    echo "$1 was deleted since it was opened" >&2; exit 3
+else
+    chown --reference="$dir" -- "$tmp" 2>/dev/null || true
+    chmod "${3:-0644}" -- "$tmp"
+fi
+mv -f -- "$tmp" "$1"
+h=$(sha256sum < "$1")
+echo "${h%% *}"
+"#;
+
+/// A text file opened for editing; pass `hash` back to `write_file_text`
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TextFile {
+    pub path: String,
+    pub content: String,
+    /// SHA-256 of the content as read
+    pub hash: String,
+}
+
+/// Read a small text file from the share for the editor
+#[tauri::command]
+async fn read_file_text(name: String, path: String) -> Result<TextFile, String> {
+    tauri::async_runtime::spawn_blocking(move || read_file_text_internal(name, path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn read_file_text_internal(name: String, path: String) -> Result<TextFile, String> {
+    if !is_sftp_container(&name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    let path = confine_container_path(&name, &path, true)?;
+    let output = run_command_bytes(
+        "docker",
+        &[
+            "exec",
+            &name,
+            "sh",
+            "-c",
+            READ_TEXT_SCRIPT,
+            "sh",
+            &path,
+            &MAX_TEXT_EDIT_BYTES.to_string(),
+        ],
+    )?;
+    let split = output
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| format!("Failed to read {}", path))?;
+    let hash = String::from_utf8_lossy(&output[..split]).to_string();
+    let content = &output[split + 1..];
+    if content.contains(&0) {
+        return Err(format!("{} is not a text file", path));
+    }
+    let content =
+        String::from_utf8(content.to_vec()).map_err(|_| format!("{} is not UTF-8 text", path))?;
+    Ok(TextFile {
+        path,
+        content,
+        hash,
+    })
+}
+
+/// Save an edited text file, refusing if it changed since it was read.
+/// `expected_hash` is the `hash` from `read_file_text`, or `None` to create a new
+/// file. Returns the new hash, so the editor can keep saving.
+#[tauri::command]
+fn write_file_text(
+    name: String,
+    path: String,
+    content: String,
+    expected_hash: Option<String>,
+) -> Result<String, String> {
+    let result = write_file_text_internal(&name, &path, &content, expected_hash.as_deref());
+    record_audit(&name, "edit", result.as_ref().err().map(|e| e.as_str()));
+    result
+}
+
+fn write_file_text_internal(
+    name: &str,
+    path: &str,
+    content: &str,
+    expected_hash: Option<&str>,
+) -> Result<String, String> {
+    if !is_sftp_container(name) {
+        return Err("Not an SFTP container".to_string());
+    }
+    if content.len() as u64 > MAX_TEXT_EDIT_BYTES {
+        return Err(format!(
+            "Files over {} bytes can't be edited here",
+            MAX_TEXT_EDIT_BYTES
+        ));
+    }
+    let path = confine_container_path(name, path, true)?;
+    let file_mode = CREDENTIALS
+        .load()
+        .remove(name)
+        .and_then(|c| c.file_mode)
+        .unwrap_or_default();
+    let mut child = new_command("docker")
+        .args([
+            "exec",
+            "-i",
+            name,
+            "sh",
+            "-c",
+            WRITE_TEXT_SCRIPT,
+            "sh",
+            &path,
+            expected_hash.unwrap_or(""),
+            &file_mode,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let written = match child.stdin.take() {
+        Some(mut stdin) => stdin
+            .write_all(content.as_bytes())
+            .map_err(|e| e.to_string()),
+        None => Err("Failed to open docker exec stdin".to_string()),
+    };
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    written?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[tauri::command]
+fn add_bookmark(name: String, path: String, label: Option<String>) -> CommandResult {
+    let path = path.trim().to_string();
+    if path.is_empty() {
+        return CommandResult {
+            success: false,
+            error: Some("Bookmark path is empty".to_string()),
+        };
+    }
+    let label = label
+        .filter(|l| !l.trim().is_empty())
+        .unwrap_or_else(|| {
+            path.trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .filter(|l| !l.is_empty())
+                .unwrap_or("/")
+                .to_string()
+        });
+
+    update_bookmarks(|all| {
+        let bookmarks = &mut all.entry(name).or_default().bookmarks;
+        match bookmarks.iter_mut().find(|b| b.path == path) {
+            Some(existing) => existing.label = label,
+            None => bookmarks.push(Bookmark { label, path }),
+        }
+    });
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+fn remove_bookmark(name: String, path: String) -> CommandResult {
+    update_bookmarks(|all| {
+        if let Some(entry) = all.get_mut(&name) {
+            entry.bookmarks.retain(|b| b.path != path);
+        }
+    });
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+fn list_bookmarks(name: String) -> ServerBookmarks {
+    load_bookmarks().remove(&name).unwrap_or_default()
+}
+
+/// Scopes are dot-separated names whose first segment namespaces them, e.g.
+/// "window.main" or "browser.<server>"
+fn check_ui_scope(scope: &str) -> Result<(), String> {
+    let valid = scope.len() <= MAX_UI_SCOPE_LEN
+        && scope.split('.').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_:".contains(c))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid UI state scope: {}", scope))
+    }
+}
+
+/// What the frontend stored under `scope`, if anything
+#[tauri::command]
+fn get_ui_state(scope: String) -> Option<serde_json::Value> {
+    load_ui_state().remove(&scope)
+}
+
+/// Remember `value` under `scope` across restarts, replacing what was there;
+/// `null` forgets it
+#[tauri::command]
+fn set_ui_state(scope: String, value: serde_json::Value) -> CommandResult {
+    let result = check_ui_scope(&scope).and_then(|()| {
+        let size = serde_json::to_string(&value).map_or(0, |v| v.len());
+        if size > MAX_UI_STATE_BYTES {
+            return Err(format!(
+                "UI state for {} is {} bytes; the limit is {}",
+                scope, size, MAX_UI_STATE_BYTES
+            ));
+        }
+        update_json_file(
+            &get_ui_state_path(),
+            |all: &mut HashMap<String, serde_json::Value>| {
+                if value.is_null() {
+                    all.remove(&scope);
+                    return Ok(());
+                }
+                let others: usize = all
+                    .iter()
+                    .filter(|(other, _)| **other != scope)
+                    .map(|(other, v)| other.len() + serde_json::to_string(v).map_or(0, |v| v.len()))
+                    .sum();
+                if others + scope.len() + size > MAX_UI_STATE_TOTAL_BYTES {
+                    return Err("The UI state store is full; clear some of it first".to_string());
+                }
+                all.insert(scope.clone(), value);
+                Ok(())
+            },
+        )
+    });
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Forget every scope in `namespace` ("window" clears "window.main" and the
+/// rest), e.g. to reset the layout
+#[tauri::command]
+fn clear_ui_state(namespace: String) -> CommandResult {
+    let prefix = format!("{}.", namespace);
+    update_json_file(
+        &get_ui_state_path(),
+        |all: &mut HashMap<String, serde_json::Value>| {
+            all.retain(|scope, _| *scope != namespace && !scope.starts_with(&prefix));
+        },
+    );
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+fn find_server(name: &str) -> Result<ServerInfo, String> {
+    list_servers()
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Unknown server: {}", name))
+}
+
+/// One sentence on a server's state, address, clients and last transfer, worded for
+/// screen readers and plain-text places alike. Korean or English by the locale.
+fn server_description(server: &ServerInfo, locale: &str) -> String {
+    let korean = locale_language(locale) == "ko";
+    // Other languages get an English sentence, so no foreign units inside it
+    let duration_locale = if korean { locale } else { "en" };
+    let name = &server.name;
+    let address = format!(
+        "{}:{}",
+        client_host(server.bind_ip.as_deref().unwrap_or("")),
+        server.port
+    );
+    let clients = if server.state == ServerState::Running {
+        count_sessions(name)
+    } else {
+        0
+    };
+    let in_maintenance = server.maintenance.is_some();
+
+    let state = match (&server.state, korean) {
+        (ServerState::Running, false) => format!(
+            "{} is {} at {} with {}",
+            name,
+            if in_maintenance {
+                "in maintenance"
+            } else {
+                "running"
+            },
+            address,
+            match clients {
+                0 => "no clients connected".to_string(),
+                1 => "1 client connected".to_string(),
+                n => format!("{} clients connected", n),
+            }
+        ),
+        (ServerState::Running, true) => format!(
+            "{} 서버는 {}에서 {} 중이고 접속한 클라이언트는 {}",
+            name,
+            address,
+            if in_maintenance { "점검" } else { "실행" },
+            match clients {
+                0 => "없으며".to_string(),
+                n => format!("{}명이며", n),
+            }
+        ),
+        (ServerState::Starting, false) => format!("{} is starting at {}", name, address),
+        (ServerState::Starting, true) => {
+            format!("{} 서버는 {}에서 시작하는 중이며", name, address)
+        }
+        (ServerState::Unhealthy { reason }, false) => {
+            format!("{} is running but not healthy ({})", name, reason)
+        }
+        (ServerState::Unhealthy { reason }, true) => {
+            format!("{} 서버는 실행 중이지만 정상이 아니며({})", name, reason)
+        }
+        (ServerState::Stopped, false) if server.on_demand.is_some() => {
+            format!("{} is stopped and starts when a client connects", name)
+        }
+        (ServerState::Stopped, true) if server.on_demand.is_some() => {
+            format!("{} 서버는 멈춰 있다가 클라이언트가 접속하면 시작되며", name)
+        }
+        (ServerState::Stopped, false) => format!("{} is stopped", name),
+        (ServerState::Stopped, true) => format!("{} 서버는 멈춰 있으며", name),
+        (ServerState::Paused, false) => {
+            format!("{} is paused and not accepting connections", name)
+        }
+        (ServerState::Paused, true) => {
+            format!("{} 서버는 일시 정지되어 접속할 수 없으며", name)
+        }
+        (
+            ServerState::ExitedError {
+                oom_killed: true, ..
+            },
+            false,
+        ) => {
+            format!("{} stopped after running out of memory", name)
+        }
+        (
+            ServerState::ExitedError {
+                oom_killed: true, ..
+            },
+            true,
+        ) => {
+            format!("{} 서버는 메모리가 부족해 멈췄으며", name)
+        }
+        (ServerState::ExitedError { exit_code, .. }, false) => {
+            format!("{} stopped with exit code {}", name, exit_code)
+        }
+        (ServerState::ExitedError { exit_code, .. }, true) => {
+            format!("{} 서버는 멈췄으며(종료 코드 {})", name, exit_code)
+        }
+        (ServerState::PortConflict { port }, false) => format!(
+            "{} can't start because another program is using port {}",
+            name, port
+        ),
+        (ServerState::PortConflict { port }, true) => format!(
+            "{} 서버는 다른 프로그램이 포트 {}번을 쓰고 있어 시작할 수 없으며",
+            name, port
+        ),
+        (ServerState::MissingVolume { path }, false) => {
+            format!(
+                "{} can't start because its folder {} is missing",
+                name, path
+            )
+        }
+        (ServerState::MissingVolume { path }, true) => {
+            format!(
+                "{} 서버는 공유 폴더가 없어 시작할 수 없으며({})",
+                name, path
+            )
+        }
+    };
+
+    let last_transfer = load_history()
+        .into_iter()
+        .rev()
+        .find(|r| r.kind == "transfer" && r.server.as_deref() == Some(name))
+        .and_then(|r| seconds_since(&r.timestamp))
+        .map(|secs| format_duration(secs, duration_locale));
+    match (last_transfer, korean) {
+        (Some(ago), false) => format!("{}; last transfer {} ago.", state, ago),
+        (Some(ago), true) => format!("{}, 마지막 전송은 {} 전입니다.", state, ago),
+        (None, false) => format!("{}; no transfers recorded.", state),
+        (None, true) => format!("{}, 전송 기록은 없습니다.", state),
+    }
+}
+
+/// A one-sentence summary of a server in the app's language, for the GUI's
+/// accessibility labels and, as `GET /servers/<name>/description`, for the CLI
+#[tauri::command]
+async fn describe_server(name: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        find_server(&name).map(|server| server_description(&server, &current_locale()))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Open the server's host directory in Finder, Explorer or the desktop's file manager
+#[tauri::command]
+fn reveal_host_path(name: String) -> CommandResult {
+    let result = find_server(&name).and_then(|server| {
+        if server.host_path.is_empty() || !Path::new(&server.host_path).is_dir() {
+            return Err(format!("Host folder not found: {}", server.host_path));
+        }
+        let opener = if cfg!(target_os = "macos") {
+            "open"
+        } else if cfg!(windows) {
+            "explorer"
+        } else {
+            "xdg-open"
+        };
+        // explorer exits non-zero even when it opened the window, so don't wait on it
+        new_command(opener)
+            .arg(&server.host_path)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to run {}: {}", opener, e))
+    });
+    match result {
+        Ok(_) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
 
-                        // Use stored bind_ip if available, otherwise use Docker's bind IP
-                        let bind_ip = stored_bind_ip.or(docker_bind_ip);
+/// Map the server through SSHFS-Win and `net use`, returning the drive letter
+#[cfg(windows)]
+fn mount_network_drive(server: &ServerInfo) -> Result<String, String> {
+    let installed = ["ProgramFiles", "ProgramFiles(x86)"].iter().any(|var| {
+        std::env::var(var)
+            .map(|dir| Path::new(&dir).join("SSHFS-Win").join("bin").exists())
+            .unwrap_or(false)
+    });
+    if !installed {
+        return Err(
+            "SSHFS-Win is not installed; install WinFsp and SSHFS-Win to map SFTP drives"
+                .to_string(),
+        );
+    }
+    let unc = format!(
+        "\\\\sshfs\\{}@{}!{}",
+        server.username,
+        client_host(server.bind_ip.as_deref().unwrap_or("")),
+        server.port
+    );
+    let output = run_command(
+        "net",
+        &["use", "*", &unc, &server.password, "/persistent:no"],
+    )?;
+    // "Drive Z: is now connected to ..." (the wording is localized, the letter is not)
+    output
+        .split_whitespace()
+        .find(|w| {
+            w.len() == 2 && w.ends_with(':') && w.starts_with(|c: char| c.is_ascii_alphabetic())
+        })
+        .map(|w| w.to_string())
+        .ok_or_else(|| format!("net use did not report a drive letter: {}", output.trim()))
+}
 
-                        Some(ServerInfo {
-                            name,
-                            port,
-                            host_path,
-                            container_path,
-                            username,
-                            password,
-                            status: status.to_string(),
-                            created_at: None,
-                            bind_ip,
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect()
+/// Mount the server with sshfs on top of macFUSE under ~/dsftp-mounts
+#[cfg(target_os = "macos")]
+fn mount_network_drive(server: &ServerInfo) -> Result<String, String> {
+    if !Path::new("/Library/Filesystems/macfuse.fs").exists() {
+        return Err("macFUSE is not installed; get it from https://osxfuse.github.io".to_string());
+    }
+    if new_command("sshfs").arg("-V").output().is_err() {
+        return Err("sshfs is not installed; install it with `brew install sshfs`".to_string());
+    }
+    let mount_point = dirs::home_dir()
+        .ok_or("Cannot determine the home directory")?
+        .join("dsftp-mounts")
+        .join(&server.name);
+    fs::create_dir_all(&mount_point).map_err(|e| e.to_string())?;
+    let mount_str = mount_point.to_string_lossy().to_string();
+    let remote = format!(
+        "{}@{}:",
+        server.username,
+        client_host(server.bind_ip.as_deref().unwrap_or(""))
+    );
+    let port = server.port.to_string();
+    let options = format!("password_stdin,volname={}", server.name);
+
+    let mut child = new_command("sshfs")
+        .args([
+            remote.as_str(),
+            mount_str.as_str(),
+            "-p",
+            port.as_str(),
+            "-o",
+            options.as_str(),
+            // Containers get a fresh host key whenever they are recreated
+            "-o",
+            "StrictHostKeyChecking=no,UserKnownHostsFile=/dev/null",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", server.password).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(mount_str)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn mount_network_drive(_server: &ServerInfo) -> Result<String, String> {
+    Err("Mounting as a network drive is only supported on Windows and macOS".to_string())
+}
+
+#[tauri::command]
+async fn mount_as_network_drive(name: String) -> MountResult {
+    let audit_name = name.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let server = find_server(&name)?;
+        if server.status != "running" {
+            return Err(format!("{} is not running", name));
         }
-        Err(_) => vec![],
+        mount_network_drive(&server)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    record_audit(
+        &audit_name,
+        "mount",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+
+    match result {
+        Ok(mount_point) => MountResult {
+            success: true,
+            mount_point: Some(mount_point),
+            error: None,
+        },
+        Err(e) => MountResult {
+            success: false,
+            mount_point: None,
+            error: Some(e),
+        },
     }
 }
 
-fn extract_port(ports_str: &str) -> u16 {
-    // Parse "0.0.0.0:2222->22/tcp" format
-    if let Some(start) = ports_str.find(':') {
-        if let Some(end) = ports_str.find("->") {
-            if let Ok(port) = ports_str[start + 1..end].parse() {
-                return port;
-            }
+/// A server definition as shared through the team config repo. Passwords and the
+/// bind address are machine-local and never leave this host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct SharedServer {
+    pub name: String,
+    pub port: u16,
+    pub host_path: String,
+    pub container_path: String,
+    pub username: String,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub restart_policy: Option<RestartPolicy>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub extra_docker_args: Vec<String>,
+    #[serde(default)]
+    pub anonymous_read_only: bool,
+    #[serde(default)]
+    pub umask: Option<String>,
+    #[serde(default)]
+    pub file_mode: Option<String>,
+    #[serde(default)]
+    pub dir_mode: Option<String>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+}
+
+impl SharedServer {
+    fn new(server: ServerInfo, creds: Option<&StoredCredentials>) -> Self {
+        SharedServer {
+            name: server.name,
+            port: server.port,
+            host_path: server.host_path,
+            container_path: server.container_path,
+            username: server.username,
+            timezone: server.timezone,
+            restart_policy: server.restart_policy,
+            depends_on: server.depends_on,
+            extra_docker_args: creds
+                .map(|c| c.extra_docker_args.clone())
+                .unwrap_or_default(),
+            anonymous_read_only: server.anonymous_read_only,
+            umask: server.umask,
+            file_mode: server.file_mode,
+            dir_mode: server.dir_mode,
+            exclude_patterns: server.exclude_patterns,
         }
     }
-    0
 }
 
-fn extract_bind_ip(ports_str: &str) -> Option<String> {
-    // Parse "192.168.1.100:2222->22/tcp" or "0.0.0.0:2222->22/tcp" format
-    if let Some(colon_pos) = ports_str.find(':') {
-        let ip = &ports_str[..colon_pos];
-        if !ip.is_empty() {
-            return Some(ip.to_string());
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncReport {
+    /// Local servers written to the repo
+    pub exported: Vec<String>,
+    /// Definitions from teammates that don't exist here yet; create them with a
+    /// password of your own
+    pub available: Vec<SharedServer>,
+    /// Whether a new commit was pushed
+    pub pushed: bool,
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String, String> {
+    let repo = repo.to_string_lossy();
+    let mut full = vec!["-C", &*repo];
+    full.extend_from_slice(args);
+    run_command("git", &full).map_err(|e| format!("git {}: {}", args[0], e.trim()))
+}
+
+/// Clone or update the team repo, publish local definitions, and report the ones
+/// only teammates have. Runs the git CLI so the user's existing credential helpers
+/// and SSH keys apply.
+fn sync_config_repo_internal(url: &str) -> Result<SyncReport, String> {
+    let repo = get_config_dir().join(TEAM_CONFIG_DIR);
+    if !repo.join(".git").is_dir() {
+        if repo.exists() {
+            return Err(format!(
+                "{} exists but is not a git repository",
+                repo.display()
+            ));
         }
+        let dest = repo.to_string_lossy();
+        run_command("git", &["clone", "--", url, &*dest])
+            .map_err(|e| format!("git clone: {}", e.trim()))?;
+    } else if git(&repo, &["remote", "get-url", "origin"])?.trim() != url {
+        git(&repo, &["remote", "set-url", "origin", url])?;
     }
-    None
+    // A freshly created remote has no branch to pull yet
+    if !git(&repo, &["ls-remote", "--heads", "origin"])?
+        .trim()
+        .is_empty()
+    {
+        git(&repo, &["pull", "--rebase", "--autostash", "origin"])?;
+    }
+
+    let path = repo.join(TEAM_CONFIG_FILE);
+    let remote: Vec<SharedServer> = read_json_file(&path);
+    let creds = load_credentials();
+    // Containers dsftp didn't create have no definition worth sharing
+    let local: Vec<SharedServer> = list_servers()
+        .into_iter()
+        .filter(|s| creds.contains_key(&s.name))
+        .map(|s| {
+            let entry = creds.get(&s.name);
+            SharedServer::new(s, entry)
+        })
+        .collect();
+    let local_names: HashSet<&str> = local.iter().map(|s| s.name.as_str()).collect();
+    let available: Vec<SharedServer> = remote
+        .iter()
+        .filter(|s| !local_names.contains(s.name.as_str()))
+        .cloned()
+        .collect();
+
+    // Local definitions win for servers that exist on both sides
+    let mut merged: Vec<SharedServer> = available.iter().cloned().chain(local.clone()).collect();
+    merged.sort_by(|a, b| a.name.cmp(&b.name));
+    write_json_file(&path, &merged);
+
+    git(&repo, &["add", TEAM_CONFIG_FILE])?;
+    let pushed = if git(&repo, &["status", "--porcelain", "--", TEAM_CONFIG_FILE])?
+        .trim()
+        .is_empty()
+    {
+        false
+    } else {
+        git(&repo, &["commit", "-m", "Update server definitions"])?;
+        git(&repo, &["push", "-u", "origin", "HEAD"])?;
+        true
+    };
+
+    Ok(SyncReport {
+        exported: local.into_iter().map(|s| s.name).collect(),
+        available,
+        pushed,
+    })
 }
 
+/// Share non-secret server definitions with a team through a git repository
 #[tauri::command]
-fn create_server(config: ServerConfig) -> CreateResult {
-    let host_path = config.host_path.replace('\\', "/");
+async fn sync_config_repo(url: String) -> Result<SyncReport, String> {
+    let url = url.trim().to_string();
+    if url.is_empty() {
+        return Err("Repository URL is required".to_string());
+    }
+    if url.starts_with('-') {
+        return Err(format!("Invalid repository URL: {}", url));
+    }
+    let repo_url = url.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || sync_config_repo_internal(&repo_url))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    if result.is_ok() {
+        update_app_settings(|settings| settings.sync_repo = Some(url));
+    }
+    result
+}
 
-    // Get network config to bind to specific IP
-    let network_config = load_network_config();
-    let interfaces = list_network_interfaces_internal();
-    let (bind_ip, _, _) = get_current_ip_internal(&interfaces, &network_config);
+/// S3-compatible bucket (AWS, MinIO, Backblaze B2, ...)
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct S3Target {
+    pub endpoint: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub bucket: String,
+    /// Key prefix, e.g. "dsftp/"
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key: String,
+    /// Empty in the settings file unless passwords are kept there (`SecretStoreKind::Json`);
+    /// otherwise it's in the secret store under `s3_secret`
+    pub secret_key: String,
+    /// Address the bucket as endpoint/bucket rather than bucket.endpoint; MinIO needs this
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// Secret store entry of an S3 target's secret key
+fn s3_secret(target: &str) -> String {
+    format!("@s3:{}", target)
+}
+
+/// The backup targets with their S3 secret keys
+fn load_backup_targets() -> HashMap<String, BackupTarget> {
+    let settings = load_app_settings();
+    let mut targets = settings.backup_targets;
+    let store = secret_store(settings.secret_store);
+    if !store.inline() {
+        for (name, target) in targets.iter_mut() {
+            if let BackupTarget::S3(s3) = target {
+                if s3.secret_key.is_empty() {
+                    if let Ok(Some(key)) = store.get(&s3_secret(name)) {
+                        s3.secret_key = key;
+                    }
+                }
+            }
+        }
+    }
+    targets
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum BackupTarget {
+    /// A folder on this machine or a mounted share
+    Local {
+        dir: String,
+    },
+    S3(S3Target),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct UploadedPart {
+    number: u32,
+    etag: String,
+}
+
+/// A multipart upload that hasn't completed; kept with its snapshot so it can resume
+/// from the first missing part
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PendingBackup {
+    pub id: String,
+    pub server: String,
+    pub target: String,
+    pub archive: String,
+    pub key: String,
+    #[serde(default)]
+    pub upload_id: Option<String>,
+    #[serde(default)]
+    #[ts(skip)]
+    parts: Vec<UploadedPart>,
+    pub created_at: String,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BackupResult {
+    pub success: bool,
+    /// Where the snapshot ended up: a file path or s3://bucket/key
+    pub location: Option<String>,
+    /// Set when the upload failed part-way; pass to `resume_backup`
+    pub pending_id: Option<String>,
+    pub error: Option<String>,
+    pub insufficient_space: Option<InsufficientSpace>,
+}
+
+fn get_backup_dir() -> PathBuf {
+    let dir = get_data_dir().join(BACKUP_DIR);
+    fs::create_dir_all(&dir).ok();
+    dir
+}
 
-    let port_mapping = format!("{}:{}:22", bind_ip, config.port);
-    let volume_mapping = format!("{}:{}", host_path, config.container_path);
-    let user_config = format!("{}:{}:1001", config.username, config.password);
+fn get_pending_backups_path() -> PathBuf {
+    get_backup_dir().join(PENDING_BACKUPS_FILE)
+}
+
+fn save_pending_backup(pending: &PendingBackup) {
+    update_json_file(
+        &get_pending_backups_path(),
+        |all: &mut HashMap<String, PendingBackup>| {
+            all.insert(pending.id.clone(), pending.clone());
+        },
+    );
+}
+
+fn remove_pending_backup(id: &str) {
+    update_json_file(
+        &get_pending_backups_path(),
+        |all: &mut HashMap<String, PendingBackup>| {
+            all.remove(id);
+        },
+    );
+}
 
+/// tar.gz the server's host folder into the backup dir
+fn snapshot_volume(server: &ServerInfo, stamp: &str) -> Result<PathBuf, String> {
+    if server.host_path.is_empty() || !Path::new(&server.host_path).is_dir() {
+        return Err(format!("Host folder not found: {}", server.host_path));
+    }
+    let archive = get_backup_dir().join(format!("{}-{}.tar.gz", server.name, stamp));
+    let archive_arg = archive.to_string_lossy();
     let result = run_command(
-        "docker",
-        &[
-            "run",
-            "-d",
-            "--name",
-            &config.name,
-            "-p",
-            &port_mapping,
-            "-v",
-            &volume_mapping,
-            "--restart",
-            "unless-stopped",
-            SFTP_IMAGE,
-            &user_config,
-        ],
+        "tar",
+        &["-czf", &*archive_arg, "-C", &server.host_path, "."],
+    );
+    if let Err(e) = result {
+        fs::remove_file(&archive).ok();
+        return Err(format!("Snapshot failed: {}", e.trim()));
+    }
+    Ok(archive)
+}
+
+fn s3_bucket(target: &S3Target) -> Result<Box<s3::Bucket>, String> {
+    let credentials = s3::creds::Credentials::new(
+        Some(target.access_key.as_str()),
+        Some(target.secret_key.as_str()),
+        None,
+        None,
+        None,
+    )
+    .map_err(|e| e.to_string())?;
+    let region = s3::Region::Custom {
+        region: target.region.clone(),
+        endpoint: target.endpoint.clone(),
+    };
+    let bucket = s3::Bucket::new(&target.bucket, region, credentials).map_err(|e| e.to_string())?;
+    Ok(if target.path_style {
+        bucket.with_path_style()
+    } else {
+        bucket
+    })
+}
+
+fn read_part(archive: &Path, number: u32) -> Result<Vec<u8>, String> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = fs::File::open(archive).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(
+        (number as u64 - 1) * BACKUP_PART_SIZE as u64,
+    ))
+    .map_err(|e| e.to_string())?;
+    let mut chunk = Vec::with_capacity(BACKUP_PART_SIZE);
+    file.take(BACKUP_PART_SIZE as u64)
+        .read_to_end(&mut chunk)
+        .map_err(|e| e.to_string())?;
+    Ok(chunk)
+}
+
+/// Upload the snapshot part by part, saving progress after every part so a failed
+/// run can pick up where it stopped
+async fn upload_to_s3(target: &S3Target, pending: &mut PendingBackup) -> Result<String, String> {
+    let bucket = s3_bucket(target)?;
+    let archive = PathBuf::from(&pending.archive);
+    let size = fs::metadata(&archive).map_err(|e| e.to_string())?.len();
+    let part_count = size.div_ceil(BACKUP_PART_SIZE as u64).max(1) as u32;
+
+    let upload_id = match &pending.upload_id {
+        Some(id) => id.clone(),
+        None => {
+            let response = bucket
+                .initiate_multipart_upload(&pending.key, "application/gzip")
+                .await
+                .map_err(|e| e.to_string())?;
+            pending.upload_id = Some(response.upload_id.clone());
+            save_pending_backup(pending);
+            response.upload_id
+        }
+    };
+
+    for number in 1..=part_count {
+        if pending.parts.iter().any(|p| p.number == number) {
+            continue;
+        }
+        let chunk = read_part(&archive, number)?;
+        let mut attempt = 0;
+        let part = loop {
+            attempt += 1;
+            match bucket
+                .put_multipart_chunk(
+                    chunk.clone(),
+                    &pending.key,
+                    number,
+                    &upload_id,
+                    "application/gzip",
+                )
+                .await
+            {
+                Ok(part) => break part,
+                Err(e) if attempt >= BACKUP_PART_RETRIES => {
+                    return Err(format!("Part {}/{}: {}", number, part_count, e));
+                }
+                Err(_) => {
+                    tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                }
+            }
+        };
+        pending.parts.push(UploadedPart {
+            number,
+            etag: part.etag,
+        });
+        save_pending_backup(pending);
+    }
+
+    let mut parts: Vec<s3::serde_types::Part> = pending
+        .parts
+        .iter()
+        .map(|p| s3::serde_types::Part {
+            part_number: p.number,
+            etag: p.etag.clone(),
+        })
+        .collect();
+    parts.sort_by_key(|p| p.part_number);
+    bucket
+        .complete_multipart_upload(&pending.key, &upload_id, parts)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(format!("s3://{}/{}", target.bucket, pending.key))
+}
+
+async fn deliver_backup(mut pending: PendingBackup) -> BackupResult {
+    let target = load_backup_targets()
+        .remove(&pending.target)
+        .ok_or_else(|| format!("Unknown backup target: {}", pending.target));
+    let archive = PathBuf::from(&pending.archive);
+
+    let mut short = None;
+    let result = match target {
+        Err(e) => Err(e),
+        Ok(BackupTarget::Local { dir }) => {
+            let dest = Path::new(&dir).join(&pending.key);
+            let size = fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+            match check_disk_space(&dest, size) {
+                Err(space) => {
+                    let message = space.to_string();
+                    short = Some(space);
+                    Err(message)
+                }
+                Ok(()) => dest
+                    .parent()
+                    .map(fs::create_dir_all)
+                    .transpose()
+                    .and_then(|_| fs::copy(&archive, &dest))
+                    .map(|_| dest.display().to_string())
+                    .map_err(|e| e.to_string()),
+            }
+        }
+        Ok(BackupTarget::S3(target)) => upload_to_s3(&target, &mut pending).await,
+    };
+    record_audit(
+        &pending.server,
+        "backup",
+        result.as_ref().err().map(|e| e.as_str()),
     );
 
     match result {
-        Ok(_) => {
-            // Store credentials for later retrieval
-            store_server_credentials(
-                &config.name,
-                StoredCredentials {
-                    username: config.username.clone(),
-                    password: config.password.clone(),
-                    host_path: config.host_path.clone(),
-                    container_path: config.container_path.clone(),
-                    bind_ip: Some(bind_ip.clone()),
-                },
-            );
+        Ok(location) => {
+            remove_pending_backup(&pending.id);
+            fs::remove_file(&archive).ok();
+            BackupResult {
+                success: true,
+                location: Some(location),
+                pending_id: None,
+                error: None,
+                insufficient_space: None,
+            }
+        }
+        Err(e) => {
+            pending.last_error = Some(e.clone());
+            save_pending_backup(&pending);
+            BackupResult {
+                success: false,
+                location: None,
+                pending_id: Some(pending.id),
+                error: Some(e),
+                insufficient_space: short,
+            }
+        }
+    }
+}
+
+#[tauri::command]
+fn list_backup_targets() -> HashMap<String, BackupTarget> {
+    let mut targets = load_app_settings().backup_targets;
+    for target in targets.values_mut() {
+        if let BackupTarget::S3(s3) = target {
+            s3.secret_key.clear();
+        }
+    }
+    targets
+}
+
+/// Add or replace a backup target; `None` removes it. An S3 target saved with an
+/// empty secret keeps the one already stored.
+#[tauri::command]
+fn set_backup_target(name: String, mut target: Option<BackupTarget>) -> CommandResult {
+    let store = secret_store(load_app_settings().secret_store);
+    if let Some(BackupTarget::S3(s3)) = &mut target {
+        if s3.endpoint.trim().is_empty() || s3.bucket.trim().is_empty() {
+            return CommandResult {
+                success: false,
+                error: Some("Endpoint and bucket are required".to_string()),
+            };
+        }
+        // The store takes the config lock itself, so it's written outside the update
+        if !s3.secret_key.is_empty() && !store.inline() {
+            if let Err(e) = store.set(&s3_secret(&name), &s3.secret_key) {
+                return CommandResult {
+                    success: false,
+                    error: Some(format!("Failed to store the secret key: {}", e)),
+                };
+            }
+            s3.secret_key.clear();
+        }
+    }
+    if !matches!(target, Some(BackupTarget::S3(_))) && !store.inline() {
+        store.remove(&s3_secret(&name)).ok();
+    }
+    update_app_settings(|settings| match target {
+        Some(BackupTarget::S3(mut s3)) => {
+            if s3.secret_key.is_empty() {
+                if let Some(BackupTarget::S3(old)) = settings.backup_targets.get(&name) {
+                    s3.secret_key = old.secret_key.clone();
+                }
+            }
+            settings.backup_targets.insert(name, BackupTarget::S3(s3));
+        }
+        Some(target) => {
+            settings.backup_targets.insert(name, target);
+        }
+        None => {
+            settings.backup_targets.remove(&name);
+        }
+    });
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// Snapshot a server's volume and send it to a configured target
+#[tauri::command]
+async fn backup_server(name: String, target: String, operation_id: Option<String>) -> BackupResult {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let server_name = name.clone();
+    let snapshot_stamp = stamp.clone();
+    let archive = tauri::async_runtime::spawn_blocking(move || {
+        let server = find_server(&server_name).map_err(|e| (e, None))?;
+        // Sized for the worst case, an archive that doesn't compress at all
+        check_disk_space(&get_backup_dir(), dir_size(Path::new(&server.host_path)))
+            .map_err(|short| (short.to_string(), Some(short)))?;
+        run_operation(operation_id, || snapshot_volume(&server, &snapshot_stamp))
+            .map_err(|e| (e.to_string(), None))
+    })
+    .await
+    .map_err(|e| (e.to_string(), None))
+    .and_then(|r| r);
+    let archive = match archive {
+        Ok(archive) => archive,
+        Err((e, short)) => {
+            record_audit(&name, "backup", Some(&e));
+            return BackupResult {
+                success: false,
+                location: None,
+                pending_id: None,
+                error: Some(e),
+                insufficient_space: short,
+            };
+        }
+    };
+
+    let prefix = match load_app_settings().backup_targets.get(&target) {
+        Some(BackupTarget::S3(s3)) => s3.prefix.clone(),
+        _ => String::new(),
+    };
+    let file_name = archive
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    deliver_backup(PendingBackup {
+        id: format!("{}-{}", name, stamp),
+        key: format!("{}{}/{}", prefix, name, file_name),
+        server: name,
+        target,
+        archive: archive.to_string_lossy().to_string(),
+        upload_id: None,
+        parts: Vec::new(),
+        created_at: chrono::Local::now().to_rfc3339(),
+        last_error: None,
+    })
+    .await
+}
+
+#[tauri::command]
+fn list_pending_backups() -> Vec<PendingBackup> {
+    let all: HashMap<String, PendingBackup> = read_json_file(&get_pending_backups_path());
+    let mut pending: Vec<PendingBackup> = all.into_values().collect();
+    pending.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    pending
+}
+
+/// Retry a failed backup, skipping parts the target already has
+#[tauri::command]
+async fn resume_backup(id: String) -> BackupResult {
+    let all: HashMap<String, PendingBackup> = read_json_file(&get_pending_backups_path());
+    match all.get(&id) {
+        Some(pending) => deliver_backup(pending.clone()).await,
+        None => BackupResult {
+            success: false,
+            location: None,
+            pending_id: None,
+            error: Some(format!("No pending backup {}", id)),
+            insufficient_space: None,
+        },
+    }
+}
+
+/// Give up on a failed backup: abort its multipart upload and delete the snapshot
+#[tauri::command]
+async fn discard_backup(id: String) -> CommandResult {
+    let all: HashMap<String, PendingBackup> = read_json_file(&get_pending_backups_path());
+    let Some(pending) = all.get(&id) else {
+        return CommandResult {
+            success: false,
+            error: Some(format!("No pending backup {}", id)),
+        };
+    };
+    if let (Some(upload_id), Some(BackupTarget::S3(target))) = (
+        &pending.upload_id,
+        load_backup_targets().get(&pending.target),
+    ) {
+        if let Ok(bucket) = s3_bucket(target) {
+            bucket.abort_upload(&pending.key, upload_id).await.ok();
+        }
+    }
+    fs::remove_file(&pending.archive).ok();
+    remove_pending_backup(&id);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// Interface changes seen by the watcher, newest first
+#[tauri::command]
+fn get_network_history(limit: Option<usize>) -> Vec<NetworkChangeEvent> {
+    get_history(None, Some("network".to_string()), limit)
+        .into_iter()
+        .filter_map(|r| serde_json::from_value(r.data).ok())
+        .collect()
+}
+
+/// Extra software baked into a server's image, for tools the stock atmoz/sftp
+/// image lacks (rsync, zip, clamav)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct ImageBuild {
+    /// Installed with the base image's package manager (apt on Debian, apk on Alpine)
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// Shell run as root after the packages are installed
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+fn check_image_build(build: &ImageBuild) -> Result<(), String> {
+    if build.packages.is_empty() && build.script.as_deref().is_none_or(|s| s.trim().is_empty()) {
+        return Err("Add packages or a script to build".to_string());
+    }
+    for package in &build.packages {
+        let valid = package.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+            && package
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-._=:~".contains(c));
+        if !valid {
+            return Err(format!("Invalid package name: {}", package));
+        }
+    }
+    Ok(())
+}
+
+/// Tag of the server's built image. Docker repositories are lowercase and can't
+/// end in a separator, which container names can.
+fn custom_image(name: &str) -> String {
+    let repo: String = name
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ ('a'..='z' | '0'..='9') => c,
+            _ => '-',
+        })
+        .collect();
+    format!(
+        "{}/{}:latest",
+        CUSTOM_IMAGE_REPO,
+        repo.trim_end_matches('-')
+    )
+}
+
+fn image_build_dir(name: &str) -> PathBuf {
+    get_config_dir().join(IMAGE_BUILDS_DIR).join(name)
+}
+
+fn image_dockerfile(build: &ImageBuild) -> String {
+    let mut dockerfile = format!(
+        "# Generated by dsftp from the server's image build; edits here are overwritten\n\
+         FROM {}\n",
+        image_ref(None)
+    );
+    if !build.packages.is_empty() {
+        dockerfile.push_str(&format!(
+            "RUN if command -v apk >/dev/null; then apk add --no-cache {packages}; \\\n    \
+             else apt-get update \\\n    \
+             && DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends {packages} \\\n    \
+             && rm -rf /var/lib/apt/lists/*; fi\n",
+            packages = build.packages.join(" ")
+        ));
+    }
+    if build
+        .script
+        .as_deref()
+        .is_some_and(|s| !s.trim().is_empty())
+    {
+        dockerfile.push_str(
+            "COPY setup.sh /tmp/dsftp-setup.sh\n\
+             RUN sh -e /tmp/dsftp-setup.sh && rm /tmp/dsftp-setup.sh\n",
+        );
+    }
+    dockerfile
+}
 
-            CreateResult {
-                success: true,
-                server: Some(ServerInfo {
-                    name: config.name,
-                    port: config.port,
-                    host_path: config.host_path,
-                    container_path: config.container_path,
-                    username: config.username,
-                    password: config.password,
-                    status: "running".to_string(),
-                    created_at: None,
-                    bind_ip: Some(bind_ip),
-                }),
-                error: None,
-            }
-        }
-        Err(e) => CreateResult {
-            success: false,
-            server: None,
-            error: Some(e),
-        },
+/// Build the server's image from `build` unless the one present was already built
+/// from it. The spec is kept as a label on the image for that comparison.
+fn ensure_custom_image(name: &str, build: &ImageBuild) -> Result<String, String> {
+    let image = custom_image(name);
+    let spec = serde_json::to_string(build).map_err(|e| e.to_string())?;
+    let format = format!("{{{{index .Config.Labels \"{}\"}}}}", IMAGE_BUILD_LABEL);
+    if run_command("docker", &["image", "inspect", "--format", &format, &image])
+        .is_ok_and(|built| built.trim() == spec)
+    {
+        return Ok(image);
     }
+
+    let dir = image_build_dir(name);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    fs::write(dir.join("Dockerfile"), image_dockerfile(build)).map_err(|e| e.to_string())?;
+    fs::write(
+        dir.join("setup.sh"),
+        build.script.as_deref().unwrap_or_default(),
+    )
+    .map_err(|e| e.to_string())?;
+    let label = format!("{}={}", IMAGE_BUILD_LABEL, spec);
+    let context = dir.to_string_lossy().to_string();
+    run_command(
+        "docker",
+        &["build", "--label", &label, "-t", &image, &context],
+    )
+    .map_err(|e| e.trim().to_string())?;
+    Ok(image)
 }
 
 #[tauri::command]
-fn start_server(name: String) -> CommandResult {
-    // Only allow atmoz/sftp containers
-    if !is_sftp_container(&name) {
-        return CommandResult {
-            success: false,
-            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
-        };
-    }
+fn get_image_build(name: String) -> Option<ImageBuild> {
+    load_credentials().remove(&name)?.image_build
+}
 
-    match run_command("docker", &["start", &name]) {
-        Ok(_) => CommandResult {
+/// Run the server on an image with `build`'s packages baked in, or back on the
+/// stock image with `None`. The image is built before anything else, and the
+/// server is only recreated once that succeeded and its image actually changes.
+#[tauri::command]
+async fn set_image_build(app: AppHandle, name: String, build: Option<ImageBuild>) -> CommandResult {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let reservations = app.state::<PortReservations>();
+        let result = find_server(&name).and_then(|server| {
+            let previous = load_credentials()
+                .remove(&name)
+                .ok_or_else(|| format!("No stored configuration for {}", name))?
+                .image_build;
+            if let Some(build) = &build {
+                check_image_build(build)?;
+                ensure_custom_image(&name, build)?;
+            }
+            if previous == build {
+                return Ok(());
+            }
+            update_credentials(|all| {
+                all.get_mut(&name)
+                    .map(|creds| creds.image_build = build.clone())
+            })
+            .ok_or_else(|| format!("No stored configuration for {}", name))?;
+            rebind_server(&app, &reservations, &server, server.bind_ip.clone())?;
+            if build.is_none() {
+                fs::remove_dir_all(image_build_dir(&name)).ok();
+                run_command("docker", &["image", "rm", &custom_image(&name)]).ok();
+            }
+            Ok(())
+        });
+        record_audit(
+            &name,
+            "set-image-build",
+            result.as_ref().err().map(|e| e.as_str()),
+        );
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    match result {
+        Ok(()) => CommandResult {
             success: true,
             error: None,
         },
@@ -460,137 +16882,242 @@ fn start_server(name: String) -> CommandResult {
     }
 }
 
-#[tauri::command]
-fn stop_server(name: String) -> CommandResult {
-    // Only allow atmoz/sftp containers
-    if !is_sftp_container(&name) {
-        return CommandResult {
-            success: false,
-            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
-        };
+/// Recreate a server with its published port bound to `ip` (the current preferred
+/// address when `None`); the share, credentials and options are kept
+fn rebind_server(
+    app: &AppHandle,
+    reservations: &PortReservations,
+    server: &ServerInfo,
+    ip: Option<String>,
+) -> Result<(), String> {
+    let creds = load_credentials()
+        .remove(&server.name)
+        .ok_or_else(|| format!("No stored configuration for {}", server.name))?;
+    let config = ServerConfig::from_info(server, &creds);
+    // Port bindings can't be changed in place
+    capture_host_identity(&server.name)?;
+    persist_logs(&server.name).ok();
+    run_command("docker", &["rm", "-f", &server.name])?;
+    match create_server_internal(app, reservations, config, ip).error {
+        Some(e) => Err(e),
+        None => Ok(()),
     }
+}
 
-    match run_command("docker", &["stop", &name]) {
-        Ok(_) => CommandResult {
-            success: true,
-            error: None,
-        },
-        Err(e) => CommandResult {
-            success: false,
-            error: Some(e),
-        },
-    }
+fn host_identity_dir(name: &str) -> PathBuf {
+    get_data_dir().join(HOST_IDENTITY_DIR).join(name)
 }
 
-#[tauri::command]
-fn remove_server(name: String) -> CommandResult {
-    // Only allow atmoz/sftp containers
-    if !is_sftp_container(&name) {
-        return CommandResult {
-            success: false,
-            error: Some("Not an SFTP container (atmoz/sftp)".to_string()),
-        };
+/// Copy a container's SSH host keys and /etc/sftp/users.conf into dsftp's store so
+/// the server keeps its identity and user list when recreated. `docker cp` also
+/// works on stopped containers; files the container lacks are skipped.
+fn capture_host_identity(name: &str) -> Result<(), String> {
+    let dir = host_identity_dir(name);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let sources = SSH_HOST_KEY_FILES
+        .iter()
+        .map(|file| (format!("/etc/ssh/{}", file), *file))
+        .chain([("/etc/sftp/users.conf".to_string(), "users.conf")]);
+    for (source, file) in sources {
+        let dest = dir.join(file).to_string_lossy().to_string();
+        run_command("docker", &["cp", &format!("{}:{}", name, source), &dest]).ok();
     }
 
-    match run_command("docker", &["rm", "-f", &name]) {
-        Ok(_) => {
-            // Remove stored credentials
-            remove_server_credentials(&name);
-            CommandResult {
-                success: true,
-                error: None,
+    let restore = get_data_dir().join(HOST_IDENTITY_DIR).join("restore.sh");
+    fs::write(&restore, HOST_KEYS_RESTORE_SCRIPT).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&restore, fs::Permissions::from_mode(0o755))
+            .map_err(|e| e.to_string())?;
+        for file in SSH_HOST_KEY_FILES.iter().filter(|f| !f.ends_with(".pub")) {
+            let key = dir.join(file);
+            if key.is_file() {
+                fs::set_permissions(&key, fs::Permissions::from_mode(0o600))
+                    .map_err(|e| e.to_string())?;
             }
         }
-        Err(e) => CommandResult {
-            success: false,
-            error: Some(e),
-        },
     }
+    Ok(())
 }
 
-#[tauri::command]
-fn get_container_status(name: String) -> String {
-    // Only check atmoz/sftp containers
-    if !is_sftp_container(&name) {
-        return "not sftp".to_string();
+/// `docker run -v` values restoring a server's captured host keys and users.conf
+fn host_identity_mounts(name: &str) -> Vec<String> {
+    let dir = host_identity_dir(name);
+    let restore = get_data_dir().join(HOST_IDENTITY_DIR).join("restore.sh");
+    let docker_path = |p: &Path| p.to_string_lossy().replace('\\', "/");
+    let mut mounts = Vec::new();
+    if restore.is_file() && SSH_HOST_KEY_FILES.iter().any(|f| dir.join(f).is_file()) {
+        mounts.push(format!("{}:/etc/dsftp/host-keys:ro", docker_path(&dir)));
+        mounts.push(format!(
+            "{}:/etc/sftp.d/dsftp-host-keys.sh:ro",
+            docker_path(&restore)
+        ));
     }
-
-    match run_command(
-        "docker",
-        &["inspect", "--format", "{{.State.Status}}", &name],
-    ) {
-        Ok(status) => status.trim().to_string(),
-        Err(_) => "not created".to_string(),
+    let users = dir.join("users.conf");
+    if users.is_file() {
+        mounts.push(format!("{}:/etc/sftp/users.conf:ro", docker_path(&users)));
     }
+    mounts
 }
 
+/// Bring an atmoz/sftp container dsftp didn't create under management. It is
+/// recreated with `username`/`password` on the same port, address and share, keeping
+/// its host keys and users.conf so clients see the same server. Like a rebind, the old
+/// container is removed before the new one is created; the captured identity stays in
+/// the store, so a failed adopt can be retried with `create_server`.
 #[tauri::command]
-fn get_container_logs(name: String, lines: u32) -> String {
-    // Only allow atmoz/sftp containers
-    if !is_sftp_container(&name) {
-        return "Not an SFTP container".to_string();
-    }
+fn adopt_server(
+    app: AppHandle,
+    reservations: State<'_, PortReservations>,
+    name: String,
+    username: String,
+    password: String,
+) -> CreateResult {
+    let result = adopt_server_internal(&app, &reservations, &name, username, password)
+        .unwrap_or_else(|e| CreateResult {
+            error: Some(e),
+            ..Default::default()
+        });
+    record_audit(&name, "adopt", result.error.as_deref());
+    result
+}
 
-    match run_command("docker", &["logs", "--tail", &lines.to_string(), &name]) {
-        Ok(logs) => logs,
-        Err(e) => e,
+fn adopt_server_internal(
+    app: &AppHandle,
+    reservations: &PortReservations,
+    name: &str,
+    username: String,
+    password: String,
+) -> Result<CreateResult, String> {
+    if !is_sftp_container(name) {
+        return Err("Not an SFTP container (atmoz/sftp)".to_string());
+    }
+    if load_credentials().contains_key(name) {
+        return Err(format!("{} is already managed by dsftp", name));
     }
+    let inspect = inspect_container(name)?;
+    let share = inspect
+        .mounts
+        .iter()
+        .find(|m| m.destination.starts_with("/home/"))
+        .ok_or_else(|| format!("{} has no share mounted under /home", name))?;
+    let binding = docker_port_bindings()
+        .into_iter()
+        .find(|a| a.server == name)
+        .ok_or_else(|| format!("{} publishes no port", name))?;
+    let timezone = inspect
+        .env
+        .iter()
+        .find_map(|e| e.strip_prefix("TZ="))
+        .map(str::to_string);
+
+    let config = ServerConfig {
+        name: name.to_string(),
+        port: binding.port,
+        host_path: share.source.clone(),
+        container_path: share.destination.clone(),
+        username,
+        password,
+        timezone,
+        restart_policy: inspect.restart_policy.unwrap_or_default(),
+        // Set when dsftp managed the container before, e.g. from another machine
+        id: inspect.server_id,
+        ..Default::default()
+    };
+    capture_host_identity(name)?;
+    persist_logs(name).ok();
+    run_command("docker", &["rm", "-f", name])?;
+    Ok(create_server_internal(
+        app,
+        reservations,
+        config,
+        Some(binding.ip),
+    ))
 }
 
+/// Move every server bound to `from_ip` onto `to_ip` (or the preferred address)
 #[tauri::command]
-fn list_files(name: String, path: String) -> Result<Vec<FileEntry>, String> {
-    // Only allow atmoz/sftp containers
-    if !is_sftp_container(&name) {
-        return Err("Not an SFTP container".to_string());
-    }
-
-    // Use docker exec to list files inside the container
-    let output = run_command("docker", &["exec", &name, "ls", "-la", &path])?;
+async fn rebind_servers(
+    app: AppHandle,
+    from_ip: String,
+    to_ip: Option<String>,
+) -> Vec<BatchStartResult> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let reservations = app.state::<PortReservations>();
+        list_servers()
+            .into_iter()
+            .filter(|s| s.bind_ip.as_deref() == Some(from_ip.as_str()))
+            .map(|server| {
+                let result = rebind_server(&app, &reservations, &server, to_ip.clone());
+                record_audit(
+                    &server.name,
+                    "rebind",
+                    result.as_ref().err().map(|e| e.as_str()),
+                );
+                BatchStartResult {
+                    server: server.name,
+                    success: result.is_ok(),
+                    error: result.err(),
+                }
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
 
-    let mut entries: Vec<FileEntry> = Vec::new();
+/// A server published on an address none of the current interfaces has, e.g. a VPN
+/// address from an earlier session
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct BindingIssue {
+    pub server: String,
+    pub bind_ip: String,
+    pub status: String,
+    /// Where `rebind_servers` moves it when not given an address: the preferred one
+    pub suggested_ip: String,
+}
 
-    for line in output.lines().skip(1) {
-        // Skip "total X" line
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 9 {
-            continue;
-        }
+fn binding_issues() -> Vec<BindingIssue> {
+    let interfaces = list_network_interfaces_internal();
+    let suggested_ip = get_current_ip_internal(&interfaces, &load_network_config()).0;
+    list_servers()
+        .into_iter()
+        .filter_map(|server| {
+            let ip = server.bind_ip.filter(|ip| !ip.is_empty())?;
+            let available = matches!(ip.as_str(), "0.0.0.0" | "::" | "127.0.0.1" | "::1")
+                || interfaces.iter().any(|i| i.address == ip);
+            (!available).then(|| BindingIssue {
+                server: server.name,
+                bind_ip: ip,
+                status: server.status,
+                suggested_ip: suggested_ip.clone(),
+            })
+        })
+        .collect()
+}
 
-        let permissions = parts[0];
-        let size: u64 = parts[4].parse().unwrap_or(0);
-        let name_part = parts[8..].join(" ");
+/// Servers bound to addresses that no longer exist; fix them with `rebind_servers`
+#[tauri::command]
+async fn list_binding_issues() -> Vec<BindingIssue> {
+    tauri::async_runtime::spawn_blocking(binding_issues)
+        .await
+        .unwrap_or_default()
+}
 
-        // Skip . and ..
-        if name_part == "." || name_part == ".." {
-            continue;
+/// Flag servers left bound to addresses that went away while dsftp wasn't running.
+/// A window opening later misses these events and asks `list_binding_issues`.
+fn check_bindings_at_startup() {
+    std::thread::spawn(|| {
+        for issue in binding_issues() {
+            events::publish(events::Event::BindingIssue(issue));
         }
-
-        let is_dir = permissions.starts_with('d');
-        let full_path = if path == "/" {
-            format!("/{}", name_part)
-        } else {
-            format!("{}/{}", path.trim_end_matches('/'), name_part)
-        };
-
-        entries.push(FileEntry {
-            name: name_part,
-            path: full_path,
-            is_dir,
-            size,
-        });
-    }
-
-    // Sort: directories first, then by name
-    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-        (true, false) => std::cmp::Ordering::Less,
-        (false, true) => std::cmp::Ordering::Greater,
-        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
     });
-
-    Ok(entries)
 }
 
 fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
+    let config = load_network_config();
     let mut interfaces: Vec<NetworkInterface> = Vec::new();
 
     // Add 0.0.0.0 option for all interfaces
@@ -598,23 +17125,25 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
         name: "All Interfaces".to_string(),
         address: "0.0.0.0".to_string(),
         is_vpn: false,
+        prefix_len: None,
     });
 
     #[cfg(target_os = "windows")]
     {
         if let Ok(output) = run_command("powershell", &[
             "-Command",
-            "Get-NetIPAddress -AddressFamily IPv4 | Where-Object {$_.PrefixOrigin -ne 'WellKnown'} | Select-Object InterfaceAlias,IPAddress | ForEach-Object { $_.InterfaceAlias + '|' + $_.IPAddress }"
+            "Get-NetIPAddress -AddressFamily IPv4 | Where-Object {$_.PrefixOrigin -ne 'WellKnown'} | Select-Object InterfaceAlias,IPAddress,PrefixLength | ForEach-Object { $_.InterfaceAlias + '|' + $_.IPAddress + '|' + $_.PrefixLength }"
         ]) {
             for line in output.lines() {
                 let parts: Vec<&str> = line.split('|').collect();
                 if parts.len() >= 2 {
                     let name = parts[0].trim().to_string();
                     let address = parts[1].trim().to_string();
+                    let prefix_len = parts.get(2).and_then(|p| p.trim().parse().ok());
                     // Filter out loopback and link-local addresses
                     if !address.starts_with("127.") && !address.starts_with("169.254.") && !address.is_empty() {
-                        let is_vpn = is_vpn_interface(&name);
-                        interfaces.push(NetworkInterface { name, address, is_vpn });
+                        let is_vpn = is_vpn_interface(&name, &config);
+                        interfaces.push(NetworkInterface { name, address, is_vpn, prefix_len });
                     }
                 }
             }
@@ -642,6 +17171,15 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
                     let parts: Vec<&str> = trimmed.split_whitespace().collect();
                     if parts.len() >= 2 {
                         let ip = parts[1].to_string();
+                        // "inet 192.168.1.5 netmask 0xffffff00 broadcast ..."
+                        let prefix_len = parts
+                            .iter()
+                            .position(|p| *p == "netmask")
+                            .and_then(|i| parts.get(i + 1))
+                            .and_then(|mask| {
+                                u32::from_str_radix(mask.trim_start_matches("0x"), 16).ok()
+                            })
+                            .map(|mask| mask.count_ones() as u8);
 
                         // Filter out unwanted IPs
                         if !ip.starts_with("127.")
@@ -651,7 +17189,7 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
                         {
                             // Ensure it's IPv4
 
-                            let is_vpn = is_vpn_interface(&current_iface);
+                            let is_vpn = is_vpn_interface(&current_iface, &config);
 
                             // Check if this IP is already added
                             let already_added = interfaces.iter().any(|i| i.address == ip);
@@ -660,6 +17198,7 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
                                     name: current_iface.clone(),
                                     address: ip,
                                     is_vpn,
+                                    prefix_len,
                                 });
                             }
                         }
@@ -691,11 +17230,12 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
                                 // Check if this IP is already added
                                 let already_added = interfaces.iter().any(|i| i.address == ip);
                                 if !already_added {
-                                    let is_vpn = is_vpn_interface(service_name);
+                                    let is_vpn = is_vpn_interface(service_name, &config);
                                     interfaces.push(NetworkInterface {
                                         name: service_name.to_string(),
                                         address: ip,
                                         is_vpn,
+                                        prefix_len: None,
                                     });
                                 }
                             }
@@ -728,11 +17268,12 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
                                 // Check if this IP is already added
                                 let already_added = interfaces.iter().any(|i| i.address == ip);
                                 if !already_added {
-                                    let is_vpn = is_vpn_interface(service_name);
+                                    let is_vpn = is_vpn_interface(service_name, &config);
                                     interfaces.push(NetworkInterface {
                                         name: service_name.to_string(),
                                         address: ip,
                                         is_vpn,
+                                        prefix_len: None,
                                     });
                                 }
                             }
@@ -762,16 +17303,19 @@ fn list_network_interfaces_internal() -> Vec<NetworkInterface> {
                 } else if line.contains("inet ") {
                     // Parse: "inet 192.168.1.100/24 brd..."
                     if let Some(addr_part) = line.split("inet ").nth(1) {
+                        let cidr = addr_part.split_whitespace().next().unwrap_or("");
+                        let prefix_len = cidr.split_once('/').and_then(|(_, p)| p.parse().ok());
                         if let Some(addr) = addr_part.split('/').next() {
                             if !addr.starts_with("127.")
                                 && !addr.starts_with("169.254.")
                                 && !current_iface.is_empty()
                             {
-                                let is_vpn = is_vpn_interface(&current_iface);
+                                let is_vpn = is_vpn_interface(&current_iface, &config);
                                 interfaces.push(NetworkInterface {
                                     name: current_iface.clone(),
                                     address: addr.to_string(),
                                     is_vpn,
+                                    prefix_len,
                                 });
                             }
                         }
@@ -827,6 +17371,106 @@ fn get_current_ip_internal(
     ("127.0.0.1".to_string(), None, false)
 }
 
+/// Whether `ip` is inside the interface's subnet. Tailscale and similar mesh VPNs
+/// give their interface a /32 in 100.64.0.0/10 while peers share that whole range.
+fn subnet_contains(iface: &NetworkInterface, ip: std::net::Ipv4Addr) -> bool {
+    let Ok(addr) = iface.address.parse::<std::net::Ipv4Addr>() else {
+        return false;
+    };
+    let cgnat = |a: std::net::Ipv4Addr| {
+        u32::from(a) >> 22 == u32::from(std::net::Ipv4Addr::new(100, 64, 0, 0)) >> 22
+    };
+    let prefix = match iface.prefix_len {
+        Some(p) if p < 31 => p,
+        _ if cgnat(addr) && cgnat(ip) => 10,
+        Some(p) => p.min(32),
+        // Without a mask, assume the common /24
+        None => 24,
+    };
+    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    u32::from(addr) & mask == u32::from(ip) & mask
+}
+
+/// Tell whether a client at `client_ip` can reach the server (or the current bind
+/// address), and suggest a better interface when it can't
+#[tauri::command]
+fn check_client_reachability(
+    client_ip: String,
+    server: Option<String>,
+) -> Result<ReachabilityReport, String> {
+    let client: std::net::Ipv4Addr = client_ip
+        .trim()
+        .parse()
+        .map_err(|_| format!("Not an IPv4 address: {}", client_ip))?;
+    let config = load_network_config();
+    let interfaces = list_network_interfaces_internal();
+    let (bind_ip, bind_interface) = match server {
+        Some(name) => {
+            let server = find_server(&name)?;
+            let ip = server.bind_ip.unwrap_or_else(|| "0.0.0.0".to_string());
+            let iface = interfaces
+                .iter()
+                .find(|i| i.address == ip)
+                .map(|i| i.name.clone());
+            (ip, iface)
+        }
+        None => {
+            let (ip, iface, _) = get_current_ip_internal(&interfaces, &config);
+            (ip, iface)
+        }
+    };
+
+    let local: Vec<&NetworkInterface> = interfaces
+        .iter()
+        .filter(|i| i.address != "0.0.0.0")
+        .collect();
+    // Prefer the VPN when both a LAN and a VPN subnet contain the client
+    let client_interface = local
+        .iter()
+        .filter(|i| subnet_contains(i, client))
+        .max_by_key(|i| i.is_vpn)
+        .map(|i| (*i).clone());
+    let same_network = if bind_ip == "0.0.0.0" {
+        client_interface.is_some()
+    } else {
+        local
+            .iter()
+            .any(|i| i.address == bind_ip && subnet_contains(i, client))
+    };
+
+    let (warning, suggested_ip) = match &client_interface {
+        _ if same_network => (None, None),
+        Some(iface) => (
+            Some(format!(
+                "{} is not on the network of {}; bind to {} ({}{}) instead",
+                client_ip,
+                bind_ip,
+                iface.address,
+                iface.name,
+                if iface.is_vpn { ", VPN" } else { "" }
+            )),
+            Some(iface.address.clone()),
+        ),
+        None => (
+            Some(format!(
+                "{} is not on any local network; it can only connect through a router or VPN",
+                client_ip
+            )),
+            local.iter().find(|i| i.is_vpn).map(|i| i.address.clone()),
+        ),
+    };
+
+    Ok(ReachabilityReport {
+        client_ip,
+        bind_ip,
+        bind_interface,
+        same_network,
+        client_interface,
+        warning,
+        suggested_ip,
+    })
+}
+
 #[tauri::command]
 fn list_network_interfaces() -> Vec<NetworkInterface> {
     list_network_interfaces_internal()
@@ -845,6 +17489,8 @@ fn get_network_info() -> NetworkInfo {
         preferred_ip: config.preferred_ip,
         preferred_interface: config.preferred_interface,
         interfaces,
+        vpn_patterns: config.vpn_patterns,
+        vpn_overrides: config.vpn_overrides,
     }
 }
 
@@ -869,34 +17515,676 @@ fn set_network_preference(ip: Option<String>, interface: Option<String>) -> Comm
 
 #[tauri::command]
 fn clear_network_preference() -> CommandResult {
-    save_network_config(&NetworkConfig::default());
+    let mut config = load_network_config();
+    config.preferred_ip = None;
+    config.preferred_interface = None;
+    save_network_config(&config);
     CommandResult {
         success: true,
         error: None,
     }
 }
 
+/// Replace the user's VPN name patterns (the built-in ones always apply)
+#[tauri::command]
+fn set_vpn_patterns(patterns: Vec<String>) -> CommandResult {
+    let mut config = load_network_config();
+    config.vpn_patterns = patterns
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    save_network_config(&config);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// Force an interface to count as VPN (or not), regardless of patterns
+#[tauri::command]
+fn mark_interface_as_vpn(name: String, is_vpn: bool) -> CommandResult {
+    let mut config = load_network_config();
+    config.vpn_overrides.insert(name, is_vpn);
+    save_network_config(&config);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// Drop a manual override so the interface is classified by patterns again
+#[tauri::command]
+fn clear_interface_vpn_override(name: String) -> CommandResult {
+    let mut config = load_network_config();
+    config.vpn_overrides.remove(&name);
+    save_network_config(&config);
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+/// The commands the Docker integration tests in tests/ drive, callable without a
+/// window. Servers are published on 127.0.0.1.
+#[cfg(feature = "docker-tests")]
+pub mod testing {
+    use super::*;
+
+    pub fn create_server(config: ServerConfig) -> CreateResult {
+        let app = tauri::test::mock_app();
+        create_server_internal(
+            app.handle(),
+            &PortReservations::default(),
+            config,
+            Some("127.0.0.1".to_string()),
+        )
+    }
+
+    pub fn list_servers() -> Vec<ServerInfo> {
+        super::list_servers()
+    }
+
+    pub fn start_server(name: &str) -> CommandResult {
+        super::start_server(name.to_string())
+    }
+
+    pub fn stop_server(name: &str) -> CommandResult {
+        super::stop_server(name.to_string())
+    }
+
+    pub fn remove_server(name: &str) -> CommandResult {
+        super::remove_server(name.to_string(), None, None, None)
+    }
+
+    pub fn container_logs(name: &str) -> String {
+        get_container_logs(name.to_string(), 100, None)
+    }
+
+    pub fn list_files(name: &str, path: &str) -> Result<Vec<FileEntry>, String> {
+        list_files_internal(name.to_string(), path.to_string(), None)
+    }
+
+    pub fn upload_files(
+        name: &str,
+        local_paths: &[String],
+        remote_dir: &str,
+    ) -> Result<UploadResult, String> {
+        let app = tauri::test::mock_app();
+        upload_files_internal(
+            app.handle(),
+            &new_transfer_id(),
+            name,
+            local_paths,
+            remote_dir,
+            ConflictPolicy::default(),
+            false,
+        )
+    }
+
+    pub fn move_path(name: &str, src: &str, dst: &str) -> CommandResult {
+        tauri::async_runtime::block_on(super::move_path(
+            name.to_string(),
+            src.to_string(),
+            dst.to_string(),
+        ))
+    }
+
+    /// Replace the server's cleanup rules with `rules` and run them
+    pub fn run_cleanup(name: &str, rules: Vec<CleanupRule>) -> Result<CleanupReport, String> {
+        let result = set_cleanup_rules(name.to_string(), rules);
+        if !result.success {
+            return Err(result.error.unwrap_or_default());
+        }
+        super::run_cleanup(name, false)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct DaemonInfo {
+    pid: u32,
+    port: u16,
+    /// First line a client must send; the file is only readable by the user
+    token: String,
+}
+
+/// One relayed event, sent as a JSON line
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonEvent {
+    event: String,
+    payload: serde_json::Value,
+}
+
+fn context() -> tauri::Context<tauri::Wry> {
+    tauri::generate_context!()
+}
+
+/// Connect to a running daemon, if there is one
+fn connect_daemon() -> Option<std::net::TcpStream> {
+    let info: DaemonInfo = read_json_file(&get_config_dir().join(DAEMON_FILE));
+    if info.port == 0 {
+        return None;
+    }
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], info.port));
+    let mut stream = std::net::TcpStream::connect_timeout(&addr, DAEMON_CONNECT_TIMEOUT).ok()?;
+    writeln!(stream, "{}", info.token).ok()?;
+    Some(stream)
+}
+
+/// Relay a daemon's watcher events to this GUI's windows, sampling stats locally in
+/// between since they feed this process's state. When the daemon goes away the GUI
+/// takes the watchers and the HTTP API over itself.
+fn spawn_daemon_relay(app: AppHandle, stream: std::net::TcpStream) {
+    std::thread::spawn(move || {
+        stream.set_read_timeout(Some(SERVER_WATCH_INTERVAL)).ok();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        let mut cpu_high_since: HashMap<String, Instant> = HashMap::new();
+        let mut last_sample = Instant::now();
+        loop {
+            match reader.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if let Ok(event) = serde_json::from_str::<DaemonEvent>(&line) {
+                        let _ = app.emit(&event.event, event.payload);
+                    }
+                    line.clear();
+                }
+                // A partial line stays in `line` until the rest arrives
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(_) => break,
+            }
+            if last_sample.elapsed() >= SERVER_WATCH_INTERVAL {
+                last_sample = Instant::now();
+                sample_stats(&app, &list_servers());
+                evaluate_alerts(&app, &mut cpu_high_since);
+            }
+        }
+        spawn_server_watcher(app);
+        spawn_api_server();
+    });
+}
+
+/// Accept GUI connections and stream the watcher's events to each one that sends
+/// the token
+fn spawn_daemon_ipc(listener: std::net::TcpListener, token: String) {
+    let clients: Arc<Mutex<Vec<std::net::TcpStream>>> = Arc::default();
+    let relay_clients = clients.clone();
+    events::subscribe("daemon relay", move |event| {
+        if !event.from_watcher() {
+            return;
+        }
+        let relayed = DaemonEvent {
+            event: event.name().to_string(),
+            payload: event.payload(),
+        };
+        let Ok(line) = serde_json::to_string(&relayed) else {
+            return;
+        };
+        if let Ok(mut clients) = relay_clients.lock() {
+            clients.retain_mut(|client| writeln!(client, "{}", line).is_ok());
+        }
+    });
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            stream.set_read_timeout(Some(DAEMON_CONNECT_TIMEOUT)).ok();
+            let Ok(reader) = stream.try_clone() else {
+                continue;
+            };
+            let mut line = String::new();
+            if BufReader::new(reader).read_line(&mut line).is_err() || line.trim() != token {
+                continue;
+            }
+            // Events are written under the client list's lock; a GUI that stops
+            // reading is dropped instead of stalling the others
+            stream.set_write_timeout(Some(DAEMON_CONNECT_TIMEOUT)).ok();
+            if let Ok(mut clients) = clients.lock() {
+                clients.push(stream);
+            }
+        }
+    });
+}
+
+/// Headless agent started with `--daemon`: runs the server watcher (auto-heal,
+/// alerts, cleanup, ingest, password rotation) without a window, so it keeps working
+/// while the GUI is closed. GUIs started meanwhile relay its events over a loopback
+/// socket instead of running a second watcher.
+pub fn run_daemon() {
+    let mut context = context();
+    context.config_mut().app.windows.clear();
+    context.config_mut().app.tray_icon = None;
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_log::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .manage(PortReservations::default())
+        .manage(StatsCache::default())
+        .manage(ActiveAlerts::default())
+        .manage(LandingPages::default())
+        .manage(PairingHost::default())
+        .manage(PairedPeers::default())
+        .setup(|app| {
+            // Checked once the log plugin is up, so the warning is recorded
+            if connect_daemon().is_some() {
+                log::warn!("dsftp daemon is already running");
+                app.handle().exit(0);
+                return Ok(());
+            }
+            migrate_data_dir();
+            migrate_server_ids();
+            migrate_token_digests();
+            let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+            let info = DaemonInfo {
+                pid: std::process::id(),
+                port: listener.local_addr()?.port(),
+                token: random_token(),
+            };
+            write_private_json_file(&get_config_dir().join(DAEMON_FILE), &info);
+            events::record_to_history();
+            notify::start(app.handle().clone());
+            spawn_daemon_ipc(listener, info.token);
+            spawn_server_watcher(app.handle().clone());
+            spawn_api_server();
+            Ok(())
+        })
+        .build(context)
+        .expect("error while starting the dsftp daemon")
+        .run(|_, event| {
+            // There are no windows whose closing should end the process
+            if let tauri::RunEvent::ExitRequested {
+                api, code: None, ..
+            } = event
+            {
+                api.prevent_exit();
+            }
+        });
+}
+
+/// Calls of one command since startup, for `get_command_metrics`
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct CommandMetrics {
+    pub command: String,
+    #[ts(type = "number")]
+    pub calls: u64,
+    /// Turned away by the rate limit or argument checks
+    #[ts(type = "number")]
+    pub rejected: u64,
+    /// Time spent in the handler itself; async commands return once their work
+    /// is spawned, so only the blocking part of a call is counted
+    #[ts(type = "number")]
+    pub total_ms: u64,
+    #[ts(type = "number")]
+    pub max_ms: u64,
+    #[ts(type = "number")]
+    pub last_request_id: u64,
+}
+
+/// Calls of a command in the current one-second window
+struct CommandWindow {
+    started: Instant,
+    calls: u32,
+}
+
+fn command_rate_limit(command: &str) -> u32 {
+    COMMAND_RATE_LIMITS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or(DEFAULT_COMMAND_RATE_LIMIT, |(_, limit)| *limit)
+}
+
+fn rate_limited(command: &str) -> bool {
+    let Ok(mut windows) = COMMAND_WINDOWS.lock() else {
+        return false;
+    };
+    let window = windows
+        .entry(command.to_string())
+        .or_insert_with(|| CommandWindow {
+            started: Instant::now(),
+            calls: 0,
+        });
+    if window.started.elapsed() >= Duration::from_secs(1) {
+        window.started = Instant::now();
+        window.calls = 0;
+    }
+    window.calls += 1;
+    window.calls > command_rate_limit(command)
+}
+
+/// Checks every command's arguments share: an object, of bounded size, with no
+/// NUL bytes that would cut a docker argument or path short
+fn check_command_args(body: &tauri::ipc::InvokeBody) -> Result<(), String> {
+    fn has_nul(value: &serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::String(s) => s.contains('\0'),
+            serde_json::Value::Array(items) => items.iter().any(has_nul),
+            serde_json::Value::Object(fields) => fields.values().any(has_nul),
+            _ => false,
+        }
+    }
+    let tauri::ipc::InvokeBody::Json(value) = body else {
+        return Ok(());
+    };
+    if !value.is_object() && !value.is_null() {
+        return Err("Arguments must be an object".to_string());
+    }
+    let size = serde_json::to_vec(value).map_or(0, |v| v.len());
+    if size > MAX_COMMAND_ARGS_BYTES {
+        return Err(format!("Arguments are too large ({} bytes)", size));
+    }
+    if has_nul(value) {
+        return Err("Arguments contain a NUL byte".to_string());
+    }
+    Ok(())
+}
+
+fn record_command_call(command: &str, request_id: u64, elapsed: Option<Duration>) {
+    let Ok(mut metrics) = COMMAND_METRICS.lock() else {
+        return;
+    };
+    let entry = metrics
+        .entry(command.to_string())
+        .or_insert_with(|| CommandMetrics {
+            command: command.to_string(),
+            ..Default::default()
+        });
+    entry.calls += 1;
+    entry.last_request_id = request_id;
+    match elapsed {
+        Some(elapsed) => {
+            let ms = elapsed.as_millis() as u64;
+            entry.total_ms += ms;
+            entry.max_ms = entry.max_ms.max(ms);
+        }
+        None => entry.rejected += 1,
+    }
+}
+
+/// Runs in front of every command: numbers the request, applies the rate limit
+/// and argument checks, and times the handler
+fn command_middleware<R: Runtime>(
+    invoke: tauri::ipc::Invoke<R>,
+    handler: &impl Fn(tauri::ipc::Invoke<R>) -> bool,
+) -> bool {
+    let command = invoke.message.command().to_string();
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let rejection = if rate_limited(&command) {
+        Err(format!(
+            "{} was called more than {} times a second",
+            command,
+            command_rate_limit(&command)
+        ))
+    } else {
+        check_command_args(invoke.message.payload())
+    };
+    if let Err(reason) = rejection {
+        record_command_call(&command, request_id, None);
+        invoke
+            .resolver
+            .reject(format!("Request {} refused: {}", request_id, reason));
+        return true;
+    }
+
+    let started = Instant::now();
+    let handled = handler(invoke);
+    let elapsed = started.elapsed();
+    if elapsed >= SLOW_COMMAND_THRESHOLD {
+        log::warn!(
+            "{} (request {}) blocked for {} ms",
+            command,
+            request_id,
+            elapsed.as_millis()
+        );
+    }
+    record_command_call(&command, request_id, Some(elapsed));
+    handled
+}
+
+fn with_command_middleware<R: Runtime>(
+    handler: impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| command_middleware(invoke, &handler)
+}
+
+/// Per-command call counts and timing, slowest first
+#[tauri::command]
+fn get_command_metrics() -> Vec<CommandMetrics> {
+    let mut metrics: Vec<CommandMetrics> = COMMAND_METRICS
+        .lock()
+        .map(|m| m.values().cloned().collect())
+        .unwrap_or_default();
+    metrics.sort_by(|a, b| b.max_ms.cmp(&a.max_ms));
+    metrics
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_log::Builder::new().build())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(PortReservations::default())
+        .manage(StatsCache::default())
+        .manage(ActiveAlerts::default())
+        .manage(LandingPages::default())
+        .manage(PairingHost::default())
+        .manage(PairedPeers::default())
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                close_window_channels(window.label());
+            }
+        })
+        .setup(|app| {
+            migrate_data_dir();
+            migrate_server_ids();
+            migrate_token_digests();
+            events::bridge_to_frontend(app.handle().clone());
+            events::record_to_history();
+            notify::start(app.handle().clone());
+            check_bindings_at_startup();
+            spawn_config_watcher();
+            // A running daemon owns the watchers and the API; relay its events rather than
+            // double them
+            match connect_daemon() {
+                Some(stream) => spawn_daemon_relay(app.handle().clone(), stream),
+                None => {
+                    spawn_server_watcher(app.handle().clone());
+                    spawn_api_server();
+                }
+            }
+            Ok(())
+        })
+        .invoke_handler(with_command_middleware(tauri::generate_handler![
             check_docker,
             get_local_ip,
             list_servers,
+            describe_server,
+            reveal_credentials,
+            pull_image,
             create_server,
+            validate_server_config,
+            get_capacity_report,
+            get_port_range,
+            set_port_range,
+            get_secret_store,
+            get_locale,
+            set_locale,
+            get_storage_paths,
+            set_data_dir,
+            set_secret_store,
+            list_port_allocations,
             start_server,
+            start_servers,
+            set_server_dependencies,
+            rotate_password,
+            set_password_rotation,
+            set_password_self_service,
+            set_account_locked,
+            import_users,
+            set_rate_limit,
+            set_banner,
+            get_image_build,
+            set_image_build,
+            set_relay,
+            set_on_demand,
+            set_idle_policy,
+            tunnels::set_tunnel,
+            tunnels::list_tunnels,
+            notify::get_notification_channels,
+            notify::set_notification_channels,
+            notify::get_smtp_settings,
+            notify::set_smtp_settings,
+            notify::send_test_email,
+            antivirus::get_scan_engine,
+            antivirus::set_scan_engine,
+            antivirus::set_virus_scan,
+            antivirus::list_quarantine,
+            antivirus::delete_quarantined,
+            antivirus::restore_quarantined,
+            tiering::get_tiering_rules,
+            tiering::set_tiering_rules,
+            tiering::run_tiering_now,
+            tiering::list_tiered,
+            tiering::restore_tiered,
+            pending::get_pending_operations,
+            pending::resolve_pending_operation,
+            schedule::preview_schedule,
+            get_api_settings,
+            set_api_settings,
+            create_api_token,
+            list_api_tokens,
+            revoke_api_token,
+            get_api_policy,
+            set_api_token_role,
+            rotate_ingest_token,
+            clear_ingest_token,
+            create_share_link,
+            list_share_links,
+            revoke_share_link,
+            create_snapshot_share,
+            list_snapshot_shares,
+            verify_image,
+            get_security_status,
+            get_container_diff,
+            cleanup_docker_artifacts,
+            list_sessions,
+            kill_session,
+            enter_maintenance,
+            exit_maintenance,
+            create_volume_index,
+            list_volume_indexes,
+            diff_volume,
+            probe_client_compat,
+            verify_hardening,
+            init_ssh_ca,
+            get_ssh_ca_public_key,
+            issue_certificate,
             stop_server,
+            pause_server,
+            resume_server,
+            set_restart_policy,
             remove_server,
+            list_removed_servers,
+            restore_removed_server,
+            delete_removed_server,
+            get_dashboard_summary,
+            global_search,
+            get_suggestions,
+            get_command_metrics,
+            dismiss_suggestion,
+            get_auto_heal_policy,
+            set_auto_heal_policy,
+            get_server_stats,
+            list_active_alerts,
+            get_alert_thresholds,
+            set_alert_thresholds,
+            get_history,
+            get_transfer_history,
+            get_active_transfers,
             get_container_status,
+            get_server_details,
+            start_landing_page,
+            stop_landing_page,
+            list_landing_pages,
+            start_pairing,
+            stop_pairing,
+            get_pairing,
+            discover_peers,
+            pair_with_peer,
+            list_paired_peers,
+            list_peer_servers,
+            push_file_to_peer,
+            pull_file_from_peer,
+            replicate_server_from_peer,
+            get_uptime_summary,
             get_container_logs,
+            list_persisted_logs,
+            get_persisted_logs,
+            get_log_retention,
+            set_log_retention,
+            follow_logs,
+            stop_log_stream,
+            open_server_window,
             list_files,
+            list_files_streaming,
+            cancel_listing,
+            cancel_operation,
+            adopt_server,
+            find_duplicates,
+            analyze_storage,
+            add_bookmark,
+            remove_bookmark,
+            list_bookmarks,
+            get_ui_state,
+            set_ui_state,
+            clear_ui_state,
+            get_cleanup_rules,
+            set_cleanup_rules,
+            preview_cleanup,
+            run_cleanup_now,
+            get_init_scripts,
+            set_init_scripts,
+            set_exclude_patterns,
+            get_ingest_rules,
+            set_ingest_rules,
+            get_file_policy,
+            set_file_policy,
+            reveal_host_path,
+            mount_as_network_drive,
+            sync_config_repo,
+            list_backup_targets,
+            set_backup_target,
+            backup_server,
+            list_pending_backups,
+            resume_backup,
+            discard_backup,
+            transfer_between_servers,
+            move_path,
+            copy_path,
+            read_file_text,
+            write_file_text,
+            preflight_upload,
+            upload_files,
+            upload_clipboard,
             list_network_interfaces,
             get_network_info,
             set_network_preference,
             clear_network_preference,
-        ])
-        .run(tauri::generate_context!())
+            check_client_reachability,
+            set_vpn_patterns,
+            mark_interface_as_vpn,
+            clear_interface_vpn_override,
+            get_network_history,
+            rebind_servers,
+            list_binding_issues,
+        ]))
+        .run(context())
         .expect("error while running tauri application");
 }
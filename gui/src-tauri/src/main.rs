@@ -1,5 +1,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    sftp_manager_lib::run()
+    if std::env::args().any(|arg| arg == "--daemon") {
+        sftp_manager_lib::run_daemon()
+    } else {
+        sftp_manager_lib::run()
+    }
 }
@@ -0,0 +1,461 @@
+//! Per-server routing of the events that call for attention: desktop
+//! notifications, webhooks and email. Each server's channels are set with
+//! `set_notification_channels`; one without any set notifies on the desktop, so a
+//! noisy dev share can be sent elsewhere or silenced without touching the others.
+//!
+//! Email goes out through the SMTP server in the app settings, whose password is
+//! kept in the secret store like the servers' own.
+
+use super::{
+    current_locale,
+    events::{self, Event},
+    find_server, format_size, load_app_settings, load_credentials,
+    pending::ReplayOutcome,
+    record_audit, secret_store, update_app_settings, update_credentials, CommandResult,
+    PolicyAction, SMTP_SECRET, WEBHOOK_TIMEOUT,
+};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export)]
+pub enum NotificationChannel {
+    Desktop,
+    /// Gets each notification POSTed as JSON: event, server, title, message and
+    /// the event's own payload
+    Webhook {
+        url: String,
+    },
+    Email {
+        to: String,
+    },
+}
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Used for events without a template of their own
+const DEFAULT_EMAIL_SUBJECT: &str = "[dsftp] {title}";
+const DEFAULT_EMAIL_BODY: &str = "{message}\n\nServer: {server}\nEvent: {event}\nTime: {time}\n";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum SmtpSecurity {
+    /// Implicit TLS, usually port 465
+    Tls,
+    /// Upgraded with STARTTLS, usually port 587
+    #[default]
+    StartTls,
+    /// Unencrypted; only for a relay on a trusted network
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct EmailTemplate {
+    /// `{server}`, `{event}`, `{title}`, `{message}` and `{time}` are filled in
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Never returned by `get_smtp_settings`; saving it empty keeps the stored one.
+    /// Only kept here while the secret store is the JSON file.
+    #[serde(default)]
+    pub password: String,
+    /// Sender, e.g. "dsftp <alerts@example.com>"
+    pub from: String,
+    /// By event name ("server-down", "low-disk-space", ...)
+    #[serde(default)]
+    pub templates: HashMap<String, EmailTemplate>,
+}
+
+/// Where a server notifies when nothing was set
+fn default_channels() -> Vec<NotificationChannel> {
+    vec![NotificationChannel::Desktop]
+}
+
+/// Title and text of the notification for `event`; `None` for events that don't
+/// warrant one
+fn describe(event: &Event) -> Option<(String, String)> {
+    let locale = current_locale();
+    match event {
+        Event::ServerDown(e) => Some((format!("{} is down", e.server), e.reason.clone())),
+        // The first restart attempt comes with ServerDown
+        Event::AutoHeal(e) => match e.outcome.as_str() {
+            "recovered" => Some((
+                format!("{} recovered", e.server),
+                format!("Back up after {} restart attempt(s)", e.attempt),
+            )),
+            "gave_up" => Some((
+                format!("{} could not be restarted", e.server),
+                e.error
+                    .clone()
+                    .unwrap_or_else(|| format!("Gave up after {} attempts", e.attempt)),
+            )),
+            _ => None,
+        },
+        Event::FileIngested(e) => Some((
+            format!("New file on {}", e.server),
+            match &e.error {
+                None => format!("{} ({})", e.path, format_size(e.size, &locale)),
+                Some(error) => format!("{} could not be processed: {}", e.path, error),
+            },
+        )),
+        Event::PasswordRotated(e) => Some((
+            format!("Password of {} changed", e.server),
+            format!("{} has a new password", e.username),
+        )),
+        Event::LowDiskSpace(e) => Some((
+            format!("{} is low on disk space", e.server),
+            format!("{} left on {}", format_size(e.available, &locale), e.path),
+        )),
+        Event::AlertRaised(e) => Some((format!("Alert on {}", e.server), e.message.clone())),
+        Event::AlertCleared(e) => {
+            Some((format!("Alert cleared on {}", e.server), e.message.clone()))
+        }
+        Event::BindingIssue(e) => Some((
+            format!("{} is bound to a missing address", e.server),
+            format!(
+                "{} no longer exists on this machine; rebind to {}",
+                e.bind_ip, e.suggested_ip
+            ),
+        )),
+        Event::IdleStopped(e) => Some(match &e.error {
+            None => (
+                format!("{} stopped while idle", e.server),
+                format!(
+                    "No sessions or transfers for {} minutes. {}",
+                    e.idle_minutes,
+                    if e.on_demand {
+                        "The next connection starts it again."
+                    } else {
+                        "Start it again when it's needed."
+                    }
+                ),
+            ),
+            Some(error) => (
+                format!("{} could not be stopped while idle", e.server),
+                error.clone(),
+            ),
+        }),
+        Event::VirusFound(e) => Some((
+            format!("Infected file on {}", e.server),
+            match &e.error {
+                None => format!("{} ({}) was quarantined", e.path, e.signature),
+                Some(error) => format!(
+                    "{} ({}) could not be quarantined and is still shared: {}",
+                    e.path, e.signature, error
+                ),
+            },
+        )),
+        Event::PolicyViolation(e) => Some((
+            format!("Upload refused on {}", e.server),
+            match (&e.error, e.action) {
+                (Some(error), _) => format!(
+                    "{}: {}, but it was left in place: {}",
+                    e.path, e.rule, error
+                ),
+                (None, PolicyAction::Quarantine) => format!("{}: {}; quarantined", e.path, e.rule),
+                (None, PolicyAction::Delete) => format!("{}: {}; deleted", e.path, e.rule),
+            },
+        )),
+        Event::OperationReplayed(e) => {
+            let error = e.error.clone().unwrap_or_default();
+            match e.outcome {
+                ReplayOutcome::Applied => None,
+                ReplayOutcome::Conflict => Some((
+                    format!("Queued change to {} held back", e.server),
+                    format!("{}. Apply or discard it in pending operations.", error),
+                )),
+                ReplayOutcome::Failed => {
+                    Some((format!("Queued change to {} failed", e.server), error))
+                }
+            }
+        }
+        Event::NetworkChanged(_)
+        | Event::CredentialsChanged(_)
+        | Event::ConfigExternallyChanged(_) => None,
+    }
+}
+
+fn deliver(
+    app: &AppHandle,
+    channel: &NotificationChannel,
+    event: &Event,
+    title: &str,
+    message: &str,
+) -> Result<(), String> {
+    match channel {
+        NotificationChannel::Desktop => app
+            .notification()
+            .builder()
+            .title(title)
+            .body(message)
+            .show()
+            .map_err(|e| e.to_string()),
+        NotificationChannel::Webhook { url } => ureq::post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .send_json(serde_json::json!({
+                "event": event.name(),
+                "server": event.server(),
+                "title": title,
+                "message": message,
+                "payload": event.payload(),
+            }))
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        NotificationChannel::Email { to } => {
+            let smtp = smtp_settings()?;
+            let time = chrono::Local::now().to_rfc3339();
+            let fields = [
+                ("server", event.server().unwrap_or("")),
+                ("event", event.name()),
+                ("title", title),
+                ("message", message),
+                ("time", &time),
+            ];
+            let (subject, body) = smtp
+                .templates
+                .get(event.name())
+                .map_or((DEFAULT_EMAIL_SUBJECT, DEFAULT_EMAIL_BODY), |template| {
+                    (template.subject.as_str(), template.body.as_str())
+                });
+            send_email(&smtp, to, &render(subject, &fields), &render(body, &fields))
+        }
+    }
+}
+
+fn render(template: &str, fields: &[(&str, &str)]) -> String {
+    fields
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// The SMTP settings with their password
+fn smtp_settings() -> Result<SmtpSettings, String> {
+    let settings = load_app_settings();
+    let mut smtp = settings.smtp.ok_or("SMTP isn't configured")?;
+    let store = secret_store(settings.secret_store);
+    if !store.inline() {
+        smtp.password = store.get(SMTP_SECRET)?.unwrap_or_default();
+    }
+    Ok(smtp)
+}
+
+fn send_email(smtp: &SmtpSettings, to: &str, subject: &str, body: &str) -> Result<(), String> {
+    let from: Mailbox = smtp
+        .from
+        .parse()
+        .map_err(|e| format!("Invalid sender {}: {}", smtp.from, e))?;
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| format!("Invalid recipient {}: {}", to, e))?;
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?;
+    let mut transport = match smtp.security {
+        SmtpSecurity::Tls => SmtpTransport::relay(&smtp.host).map_err(|e| e.to_string())?,
+        SmtpSecurity::StartTls => {
+            SmtpTransport::starttls_relay(&smtp.host).map_err(|e| e.to_string())?
+        }
+        SmtpSecurity::None => SmtpTransport::builder_dangerous(&smtp.host),
+    }
+    .port(smtp.port)
+    .timeout(Some(SMTP_TIMEOUT));
+    if let Some(username) = smtp.username.as_ref().filter(|u| !u.is_empty()) {
+        transport =
+            transport.credentials(Credentials::new(username.clone(), smtp.password.clone()));
+    }
+    transport
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Send every notable server event to that server's channels
+pub fn start(app: AppHandle) {
+    events::subscribe("notifications", move |event| {
+        let Some(server) = event.server() else {
+            return;
+        };
+        let Some((title, message)) = describe(event) else {
+            return;
+        };
+        let channels = load_credentials()
+            .get(server)
+            .and_then(|c| c.notification_channels.clone())
+            .unwrap_or_else(default_channels);
+        for channel in &channels {
+            if let Err(e) = deliver(&app, channel, event, &title, &message) {
+                log::warn!("Notifying {:?} for {} failed: {}", channel, server, e);
+            }
+        }
+    });
+}
+
+fn check_channel(channel: &NotificationChannel) -> Result<(), String> {
+    match channel {
+        NotificationChannel::Desktop => Ok(()),
+        NotificationChannel::Webhook { url } => {
+            if url.starts_with("http://") || url.starts_with("https://") {
+                Ok(())
+            } else {
+                Err(format!("Invalid webhook URL: {}", url))
+            }
+        }
+        NotificationChannel::Email { to } => {
+            let valid = to
+                .split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+                && !to.chars().any(|c| c.is_whitespace() || c.is_control());
+            if valid {
+                Ok(())
+            } else {
+                Err(format!("Invalid email address: {}", to))
+            }
+        }
+    }
+}
+
+/// Where a server's notifications go
+#[tauri::command]
+pub fn get_notification_channels(name: String) -> Vec<NotificationChannel> {
+    load_credentials()
+        .get(&name)
+        .and_then(|c| c.notification_channels.clone())
+        .unwrap_or_else(default_channels)
+}
+
+/// Route a server's notifications to `channels`; an empty list silences it
+#[tauri::command]
+pub fn set_notification_channels(
+    name: String,
+    channels: Vec<NotificationChannel>,
+) -> CommandResult {
+    let result = channels.iter().try_for_each(check_channel).and_then(|_| {
+        find_server(&name)?;
+        update_credentials(|all| {
+            all.get_mut(&name)
+                .map(|creds| creds.notification_channels = Some(channels.clone()))
+        })
+        .ok_or_else(|| format!("{} has no stored settings", name))
+    });
+    record_audit(
+        &name,
+        "set-notification-channels",
+        result.as_ref().err().map(String::as_str),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// SMTP settings for email notifications, without the password
+#[tauri::command]
+pub fn get_smtp_settings() -> Option<SmtpSettings> {
+    load_app_settings().smtp.map(|mut smtp| {
+        smtp.password.clear();
+        smtp
+    })
+}
+
+fn save_smtp_settings(smtp: Option<SmtpSettings>) -> Result<(), String> {
+    // The store takes the config lock itself, so it's written outside the update
+    let store = secret_store(load_app_settings().secret_store);
+    match smtp {
+        Some(mut smtp) => {
+            if smtp.host.trim().is_empty() {
+                return Err("SMTP host is required".to_string());
+            }
+            smtp.from
+                .parse::<Mailbox>()
+                .map_err(|e| format!("Invalid sender {}: {}", smtp.from, e))?;
+            if !smtp.password.is_empty() && !store.inline() {
+                store.set(SMTP_SECRET, &smtp.password)?;
+                smtp.password.clear();
+            }
+            update_app_settings(|settings| {
+                if smtp.password.is_empty() && store.inline() {
+                    smtp.password = settings
+                        .smtp
+                        .as_ref()
+                        .map(|old| old.password.clone())
+                        .unwrap_or_default();
+                }
+                settings.smtp = Some(smtp);
+            });
+        }
+        None => {
+            if !store.inline() {
+                store.remove(SMTP_SECRET)?;
+            }
+            update_app_settings(|settings| settings.smtp = None);
+        }
+    }
+    Ok(())
+}
+
+/// Save the SMTP server email notifications go through, or drop it with `None`.
+/// Saved with an empty password, the stored one is kept.
+#[tauri::command]
+pub fn set_smtp_settings(settings: Option<SmtpSettings>) -> CommandResult {
+    let result = save_smtp_settings(settings);
+    CommandResult {
+        success: result.is_ok(),
+        error: result.err(),
+    }
+}
+
+/// Send a test message through the saved SMTP settings, to `to` or else the sender
+#[tauri::command]
+pub async fn send_test_email(to: Option<String>) -> CommandResult {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let smtp = smtp_settings()?;
+        let to = to
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| smtp.from.clone());
+        send_email(
+            &smtp,
+            &to,
+            "dsftp test email",
+            "This message confirms dsftp can send notifications with these SMTP settings.\n",
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    CommandResult {
+        success: result.is_ok(),
+        error: result.err(),
+    }
+}
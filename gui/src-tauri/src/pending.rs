@@ -0,0 +1,309 @@
+//! Changes made while the Docker daemon can't be reached (stopped, restarting, or a
+//! remote `DOCKER_HOST` that dropped off the network). Instead of failing, they are
+//! queued in the data folder and replayed in order by the server watcher once
+//! Docker answers again.
+//!
+//! A change is held back rather than replayed when its server was removed or
+//! replaced meanwhile, or when the setting it changes no longer has the value it
+//! had when queued (edited in the config file or by another dsftp). The user then
+//! applies or discards it with `resolve_pending_operation`.
+
+use super::{
+    events::{self, Event},
+    get_data_dir, list_servers, load_credentials, random_token, read_json_file, record_audit,
+    remove_server, run_command, server_id, set_banner, set_rate_limit, set_restart_policy,
+    update_json_file, write_audit_entry, AuditEntry, CommandResult, RateLimit, RestartPolicy,
+    ServerInfo, PENDING_OPERATIONS_FILE,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::path::PathBuf;
+use ts_rs::TS;
+
+thread_local! {
+    /// Set while a queued change is applied, so Docker dropping out mid-replay
+    /// doesn't queue it a second time
+    static REPLAYING: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum QueuedChange {
+    Banner {
+        text: Option<String>,
+    },
+    RateLimit {
+        config: Option<RateLimit>,
+    },
+    RestartPolicy {
+        policy: RestartPolicy,
+    },
+    Remove {
+        archive: Option<bool>,
+        include_volume: Option<bool>,
+    },
+}
+
+impl QueuedChange {
+    /// Audit action of the command that applies it
+    fn action(&self) -> &'static str {
+        match self {
+            QueuedChange::Banner { .. } => "set-banner",
+            QueuedChange::RateLimit { .. } => "set-rate-limit",
+            QueuedChange::RestartPolicy { .. } => "set-restart-policy",
+            QueuedChange::Remove { .. } => "remove",
+        }
+    }
+
+    /// The stored setting it changes, for telling whether it moved while queued.
+    /// Restart policies live in Docker only and removals change no setting.
+    fn current(&self, server: &str) -> Option<serde_json::Value> {
+        let creds = load_credentials().remove(server)?;
+        let value = match self {
+            QueuedChange::Banner { .. } => serde_json::to_value(creds.banner),
+            QueuedChange::RateLimit { .. } => serde_json::to_value(creds.rate_limit),
+            QueuedChange::RestartPolicy { .. } | QueuedChange::Remove { .. } => return None,
+        };
+        value.ok()
+    }
+
+    fn apply(&self, server: &str) -> CommandResult {
+        let name = server.to_string();
+        REPLAYING.set(true);
+        let result = match self.clone() {
+            QueuedChange::Banner { text } => set_banner(name, text),
+            QueuedChange::RateLimit { config } => set_rate_limit(name, config),
+            QueuedChange::RestartPolicy { policy } => set_restart_policy(name, policy),
+            QueuedChange::Remove {
+                archive,
+                include_volume,
+            } => remove_server(name, archive, include_volume, None),
+        };
+        REPLAYING.set(false);
+        result
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PendingOperation {
+    pub id: String,
+    pub server: String,
+    /// Replay only touches the server that had this id when the change was made
+    pub server_id: Option<String>,
+    pub change: QueuedChange,
+    /// The stored setting when the change was queued
+    #[ts(type = "unknown")]
+    pub base: Option<serde_json::Value>,
+    pub queued_at: String,
+    /// Why replay held the change back; it waits for `resolve_pending_operation`
+    pub conflict: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ReplayOutcome {
+    Applied,
+    /// Held back until the user resolves it
+    Conflict,
+    /// Docker refused it; it was dropped from the queue
+    Failed,
+}
+
+/// Published for every queued change the watcher (or a resolution) replays
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ReplayedOperation {
+    pub server: String,
+    pub operation: PendingOperation,
+    pub outcome: ReplayOutcome,
+    /// The conflict or the command's error
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Resolution {
+    /// Replay it anyway, over whatever changed meanwhile
+    Apply,
+    Discard,
+}
+
+fn queue_path() -> PathBuf {
+    get_data_dir().join(PENDING_OPERATIONS_FILE)
+}
+
+fn load_queue() -> Vec<PendingOperation> {
+    read_json_file(&queue_path())
+}
+
+/// Whether the Docker daemon (not just the CLI) answers
+fn docker_reachable() -> bool {
+    run_command("docker", &["version", "--format", "{{.Server.Version}}"]).is_ok()
+}
+
+/// Queue `change` when Docker can't be reached, returning what the command should
+/// answer; `None` means Docker is up and the command goes ahead. A change to a
+/// setting already queued for the server replaces that one, keeping its base.
+pub(crate) fn defer(server: &str, change: QueuedChange) -> Option<CommandResult> {
+    // Only servers dsftp manages are queued; anything else fails as before
+    if REPLAYING.get() || !load_credentials().contains_key(server) || docker_reachable() {
+        return None;
+    }
+    let action = change.action();
+    let mut operation = PendingOperation {
+        id: random_token(),
+        server: server.to_string(),
+        server_id: server_id(server),
+        base: change.current(server),
+        change,
+        queued_at: chrono::Local::now().to_rfc3339(),
+        conflict: None,
+    };
+    update_json_file(&queue_path(), |queue: &mut Vec<PendingOperation>| {
+        if let Some(index) = queue
+            .iter()
+            .position(|op| op.server == server && op.change.action() == action)
+        {
+            let replaced = queue.remove(index);
+            operation.base = replaced.base;
+        }
+        queue.push(operation.clone());
+    });
+    write_audit_entry(AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        server: server.to_string(),
+        server_id: operation.server_id.clone(),
+        action: format!("queue-{}", action),
+        success: true,
+        detail: Some("Docker is unreachable; applied once it is back".to_string()),
+    });
+    Some(CommandResult {
+        success: true,
+        error: None,
+    })
+}
+
+/// Why `operation` can't be replayed as queued, if it can't
+fn conflict(operation: &PendingOperation, servers: &[ServerInfo]) -> Option<String> {
+    let Some(server) = servers.iter().find(|s| s.name == operation.server) else {
+        return Some(format!("{} no longer exists", operation.server));
+    };
+    if operation.server_id.is_some() && server.id != operation.server_id {
+        return Some(format!(
+            "{} was replaced by another server of that name",
+            operation.server
+        ));
+    }
+    let current = operation.change.current(&operation.server);
+    if operation.base.is_some() && current != operation.base {
+        return Some(format!(
+            "The setting changed after this was queued; it is now {}",
+            current.unwrap_or_default()
+        ));
+    }
+    None
+}
+
+/// Apply `operation` unless it conflicts, dropping it from the queue unless it's
+/// held back. Returns false when Docker went away again, leaving it queued.
+fn replay(operation: PendingOperation, servers: &[ServerInfo], force: bool) -> bool {
+    let conflict = (!force).then(|| conflict(&operation, servers)).flatten();
+    let (outcome, error) = match conflict {
+        Some(reason) => {
+            let id = operation.id.clone();
+            let held = reason.clone();
+            update_json_file(&queue_path(), |queue: &mut Vec<PendingOperation>| {
+                if let Some(op) = queue.iter_mut().find(|op| op.id == id) {
+                    op.conflict = Some(held);
+                }
+            });
+            (ReplayOutcome::Conflict, Some(reason))
+        }
+        None => {
+            let result = operation.change.apply(&operation.server);
+            if !result.success && !docker_reachable() {
+                return false;
+            }
+            let id = operation.id.clone();
+            update_json_file(&queue_path(), |queue: &mut Vec<PendingOperation>| {
+                queue.retain(|op| op.id != id);
+            });
+            if result.success {
+                (ReplayOutcome::Applied, None)
+            } else {
+                (ReplayOutcome::Failed, result.error)
+            }
+        }
+    };
+    events::publish(Event::OperationReplayed(ReplayedOperation {
+        server: operation.server.clone(),
+        operation,
+        outcome,
+        error,
+    }));
+    true
+}
+
+/// Replay the queue in order once Docker answers; held-back changes wait
+pub(crate) fn tick() {
+    let queue = load_queue();
+    if queue.iter().all(|op| op.conflict.is_some()) || !docker_reachable() {
+        return;
+    }
+    let servers = list_servers();
+    for operation in queue.into_iter().filter(|op| op.conflict.is_none()) {
+        if !replay(operation, &servers, false) {
+            break;
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_pending_operations() -> Vec<PendingOperation> {
+    load_queue()
+}
+
+/// Settle a held-back change: replay it over what changed meanwhile (now, or once
+/// Docker is back), or drop it
+#[tauri::command]
+pub fn resolve_pending_operation(id: String, resolution: Resolution) -> CommandResult {
+    let Some(operation) = load_queue().into_iter().find(|op| op.id == id) else {
+        return CommandResult {
+            success: false,
+            error: Some(format!("No pending operation {}", id)),
+        };
+    };
+    match resolution {
+        Resolution::Discard => {
+            update_json_file(&queue_path(), |queue: &mut Vec<PendingOperation>| {
+                queue.retain(|op| op.id != id);
+            });
+            record_audit(
+                &operation.server,
+                &format!("discard-{}", operation.change.action()),
+                None,
+            );
+        }
+        Resolution::Apply => {
+            // Cleared, so the watcher replays it if Docker is still away
+            update_json_file(&queue_path(), |queue: &mut Vec<PendingOperation>| {
+                if let Some(op) = queue.iter_mut().find(|op| op.id == id) {
+                    op.conflict = None;
+                    op.base = None;
+                    op.server_id = None;
+                }
+            });
+            if docker_reachable() {
+                replay(operation, &list_servers(), true);
+            }
+        }
+    }
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
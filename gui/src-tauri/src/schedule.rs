@@ -0,0 +1,310 @@
+//! Cron-style schedules ("*/15 9-17 * * mon-fri") and their upcoming run times
+//! in the local timezone. Fields are minute, hour, day of month, month and day of
+//! week; `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are accepted too.
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Run times `preview_schedule` returns when not told how many
+const DEFAULT_PREVIEW_RUNS: usize = 5;
+const MAX_PREVIEW_RUNS: usize = 100;
+/// A schedule with no run in this many years never runs, e.g. "0 0 30 2 *"
+const SEARCH_YEARS: i32 = 5;
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Where an expression went wrong, so the editor can underline it
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct ScheduleError {
+    pub message: String,
+    /// Character offset of the offending text in the expression
+    pub position: usize,
+    pub length: usize,
+}
+
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+    /// Value of the first name, e.g. 1 for "jan"
+    names_from: u32,
+}
+
+const FIELDS: [Field; 5] = [
+    Field {
+        name: "minute",
+        min: 0,
+        max: 59,
+        names: &[],
+        names_from: 0,
+    },
+    Field {
+        name: "hour",
+        min: 0,
+        max: 23,
+        names: &[],
+        names_from: 0,
+    },
+    Field {
+        name: "day of month",
+        min: 1,
+        max: 31,
+        names: &[],
+        names_from: 0,
+    },
+    Field {
+        name: "month",
+        min: 1,
+        max: 12,
+        names: MONTH_NAMES,
+        names_from: 1,
+    },
+    // 7 is Sunday as well as 0
+    Field {
+        name: "day of week",
+        min: 0,
+        max: 7,
+        names: WEEKDAY_NAMES,
+        names_from: 0,
+    },
+];
+
+/// A parsed expression: one bit per allowed value of each field
+pub(crate) struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month or week was `*`, which changes how the two combine
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn error(message: String, position: usize, text: &str) -> ScheduleError {
+    ScheduleError {
+        message,
+        position,
+        length: text.chars().count().max(1),
+    }
+}
+
+fn parse_value(field: &Field, text: &str, position: usize) -> Result<u32, ScheduleError> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(index) = field.names.iter().position(|n| *n == lower) {
+        return Ok(index as u32 + field.names_from);
+    }
+    let value: u32 = text.parse().map_err(|_| {
+        error(
+            format!("'{}' is not a valid {}", text, field.name),
+            position,
+            text,
+        )
+    })?;
+    if !(field.min..=field.max).contains(&value) {
+        return Err(error(
+            format!(
+                "{} must be between {} and {}, not {}",
+                field.name, field.min, field.max, value
+            ),
+            position,
+            text,
+        ));
+    }
+    Ok(value)
+}
+
+/// One field's list of `*`, `a`, `a-b`, each optionally with `/step`
+fn parse_field(field: &Field, text: &str, position: usize) -> Result<u64, ScheduleError> {
+    let mut bits = 0u64;
+    let mut offset = position;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step_at = offset + range.len() + 1;
+                let step: u32 = step.parse().ok().filter(|s| *s > 0).ok_or_else(|| {
+                    error(format!("'{}' is not a valid step", step), step_at, step)
+                })?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (field.min, field.max)
+        } else if let Some((from, to)) = range.split_once('-') {
+            let start = parse_value(field, from, offset)?;
+            let end = parse_value(field, to, offset + from.len() + 1)?;
+            if end < start {
+                return Err(error(
+                    format!("Range {} runs backwards", range),
+                    offset,
+                    range,
+                ));
+            }
+            (start, end)
+        } else if range.is_empty() {
+            return Err(error(
+                format!("Empty entry in {}", field.name),
+                offset,
+                range,
+            ));
+        } else {
+            let start = parse_value(field, range, offset)?;
+            // "5/10" means from 5 to the end in steps of 10
+            (start, if step > 1 { field.max } else { start })
+        };
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+        offset += part.len() + 1;
+    }
+    Ok(bits)
+}
+
+impl Schedule {
+    pub(crate) fn parse(expr: &str) -> Result<Schedule, ScheduleError> {
+        let trimmed = expr.trim();
+        let lead = expr.len() - expr.trim_start().len();
+        let expanded = match trimmed.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => Some("0 0 1 1 *"),
+            "@monthly" => Some("0 0 1 * *"),
+            "@weekly" => Some("0 0 * * 0"),
+            "@daily" | "@midnight" => Some("0 0 * * *"),
+            "@hourly" => Some("0 * * * *"),
+            _ if trimmed.starts_with('@') => {
+                return Err(error(
+                    format!("Unknown shorthand {}", trimmed),
+                    lead,
+                    trimmed,
+                ))
+            }
+            _ => None,
+        };
+        if let Some(expanded) = expanded {
+            return Schedule::parse(expanded);
+        }
+
+        // Fields with the byte offset each starts at
+        let mut fields = Vec::new();
+        let mut start = None;
+        for (index, c) in expr.char_indices().chain([(expr.len(), ' ')]) {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some(index),
+                (true, Some(from)) => {
+                    fields.push((from, &expr[from..index]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if fields.len() != FIELDS.len() {
+            let (position, text) = match fields.get(FIELDS.len()) {
+                Some((at, text)) => (*at, *text),
+                None => (expr.len(), ""),
+            };
+            return Err(error(
+                format!(
+                    "Expected 5 fields (minute hour day month weekday), found {}",
+                    fields.len()
+                ),
+                expr[..position].chars().count(),
+                text,
+            ));
+        }
+
+        let mut bits = [0u64; 5];
+        for (i, ((at, text), field)) in fields.iter().zip(FIELDS.iter()).enumerate() {
+            let position = expr[..*at].chars().count();
+            bits[i] = parse_field(field, text, position)?;
+        }
+        // Fold 7 into 0, both being Sunday
+        let weekdays = (bits[4] | (bits[4] >> 7)) & 0x7f;
+        Ok(Schedule {
+            minutes: bits[0],
+            hours: bits[1],
+            days: bits[2],
+            months: bits[3],
+            weekdays,
+            any_day: fields[2].1 == "*",
+            any_weekday: fields[4].1 == "*",
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        // Like cron: when both are restricted, either one may match
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// The first run strictly after `after`, in local wall-clock time
+    fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after.year() + SEARCH_YEARS;
+        while time.year() <= limit {
+            let date = time.date();
+            if self.months & (1 << date.month()) == 0 {
+                let (year, month) = match date.month() {
+                    12 => (date.year() + 1, 1),
+                    month => (date.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    /// The next `count` runs in the local timezone. Times a DST change skips
+    /// are skipped; repeated ones run once, at the earlier instant.
+    pub(crate) fn upcoming(&self, count: usize) -> Vec<chrono::DateTime<Local>> {
+        let mut runs = Vec::new();
+        let mut cursor = Local::now().naive_local();
+        while runs.len() < count {
+            let Some(next) = self.next_after(cursor) else {
+                break;
+            };
+            if let Some(time) = Local.from_local_datetime(&next).earliest() {
+                runs.push(time);
+            }
+            cursor = next;
+        }
+        runs
+    }
+}
+
+/// Check a schedule expression and list its next `n` run times, so it can be
+/// verified before it is saved
+#[tauri::command]
+pub fn preview_schedule(expr: String, n: Option<usize>) -> Result<Vec<String>, ScheduleError> {
+    let schedule = Schedule::parse(&expr)?;
+    let count = n.unwrap_or(DEFAULT_PREVIEW_RUNS).clamp(1, MAX_PREVIEW_RUNS);
+    let runs = schedule.upcoming(count);
+    if runs.is_empty() {
+        return Err(error(
+            format!(
+                "This schedule never runs in the next {} years",
+                SEARCH_YEARS
+            ),
+            0,
+            &expr,
+        ));
+    }
+    Ok(runs.iter().map(|t| t.to_rfc3339()).collect())
+}
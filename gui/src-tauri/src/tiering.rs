@@ -0,0 +1,416 @@
+//! Storage tiering: files nobody has modified for a while move out of a server's
+//! host folder to a backup target (a folder or an S3 bucket), so active shares stay
+//! small. Each move is recorded per server and can be undone with
+//! `restore_tiered`; a placeholder can be left where the file was, telling SFTP
+//! users where it went.
+
+use super::{
+    check_disk_space, find_server, get_config_dir, get_data_dir, load_app_settings,
+    load_backup_targets, read_json_file, record_audit, run_command, run_operation, s3_bucket,
+    update_json_file, write_audit_entry, AuditEntry, BackupTarget, CommandError, CommandResult,
+    ServerInfo, TIERED_DIR, TIERING_RULES_FILE,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use ts_rs::TS;
+
+/// How often the watcher applies the rules
+const TIERING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Appended to a moved file's name for the placeholder left in its place
+const PLACEHOLDER_SUFFIX: &str = ".dsftp-archived";
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TieringRule {
+    /// Folder under the share, relative to its root; the whole share when empty
+    #[serde(default)]
+    pub path: String,
+    /// Files not modified for this many days are moved
+    pub days: u32,
+    /// Backup target the files move to
+    pub target: String,
+    /// Leave `<name>.dsftp-archived` behind, describing where the file went
+    #[serde(default)]
+    pub placeholder: bool,
+}
+
+/// A file moved off the share; also the content of its placeholder
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TieredFile {
+    /// Relative to the share root
+    pub path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    #[ts(type = "number")]
+    pub modified: i64,
+    pub target: String,
+    /// Path of the copy in a folder target, or its key in an S3 bucket
+    pub location: String,
+    pub archived_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TieringReport {
+    pub server: String,
+    pub files: Vec<TieredFile>,
+    #[ts(type = "number")]
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+fn rules_path() -> PathBuf {
+    get_config_dir().join(TIERING_RULES_FILE)
+}
+
+fn load_rules() -> HashMap<String, Vec<TieringRule>> {
+    read_json_file(&rules_path())
+}
+
+/// Where a server's moved files are recorded, keyed by their path in the share
+fn index_path(name: &str) -> PathBuf {
+    get_data_dir()
+        .join(TIERED_DIR)
+        .join(format!("{}.json", name))
+}
+
+fn share_relative(path: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(path.trim_start_matches(['/', '\\']));
+    if relative
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(format!("Path must stay inside the share: {}", path));
+    }
+    Ok(relative.to_path_buf())
+}
+
+/// Files under `dir` last modified before `cutoff`, with their size and mtime.
+/// Symlinks and placeholders are left alone.
+fn stale_files(dir: &Path, cutoff: SystemTime, found: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            stale_files(&entry.path(), cutoff, found);
+        } else if meta.is_file()
+            && !entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(PLACEHOLDER_SUFFIX)
+        {
+            if let Some(modified) = meta.modified().ok().filter(|m| *m < cutoff) {
+                found.push((entry.path(), meta.len(), modified));
+            }
+        }
+    }
+}
+
+/// Remove a file from the share. Files SFTP users created belong to the container's
+/// user, so the host may not be allowed to; the container always is.
+fn remove_share_file(server: &ServerInfo, relative: &Path, host_file: &Path) -> Result<(), String> {
+    if fs::remove_file(host_file).is_ok() {
+        return Ok(());
+    }
+    let container_file = format!(
+        "{}/{}",
+        server.container_path.trim_end_matches('/'),
+        relative.to_string_lossy().replace('\\', "/")
+    );
+    run_command(
+        "docker",
+        &["exec", &server.name, "rm", "-f", "--", &container_file],
+    )
+    .map(|_| ())
+}
+
+/// Copy one file to the target, returning where it went
+fn copy_to_target(
+    server: &str,
+    relative: &Path,
+    host_file: &Path,
+    target: &BackupTarget,
+) -> Result<String, String> {
+    let key = format!(
+        "tiered/{}/{}",
+        server,
+        relative.to_string_lossy().replace('\\', "/")
+    );
+    match target {
+        BackupTarget::Local { dir } => {
+            let dest = Path::new(dir).join(&key);
+            let size = fs::metadata(host_file).map(|m| m.len()).unwrap_or(0);
+            check_disk_space(&dest, size).map_err(|e| e.to_string())?;
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::copy(host_file, &dest).map_err(|e| e.to_string())?;
+            Ok(dest.to_string_lossy().to_string())
+        }
+        BackupTarget::S3(s3) => {
+            let bucket = s3_bucket(s3)?;
+            let key = format!("{}{}", s3.prefix, key);
+            tauri::async_runtime::block_on(async {
+                let mut file = tokio::fs::File::open(host_file)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                bucket
+                    .put_object_stream(&mut file, &key)
+                    .await
+                    .map_err(|e| e.to_string())
+            })?;
+            Ok(key)
+        }
+    }
+}
+
+/// Move every file the server's rules select, one at a time: copied, then removed
+/// from the share, then recorded
+fn run_tiering(name: &str) -> Result<TieringReport, String> {
+    let server = find_server(name)?;
+    let rules = load_rules().remove(name).unwrap_or_default();
+    let targets = load_backup_targets();
+    let share = Path::new(&server.host_path);
+    let mut report = TieringReport {
+        server: name.to_string(),
+        files: Vec::new(),
+        bytes: 0,
+        errors: Vec::new(),
+    };
+
+    for rule in &rules {
+        let Some(target) = targets.get(&rule.target) else {
+            report
+                .errors
+                .push(format!("Unknown backup target: {}", rule.target));
+            continue;
+        };
+        let cutoff = SystemTime::now() - Duration::from_secs(rule.days as u64 * 24 * 60 * 60);
+        let mut files = Vec::new();
+        stale_files(&share.join(share_relative(&rule.path)?), cutoff, &mut files);
+
+        for (host_file, size, modified) in files {
+            let Ok(relative) = host_file.strip_prefix(share).map(Path::to_path_buf) else {
+                continue;
+            };
+            let result = copy_to_target(name, &relative, &host_file, target).and_then(|location| {
+                remove_share_file(&server, &relative, &host_file)?;
+                Ok(location)
+            });
+            let path = relative.to_string_lossy().replace('\\', "/");
+            write_audit_entry(AuditEntry {
+                timestamp: chrono::Local::now().to_rfc3339(),
+                server: name.to_string(),
+                server_id: None,
+                action: "tier-archive".to_string(),
+                success: result.is_ok(),
+                detail: Some(match &result {
+                    Ok(location) => format!("{} -> {}", path, location),
+                    Err(e) => format!("{}: {}", path, e),
+                }),
+            });
+            let location = match result {
+                Ok(location) => location,
+                Err(e) => {
+                    report.errors.push(format!("{}: {}", path, e.trim()));
+                    continue;
+                }
+            };
+
+            let tiered = TieredFile {
+                path: path.clone(),
+                size,
+                modified: modified
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0),
+                target: rule.target.clone(),
+                location,
+                archived_at: chrono::Local::now().to_rfc3339(),
+            };
+            if rule.placeholder {
+                let mut placeholder = host_file.into_os_string();
+                placeholder.push(PLACEHOLDER_SUFFIX);
+                if let Ok(json) = serde_json::to_string_pretty(&tiered) {
+                    // Best effort: the folder may not be writable from the host
+                    fs::write(placeholder, json).ok();
+                }
+            }
+            update_json_file(
+                &index_path(name),
+                |index: &mut HashMap<String, TieredFile>| {
+                    index.insert(path, tiered.clone());
+                },
+            );
+            report.bytes += size;
+            report.files.push(tiered);
+        }
+    }
+    Ok(report)
+}
+
+/// Hourly tiering pass over servers that have rules, running or not
+pub(crate) fn tick(servers: &[ServerInfo], last_run: &mut Option<Instant>) {
+    if last_run.is_some_and(|t| t.elapsed() < TIERING_INTERVAL) {
+        return;
+    }
+    *last_run = Some(Instant::now());
+    let rules = load_rules();
+    for server in servers {
+        if rules.get(&server.name).is_some_and(|r| !r.is_empty()) {
+            if let Err(e) = run_tiering(&server.name) {
+                record_audit(&server.name, "tier-archive", Some(&e));
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_tiering_rules(name: String) -> Vec<TieringRule> {
+    load_rules().remove(&name).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn set_tiering_rules(name: String, rules: Vec<TieringRule>) -> CommandResult {
+    let targets = load_app_settings().backup_targets;
+    let invalid = rules.iter().find_map(|rule| {
+        if rule.days == 0 {
+            return Some("Files must be left at least 1 day".to_string());
+        }
+        if !targets.contains_key(&rule.target) {
+            return Some(format!("Unknown backup target: {}", rule.target));
+        }
+        share_relative(&rule.path).err()
+    });
+    if let Some(error) = invalid {
+        return CommandResult {
+            success: false,
+            error: Some(error),
+        };
+    }
+    update_json_file(
+        &rules_path(),
+        |all: &mut HashMap<String, Vec<TieringRule>>| {
+            if rules.is_empty() {
+                all.remove(&name);
+            } else {
+                all.insert(name, rules);
+            }
+        },
+    );
+    CommandResult {
+        success: true,
+        error: None,
+    }
+}
+
+#[tauri::command]
+pub async fn run_tiering_now(
+    name: String,
+    operation_id: Option<String>,
+) -> Result<TieringReport, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || run_operation(operation_id, || run_tiering(&name)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Files moved off the server's share, by path
+#[tauri::command]
+pub fn list_tiered(name: String) -> Vec<TieredFile> {
+    let index: HashMap<String, TieredFile> = read_json_file(&index_path(&name));
+    let mut files: Vec<TieredFile> = index.into_values().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Bring a moved file back to where it was, replacing its placeholder. It comes
+/// back as just modified, so the rules leave it for another full period.
+fn restore_file(name: &str, path: &str) -> Result<(), String> {
+    let server = find_server(name)?;
+    let index: HashMap<String, TieredFile> = read_json_file(&index_path(name));
+    let tiered = index
+        .get(path)
+        .ok_or_else(|| format!("{} was not moved off the share", path))?;
+    let host_file = Path::new(&server.host_path).join(share_relative(path)?);
+    if host_file.exists() {
+        return Err(format!("{} exists in the share again", path));
+    }
+    if let Some(parent) = host_file.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let target = load_backup_targets()
+        .remove(&tiered.target)
+        .ok_or_else(|| format!("Unknown backup target: {}", tiered.target))?;
+    match target {
+        BackupTarget::Local { .. } => {
+            fs::copy(&tiered.location, &host_file).map_err(|e| e.to_string())?;
+            fs::remove_file(&tiered.location).ok();
+        }
+        BackupTarget::S3(s3) => {
+            let bucket = s3_bucket(&s3)?;
+            tauri::async_runtime::block_on(async {
+                let mut file = tokio::fs::File::create(&host_file)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let status = bucket
+                    .get_object_to_writer(&tiered.location, &mut file)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if status != 200 {
+                    return Err(format!("S3 answered {}", status));
+                }
+                bucket
+                    .delete_object(&tiered.location)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .inspect_err(|_| {
+                fs::remove_file(&host_file).ok();
+            })?;
+        }
+    }
+
+    let mut placeholder = host_file.into_os_string();
+    placeholder.push(PLACEHOLDER_SUFFIX);
+    fs::remove_file(placeholder).ok();
+    update_json_file(
+        &index_path(name),
+        |index: &mut HashMap<String, TieredFile>| index.remove(path),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn restore_tiered(name: String, path: String) -> CommandResult {
+    let server = name.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || restore_file(&name, &path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    record_audit(
+        &server,
+        "tier-restore",
+        result.as_ref().err().map(|e| e.as_str()),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
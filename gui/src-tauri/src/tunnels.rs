@@ -0,0 +1,312 @@
+//! Public tunnels through cloudflared or ngrok, so a server is reachable from the
+//! internet without opening ports. The tunnel follows the server: the watcher
+//! starts it while the server runs and tears it down when the server stops.
+
+use super::{
+    client_host, default_bind_ip, find_server, load_credentials, new_command, record_audit,
+    update_credentials, CommandResult, ServerInfo,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Stdio};
+use std::sync::{Arc, LazyLock, Mutex};
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum TunnelProvider {
+    Cloudflared,
+    Ngrok,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, TS)]
+#[ts(export)]
+pub struct TunnelConfig {
+    pub provider: TunnelProvider,
+    /// cloudflared: token of a named tunnel; a throwaway trycloudflare.com
+    /// tunnel when unset. ngrok: authtoken, if not already in ngrok's own config
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Public hostname routed to a named cloudflared tunnel, which cloudflared
+    /// doesn't print itself
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TunnelStatus {
+    pub server: String,
+    pub provider: TunnelProvider,
+    pub running: bool,
+    /// e.g. "tcp://0.tcp.ngrok.io:12345"; `None` until the provider reports it
+    pub public_address: Option<String>,
+    /// Last thing the provider logged before exiting
+    pub error: Option<String>,
+}
+
+struct RunningTunnel {
+    config: TunnelConfig,
+    child: Child,
+    public_address: Arc<Mutex<Option<String>>>,
+    last_line: Arc<Mutex<Option<String>>>,
+}
+
+static TUNNELS: LazyLock<Mutex<HashMap<String, RunningTunnel>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The public address in one line of provider output, if it carries one
+fn public_address_in(provider: TunnelProvider, line: &str) -> Option<String> {
+    match provider {
+        // Boxed banner: "|  https://some-words.trycloudflare.com  |"
+        TunnelProvider::Cloudflared => line
+            .split_whitespace()
+            .find(|word| word.starts_with("https://") && word.contains(".trycloudflare.com"))
+            .map(str::to_string),
+        // JSON log: {"msg":"started tunnel",...,"url":"tcp://0.tcp.ngrok.io:12345"}
+        TunnelProvider::Ngrok => {
+            let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+            if entry["msg"] != "started tunnel" {
+                return None;
+            }
+            entry["url"].as_str().map(str::to_string)
+        }
+    }
+}
+
+fn watch_output(
+    pipe: impl Read + Send + 'static,
+    provider: TunnelProvider,
+    public_address: Arc<Mutex<Option<String>>>,
+    last_line: Arc<Mutex<Option<String>>>,
+) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if let Some(address) = public_address_in(provider, &line) {
+                if let Ok(mut slot) = public_address.lock() {
+                    *slot = Some(address);
+                }
+            }
+            if let Ok(mut slot) = last_line.lock() {
+                *slot = Some(line);
+            }
+        }
+    });
+}
+
+fn start(server: &ServerInfo, config: &TunnelConfig) -> Result<RunningTunnel, String> {
+    let bind_ip = server.bind_ip.clone().unwrap_or_else(default_bind_ip);
+    let local = format!("{}:{}", client_host(&bind_ip), server.port);
+    let mut command = match (config.provider, &config.token) {
+        (TunnelProvider::Cloudflared, Some(token)) => {
+            let mut command = new_command("cloudflared");
+            command.args([
+                "tunnel",
+                "--no-autoupdate",
+                "run",
+                "--token",
+                token.as_str(),
+            ]);
+            command
+        }
+        (TunnelProvider::Cloudflared, None) => {
+            let mut command = new_command("cloudflared");
+            let url = format!("tcp://{}", local);
+            command.args(["tunnel", "--no-autoupdate", "--url", url.as_str()]);
+            command
+        }
+        (TunnelProvider::Ngrok, token) => {
+            let mut command = new_command("ngrok");
+            command.args([
+                "tcp",
+                local.as_str(),
+                "--log",
+                "stdout",
+                "--log-format",
+                "json",
+            ]);
+            if let Some(token) = token {
+                command.args(["--authtoken", token.as_str()]);
+            }
+            command
+        }
+    };
+    let program = match config.provider {
+        TunnelProvider::Cloudflared => "cloudflared",
+        TunnelProvider::Ngrok => "ngrok",
+    };
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run {}: {}", program, e))?;
+
+    let public_address = Arc::new(Mutex::new(config.hostname.clone()));
+    let last_line = Arc::new(Mutex::new(None));
+    if let Some(stdout) = child.stdout.take() {
+        watch_output(
+            stdout,
+            config.provider,
+            Arc::clone(&public_address),
+            Arc::clone(&last_line),
+        );
+    }
+    if let Some(stderr) = child.stderr.take() {
+        watch_output(
+            stderr,
+            config.provider,
+            Arc::clone(&public_address),
+            Arc::clone(&last_line),
+        );
+    }
+    Ok(RunningTunnel {
+        config: config.clone(),
+        child,
+        public_address,
+        last_line,
+    })
+}
+
+fn stop(mut tunnel: RunningTunnel) {
+    tunnel.child.kill().ok();
+    tunnel.child.wait().ok();
+}
+
+fn status(name: &str, tunnel: &mut RunningTunnel) -> TunnelStatus {
+    let running = matches!(tunnel.child.try_wait(), Ok(None));
+    TunnelStatus {
+        server: name.to_string(),
+        provider: tunnel.config.provider,
+        running,
+        public_address: tunnel
+            .public_address
+            .lock()
+            .ok()
+            .and_then(|a| a.clone())
+            .filter(|_| running),
+        error: if running {
+            None
+        } else {
+            tunnel.last_line.lock().ok().and_then(|l| l.clone())
+        },
+    }
+}
+
+/// Where clients reach a server through its tunnel, once the provider has said
+pub(crate) fn public_address(name: &str) -> Option<String> {
+    let mut tunnels = TUNNELS.lock().ok()?;
+    let tunnel = tunnels.get_mut(name)?;
+    status(name, tunnel).public_address
+}
+
+fn sync(
+    tunnels: &mut HashMap<String, RunningTunnel>,
+    server: &ServerInfo,
+    wanted: Option<TunnelConfig>,
+) {
+    let current = tunnels.remove(&server.name);
+    let Some(config) = wanted else {
+        if let Some(tunnel) = current {
+            stop(tunnel);
+            record_audit(&server.name, "stop-tunnel", None);
+        }
+        return;
+    };
+    if let Some(mut tunnel) = current {
+        let alive = matches!(tunnel.child.try_wait(), Ok(None));
+        if alive && tunnel.config == config {
+            tunnels.insert(server.name.clone(), tunnel);
+            return;
+        }
+        stop(tunnel);
+    }
+    match start(server, &config) {
+        Ok(tunnel) => {
+            record_audit(&server.name, "start-tunnel", None);
+            tunnels.insert(server.name.clone(), tunnel);
+        }
+        Err(e) => record_audit(&server.name, "start-tunnel", Some(&e)),
+    }
+}
+
+/// Run a tunnel for every running server that has one configured, and tear
+/// down the rest
+pub(crate) fn tick(servers: &[ServerInfo]) {
+    let creds = load_credentials();
+    let Ok(mut tunnels) = TUNNELS.lock() else {
+        return;
+    };
+    for server in servers {
+        let wanted = creds
+            .get(&server.name)
+            .and_then(|c| c.tunnel.clone())
+            .filter(|_| server.status == "running");
+        sync(&mut tunnels, server, wanted);
+    }
+    let gone: Vec<String> = tunnels
+        .keys()
+        .filter(|name| !servers.iter().any(|s| &s.name == *name))
+        .cloned()
+        .collect();
+    for name in gone {
+        if let Some(tunnel) = tunnels.remove(&name) {
+            stop(tunnel);
+        }
+    }
+}
+
+/// Tear down a server's tunnel right away, e.g. because it was stopped
+pub(crate) fn close(name: &str) {
+    let tunnel = TUNNELS.lock().ok().and_then(|mut t| t.remove(name));
+    if let Some(tunnel) = tunnel {
+        stop(tunnel);
+        record_audit(name, "stop-tunnel", None);
+    }
+}
+
+/// Expose a server through cloudflared or ngrok, or remove its tunnel with
+/// `None`. The setting is kept, so the tunnel comes back with the server.
+#[tauri::command]
+pub fn set_tunnel(name: String, config: Option<TunnelConfig>) -> CommandResult {
+    let result = find_server(&name).and_then(|server| {
+        update_credentials(|all| {
+            all.get_mut(&name)
+                .map(|creds| creds.tunnel = config.clone())
+        })
+        .ok_or_else(|| format!("{} has no stored settings", name))?;
+        let wanted = config.clone().filter(|_| server.status == "running");
+        let mut tunnels = TUNNELS.lock().map_err(|e| e.to_string())?;
+        sync(&mut tunnels, &server, wanted);
+        Ok(())
+    });
+    record_audit(
+        &name,
+        "set-tunnel",
+        result.as_ref().err().map(String::as_str),
+    );
+    match result {
+        Ok(()) => CommandResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => CommandResult {
+            success: false,
+            error: Some(e),
+        },
+    }
+}
+
+/// Tunnels this process is running
+#[tauri::command]
+pub fn list_tunnels() -> Vec<TunnelStatus> {
+    let Ok(mut tunnels) = TUNNELS.lock() else {
+        return vec![];
+    };
+    tunnels
+        .iter_mut()
+        .map(|(name, tunnel)| status(name, tunnel))
+        .collect()
+}
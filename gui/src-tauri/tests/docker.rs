@@ -0,0 +1,320 @@
+//! End-to-end tests against real atmoz/sftp containers. Needs a running Docker daemon:
+//!
+//!     cargo test --features docker-tests --test docker
+//!
+//! Every container is removed when its guard drops, including when a test panics.
+
+use sftp_manager_lib::testing;
+use sftp_manager_lib::{CleanupRule, FileEntry, ServerConfig};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use testcontainers::core::WaitFor;
+use testcontainers::runners::SyncRunner;
+use testcontainers::{Container, GenericImage, ImageExt};
+
+const USER: &str = "tester";
+const UPLOAD_DIR: &str = "/home/tester/upload";
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Names a shell or an option parser would mangle if they were ever interpolated
+const HOSTILE_NAMES: &[&str] = &[
+    "with space.txt",
+    "it's \"quoted\".txt",
+    "$(touch pwned).txt",
+    "-leading-dash.txt",
+    "new\nline.txt",
+];
+
+/// Keep the run's config (stored passwords, audit log) away from the real one
+fn isolate_config() {
+    static CONFIG_DIR: Once = Once::new();
+    CONFIG_DIR.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("dsftp-tests-{}", std::process::id()));
+        std::env::set_var("DSFTP_CONFIG_DIR", dir);
+    });
+}
+
+/// Removes a server created through dsftp when dropped
+struct ServerGuard {
+    name: String,
+    host_dir: TempDir,
+}
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        testing::remove_server(&self.name);
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("no free port")
+}
+
+fn server_config(name: &str, host_dir: &Path) -> ServerConfig {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "port": free_port(),
+        "host_path": host_dir.to_string_lossy(),
+        "container_path": UPLOAD_DIR,
+        "username": USER,
+        "password": "secret",
+    }))
+    .unwrap()
+}
+
+fn create_server(label: &str) -> ServerGuard {
+    isolate_config();
+    let name = format!("dsftp-it-{}-{}", std::process::id(), label);
+    let host_dir = TempDir::new().unwrap();
+    // Cleans up even if the create itself half-failed
+    let guard = ServerGuard {
+        name: name.clone(),
+        host_dir,
+    };
+    let result = testing::create_server(server_config(&name, guard.host_dir.path()));
+    assert!(result.success, "create failed: {:?}", result.error);
+    guard
+}
+
+fn status(name: &str) -> Option<String> {
+    testing::list_servers()
+        .into_iter()
+        .find(|s| s.name == name)
+        .map(|s| s.status)
+}
+
+/// Poll until `check` passes, so tests don't race container startup
+fn eventually(what: &str, check: impl Fn() -> bool) {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while !check() {
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// A bare atmoz/sftp container dsftp didn't create; removed when dropped
+fn scratch_container() -> Container<GenericImage> {
+    GenericImage::new("atmoz/sftp", "latest")
+        .with_wait_for(WaitFor::message_on_stderr("Server listening"))
+        .with_cmd([format!("{}:secret:1001:100:upload", USER)])
+        .start()
+        .expect("failed to start atmoz/sftp")
+}
+
+/// Run `script` in the container with `args` as $1.., outside dsftp
+fn container_sh(id: &str, script: &str, args: &[&str]) -> String {
+    let output = Command::new("docker")
+        .args(["exec", id, "sh", "-c", script, "sh"])
+        .args(args)
+        .output()
+        .expect("failed to run docker");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn find_entry<'a>(entries: &'a [FileEntry], name: &str) -> Option<&'a FileEntry> {
+    entries.iter().find(|e| e.name == name)
+}
+
+#[test]
+fn server_lifecycle() {
+    let server = create_server("lifecycle");
+    assert_eq!(status(&server.name).as_deref(), Some("running"));
+
+    let result = testing::stop_server(&server.name);
+    assert!(result.success, "stop failed: {:?}", result.error);
+    assert_eq!(status(&server.name).as_deref(), Some("stopped"));
+
+    let result = testing::start_server(&server.name);
+    assert!(result.success, "start failed: {:?}", result.error);
+    assert_eq!(status(&server.name).as_deref(), Some("running"));
+    eventually("sshd to start", || {
+        testing::container_logs(&server.name).contains("Server listening")
+    });
+
+    let result = testing::remove_server(&server.name);
+    assert!(result.success, "remove failed: {:?}", result.error);
+    assert_eq!(status(&server.name), None);
+}
+
+#[test]
+fn duplicate_name_is_rejected() {
+    let server = create_server("duplicate");
+    let host_dir = TempDir::new().unwrap();
+    let result = testing::create_server(server_config(&server.name, host_dir.path()));
+    assert!(!result.success);
+}
+
+#[test]
+fn host_files_show_up_in_listing() {
+    let server = create_server("listing");
+    std::fs::create_dir(server.host_dir.path().join("docs")).unwrap();
+    std::fs::write(server.host_dir.path().join("docs/report.txt"), b"hello").unwrap();
+
+    let entries = testing::list_files(&server.name, UPLOAD_DIR).unwrap();
+    let docs = entries
+        .iter()
+        .find(|e| e.name == "docs")
+        .expect("docs missing");
+    assert!(docs.is_dir);
+
+    let entries = testing::list_files(&server.name, &format!("{}/docs", UPLOAD_DIR)).unwrap();
+    let report = entries
+        .iter()
+        .find(|e| e.name == "report.txt")
+        .expect("report.txt missing");
+    assert_eq!(report.size, 5);
+}
+
+#[test]
+fn upload_into_existing_container() {
+    isolate_config();
+    let container = scratch_container();
+    let local = tempfile::Builder::new().suffix(".bin").tempfile().unwrap();
+    std::fs::write(local.path(), vec![7u8; 4096]).unwrap();
+    let local_path = local.path().to_string_lossy().to_string();
+
+    let result = testing::upload_files(container.id(), &[local_path], UPLOAD_DIR).unwrap();
+    assert!(result.success, "upload failed: {:?}", result.errors);
+    assert_eq!(result.bytes_transferred, 4096);
+
+    let file_name = local.path().file_name().unwrap().to_string_lossy();
+    let entries = testing::list_files(container.id(), UPLOAD_DIR).unwrap();
+    let uploaded = entries
+        .iter()
+        .find(|e| e.name == file_name)
+        .expect("uploaded file missing");
+    assert_eq!(uploaded.size, 4096);
+}
+
+#[test]
+fn listing_a_missing_directory_fails() {
+    isolate_config();
+    let container = scratch_container();
+    let error = testing::list_files(container.id(), "/home/tester/nope").unwrap_err();
+    assert!(error.contains("No such directory"), "{}", error);
+}
+
+#[test]
+fn hostile_names_round_trip() {
+    isolate_config();
+    let container = scratch_container();
+    let local_dir = TempDir::new().unwrap();
+    let local_paths: Vec<String> = HOSTILE_NAMES
+        .iter()
+        .map(|name| {
+            let path = local_dir.path().join(name);
+            std::fs::write(&path, name.as_bytes()).unwrap();
+            path.to_string_lossy().into_owned()
+        })
+        .collect();
+
+    let result = testing::upload_files(container.id(), &local_paths, UPLOAD_DIR).unwrap();
+    assert!(result.success, "upload failed: {:?}", result.errors);
+    assert_eq!(result.uploaded.len(), HOSTILE_NAMES.len());
+
+    let entries = testing::list_files(container.id(), UPLOAD_DIR).unwrap();
+    for name in HOSTILE_NAMES {
+        let entry = find_entry(&entries, name).unwrap_or_else(|| panic!("{:?} missing", name));
+        assert!(!entry.lossy_name, "{:?} listed as lossy", name);
+        assert_eq!(entry.path, format!("{}/{}", UPLOAD_DIR, name));
+        assert_eq!(entry.size, name.len() as u64);
+    }
+
+    for name in HOSTILE_NAMES {
+        let src = format!("{}/{}", UPLOAD_DIR, name);
+        let result = testing::move_path(container.id(), &src, &format!("{}.moved", src));
+        assert!(
+            result.success,
+            "rename of {:?} failed: {:?}",
+            name, result.error
+        );
+    }
+    let entries = testing::list_files(container.id(), UPLOAD_DIR).unwrap();
+    assert_eq!(entries.len(), HOSTILE_NAMES.len());
+    for name in HOSTILE_NAMES {
+        let entry = find_entry(&entries, &format!("{}.moved", name))
+            .unwrap_or_else(|| panic!("{:?} not renamed", name));
+        let content = container_sh(container.id(), r#"cat -- "$1""#, &[&entry.path]);
+        assert_eq!(content, *name);
+    }
+
+    let rules = vec![CleanupRule::MaxSize {
+        path: UPLOAD_DIR.to_string(),
+        max_bytes: 0,
+    }];
+    let report = testing::run_cleanup(container.id(), rules).unwrap();
+    assert!(
+        report.errors.is_empty(),
+        "delete failed: {:?}",
+        report.errors
+    );
+    assert_eq!(report.files.len(), HOSTILE_NAMES.len());
+    assert!(testing::list_files(container.id(), UPLOAD_DIR)
+        .unwrap()
+        .is_empty());
+
+    // Nothing along the way ran a name as a command
+    let pwned = container_sh(container.id(), "find / -xdev -name pwned", &[]);
+    assert_eq!(pwned, "");
+}
+
+#[test]
+fn non_utf8_names_are_listed_but_not_addressed() {
+    isolate_config();
+    let container = scratch_container();
+    container_sh(
+        container.id(),
+        r#"printf data > "$1/$(printf 'bad\377name.txt')""#,
+        &[UPLOAD_DIR],
+    );
+
+    let entries = testing::list_files(container.id(), UPLOAD_DIR).unwrap();
+    let entry = find_entry(&entries, "bad\u{FFFD}name.txt").expect("lossy entry missing");
+    assert!(entry.lossy_name);
+    assert_eq!(entry.size, 4);
+
+    // The lossy rendering names no file, so it's refused rather than guessed at
+    let result = testing::move_path(
+        container.id(),
+        &entry.path,
+        &format!("{}/renamed.txt", UPLOAD_DIR),
+    );
+    assert!(!result.success);
+    assert!(
+        result
+            .error
+            .as_deref()
+            .unwrap_or("")
+            .contains("not valid UTF-8"),
+        "{:?}",
+        result.error
+    );
+
+    let rules = vec![CleanupRule::MaxSize {
+        path: UPLOAD_DIR.to_string(),
+        max_bytes: 0,
+    }];
+    let report = testing::run_cleanup(container.id(), rules).unwrap();
+    assert!(report.files.is_empty(), "{:?}", report.files);
+
+    // Still there, with its original bytes
+    let listed = container_sh(
+        container.id(),
+        r#"for f in "$1"/*; do printf %s "${f##*/}"; done | od -An -c | tr -d ' \n'"#,
+        &[UPLOAD_DIR],
+    );
+    assert_eq!(listed, "bad377name.txt");
+}
@@ -155,7 +155,6 @@ function App() {
       host_path: config.host_path,
       container_path: config.container_path,
       username: config.username,
-      password: config.password,
       status: 'creating',
     };
     setServers(prev => [placeholderServer, ...prev]);
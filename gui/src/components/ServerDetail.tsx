@@ -19,7 +19,7 @@ import {
   ChevronUp,
   WifiOff
 } from 'lucide-react';
-import type { Server, ActionType, FileEntry, NetworkInterface } from '../types';
+import type { Server, ActionType, FileEntry, NetworkInterface, Credentials } from '../types';
 
 const ActionLabels: Record<ActionType, string> = {
   starting: 'Starting...',
@@ -41,6 +41,7 @@ interface ServerDetailProps {
 
 function ServerDetail({ server, localIP, networkInterfaces, onStart, onStop, onRemove, onBack, onDismissError }: ServerDetailProps) {
   const [showPassword, setShowPassword] = useState(false);
+  const [password, setPassword] = useState<string | null>(null);
   const [showLogs, setShowLogs] = useState(false);
   const [logs, setLogs] = useState('');
   const [logsLoading, setLogsLoading] = useState(false);
@@ -125,6 +126,19 @@ function ServerDetail({ server, localIP, networkInterfaces, onStart, onStop, onR
     return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
   }
 
+  // The password is only fetched when the user asks to see or copy it
+  async function revealPassword(): Promise<string> {
+    if (password !== null) return password;
+    const credentials = await invoke<Credentials>('reveal_credentials', { name: server.name });
+    setPassword(credentials.password);
+    return credentials.password;
+  }
+
+  async function togglePassword() {
+    if (!showPassword) await revealPassword();
+    setShowPassword(!showPassword);
+  }
+
   async function copyToClipboard(text: string, label: string) {
     await navigator.clipboard.writeText(text);
     setCopied(label);
@@ -138,18 +152,16 @@ function ServerDetail({ server, localIP, networkInterfaces, onStart, onStop, onR
     host: displayIP,
     port: server.port,
     username: server.username,
-    password: server.password,
     hostPath: hostPath,
     containerPath: containerPathValue,
     command: `sftp -P ${server.port} ${server.username}@${displayIP}`,
-    url: `sftp://${server.username}:${server.password}@${displayIP}:${server.port}`
+    url: `sftp://${server.username}@${displayIP}:${server.port}`
   };
 
   const debugInfo = `Server: ${server.name}
 Host: ${displayIP}
 Port: ${server.port}
 Username: ${server.username}
-Password: ${server.password}
 Host Path: ${hostPath}
 Container Path: ${containerPathValue}
 SFTP Command: ${connectionInfo.command}
@@ -303,16 +315,16 @@ FileZilla URL: ${connectionInfo.url}`;
             </span>
             <div className="flex-1 flex items-center gap-2">
               <code className="flex-1 px-3 py-2 bg-gray-100 dark:bg-gray-700 text-gray-800 dark:text-gray-200 rounded font-mono text-sm">
-                {showPassword ? (connectionInfo.password || '') : '••••••••'}
+                {showPassword ? (password || '') : '••••••••'}
               </code>
               <button
-                onClick={() => setShowPassword(!showPassword)}
+                onClick={togglePassword}
                 className="p-2 text-gray-600 dark:text-gray-400 hover:bg-gray-100 dark:hover:bg-gray-700 rounded"
               >
                 {showPassword ? <EyeOff size={16} /> : <Eye size={16} />}
               </button>
               <button
-                onClick={() => copyToClipboard(connectionInfo.password || '', 'pass')}
+                onClick={async () => copyToClipboard(await revealPassword(), 'pass')}
                 className="p-2 text-gray-600 dark:text-gray-400 hover:bg-gray-100 dark:hover:bg-gray-700 rounded"
               >
                 <Copy size={16} />
@@ -1,6 +1,7 @@
 import { Play, Square, Trash2, Copy, MoreVertical, Loader2, X, AlertCircle, Shield, WifiOff } from 'lucide-react';
-import { useState } from 'react';
-import type { Server, ActionType, NetworkInterface } from '../types';
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import type { Server, ActionType, NetworkInterface, Credentials } from '../types';
 
 const ActionLabels: Record<ActionType, string> = {
   starting: 'Starting...',
@@ -113,6 +114,13 @@ interface ServerCardProps {
 
 function ServerCard({ server, localIP, networkInterfaces, onSelect, onStart, onStop, onRemove, onDismissError }: ServerCardProps) {
   const [showMenu, setShowMenu] = useState(false);
+  // Spoken summary from the backend, shared with the CLI
+  const [description, setDescription] = useState<string>();
+  useEffect(() => {
+    invoke<string>('describe_server', { name: server.name })
+      .then(setDescription)
+      .catch(() => setDescription(undefined));
+  }, [server.name, server.status]);
   const isRunning = server.status === 'running';
   const action = server.action;
   const hasAction = !!action;
@@ -133,11 +141,10 @@ function ServerCard({ server, localIP, networkInterfaces, onSelect, onStart, onS
 Host: ${displayIP}
 Port: ${server.port}
 Username: ${server.username}
-Password: ${server.password}
 Host Path: ${hostPath}
 Container Path: ${containerPath}
 SFTP Command: sftp -P ${server.port} ${server.username}@${displayIP}
-FileZilla URL: sftp://${server.username}:${server.password}@${displayIP}:${server.port}`;
+FileZilla URL: sftp://${server.username}@${displayIP}:${server.port}`;
 
   async function copyToClipboard(text: string) {
     await navigator.clipboard.writeText(text);
@@ -202,6 +209,8 @@ FileZilla URL: sftp://${server.username}:${server.password}@${displayIP}:${serve
     <div
       className="bg-white dark:bg-gray-800 rounded-xl border border-gray-200 dark:border-gray-700 p-4 hover:shadow-lg transition-shadow cursor-pointer"
       onClick={onSelect}
+      role="group"
+      aria-label={description}
     >
       <div className="flex items-start justify-between">
         <div className="flex items-center gap-3">
@@ -269,7 +278,10 @@ FileZilla URL: sftp://${server.username}:${server.password}@${displayIP}:${serve
                   Copy SFTP Command
                 </button>
                 <button
-                  onClick={() => copyToClipboard(server.password)}
+                  onClick={async () => {
+                    const credentials = await invoke<Credentials>('reveal_credentials', { name: server.name });
+                    await copyToClipboard(credentials.password);
+                  }}
                   className="w-full px-4 py-2 text-left text-sm text-gray-700 dark:text-gray-300 hover:bg-gray-100 dark:hover:bg-gray-700 flex items-center gap-2"
                 >
                   <Copy size={14} />
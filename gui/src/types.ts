@@ -1,11 +1,16 @@
-export interface ServerConfig {
-  name: string;
-  port: number;
-  host_path: string;
-  container_path: string;
-  username: string;
-  password: string;
-}
+// Backend payload types are generated from the Rust structs by ts-rs (`npm run bindings`)
+import type { ServerConfig as BackendServerConfig } from './bindings/ServerConfig';
+
+export type { FileEntry } from './bindings/FileEntry';
+export type { NetworkInfo } from './bindings/NetworkInfo';
+export type { NetworkInterface } from './bindings/NetworkInterface';
+
+// Fields the backend defaults (`#[serde(default)]`) may be left out
+export type ServerConfig = Pick<
+  BackendServerConfig,
+  'name' | 'port' | 'host_path' | 'container_path' | 'username' | 'password'
+> &
+  Partial<BackendServerConfig>;
 
 export type ServerStatus = 'running' | 'stopped' | 'exited' | 'creating' | 'not created';
 
@@ -24,35 +29,18 @@ export interface Server {
   container_path?: string;
   containerPath?: string;
   username: string;
-  password: string;
   status: ServerStatus;
   action?: ServerAction | null;
   bind_ip?: string | null;
 }
 
+// Only `reveal_credentials` returns a server's password
+export interface Credentials {
+  username: string;
+  password: string;
+}
+
 export interface CreateResult {
   success: boolean;
   error?: string;
 }
-
-export interface NetworkInterface {
-  name: string;
-  address: string;
-  is_vpn: boolean;
-}
-
-export interface NetworkInfo {
-  current_ip: string;
-  current_interface: string | null;
-  is_vpn: boolean;
-  preferred_ip: string | null;
-  preferred_interface: string | null;
-  interfaces: NetworkInterface[];
-}
-
-export interface FileEntry {
-  name: string;
-  path: string;
-  is_dir: boolean;
-  size: number;
-}